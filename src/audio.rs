@@ -1,31 +1,472 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How far ahead of the newest frame `AudioFeatureBuffer::sample` will extrapolate.
+/// Matches the most negative latency the UI allows.
+pub const MAX_PREDICTION_MS: u64 = 200;
+
+/// Number of feature frames kept (several seconds at typical callback rates)
+const FEATURE_BUFFER_FRAMES: usize = 512;
+
+/// Spectrum bands reported per frame: low, mid, high
+pub const BAND_COUNT: usize = 3;
+
+/// Display names for the bands, in index order (`AudioModulation::band`)
+pub const BAND_NAMES: [&str; BAND_COUNT] = ["Low", "Mid", "High"];
+
+/// Band edges in Hz; band `i` covers `BAND_EDGES_HZ[i]..BAND_EDGES_HZ[i + 1]`
+const BAND_EDGES_HZ: [f32; BAND_COUNT + 1] = [20.0, 250.0, 4000.0, 16000.0];
+
+/// Mono samples per FFT window (about 21 ms at 48 kHz)
+const FFT_SIZE: usize = 1024;
+
+/// New samples between analyses (half a window, about 11 ms at 48 kHz); callbacks in
+/// between reuse the last bands
+const FFT_HOP: usize = 512;
+
+/// Per-analysis decay of each band's running peak, so normalization adapts to the room
+const BAND_PEAK_DECAY: f32 = 0.998;
+
+/// Smallest running peak; quieter input reads as near zero instead of being amplified
+const MIN_BAND_PEAK: f32 = 0.01;
+
+/// Closest two hits can be and both count as beats (200 BPM)
+const MIN_BEAT_INTERVAL_S: f64 = 0.3;
+
+/// A longer gap between hits (under 30 BPM) restarts tempo tracking
+const MAX_BEAT_INTERVAL_S: f64 = 2.0;
+
+/// Time constant of the level average hits are measured against
+const ENVELOPE_TIME_S: f32 = 1.0;
+
+/// A hit must also clear the average by this much, so noise in a quiet room never triggers
+const ONSET_FLOOR: f32 = 0.01;
+
+/// Inter-onset intervals averaged into the tempo estimate
+const TEMPO_INTERVALS: usize = 8;
+
+/// How many beats the flywheel keeps predicting after the last hit
+const FLYWHEEL_MAX_BEATS: f64 = 16.0;
 
 pub struct AudioListener {
     _stream: cpal::Stream, // Keep stream alive
     pub peak_detected: Arc<AtomicBool>,
     pub current_volume: Arc<Mutex<f32>>,
+    pub features: Arc<Mutex<AudioFeatureBuffer>>,
+}
+
+/// One timestamped audio analysis result
+#[derive(Clone, Copy, Debug)]
+pub struct AudioFeatureFrame {
+    pub time: Instant,
+    pub volume: f32,
+    pub bands: [f32; BAND_COUNT], // 0.0-1.0 energy per band, normalized to its recent peak
+}
+
+/// Audio values effects see this frame, sent to the UI for meters
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AudioLevels {
+    pub rms: f32,
+    pub bands: [f32; BAND_COUNT],
+}
+
+/// Ring buffer of recent feature frames, so effects can sample the audio as it was
+/// `latency_ms` ago (or slightly ahead of it, for negative latency).
+pub struct AudioFeatureBuffer {
+    frames: VecDeque<AudioFeatureFrame>,
+    capacity: usize,
+}
+
+impl AudioFeatureBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self { frames: VecDeque::with_capacity(capacity), capacity: capacity.max(2) }
+    }
+
+    pub fn push(&mut self, frame: AudioFeatureFrame) {
+        // Callbacks can arrive out of order across threads; drop stale frames
+        if let Some(last) = self.frames.back() {
+            if frame.time < last.time {
+                return;
+            }
+        }
+        if self.frames.len() >= self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    /// Capture time of the newest frame, used to detect stalled input
+    pub fn latest_time(&self) -> Option<Instant> {
+        self.frames.back().map(|f| f.time)
+    }
+
+    /// Volume at `at`, linearly interpolated between the surrounding frames.
+    /// Interpolating rather than taking the nearest frame smooths out callback jitter.
+    /// Times after the newest frame are extrapolated from the last two frames,
+    /// up to `MAX_PREDICTION_MS`.
+    pub fn sample(&self, at: Instant) -> Option<f32> {
+        let first = self.frames.front()?;
+        let last = self.frames.back()?;
+
+        if at <= first.time {
+            return Some(first.volume);
+        }
+
+        if at >= last.time {
+            let prev = match self.frames.len() {
+                n if n >= 2 => self.frames[n - 2],
+                _ => return Some(last.volume),
+            };
+            let span = last.time.duration_since(prev.time).as_secs_f32();
+            if span <= 0.0 {
+                return Some(last.volume);
+            }
+            let ahead = at.duration_since(last.time)
+                .min(Duration::from_millis(MAX_PREDICTION_MS))
+                .as_secs_f32();
+            let slope = (last.volume - prev.volume) / span;
+            return Some((last.volume + slope * ahead).max(0.0));
+        }
+
+        // Frames are time-ordered: find the first frame at or after `at`
+        let idx = self.frames.partition_point(|f| f.time < at);
+        let after = self.frames[idx];
+        let before = self.frames[idx.saturating_sub(1)];
+        let span = after.time.duration_since(before.time).as_secs_f32();
+        if span <= 0.0 {
+            return Some(after.volume);
+        }
+        let frac = at.duration_since(before.time).as_secs_f32() / span;
+        Some(before.volume + (after.volume - before.volume) * frac)
+    }
+
+    /// Band energies of the newest frame captured at or before `at` (the oldest frame
+    /// if `at` precedes them all). Unlike `sample` this never extrapolates, as band
+    /// values jump too much between windows to predict.
+    pub fn sample_bands(&self, at: Instant) -> Option<[f32; BAND_COUNT]> {
+        let idx = self.frames.partition_point(|f| f.time <= at);
+        self.frames.get(idx.saturating_sub(1)).map(|f| f.bands)
+    }
+}
+
+/// What one `BeatDetector::process` call found
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BeatUpdate {
+    pub onset: bool, // A hit was heard
+    pub beat: bool,  // A beat lands now: a heard hit, or one the flywheel predicted between hits
+}
+
+/// Finds beats in the audio level envelope. A hit is a rising edge of the level through
+/// a multiple of its own recent average; the gaps between hits give the tempo. With the
+/// flywheel on, beats keep arriving on the tempo grid between (and for a while after)
+/// hits, and a hit shortly after a predicted beat re-anchors the grid instead of
+/// counting twice.
+pub struct BeatDetector {
+    envelope: f32,
+    above: bool,
+    last_time: Option<Instant>,
+    last_onset: Option<Instant>,
+    last_beat: Option<(Instant, bool)>, // When, and whether it was predicted
+    intervals: VecDeque<f64>,
+    bpm: Option<f64>,
+}
+
+impl Default for BeatDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BeatDetector {
+    pub fn new() -> Self {
+        Self {
+            envelope: 0.0,
+            above: false,
+            last_time: None,
+            last_onset: None,
+            last_beat: None,
+            intervals: VecDeque::with_capacity(TEMPO_INTERVALS),
+            bpm: None,
+        }
+    }
+
+    /// Tempo estimated from recent hits, once two have been heard
+    pub fn bpm(&self) -> Option<f64> {
+        self.bpm
+    }
+
+    fn period(&self) -> Option<f64> {
+        self.bpm.map(|bpm| 60.0 / bpm)
+    }
+
+    /// Feed the level at `now`; None while the input is stalled, so only the flywheel runs.
+    /// `sensitivity` (0.0-1.0) lowers how far over its average the level must jump to count
+    /// as a hit: 3x at 0, 2x at 0.5, any rise at 1.
+    pub fn process(&mut self, now: Instant, level: Option<f32>, sensitivity: f32, flywheel: bool) -> BeatUpdate {
+        let mut update = BeatUpdate::default();
+        if let Some(level) = level {
+            let dt = match self.last_time {
+                Some(last) => now.saturating_duration_since(last).as_secs_f32(),
+                None => {
+                    self.envelope = level; // Start from the current level, not silence
+                    0.0
+                }
+            };
+            self.last_time = Some(now);
+
+            let ratio = 1.0 + 2.0 * (1.0 - sensitivity.clamp(0.0, 1.0));
+            let above = level > self.envelope * ratio + ONSET_FLOOR;
+            let rising = above && !self.above;
+            self.above = above;
+            self.envelope += (level - self.envelope) * (1.0 - (-dt / ENVELOPE_TIME_S).exp());
+
+            let since_onset = self.last_onset.map(|t| now.saturating_duration_since(t).as_secs_f64());
+            if rising && since_onset.map_or(true, |s| s >= MIN_BEAT_INTERVAL_S) {
+                if let Some(interval) = since_onset {
+                    self.add_interval(interval);
+                }
+                self.last_onset = Some(now);
+                update.onset = true;
+
+                // A hit just after a predicted beat is that beat, heard late
+                let late = match (self.last_beat, self.period()) {
+                    (Some((at, true)), Some(period)) => now.saturating_duration_since(at).as_secs_f64() < period * 0.25,
+                    _ => false,
+                };
+                update.beat = !late;
+                self.last_beat = Some((now, false));
+                return update;
+            }
+        }
+
+        // Flywheel: beats on the tempo grid from the last beat, until the hits have been gone a while
+        if let (true, Some(period), Some((last, _)), Some(onset)) = (flywheel, self.period(), self.last_beat, self.last_onset) {
+            let since_onset = now.saturating_duration_since(onset).as_secs_f64();
+            if now.saturating_duration_since(last).as_secs_f64() >= period && since_onset < period * FLYWHEEL_MAX_BEATS {
+                update.beat = true;
+                // Step from the previous beat rather than `now`, so frame timing doesn't drift the grid
+                self.last_beat = Some((last + Duration::from_secs_f64(period), true));
+            }
+        }
+        update
+    }
+
+    fn add_interval(&mut self, interval: f64) {
+        if interval > MAX_BEAT_INTERVAL_S {
+            self.intervals.clear();
+            return;
+        }
+        // A gap spanning several beats (missed or quiet hits) counts as that many beats
+        let beats = self.period().map_or(1.0, |period| (interval / period).round().max(1.0));
+        let interval = interval / beats;
+        if interval < MIN_BEAT_INTERVAL_S {
+            return;
+        }
+        if self.intervals.len() == TEMPO_INTERVALS {
+            self.intervals.pop_front();
+        }
+        self.intervals.push_back(interval);
+
+        // Trimmed mean: the middle half of the sorted intervals, or the median of a few
+        let mut sorted: Vec<f64> = self.intervals.iter().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let average = if sorted.len() > 3 {
+            let (start, end) = (sorted.len() / 4, 3 * sorted.len() / 4);
+            sorted[start..end].iter().sum::<f64>() / (end - start) as f64
+        } else {
+            sorted[sorted.len() / 2]
+        };
+        self.bpm = Some(60.0 / average);
+    }
+}
+
+/// Splits captured audio into band energies with an FFT over the most recent
+/// `FFT_SIZE` mono samples, once every `FFT_HOP` new samples. Each band is normalized
+/// to its own decaying peak, so the values stay in 0.0-1.0 whatever the input gain.
+/// Everything the audio callback needs is allocated up front.
+pub struct SpectrumAnalyzer {
+    window: Vec<f32>, // Hann window
+    twiddles: Vec<(f32, f32)>, // (cos, sin) of -2πk/FFT_SIZE for k < FFT_SIZE/2
+    bin_bands: Vec<Option<usize>>, // Band of each FFT bin below Nyquist
+    samples: VecDeque<f32>,
+    re: Vec<f32>,
+    im: Vec<f32>,
+    since_analysis: usize, // New samples since the last FFT
+    bands: Option<[f32; BAND_COUNT]>, // Last normalized result
+    band_peaks: [f32; BAND_COUNT],
+}
+
+impl SpectrumAnalyzer {
+    pub fn new(sample_rate: u32) -> Self {
+        let window = (0..FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / FFT_SIZE as f32).cos())
+            .collect();
+        let twiddles = (0..FFT_SIZE / 2)
+            .map(|k| {
+                let (sin, cos) = (-std::f32::consts::TAU * k as f32 / FFT_SIZE as f32).sin_cos();
+                (cos, sin)
+            })
+            .collect();
+        let bin_hz = sample_rate.max(1) as f32 / FFT_SIZE as f32;
+        let bin_bands = (0..FFT_SIZE / 2)
+            .map(|bin| {
+                let hz = bin as f32 * bin_hz;
+                // Bin 0 is DC
+                (bin > 0).then(|| BAND_EDGES_HZ.windows(2).position(|edge| hz >= edge[0] && hz < edge[1])).flatten()
+            })
+            .collect();
+        Self {
+            window,
+            twiddles,
+            bin_bands,
+            samples: VecDeque::with_capacity(FFT_SIZE),
+            re: vec![0.0; FFT_SIZE],
+            im: vec![0.0; FFT_SIZE],
+            since_analysis: 0,
+            bands: None,
+            band_peaks: [MIN_BAND_PEAK; BAND_COUNT],
+        }
+    }
+
+    /// Add interleaved samples (`channels` per frame, downmixed to mono) and return the
+    /// normalized band energies, or None until a full window has been captured. Bands
+    /// are recomputed once `FFT_HOP` samples have arrived; until then the last are returned.
+    pub fn push(&mut self, data: &[f32], channels: usize) -> Option<[f32; BAND_COUNT]> {
+        for frame in data.chunks(channels.max(1)) {
+            if self.samples.len() == FFT_SIZE {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(frame.iter().sum::<f32>() / frame.len() as f32);
+            self.since_analysis += 1;
+        }
+        if self.samples.len() < FFT_SIZE {
+            return None;
+        }
+        if self.bands.is_some() && self.since_analysis < FFT_HOP {
+            return self.bands;
+        }
+        self.since_analysis = 0;
+
+        let energies = self.band_energies();
+        let mut bands = [0.0; BAND_COUNT];
+        for ((band, peak), energy) in bands.iter_mut().zip(self.band_peaks.iter_mut()).zip(energies) {
+            *peak = (*peak * BAND_PEAK_DECAY).max(energy).max(MIN_BAND_PEAK);
+            *band = (energy / *peak).clamp(0.0, 1.0);
+        }
+        self.bands = Some(bands);
+        self.bands
+    }
+
+    /// Amplitude in each band of the current window: about 1.0 for a full-scale sine
+    fn band_energies(&mut self) -> [f32; BAND_COUNT] {
+        for ((re, sample), w) in self.re.iter_mut().zip(&self.samples).zip(&self.window) {
+            *re = sample * w;
+        }
+        self.im.fill(0.0);
+        fft(&mut self.re, &mut self.im, &self.twiddles);
+
+        // Hann window gain is 0.5; the factor 2 folds in the mirrored negative frequencies
+        let scale = 2.0 / (FFT_SIZE as f32 * 0.5);
+        let mut power = [0.0f32; BAND_COUNT];
+        for (bin, band) in self.bin_bands.iter().enumerate() {
+            let Some(band) = *band else { continue };
+            let magnitude = (self.re[bin] * self.re[bin] + self.im[bin] * self.im[bin]).sqrt() * scale;
+            power[band] += magnitude * magnitude;
+        }
+        // A windowed sine spreads over a few bins; Hann's energy is 1.5x its peak bin's
+        power.map(|p| (p / 1.5).sqrt())
+    }
+}
+
+/// In-place iterative radix-2 FFT; the length must be a power of two and `twiddles`
+/// must hold (cos, sin) of -2πk/n for k < n/2
+fn fft(re: &mut [f32], im: &mut [f32], twiddles: &[(f32, f32)]) {
+    let n = re.len();
+    debug_assert!(n.is_power_of_two() && im.len() == n && twiddles.len() == n / 2);
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        // Twiddle k of this stage is twiddle k * stride of the full-size table
+        let stride = n / len;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (cos, sin) = twiddles[k * stride];
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Names of the capture devices on the default host, for choosing `AudioConfig::input_device`
+pub fn input_device_names() -> Vec<String> {
+    match cpal::default_host().input_devices() {
+        Ok(devices) => devices.filter_map(|d| d.name().ok()).collect(),
+        Err(e) => {
+            log::warn!("Failed to list audio input devices: {}", e);
+            Vec::new()
+        }
+    }
 }
 
 impl AudioListener {
-    pub fn new() -> Option<Self> {
+    /// Capture from the input named `device_name`, or the system default when None.
+    /// A named device that can't be found falls back to the default with a warning.
+    pub fn new(device_name: Option<&str>) -> Option<Self> {
         let host = cpal::default_host();
-        let device = host.default_input_device()?;
+        let named = device_name.and_then(|name| {
+            let found = host.input_devices().ok()?.find(|d| d.name().is_ok_and(|n| n == name));
+            if found.is_none() {
+                log::warn!("Audio input '{}' not found, using the default input", name);
+            }
+            found
+        });
+        let device = match named {
+            Some(device) => device,
+            None => host.default_input_device()?,
+        };
         let config = device.default_input_config().ok()?;
+        let mut spectrum = SpectrumAnalyzer::new(config.sample_rate().0);
+        let channels = config.channels() as usize;
 
         let peak_flag = Arc::new(AtomicBool::new(false));
         let volume_level = Arc::new(Mutex::new(0.0));
+        let features = Arc::new(Mutex::new(AudioFeatureBuffer::new(FEATURE_BUFFER_FRAMES)));
 
         let peak_clone = peak_flag.clone();
         let vol_clone = volume_level.clone();
+        let features_clone = features.clone();
 
         let err_fn = |err| eprintln!("Audio stream error: {}", err);
 
         let stream = match config.sample_format() {
             cpal::SampleFormat::F32 => device.build_input_stream(
                 &config.into(),
-                move |data: &[f32], _: &_| check_audio(data, &peak_clone, &vol_clone),
+                move |data: &[f32], _: &_| check_audio(data, channels, &mut spectrum, &peak_clone, &vol_clone, &features_clone),
                 err_fn
             ).ok()?,
             _ => return None, // Only support F32 for simplicity right now
@@ -37,11 +478,19 @@ impl AudioListener {
             _stream: stream,
             peak_detected: peak_flag,
             current_volume: volume_level,
+            features,
         })
     }
 }
 
-fn check_audio(data: &[f32], peak_flag: &Arc<AtomicBool>, vol_lock: &Arc<Mutex<f32>>) {
+fn check_audio(
+    data: &[f32],
+    channels: usize,
+    spectrum: &mut SpectrumAnalyzer,
+    peak_flag: &Arc<AtomicBool>,
+    vol_lock: &Arc<Mutex<f32>>,
+    features: &Arc<Mutex<AudioFeatureBuffer>>,
+) {
     // Guard against empty buffers
     if data.is_empty() {
         return;
@@ -54,10 +503,18 @@ fn check_audio(data: &[f32], peak_flag: &Arc<AtomicBool>, vol_lock: &Arc<Mutex<f
     }
     let rms = (sum_squares / data.len() as f32).sqrt();
 
+    // Band energies; zero until the first full FFT window
+    let bands = spectrum.push(data, channels).unwrap_or_default();
+
     // Update volume for UI
     if let Ok(mut v) = vol_lock.try_lock() {
         // Smooth decay for visual
         *v = (*v * 0.9) + (rms * 0.1); 
+
+        // Timestamp the smoothed value so the engine can apply latency compensation
+        if let Ok(mut buffer) = features.try_lock() {
+            buffer.push(AudioFeatureFrame { time: Instant::now(), volume: *v, bands });
+        }
     }
 
     // 2. Transient Detection (Simple Threshold)
@@ -75,3 +532,115 @@ fn check_audio(data: &[f32], peak_flag: &Arc<AtomicBool>, vol_lock: &Arc<Mutex<f
         peak_flag.store(false, Ordering::Relaxed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(hz: f32, amplitude: f32, sample_rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (std::f32::consts::TAU * hz * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_spectrum_bands_follow_tone_frequency() {
+        let mut analyzer = SpectrumAnalyzer::new(48_000);
+        assert_eq!(analyzer.push(&sine(100.0, 0.5, 48_000, FFT_SIZE / 2), 1), None);
+
+        // Each tone lands in its own band, whose measured amplitude matches the sine
+        for (hz, band) in [(100.0, 0), (1000.0, 1), (8000.0, 2)] {
+            let mut analyzer = SpectrumAnalyzer::new(48_000);
+            let bands = analyzer.push(&sine(hz, 0.5, 48_000, FFT_SIZE), 1).unwrap();
+            assert_eq!(bands[band], 1.0, "{} Hz: {:?}", hz, bands);
+            let energies = analyzer.band_energies();
+            assert!((energies[band] - 0.5).abs() < 0.05, "{} Hz: {:?}", hz, energies);
+            for (other, energy) in energies.iter().enumerate().filter(|(i, _)| *i != band) {
+                assert!(*energy < 0.05, "{} Hz leaked into band {}: {:?}", hz, other, energies);
+            }
+        }
+
+        // Stereo is downmixed; a quieter tone reads relative to the recent peak
+        let mut analyzer = SpectrumAnalyzer::new(48_000);
+        let stereo: Vec<f32> = sine(1000.0, 0.5, 48_000, FFT_SIZE).into_iter().flat_map(|s| [s, s]).collect();
+        analyzer.push(&stereo, 2).unwrap();
+        let bands = analyzer.push(&sine(1000.0, 0.25, 48_000, FFT_SIZE), 1).unwrap();
+        assert!((bands[1] - 0.5).abs() < 0.05, "{:?}", bands);
+    }
+
+    #[test]
+    fn test_spectrum_analyzes_once_per_hop() {
+        let mut analyzer = SpectrumAnalyzer::new(48_000);
+        let loud = analyzer.push(&sine(1000.0, 0.5, 48_000, FFT_SIZE), 1).unwrap();
+
+        // Short callbacks of silence reuse the last bands until a hop has arrived
+        let quiet = vec![0.0; FFT_HOP / 4];
+        for _ in 0..3 {
+            assert_eq!(analyzer.push(&quiet, 1), Some(loud));
+        }
+        let bands = analyzer.push(&quiet, 1).unwrap();
+        assert!(bands[1] < loud[1], "{:?}", bands);
+        assert_eq!(analyzer.push(&quiet, 1), Some(bands));
+    }
+
+    /// Feed a click track sampled every 10 ms (clicks last 30 ms) and return the times of
+    /// the beats it reports, in seconds
+    fn beats_for_clicks(clicks: &[f64], seconds: f64, flywheel: bool) -> (Vec<f64>, BeatDetector) {
+        let start = Instant::now();
+        let mut detector = BeatDetector::new();
+        let mut beats = Vec::new();
+        for frame in 0..(seconds * 100.0) as u64 {
+            let t = frame as f64 / 100.0;
+            let clicking = clicks.iter().any(|c| t >= *c - 1e-9 && t < c + 0.03 - 1e-9);
+            let level = if clicking { 0.8 } else { 0.02 };
+            let update = detector.process(start + Duration::from_millis(frame * 10), Some(level), 0.5, flywheel);
+            if update.beat {
+                beats.push(t);
+            }
+        }
+        (beats, detector)
+    }
+
+    #[test]
+    fn test_beat_detector_follows_click_track() {
+        // 120 BPM for 8 seconds
+        let clicks: Vec<f64> = (0..16).map(|i| 0.25 + i as f64 * 0.5).collect();
+        let (beats, detector) = beats_for_clicks(&clicks, 8.2, false);
+        assert_eq!(beats.len(), 16);
+        assert!(beats.iter().zip(&clicks).all(|(b, c)| (b - c).abs() < 0.011), "{:?}", beats);
+        assert!((detector.bpm().unwrap() - 120.0).abs() < 0.5, "{:?}", detector.bpm());
+
+        // Four clicks missing: only the flywheel fills them in, on the tempo grid
+        let gapped: Vec<f64> = clicks.iter().copied().filter(|c| !(4.0..6.0).contains(c)).collect();
+        let (beats, detector) = beats_for_clicks(&gapped, 8.2, false);
+        assert_eq!(beats.len(), 12);
+        assert!((detector.bpm().unwrap() - 120.0).abs() < 0.5);
+        let (beats, _) = beats_for_clicks(&gapped, 8.2, true);
+        assert_eq!(beats.len(), 16);
+        assert!(beats.iter().zip(&clicks).all(|(b, c)| (b - c).abs() < 0.011), "{:?}", beats);
+
+        // A hit landing just after its predicted beat re-anchors instead of doubling
+        let late: Vec<f64> = gapped.iter().map(|c| if *c > 6.0 { c + 0.04 } else { *c }).collect();
+        let (beats, _) = beats_for_clicks(&late, 8.25, true);
+        assert_eq!(beats.len(), 16, "{:?}", beats);
+        assert!((beats[13] - 6.79).abs() < 0.011, "{:?}", beats);
+
+        // Stalled input: only predicted beats, and none without a tempo yet
+        let mut detector = BeatDetector::new();
+        assert_eq!(detector.process(Instant::now(), None, 0.5, true), BeatUpdate::default());
+    }
+
+    #[test]
+    fn test_sample_bands_uses_frame_before_time() {
+        let start = Instant::now();
+        let mut buffer = AudioFeatureBuffer::new(8);
+        assert_eq!(buffer.sample_bands(start), None);
+        for (ms, level) in [(0, 0.1), (10, 0.5), (20, 0.9)] {
+            buffer.push(AudioFeatureFrame { time: start + Duration::from_millis(ms), volume: level, bands: [level; BAND_COUNT] });
+        }
+        assert_eq!(buffer.sample_bands(start + Duration::from_millis(15)), Some([0.5; BAND_COUNT]));
+        assert_eq!(buffer.sample_bands(start + Duration::from_millis(20)), Some([0.9; BAND_COUNT]));
+        assert_eq!(buffer.sample_bands(start + Duration::from_millis(500)), Some([0.9; BAND_COUNT]));
+        assert_eq!(buffer.sample_bands(start.checked_sub(Duration::from_millis(5)).unwrap()), Some([0.1; BAND_COUNT]));
+    }
+}
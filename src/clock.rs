@@ -0,0 +1,81 @@
+//! Render time source.
+//!
+//! Effects never read the wall clock themselves: the engine asks its `Clock` for the
+//! current time once per frame and hands it down as `t` / `beat`. Swapping in a
+//! `MockClock` lets tests render any effect at exact timestamps.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+pub trait Clock: Send {
+    /// Milliseconds since the clock started
+    fn now_ms(&self) -> u64;
+
+    /// `now_ms` in seconds, the unit effects take as `t`
+    fn now_secs(&self) -> f32 {
+        self.now_ms() as f32 / 1000.0
+    }
+}
+
+/// Wall-clock time since creation
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+}
+
+/// Manually driven clock. Clones share the same time, so a test can hand one to the
+/// engine and keep another to advance.
+#[derive(Clone, Default)]
+pub struct MockClock {
+    ms: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self { ms: Arc::new(AtomicU64::new(start_ms)) }
+    }
+
+    pub fn set(&self, ms: u64) {
+        self.ms.store(ms, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.ms.fetch_add(ms, Ordering::Relaxed);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_ms(&self) -> u64 {
+        self.ms.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_is_shared_between_clones() {
+        let clock = MockClock::new(250);
+        let engine_side: Box<dyn Clock> = Box::new(clock.clone());
+        assert_eq!(engine_side.now_ms(), 250);
+
+        clock.advance(1000);
+        assert_eq!(engine_side.now_ms(), 1250);
+        assert_eq!(engine_side.now_secs(), 1.25);
+        clock.set(0);
+        assert_eq!(engine_side.now_ms(), 0);
+    }
+}
@@ -0,0 +1,153 @@
+//! Programmatic control of the app.
+//!
+//! Every control surface (Launchpad pads and the UI header) pushes `AppCommand`s into
+//! one `Sender<AppCommand>` (see `MyApp::command_sender`). The UI thread owns `AppState`
+//! and the engine and drains the channel once per frame, so commands from different
+//! surfaces are applied in arrival order and never race. Future network remotes (OSC,
+//! TCP, WebSocket) should push commands through the same sender.
+
+use crate::engine::LightingEngine;
+use crate::model::AppState;
+
+/// Range of the master speed slider
+pub const SPEED_RANGE: std::ops::RangeInclusive<f32> = 0.1..=5.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AppCommand {
+    /// Activate a scene as the top layer (`AppState::trigger_scene`): its exclusivity group
+    /// is replaced, and an ungrouped scene that is already active toggles off
+    TriggerScene(u64),
+    /// Remove a scene from the active layers (`AppState::deactivate_scene`)
+    DeactivateScene(u64),
+    /// Momentary pad pressed: activate the scene until its release (`AppState::hold_scene`)
+    HoldScene(u64),
+    /// Momentary pad released: restore the scenes active before the hold (`AppState::release_scene`)
+    ReleaseScene(u64),
+    /// Deactivate every scene; loose masks render instead
+    ClearScenes,
+    /// Latch blackout on or off, None toggles. Saved (`AppState::blackout`) so a restart stays dark.
+    SetBlackout(Option<bool>),
+    /// Hold or release the current frame (`AppState::frozen`), None toggles. Never saved.
+    SetFrozen(Option<bool>),
+    /// Stop or resume sACN output under `NetworkConfig::pause_policy`, None toggles. Never saved.
+    SetPaused(Option<bool>),
+    /// Master speed multiplier on the engine, clamped to `SPEED_RANGE`. Never saved.
+    SetSpeed(f32),
+    /// Master brightness (`AppState::master_brightness`), clamped to 0.0-1.0
+    SetMasterBrightness(f32),
+    /// Fixed tempo (`AppState::bpm`); None follows Link / audio again
+    SetBpm(Option<f64>),
+    /// Set one param on a Global scene's effect, overriding its preset if it has one
+    SetEffectParam { scene_id: u64, effect: usize, param: String, value: serde_json::Value },
+}
+
+impl AppCommand {
+    /// Apply the command. Returns true when saved state changed and should be persisted.
+    pub fn apply(self, state: &mut AppState, engine: &mut LightingEngine) -> bool {
+        match self {
+            AppCommand::SetBlackout(on) => {
+                let on = on.unwrap_or(!engine.is_blackout());
+                engine.set_blackout(state, on);
+                state.blackout = on;
+                true
+            }
+            AppCommand::SetPaused(on) => {
+                let on = on.unwrap_or(!engine.is_paused());
+                engine.set_paused(state, on);
+                false
+            }
+            AppCommand::SetSpeed(speed) => {
+                engine.speed = speed.clamp(*SPEED_RANGE.start(), *SPEED_RANGE.end());
+                false
+            }
+            command => command.apply_to_state(state),
+        }
+    }
+
+    /// The part of `apply` that only touches `AppState`
+    fn apply_to_state(self, state: &mut AppState) -> bool {
+        match self {
+            AppCommand::TriggerScene(id) => {
+                state.trigger_scene(id);
+                false
+            }
+            AppCommand::DeactivateScene(id) => {
+                state.deactivate_scene(id);
+                false
+            }
+            AppCommand::HoldScene(id) => {
+                state.hold_scene(id);
+                false
+            }
+            AppCommand::ReleaseScene(id) => {
+                state.release_scene(id);
+                false
+            }
+            AppCommand::ClearScenes => {
+                state.clear_active_scenes();
+                false
+            }
+            AppCommand::SetFrozen(on) => {
+                state.frozen = on.unwrap_or(!state.frozen);
+                false
+            }
+            AppCommand::SetBpm(bpm) => {
+                state.bpm = bpm.filter(|bpm| bpm.is_finite() && *bpm > 0.0);
+                true
+            }
+            AppCommand::SetMasterBrightness(level) => {
+                state.master_brightness = if level.is_finite() { level.clamp(0.0, 1.0) } else { 1.0 };
+                true
+            }
+            AppCommand::SetEffectParam { scene_id, effect, param, value } => {
+                let Some(config) = state.scenes.iter_mut()
+                    .find(|s| s.id == scene_id)
+                    .and_then(|s| s.global_effects.get_mut(effect)) else {
+                    log::warn!("Ignoring param '{}' for missing effect {} of scene {}", param, effect, scene_id);
+                    return false;
+                };
+                config.effect.params.insert(param, value);
+                true
+            }
+            AppCommand::SetBlackout(_) | AppCommand::SetPaused(_) | AppCommand::SetSpeed(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_commands() {
+        let mut state = AppState::default();
+        assert!(!AppCommand::SetFrozen(None).apply_to_state(&mut state));
+        assert!(state.frozen);
+        AppCommand::SetFrozen(Some(true)).apply_to_state(&mut state);
+        assert!(state.frozen);
+
+        assert!(AppCommand::SetBpm(Some(128.0)).apply_to_state(&mut state));
+        assert_eq!(state.bpm, Some(128.0));
+        AppCommand::SetBpm(Some(f64::NAN)).apply_to_state(&mut state);
+        assert_eq!(state.bpm, None);
+
+        assert!(AppCommand::SetMasterBrightness(0.4).apply_to_state(&mut state));
+        assert_eq!(state.master_brightness, 0.4);
+        AppCommand::SetMasterBrightness(3.0).apply_to_state(&mut state);
+        assert_eq!(state.master_brightness, 1.0);
+
+        // Missing scenes and effects are ignored
+        AppCommand::TriggerScene(7).apply_to_state(&mut state);
+        assert!(state.active_scene_ids.is_empty());
+        let set_param = AppCommand::SetEffectParam { scene_id: 7, effect: 0, param: "speed".into(), value: 2.0.into() };
+        assert!(!set_param.apply_to_state(&mut state));
+    }
+
+    #[test]
+    fn test_clear_scenes_command() {
+        let mut state = AppState { active_scene_ids: vec![1, 2], selected_scene_id: Some(2), ..Default::default() };
+        assert!(!AppCommand::ClearScenes.apply_to_state(&mut state));
+        assert!(state.active_scene_ids.is_empty());
+        assert_eq!(state.selected_scene_id, None);
+    }
+}
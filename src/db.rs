@@ -38,7 +38,20 @@ impl Database {
                 y REAL NOT NULL,
                 spacing REAL NOT NULL,
                 flipped INTEGER NOT NULL DEFAULT 0,
-                color_order TEXT NOT NULL DEFAULT 'RGB'
+                color_order TEXT NOT NULL DEFAULT 'RGB',
+                notes TEXT NOT NULL DEFAULT '',
+                leds_per_meter REAL,
+                length_m REAL,
+                min_brightness INTEGER NOT NULL DEFAULT 0,
+                brightness REAL NOT NULL DEFAULT 1.0,
+                white_mode TEXT NOT NULL DEFAULT 'Add',
+                pre_channels_json TEXT,
+                post_channels_json TEXT,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                mirror_source INTEGER,
+                mirror_reversed INTEGER NOT NULL DEFAULT 0,
+                z REAL,
+                channels_per_pixel INTEGER NOT NULL DEFAULT 0
             );
             CREATE INDEX IF NOT EXISTS idx_strips_universe ON strips(universe);
 
@@ -47,7 +60,9 @@ impl Database {
                 mask_type TEXT NOT NULL,
                 x REAL NOT NULL,
                 y REAL NOT NULL,
-                params_json TEXT NOT NULL
+                params_json TEXT NOT NULL,
+                modulation_json TEXT,
+                z REAL
             );
 
             CREATE TABLE IF NOT EXISTS scenes (
@@ -59,7 +74,14 @@ impl Database {
                 global_effects_json TEXT,
                 launchpad_btn INTEGER,
                 launchpad_is_cc INTEGER NOT NULL DEFAULT 0,
-                launchpad_color INTEGER
+                launchpad_color INTEGER,
+                launchpad_momentary INTEGER NOT NULL DEFAULT 0,
+                display_order INTEGER NOT NULL DEFAULT 0,
+                notes TEXT NOT NULL DEFAULT '',
+                exclusive_group TEXT NOT NULL DEFAULT 'Main',
+                layer INTEGER NOT NULL DEFAULT 0,
+                opacity REAL NOT NULL DEFAULT 1.0,
+                blend_mode TEXT NOT NULL DEFAULT ''
             );
             CREATE INDEX IF NOT EXISTS idx_scenes_name ON scenes(name);
 
@@ -70,7 +92,9 @@ impl Database {
                 x REAL NOT NULL,
                 y REAL NOT NULL,
                 params_json TEXT NOT NULL,
+                modulation_json TEXT,
                 display_order INTEGER NOT NULL DEFAULT 0,
+                z REAL,
                 PRIMARY KEY (scene_id, mask_id),
                 FOREIGN KEY (scene_id) REFERENCES scenes(id) ON DELETE CASCADE
             );
@@ -90,6 +114,51 @@ impl Database {
                 audio_sensitivity REAL NOT NULL DEFAULT 0.5,
                 layout_locked INTEGER NOT NULL DEFAULT 0,
                 midi_enabled INTEGER NOT NULL DEFAULT 1,
+                network_routes_json TEXT,
+                midi_init_json TEXT,
+                preview_port INTEGER,
+                bpm REAL,
+                tap_btn INTEGER,
+                tap_is_cc INTEGER NOT NULL DEFAULT 0,
+                panic_btn INTEGER,
+                panic_is_cc INTEGER NOT NULL DEFAULT 0,
+                presets_json TEXT,
+                startup_fade_ms INTEGER NOT NULL DEFAULT 0,
+                state_version INTEGER NOT NULL DEFAULT 0,
+                freeze_btn INTEGER,
+                freeze_is_cc INTEGER NOT NULL DEFAULT 0,
+                freeze_resume_phase INTEGER NOT NULL DEFAULT 0,
+                canvas_width_m REAL,
+                chains_json TEXT,
+                shuffle_params_json TEXT,
+                shuffle_interval_ms INTEGER,
+                active_scene_ids_json TEXT,
+                midi_heartbeat_ms INTEGER,
+                render_budget_ms REAL,
+                idle_scene_id INTEGER,
+                idle_timeout_ms INTEGER,
+                output_threads INTEGER NOT NULL DEFAULT 0,
+                midi_min_velocity INTEGER NOT NULL DEFAULT 1,
+                master_brightness REAL NOT NULL DEFAULT 1.0,
+                blackout INTEGER NOT NULL DEFAULT 0,
+                network_probe_ms INTEGER,
+                quantize TEXT NOT NULL DEFAULT '',
+                max_flash_hz REAL,
+                no_strobe INTEGER NOT NULL DEFAULT 0,
+                pause_policy TEXT NOT NULL DEFAULT '',
+                network_protocol TEXT NOT NULL DEFAULT 'sacn',
+                network_max_fps INTEGER NOT NULL DEFAULT 44,
+                network_unicast_ips_json TEXT,
+                transition_ms INTEGER NOT NULL DEFAULT 0,
+                beat_pad INTEGER,
+                audio_input_device TEXT,
+                network_keepalive_ms INTEGER,
+                midi_ports_json TEXT,
+                midi_cc_bindings_json TEXT,
+                midi_port_filter_json TEXT,
+                midi_debounce_ms INTEGER NOT NULL DEFAULT 30,
+                gamma REAL NOT NULL DEFAULT 2.2,
+                manual_addressing INTEGER NOT NULL DEFAULT 0,
                 FOREIGN KEY (selected_scene_id) REFERENCES scenes(id) ON DELETE SET NULL
             );
 
@@ -108,7 +177,77 @@ impl Database {
         // Ignore error if column already exists
         let _ = self.conn.execute("ALTER TABLE scenes ADD COLUMN global_effects_json TEXT", []);
         let _ = self.conn.execute("ALTER TABLE scenes ADD COLUMN category TEXT NOT NULL DEFAULT 'Uncategorized'", []);
+        let _ = self.conn.execute("ALTER TABLE scenes ADD COLUMN display_order INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE scenes ADD COLUMN notes TEXT NOT NULL DEFAULT ''", []);
+        let _ = self.conn.execute("ALTER TABLE scenes ADD COLUMN exclusive_group TEXT NOT NULL DEFAULT 'Main'", []);
+        let _ = self.conn.execute("ALTER TABLE scenes ADD COLUMN layer INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE scenes ADD COLUMN opacity REAL NOT NULL DEFAULT 1.0", []);
+        let _ = self.conn.execute("ALTER TABLE scenes ADD COLUMN blend_mode TEXT NOT NULL DEFAULT ''", []);
+        let _ = self.conn.execute("ALTER TABLE scenes ADD COLUMN launchpad_momentary INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN notes TEXT NOT NULL DEFAULT ''", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN leds_per_meter REAL", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN length_m REAL", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN min_brightness INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN pre_channels_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN post_channels_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN enabled INTEGER NOT NULL DEFAULT 1", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN mirror_source INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN mirror_reversed INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE masks ADD COLUMN modulation_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE scene_masks ADD COLUMN modulation_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN z REAL", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN channels_per_pixel INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN brightness REAL NOT NULL DEFAULT 1.0", []);
+        let _ = self.conn.execute("ALTER TABLE strips ADD COLUMN white_mode TEXT NOT NULL DEFAULT 'Add'", []);
+        let _ = self.conn.execute("ALTER TABLE masks ADD COLUMN z REAL", []);
+        let _ = self.conn.execute("ALTER TABLE scene_masks ADD COLUMN z REAL", []);
         let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN midi_enabled INTEGER NOT NULL DEFAULT 1", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN network_routes_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN midi_init_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN preview_port INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN bpm REAL", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN tap_btn INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN tap_is_cc INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN panic_btn INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN panic_is_cc INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN presets_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN startup_fade_ms INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN state_version INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN freeze_btn INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN freeze_is_cc INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN freeze_resume_phase INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN canvas_width_m REAL", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN chains_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN shuffle_params_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN shuffle_interval_ms INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN active_scene_ids_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN midi_heartbeat_ms INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN render_budget_ms REAL", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN idle_scene_id INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN idle_timeout_ms INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN output_threads INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN midi_min_velocity INTEGER NOT NULL DEFAULT 1", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN master_brightness REAL NOT NULL DEFAULT 1.0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN blackout INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN network_probe_ms INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN quantize TEXT NOT NULL DEFAULT ''", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN max_flash_hz REAL", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN no_strobe INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN pause_policy TEXT NOT NULL DEFAULT ''", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN midi_ports_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN midi_cc_bindings_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN midi_port_filter_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN midi_debounce_ms INTEGER NOT NULL DEFAULT 30", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN gamma REAL NOT NULL DEFAULT 2.2", []);
+        // Rigs saved before auto-addressing existed were addressed by hand; keep it that way
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN manual_addressing INTEGER NOT NULL DEFAULT 1", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN network_protocol TEXT NOT NULL DEFAULT 'sacn'", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN network_max_fps INTEGER NOT NULL DEFAULT 44", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN network_keepalive_ms INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN network_unicast_ips_json TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN transition_ms INTEGER NOT NULL DEFAULT 0", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN beat_pad INTEGER", []);
+        let _ = self.conn.execute("ALTER TABLE app_config ADD COLUMN audio_input_device TEXT", []);
 
         Ok(())
     }
@@ -144,108 +283,21 @@ impl Database {
 
         // Migrate strips
         for strip in &state.strips {
-            tx.execute(
-                "INSERT INTO strips (id, universe, start_channel, pixel_count, x, y, spacing, flipped, color_order)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    strip.id as i64,
-                    strip.universe,
-                    strip.start_channel,
-                    strip.pixel_count,
-                    strip.x,
-                    strip.y,
-                    strip.spacing,
-                    if strip.flipped { 1 } else { 0 },
-                    strip.color_order,
-                ],
-            )?;
+            insert_strip(&tx, strip)?;
         }
 
         // Migrate global masks
         for mask in &state.masks {
-            let params_json = serde_json::to_string(&mask.params)?;
-            tx.execute(
-                "INSERT INTO masks (id, mask_type, x, y, params_json)
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
-                params![mask.id as i64, mask.mask_type, mask.x, mask.y, params_json],
-            )?;
+            insert_mask(&tx, mask)?;
         }
 
         // Migrate scenes
-        for scene in &state.scenes {
-            let global_effect_json = scene.global.as_ref()
-                .map(|g| serde_json::to_string(g))
-                .transpose()?;
-            let global_effects_json = serde_json::to_string(&scene.global_effects)?;
-
-            tx.execute(
-                "INSERT INTO scenes (id, name, kind, category, global_effect_json, global_effects_json, launchpad_btn, launchpad_is_cc, launchpad_color)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    scene.id as i64,
-                    scene.name,
-                    scene.kind,
-                    scene.category,
-                    global_effect_json,
-                    global_effects_json,
-                    scene.launchpad_btn.map(|v| v as i64),
-                    if scene.launchpad_is_cc { 1 } else { 0 },
-                    scene.launchpad_color.map(|v| v as i64),
-                ],
-            )?;
-
-            // Migrate scene masks
-            for (idx, mask) in scene.masks.iter().enumerate() {
-                let params_json = serde_json::to_string(&mask.params)?;
-                tx.execute(
-                    "INSERT INTO scene_masks (scene_id, mask_id, mask_type, x, y, params_json, display_order)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                    params![
-                        scene.id as i64,
-                        mask.id as i64,
-                        mask.mask_type,
-                        mask.x,
-                        mask.y,
-                        params_json,
-                        idx as i64,
-                    ],
-                )?;
-            }
+        for (idx, scene) in state.scenes.iter().enumerate() {
+            insert_scene(&tx, scene, idx as i64)?;
         }
 
         // Migrate app config
-        tx.execute(
-            "UPDATE app_config SET
-                selected_scene_id = ?1,
-                network_use_multicast = ?2,
-                network_unicast_ip = ?3,
-                network_universe = ?4,
-                bind_address = ?5,
-                mode = ?6,
-                effect = ?7,
-                audio_latency_ms = ?8,
-                audio_use_flywheel = ?9,
-                audio_hybrid_sync = ?10,
-                audio_sensitivity = ?11,
-                layout_locked = ?12,
-                midi_enabled = ?13
-             WHERE id = 1",
-            params![
-                state.selected_scene_id.map(|id| id as i64),
-                if state.network.use_multicast { 1 } else { 0 },
-                state.network.unicast_ip,
-                state.network.universe,
-                state.bind_address,
-                state.mode,
-                state.effect,
-                state.audio.latency_ms,
-                if state.audio.use_flywheel { 1 } else { 0 },
-                if state.audio.hybrid_sync { 1 } else { 0 },
-                state.audio.sensitivity,
-                if state.layout_locked { 1 } else { 0 },
-                if state.midi_enabled { 1 } else { 0 },
-            ],
-        )?;
+        write_app_config(&tx, state)?;
 
         tx.commit()?;
         Ok(())
@@ -255,45 +307,61 @@ impl Database {
     pub fn load_state(&self) -> Result<AppState> {
         // Load strips
         let mut stmt = self.conn.prepare(
-            "SELECT id, universe, start_channel, pixel_count, x, y, spacing, flipped, color_order FROM strips ORDER BY id"
+            "SELECT id, universe, start_channel, pixel_count, x, y, spacing, flipped, color_order, notes, leds_per_meter, length_m, min_brightness, pre_channels_json, post_channels_json, enabled, mirror_source, mirror_reversed, z, channels_per_pixel, brightness, white_mode FROM strips ORDER BY id"
         )?;
         let strips = stmt.query_map([], |row| {
             let pixel_count: usize = row.get(3)?;
             Ok(PixelStrip {
                 id: row.get::<_, i64>(0)? as u64,
+                enabled: row.get::<_, i64>(15)? != 0,
                 universe: row.get(1)?,
                 start_channel: row.get(2)?,
                 pixel_count,
                 x: row.get(4)?,
                 y: row.get(5)?,
+                z: row.get(18)?,
                 spacing: row.get(6)?,
+                leds_per_meter: row.get(10)?,
+                length_m: row.get(11)?,
+                min_brightness: row.get(12)?,
+                brightness: row.get::<_, f32>(20)?.clamp(0.0, 1.0),
+                pre_channels: parse_channels(row.get(13)?)?,
+                post_channels: parse_channels(row.get(14)?)?,
                 flipped: row.get::<_, i64>(7)? != 0,
                 color_order: row.get(8)?,
-                data: vec![[0, 0, 0]; pixel_count], // Initialize with black pixels
+                white_mode: row.get(21)?,
+                channels_per_pixel: row.get(19)?,
+                notes: row.get(9)?,
+                mirror_source: row.get::<_, Option<i64>>(16)?.map(|id| id as u64),
+                mirror_reversed: row.get::<_, i64>(17)? != 0,
+                data: Vec::new(), // Allocated by AppState::init_runtime
             })
         })?.collect::<Result<Vec<_>, _>>()?;
 
         // Load global masks
         let mut stmt = self.conn.prepare(
-            "SELECT id, mask_type, x, y, params_json FROM masks ORDER BY id"
+            "SELECT id, mask_type, x, y, params_json, modulation_json, z FROM masks ORDER BY id"
         )?;
         let masks = stmt.query_map([], |row| {
             let params_json: String = row.get(4)?;
             let params: HashMap<String, serde_json::Value> = serde_json::from_str(&params_json)
                 .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            let modulation = parse_modulation(row.get(5)?)?;
 
             Ok(Mask {
                 id: row.get::<_, i64>(0)? as u64,
                 mask_type: row.get(1)?,
                 x: row.get(2)?,
                 y: row.get(3)?,
+                z: row.get(6)?,
                 params,
+                modulation,
             })
         })?.collect::<Result<Vec<_>, _>>()?;
 
         // Load scenes
         let mut stmt = self.conn.prepare(
-            "SELECT id, name, kind, category, global_effect_json, global_effects_json, launchpad_btn, launchpad_is_cc, launchpad_color FROM scenes ORDER BY id"
+            "SELECT id, name, kind, category, global_effect_json, global_effects_json, launchpad_btn, launchpad_is_cc, launchpad_color, notes, exclusive_group, layer, opacity, blend_mode, launchpad_momentary FROM scenes ORDER BY display_order, id"
         )?;
         let scene_rows: Vec<_> = stmt.query_map([], |row| {
             Ok((
@@ -306,26 +374,35 @@ impl Database {
                 row.get::<_, Option<i64>>(6)?,
                 row.get::<_, i64>(7)?,
                 row.get::<_, Option<i64>>(8)?,
+                row.get::<_, String>(9)?,
+                row.get::<_, String>(10)?,
+                row.get::<_, i32>(11)?,
+                row.get::<_, f32>(12)?,
+                row.get::<_, String>(13)?,
+                row.get::<_, i64>(14)?,
             ))
         })?.collect::<Result<Vec<_>, _>>()?;
 
         let mut scenes = Vec::new();
-        for (id, name, kind, category, global_json, global_effects_json, launchpad_btn, launchpad_is_cc, launchpad_color) in scene_rows {
+        for (id, name, kind, category, global_json, global_effects_json, launchpad_btn, launchpad_is_cc, launchpad_color, notes, exclusive_group, layer, opacity, blend_mode, launchpad_momentary) in scene_rows {
             // Load scene masks
             let mut stmt = self.conn.prepare(
-                "SELECT mask_id, mask_type, x, y, params_json FROM scene_masks WHERE scene_id = ?1 ORDER BY display_order"
+                "SELECT mask_id, mask_type, x, y, params_json, modulation_json, z FROM scene_masks WHERE scene_id = ?1 ORDER BY display_order"
             )?;
             let scene_masks = stmt.query_map([id as i64], |row| {
                 let params_json: String = row.get(4)?;
                 let params: HashMap<String, serde_json::Value> = serde_json::from_str(&params_json)
                     .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+                let modulation = parse_modulation(row.get(5)?)?;
 
                 Ok(Mask {
                     id: row.get::<_, i64>(0)? as u64,
                     mask_type: row.get(1)?,
                     x: row.get(2)?,
                     y: row.get(3)?,
+                    z: row.get(6)?,
                     params,
+                    modulation,
                 })
             })?.collect::<Result<Vec<_>, _>>()?;
 
@@ -351,6 +428,12 @@ impl Database {
                 launchpad_btn: launchpad_btn.map(|v| v as u8),
                 launchpad_is_cc: launchpad_is_cc != 0,
                 launchpad_color: launchpad_color.map(|v| v as u8),
+                launchpad_momentary: launchpad_momentary != 0,
+                notes,
+                exclusive_group,
+                layer,
+                opacity,
+                blend_mode,
             });
         }
 
@@ -394,28 +477,190 @@ impl Database {
             }
         )?;
 
-        Ok(AppState {
+        // Columns added after the initial schema are read by name
+        let (network_routes_json, midi_init_json, preview_port, bpm, tap_btn, tap_is_cc, panic_btn, panic_is_cc, presets_json, startup_fade_ms, version,
+             freeze_btn, freeze_is_cc, freeze_resume_phase, canvas_width_m, chains_json, shuffle_params_json, shuffle_interval_ms, active_scene_ids_json, midi_heartbeat_ms, render_budget_ms,
+             idle_scene_id, idle_timeout_ms, output_threads, midi_min_velocity, master_brightness, blackout, network_probe_ms, quantize, max_flash_hz, no_strobe, pause_policy, midi_ports_json, midi_cc_bindings_json, midi_port_filter_json, midi_debounce_ms, gamma, manual_addressing, network_protocol, network_max_fps, network_keepalive_ms, network_unicast_ips_json, transition_ms, beat_pad, audio_input_device): (
+            Option<String>, Option<String>, Option<u16>, Option<f64>, Option<u8>, i64, Option<u8>, i64, Option<String>, i64, u32,
+            Option<u8>, i64, i64, Option<f32>, Option<String>, Option<String>, Option<i64>, Option<String>, Option<i64>, Option<f32>,
+            Option<i64>, Option<i64>, i64, i64, f32, i64, Option<i64>, String, Option<f32>, i64, String, Option<String>, Option<String>, Option<String>, i64, f32, i64, String, i64, Option<i64>, Option<String>, i64, Option<u8>, Option<String>
+        ) = self.conn.query_row(
+            "SELECT network_routes_json, midi_init_json, preview_port, bpm, tap_btn, tap_is_cc, panic_btn, panic_is_cc, presets_json,
+                    startup_fade_ms, state_version, freeze_btn, freeze_is_cc, freeze_resume_phase, canvas_width_m,
+                    chains_json, shuffle_params_json, shuffle_interval_ms, active_scene_ids_json, midi_heartbeat_ms, render_budget_ms,
+                    idle_scene_id, idle_timeout_ms, output_threads, midi_min_velocity, master_brightness, blackout, network_probe_ms, quantize, max_flash_hz, no_strobe, pause_policy, midi_ports_json, midi_cc_bindings_json, midi_port_filter_json, midi_debounce_ms, gamma, manual_addressing, network_protocol, network_max_fps, network_keepalive_ms, network_unicast_ips_json, transition_ms, beat_pad, audio_input_device
+             FROM app_config WHERE id = 1",
+            [],
+            |row| Ok((
+                row.get("network_routes_json")?,
+                row.get("midi_init_json")?,
+                row.get("preview_port")?,
+                row.get("bpm")?,
+                row.get("tap_btn")?,
+                row.get("tap_is_cc")?,
+                row.get("panic_btn")?,
+                row.get("panic_is_cc")?,
+                row.get("presets_json")?,
+                row.get("startup_fade_ms")?,
+                row.get("state_version")?,
+                row.get("freeze_btn")?,
+                row.get("freeze_is_cc")?,
+                row.get("freeze_resume_phase")?,
+                row.get("canvas_width_m")?,
+                row.get("chains_json")?,
+                row.get("shuffle_params_json")?,
+                row.get("shuffle_interval_ms")?,
+                row.get("active_scene_ids_json")?,
+                row.get("midi_heartbeat_ms")?,
+                row.get("render_budget_ms")?,
+                row.get("idle_scene_id")?,
+                row.get("idle_timeout_ms")?,
+                row.get("output_threads")?,
+                row.get("midi_min_velocity")?,
+                row.get("master_brightness")?,
+                row.get("blackout")?,
+                row.get("network_probe_ms")?,
+                row.get("quantize")?,
+                row.get("max_flash_hz")?,
+                row.get("no_strobe")?,
+                row.get("pause_policy")?,
+                row.get("midi_ports_json")?,
+                row.get("midi_cc_bindings_json")?,
+                row.get("midi_port_filter_json")?,
+                row.get("midi_debounce_ms")?,
+                row.get("gamma")?,
+                row.get("manual_addressing")?,
+                row.get("network_protocol")?,
+                row.get("network_max_fps")?,
+                row.get("network_keepalive_ms")?,
+                row.get("network_unicast_ips_json")?,
+                row.get("transition_ms")?,
+                row.get("beat_pad")?,
+                row.get("audio_input_device")?,
+            ))
+        )?;
+        let network_routes = network_routes_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .context("Failed to parse network routes JSON")?
+            .unwrap_or_default();
+        let midi_init = midi_init_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .context("Failed to parse MIDI init JSON")?
+            .unwrap_or_default();
+        let presets = presets_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .context("Failed to parse effect presets JSON")?
+            .unwrap_or_default();
+        let chains = chains_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .context("Failed to parse strip chains JSON")?
+            .unwrap_or_default();
+        let shuffle_params = shuffle_params_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .context("Failed to parse shuffle params JSON")?
+            .unwrap_or_default();
+        let active_scene_ids = active_scene_ids_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .context("Failed to parse active scenes JSON")?
+            .unwrap_or_default();
+        let midi_ports = midi_ports_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .context("Failed to parse MIDI ports JSON")?;
+        let midi_cc_bindings = midi_cc_bindings_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .context("Failed to parse MIDI CC bindings JSON")?
+            .unwrap_or_default();
+        let midi_port_filter = midi_port_filter_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .context("Failed to parse MIDI port filter JSON")?;
+        // Without the list column the row predates it; the single IP is migrated over
+        let (unicast_ips, unicast_ip) = match network_unicast_ips_json {
+            Some(json) => (serde_json::from_str(&json).context("Failed to parse unicast IPs JSON")?, String::new()),
+            None => (Vec::new(), network_unicast_ip),
+        };
+
+        let mut state = AppState {
+            version,
             strips,
             masks,
             scenes,
             selected_scene_id,
             network: NetworkConfig {
                 use_multicast: network_use_multicast != 0,
-                unicast_ip: network_unicast_ip,
+                unicast_ips,
+                unicast_ip,
                 universe: network_universe,
+                routes: network_routes,
+                probe_interval_ms: network_probe_ms.map(|ms| ms.max(0) as u64),
+                pause_policy,
+                protocol: DmxProtocol::from_name(&network_protocol),
+                max_fps: network_max_fps.clamp(0, 1000) as u16,
+                keepalive_ms: network_keepalive_ms.map(|ms| (ms.max(0) as u64).clamp(MIN_KEEPALIVE_MS, MAX_KEEPALIVE_MS)),
             },
             audio: AudioConfig {
                 latency_ms: audio_latency_ms,
                 use_flywheel: audio_use_flywheel != 0,
                 hybrid_sync: audio_hybrid_sync != 0,
                 sensitivity: audio_sensitivity,
+                input_device: audio_input_device,
             },
             bind_address,
             mode,
             effect,
             layout_locked: layout_locked != 0,
             midi_enabled: midi_enabled != 0,
-        })
+            midi_init,
+            preview_port,
+            bpm,
+            tap_btn,
+            tap_is_cc: tap_is_cc != 0,
+            panic_btn,
+            panic_is_cc: panic_is_cc != 0,
+            presets,
+            startup_fade_ms: startup_fade_ms.max(0) as u64,
+            frozen: false,
+            momentary_holds: Vec::new(),
+            freeze_btn,
+            freeze_is_cc: freeze_is_cc != 0,
+            freeze_resume_phase: freeze_resume_phase != 0,
+            canvas_width_m,
+            chains,
+            shuffle_params,
+            shuffle_interval_ms: shuffle_interval_ms.map(|ms| ms.max(0) as u64),
+            active_scene_ids,
+            midi_heartbeat_ms: midi_heartbeat_ms.map(|ms| ms.max(0) as u64),
+            render_budget_ms,
+            idle_scene_id: idle_scene_id.map(|id| id as u64),
+            idle_timeout_ms: idle_timeout_ms.map(|ms| ms.max(0) as u64),
+            output_threads: output_threads.clamp(0, 16) as u8,
+            midi_min_velocity: midi_min_velocity.clamp(0, 127) as u8,
+            midi_ports,
+            midi_cc_bindings,
+            midi_port_filter,
+            midi_debounce_ms: midi_debounce_ms.max(0) as u64,
+            gamma: gamma.clamp(MIN_GAMMA, MAX_GAMMA),
+            manual_addressing: manual_addressing != 0,
+            transition_ms: transition_ms.clamp(0, 60_000) as u32,
+            beat_pad,
+            master_brightness: master_brightness.clamp(0.0, 1.0),
+            blackout: blackout != 0,
+            quantize,
+            max_flash_hz,
+            no_strobe: no_strobe != 0,
+        };
+        state.init_runtime();
+        state.migrate()?;
+        state.resume_scenes();
+        Ok(state)
     }
 
     /// Save entire app state to database (transactional)
@@ -433,108 +678,21 @@ impl Database {
 
         // Save strips
         for strip in &state.strips {
-            tx.execute(
-                "INSERT INTO strips (id, universe, start_channel, pixel_count, x, y, spacing, flipped, color_order)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    strip.id as i64,
-                    strip.universe,
-                    strip.start_channel,
-                    strip.pixel_count,
-                    strip.x,
-                    strip.y,
-                    strip.spacing,
-                    if strip.flipped { 1 } else { 0 },
-                    strip.color_order,
-                ],
-            )?;
+            insert_strip(&tx, strip)?;
         }
 
         // Save global masks
         for mask in &state.masks {
-            let params_json = serde_json::to_string(&mask.params)?;
-            tx.execute(
-                "INSERT INTO masks (id, mask_type, x, y, params_json)
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
-                params![mask.id as i64, mask.mask_type, mask.x, mask.y, params_json],
-            )?;
+            insert_mask(&tx, mask)?;
         }
 
         // Save scenes
-        for scene in &state.scenes {
-            let global_effect_json = scene.global.as_ref()
-                .map(|g| serde_json::to_string(g))
-                .transpose()?;
-            let global_effects_json = serde_json::to_string(&scene.global_effects)?;
-
-            tx.execute(
-                "INSERT INTO scenes (id, name, kind, category, global_effect_json, global_effects_json, launchpad_btn, launchpad_is_cc, launchpad_color)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    scene.id as i64,
-                    scene.name,
-                    scene.kind,
-                    scene.category,
-                    global_effect_json,
-                    global_effects_json,
-                    scene.launchpad_btn.map(|v| v as i64),
-                    if scene.launchpad_is_cc { 1 } else { 0 },
-                    scene.launchpad_color.map(|v| v as i64),
-                ],
-            )?;
-
-            // Save scene masks
-            for (idx, mask) in scene.masks.iter().enumerate() {
-                let params_json = serde_json::to_string(&mask.params)?;
-                tx.execute(
-                    "INSERT INTO scene_masks (scene_id, mask_id, mask_type, x, y, params_json, display_order)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                    params![
-                        scene.id as i64,
-                        mask.id as i64,
-                        mask.mask_type,
-                        mask.x,
-                        mask.y,
-                        params_json,
-                        idx as i64,
-                    ],
-                )?;
-            }
+        for (idx, scene) in state.scenes.iter().enumerate() {
+            insert_scene(&tx, scene, idx as i64)?;
         }
 
         // Save app config
-        tx.execute(
-            "UPDATE app_config SET
-                selected_scene_id = ?1,
-                network_use_multicast = ?2,
-                network_unicast_ip = ?3,
-                network_universe = ?4,
-                bind_address = ?5,
-                mode = ?6,
-                effect = ?7,
-                audio_latency_ms = ?8,
-                audio_use_flywheel = ?9,
-                audio_hybrid_sync = ?10,
-                audio_sensitivity = ?11,
-                layout_locked = ?12,
-                midi_enabled = ?13
-             WHERE id = 1",
-            params![
-                state.selected_scene_id.map(|id| id as i64),
-                if state.network.use_multicast { 1 } else { 0 },
-                state.network.unicast_ip,
-                state.network.universe,
-                state.bind_address,
-                state.mode,
-                state.effect,
-                state.audio.latency_ms,
-                if state.audio.use_flywheel { 1 } else { 0 },
-                if state.audio.hybrid_sync { 1 } else { 0 },
-                state.audio.sensitivity,
-                if state.layout_locked { 1 } else { 0 },
-                if state.midi_enabled { 1 } else { 0 },
-            ],
-        )?;
+        write_app_config(&tx, state)?;
 
         // Re-enable foreign key checks
         tx.execute("PRAGMA foreign_keys = ON", [])?;
@@ -543,17 +701,14 @@ impl Database {
         Ok(())
     }
 
-    /// Export entire state to JSON string
-    pub fn export_to_json(&self) -> Result<String> {
-        let state = self.load_state()?;
-        let json = serde_json::to_string_pretty(&state)?;
-        Ok(json)
-    }
+    /// Import a state read by `state_file::load` (JSON or binary)
+    pub fn import_state(&mut self, mut import_state: AppState, merge: bool) -> Result<()> {
+        import_state.init_runtime();
+        import_state.migrate()?;
 
-    /// Import from JSON string
-    pub fn import_from_json(&mut self, json: &str, merge: bool) -> Result<()> {
-        let import_state: AppState = serde_json::from_str(json)
-            .context("Invalid JSON format")?;
+        if !merge {
+            keep_machine_settings(&mut import_state, self.load_state()?);
+        }
 
         let tx = self.conn.transaction()?;
 
@@ -581,25 +736,16 @@ impl Database {
                 }
             }
 
-            tx.execute(
-                "INSERT INTO strips (id, universe, start_channel, pixel_count, x, y, spacing, flipped, color_order)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    strip.id as i64,
-                    strip.universe,
-                    strip.start_channel,
-                    strip.pixel_count,
-                    strip.x,
-                    strip.y,
-                    strip.spacing,
-                    if strip.flipped { 1 } else { 0 },
-                    strip.color_order,
-                ],
-            )?;
+            insert_strip(&tx, strip)?;
         }
 
-        // Import scenes and masks similarly
-        for scene in &import_state.scenes {
+        // Import scenes and masks similarly; merged scenes go after the existing ones
+        let order_base: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(display_order) + 1, 0) FROM scenes",
+            [],
+            |row| row.get(0)
+        )?;
+        for (idx, scene) in import_state.scenes.iter().enumerate() {
             if merge {
                 let exists: bool = tx.query_row(
                     "SELECT COUNT(*) > 0 FROM scenes WHERE id = ?1",
@@ -611,76 +757,308 @@ impl Database {
                 }
             }
 
-            let global_effect_json = scene.global.as_ref()
-                .map(|g| serde_json::to_string(g))
-                .transpose()?;
-            let global_effects_json = serde_json::to_string(&scene.global_effects)?;
-
-            tx.execute(
-                "INSERT INTO scenes (id, name, kind, category, global_effect_json, global_effects_json, launchpad_btn, launchpad_is_cc, launchpad_color)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    scene.id as i64,
-                    scene.name,
-                    scene.kind,
-                    scene.category,
-                    global_effect_json,
-                    global_effects_json,
-                    scene.launchpad_btn.map(|v| v as i64),
-                    if scene.launchpad_is_cc { 1 } else { 0 },
-                    scene.launchpad_color.map(|v| v as i64),
-                ],
-            )?;
-
-            for (idx, mask) in scene.masks.iter().enumerate() {
-                let params_json = serde_json::to_string(&mask.params)?;
-                tx.execute(
-                    "INSERT INTO scene_masks (scene_id, mask_id, mask_type, x, y, params_json, display_order)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                    params![
-                        scene.id as i64,
-                        mask.id as i64,
-                        mask.mask_type,
-                        mask.x,
-                        mask.y,
-                        params_json,
-                        idx as i64,
-                    ],
-                )?;
-            }
+            insert_scene(&tx, scene, order_base + idx as i64)?;
         }
 
         // In replace mode, update app config
         if !merge {
-            tx.execute(
-                "UPDATE app_config SET
-                    selected_scene_id = ?1,
-                    network_use_multicast = ?2,
-                    network_unicast_ip = ?3,
-                    network_universe = ?4,
-                    audio_latency_ms = ?5,
-                    audio_use_flywheel = ?6,
-                    audio_hybrid_sync = ?7,
-                    audio_sensitivity = ?8,
-                    layout_locked = ?9,
-                    midi_enabled = ?10
-                 WHERE id = 1",
-                params![
-                    import_state.selected_scene_id.map(|id| id as i64),
-                    if import_state.network.use_multicast { 1 } else { 0 },
-                    import_state.network.unicast_ip,
-                    import_state.network.universe,
-                    import_state.audio.latency_ms,
-                    if import_state.audio.use_flywheel { 1 } else { 0 },
-                    if import_state.audio.hybrid_sync { 1 } else { 0 },
-                    import_state.audio.sensitivity,
-                    if import_state.layout_locked { 1 } else { 0 },
-                    if import_state.midi_enabled { 1 } else { 0 },
-                ],
-            )?;
+            write_app_config(&tx, &import_state)?;
         }
 
         tx.commit()?;
         Ok(())
     }
 }
+
+/// Carry over the settings that describe this machine rather than the show,
+/// so a replace import from another computer keeps its NIC, ports and devices
+fn keep_machine_settings(import_state: &mut AppState, local: AppState) {
+    import_state.bind_address = local.bind_address;
+    import_state.mode = local.mode;
+    import_state.effect = local.effect;
+    import_state.midi_ports = local.midi_ports;
+    import_state.midi_port_filter = local.midi_port_filter;
+    import_state.output_threads = local.output_threads;
+    import_state.network.probe_interval_ms = local.network.probe_interval_ms;
+    import_state.audio.input_device = local.audio.input_device;
+}
+
+/// Insert a single strip row
+fn insert_strip(conn: &Connection, strip: &PixelStrip) -> Result<()> {
+    conn.execute(
+        "INSERT INTO strips (id, universe, start_channel, pixel_count, x, y, spacing, flipped, color_order, notes, leds_per_meter, length_m, min_brightness, pre_channels_json, post_channels_json, enabled, mirror_source, mirror_reversed, z, channels_per_pixel, brightness, white_mode)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
+        params![
+            strip.id as i64,
+            strip.universe,
+            strip.start_channel,
+            strip.pixel_count,
+            strip.x,
+            strip.y,
+            strip.spacing,
+            if strip.flipped { 1 } else { 0 },
+            strip.color_order,
+            strip.notes,
+            strip.leds_per_meter,
+            strip.length_m,
+            strip.min_brightness,
+            serde_json::to_string(&strip.pre_channels)?,
+            serde_json::to_string(&strip.post_channels)?,
+            if strip.enabled { 1 } else { 0 },
+            strip.mirror_source.map(|id| id as i64),
+            if strip.mirror_reversed { 1 } else { 0 },
+            strip.z,
+            strip.channels_per_pixel,
+            strip.brightness,
+            strip.white_mode,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Insert a single global mask row
+fn insert_mask(conn: &Connection, mask: &Mask) -> Result<()> {
+    let params_json = serde_json::to_string(&mask.params)?;
+    let modulation_json = serde_json::to_string(&mask.modulation)?;
+    conn.execute(
+        "INSERT INTO masks (id, mask_type, x, y, params_json, modulation_json, z)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![mask.id as i64, mask.mask_type, mask.x, mask.y, params_json, modulation_json, mask.z],
+    )?;
+    Ok(())
+}
+
+/// Parse a nullable `modulation_json` column; rows from older versions have none
+fn parse_modulation(json: Option<String>) -> rusqlite::Result<Vec<AudioModulation>> {
+    json.map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+        .map(|m| m.unwrap_or_default())
+}
+
+fn parse_channels(json: Option<String>) -> rusqlite::Result<Vec<FixtureChannel>> {
+    json.map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+        .map(|c| c.unwrap_or_default())
+}
+
+/// Insert a scene row at `display_order` along with its ordered masks
+fn insert_scene(conn: &Connection, scene: &Scene, display_order: i64) -> Result<()> {
+    let global_effect_json = scene.global.as_ref()
+        .map(|g| serde_json::to_string(g))
+        .transpose()?;
+    let global_effects_json = serde_json::to_string(&scene.global_effects)?;
+
+    conn.execute(
+        "INSERT INTO scenes (id, name, kind, category, global_effect_json, global_effects_json, launchpad_btn, launchpad_is_cc, launchpad_color, display_order, notes, exclusive_group, layer, opacity, blend_mode, launchpad_momentary)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        params![
+            scene.id as i64,
+            scene.name,
+            scene.kind,
+            scene.category,
+            global_effect_json,
+            global_effects_json,
+            scene.launchpad_btn.map(|v| v as i64),
+            if scene.launchpad_is_cc { 1 } else { 0 },
+            scene.launchpad_color.map(|v| v as i64),
+            display_order,
+            scene.notes,
+            scene.exclusive_group,
+            scene.layer,
+            scene.opacity,
+            scene.blend_mode,
+            if scene.launchpad_momentary { 1 } else { 0 },
+        ],
+    )?;
+
+    for (idx, mask) in scene.masks.iter().enumerate() {
+        let params_json = serde_json::to_string(&mask.params)?;
+        let modulation_json = serde_json::to_string(&mask.modulation)?;
+        conn.execute(
+            "INSERT INTO scene_masks (scene_id, mask_id, mask_type, x, y, params_json, modulation_json, display_order, z)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                scene.id as i64,
+                mask.id as i64,
+                mask.mask_type,
+                mask.x,
+                mask.y,
+                params_json,
+                modulation_json,
+                idx as i64,
+                mask.z,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Write the single app_config row
+fn write_app_config(conn: &Connection, state: &AppState) -> Result<()> {
+    let network_routes_json = serde_json::to_string(&state.network.routes)?;
+    let midi_init_json = serde_json::to_string(&state.midi_init)?;
+    let presets_json = serde_json::to_string(&state.presets)?;
+    let chains_json = serde_json::to_string(&state.chains)?;
+    let shuffle_params_json = serde_json::to_string(&state.shuffle_params)?;
+    let active_scene_ids_json = serde_json::to_string(&state.active_scene_ids)?;
+    let midi_ports_json = state.midi_ports.as_ref().map(serde_json::to_string).transpose()?;
+    let midi_cc_bindings_json = serde_json::to_string(&state.midi_cc_bindings)?;
+    let midi_port_filter_json = state.midi_port_filter.as_ref().map(serde_json::to_string).transpose()?;
+    let network_unicast_ips_json = serde_json::to_string(&state.network.unicast_ips)?;
+
+    conn.execute(
+        "UPDATE app_config SET
+            selected_scene_id = ?1,
+            network_use_multicast = ?2,
+            network_unicast_ip = ?3,
+            network_universe = ?4,
+            bind_address = ?5,
+            mode = ?6,
+            effect = ?7,
+            audio_latency_ms = ?8,
+            audio_use_flywheel = ?9,
+            audio_hybrid_sync = ?10,
+            audio_sensitivity = ?11,
+            layout_locked = ?12,
+            midi_enabled = ?13,
+            network_routes_json = ?14,
+            midi_init_json = ?15,
+            preview_port = ?16,
+            bpm = ?17,
+            tap_btn = ?18,
+            tap_is_cc = ?19,
+            panic_btn = ?20,
+            panic_is_cc = ?21,
+            presets_json = ?22,
+            startup_fade_ms = ?23,
+            state_version = ?24,
+            freeze_btn = ?25,
+            freeze_is_cc = ?26,
+            freeze_resume_phase = ?27,
+            canvas_width_m = ?28,
+            chains_json = ?29,
+            shuffle_params_json = ?30,
+            shuffle_interval_ms = ?31,
+            active_scene_ids_json = ?32,
+            midi_heartbeat_ms = ?33,
+            render_budget_ms = ?34,
+            idle_scene_id = ?35,
+            idle_timeout_ms = ?36,
+            output_threads = ?37,
+            midi_min_velocity = ?38,
+            master_brightness = ?39,
+            blackout = ?40,
+            network_probe_ms = ?41,
+            quantize = ?42,
+            max_flash_hz = ?43,
+            no_strobe = ?44,
+            pause_policy = ?45,
+            midi_ports_json = ?46,
+            midi_cc_bindings_json = ?47,
+            midi_port_filter_json = ?48,
+            midi_debounce_ms = ?49,
+            gamma = ?50,
+            manual_addressing = ?51,
+            network_protocol = ?52,
+            network_max_fps = ?53,
+            network_keepalive_ms = ?54,
+            network_unicast_ips_json = ?55,
+            transition_ms = ?56,
+            beat_pad = ?57,
+            audio_input_device = ?58
+         WHERE id = 1",
+        params![
+            state.selected_scene_id.map(|id| id as i64),
+            if state.network.use_multicast { 1 } else { 0 },
+            state.network.unicast_ips.first().cloned().unwrap_or_default(), // For older builds
+            state.network.universe,
+            state.bind_address,
+            state.mode,
+            state.effect,
+            state.audio.latency_ms,
+            if state.audio.use_flywheel { 1 } else { 0 },
+            if state.audio.hybrid_sync { 1 } else { 0 },
+            state.audio.sensitivity,
+            if state.layout_locked { 1 } else { 0 },
+            if state.midi_enabled { 1 } else { 0 },
+            network_routes_json,
+            midi_init_json,
+            state.preview_port,
+            state.bpm,
+            state.tap_btn.map(|v| v as i64),
+            if state.tap_is_cc { 1 } else { 0 },
+            state.panic_btn.map(|v| v as i64),
+            if state.panic_is_cc { 1 } else { 0 },
+            presets_json,
+            state.startup_fade_ms as i64,
+            state.version,
+            state.freeze_btn.map(|v| v as i64),
+            if state.freeze_is_cc { 1 } else { 0 },
+            if state.freeze_resume_phase { 1 } else { 0 },
+            state.canvas_width_m,
+            chains_json,
+            shuffle_params_json,
+            state.shuffle_interval_ms.map(|ms| ms as i64),
+            active_scene_ids_json,
+            state.midi_heartbeat_ms.map(|ms| ms as i64),
+            state.render_budget_ms,
+            state.idle_scene_id.map(|id| id as i64),
+            state.idle_timeout_ms.map(|ms| ms as i64),
+            state.output_threads as i64,
+            state.midi_min_velocity as i64,
+            state.master_brightness,
+            if state.blackout { 1 } else { 0 },
+            state.network.probe_interval_ms.map(|ms| ms as i64),
+            state.quantize,
+            state.max_flash_hz,
+            if state.no_strobe { 1 } else { 0 },
+            state.network.pause_policy,
+            midi_ports_json,
+            midi_cc_bindings_json,
+            midi_port_filter_json,
+            state.midi_debounce_ms as i64,
+            state.gamma,
+            if state.manual_addressing { 1 } else { 0 },
+            state.network.protocol.as_str(),
+            state.network.max_fps,
+            state.network.keepalive_ms.map(|ms| ms as i64),
+            network_unicast_ips_json,
+            state.transition_ms,
+            state.beat_pad.map(|v| v as i64),
+            state.audio.input_device,
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_import_keeps_machine_settings() {
+        let path = std::env::temp_dir().join(format!("lightspeed-import-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut db = Database::open(&path).unwrap();
+        db.save_state(&AppState { bind_address: Some("10.0.0.5".into()), output_threads: 4, ..Default::default() }).unwrap();
+
+        let show = AppState {
+            bind_address: Some("192.168.1.20".into()),
+            layout_locked: true,
+            strips: vec![PixelStrip { id: 7, pixel_count: 10, ..Default::default() }],
+            ..Default::default()
+        };
+        db.import_state(show, false).unwrap();
+
+        let loaded = db.load_state().unwrap();
+        assert_eq!(loaded.bind_address.as_deref(), Some("10.0.0.5"));
+        assert_eq!(loaded.output_threads, 4);
+        assert!(loaded.layout_locked);
+        assert_eq!(loaded.strips.len(), 1);
+
+        drop(db);
+        let _ = std::fs::remove_file(&path);
+    }
+}
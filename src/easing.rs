@@ -0,0 +1,33 @@
+//! Easing curves mapping linear progress (0.0-1.0) onto a shaped 0.0-1.0 ramp.
+
+/// Curve names accepted by `ease`, in editor order
+pub const CURVES: &[&str] = &["linear", "ease_in", "ease_out", "ease_in_out"];
+
+/// Shape `x` (clamped to 0.0-1.0) with `curve`. Unknown curves are linear.
+pub fn ease(curve: &str, x: f64) -> f64 {
+    let x = x.clamp(0.0, 1.0);
+    match curve {
+        "ease_in" => x * x,
+        "ease_out" => 1.0 - (1.0 - x) * (1.0 - x),
+        "ease_in_out" => x * x * (3.0 - 2.0 * x), // Smoothstep
+        _ => x,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curves_span_zero_to_one() {
+        for curve in CURVES {
+            assert_eq!(ease(curve, 0.0), 0.0, "{}", curve);
+            assert_eq!(ease(curve, 1.0), 1.0, "{}", curve);
+            assert_eq!(ease(curve, 2.0), 1.0, "{} should clamp", curve);
+        }
+        assert!(ease("ease_in", 0.5) < 0.5);
+        assert!(ease("ease_out", 0.5) > 0.5);
+        assert_eq!(ease("ease_in_out", 0.5), 0.5);
+        assert_eq!(ease("nope", 0.25), 0.25);
+    }
+}
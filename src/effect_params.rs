@@ -0,0 +1,300 @@
+//! Typed view of `GlobalEffect::params`.
+//!
+//! Params stay a JSON map on disk (presets layer overrides key by key, automation and LFOs
+//! address params by name), and are read into `EffectParams` for rendering and validation.
+//! Rendering reads leniently: a missing or malformed param takes its default, as it always
+//! has. `EffectParams::strict` reports the first problem instead, for showing to the user.
+
+use crate::model::parse_color;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Concrete params of each global effect kind, with defaults filled in
+#[derive(Clone, Debug, PartialEq)]
+pub enum EffectParams {
+    Solid { color: [u8; 3] },
+    Rainbow { speed: f32 },
+    Gradient { stops: Vec<(f32, [u8; 3])>, scroll_speed: f32 },
+    Flash { color: [u8; 3], rate: String, decay: f64 },
+    Strobe { color: [u8; 3], frequency_hz: f32, duty_cycle: f32 },
+    Sparkle { color: [u8; 3], density: f32, life: f32, decay: f64, sync_mode: String },
+    ColorWash { color_a: [u8; 3], color_b: [u8; 3], sync_to_beat: bool, rate: String, period: f64 },
+    GlitchSparkle { background_color: [u8; 3], sparkle_color: [u8; 3], density: f32, fade_time: f32, decay: f64, sync_mode: String },
+    PulseWave { color: [u8; 3], sync: bool, rate: String, speed: f64, tail_length: f32, decay: f32, direction: String },
+    ZoneAlternate { group_a_strips: Vec<u64>, group_b_strips: Vec<u64>, group_a_color: [u8; 3], group_b_color: [u8; 3], rate: String, mode: String },
+    Plasma { speed: f32, scale: f32, palette: String, color_a: [u8; 3], color_b: [u8; 3] },
+    Ripple { origin_x: f32, origin_y: f32, origin_z: Option<f32>, speed: f32, width: f32, decay: f32, trigger: String, interval: f32, color: [u8; 3] },
+    SpectrumBars { color_low: [u8; 3], color_mid: [u8; 3], color_high: [u8; 3] },
+}
+
+/// Why an effect's params don't match its kind
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamError {
+    UnknownKind(String),
+    /// A key the kind doesn't read, usually a typo or left over from another kind
+    UnknownParam(String),
+    WrongType { param: String, expected: &'static str },
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamError::UnknownKind(kind) => write!(f, "Unknown effect kind '{}'", kind),
+            ParamError::UnknownParam(param) => write!(f, "Unknown param '{}'", param),
+            ParamError::WrongType { param, expected } => write!(f, "Param '{}' should be {}", param, expected),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+/// Param names each kind reads, or None for an unknown kind. LFO settings for a param
+/// (`<param>_lfo_*`) are accepted alongside it.
+pub fn param_names(kind: &str) -> Option<&'static [&'static str]> {
+    Some(match kind {
+        "Solid" => &["color"],
+        "Rainbow" => &["speed"],
+        "Gradient" => &["stops", "scroll_speed"],
+        "Flash" => &["color", "rate", "decay"],
+        "Strobe" => &["color", "frequency_hz", "duty_cycle"],
+        "Sparkle" => &["color", "density", "life", "decay", "sync_mode"],
+        "ColorWash" => &["color_a", "color_b", "sync_to_beat", "rate", "period"],
+        "GlitchSparkle" => &["background_color", "sparkle_color", "density", "fade_time", "decay", "sync_mode"],
+        "PulseWave" => &["color", "sync", "rate", "speed", "tail_length", "decay", "direction"],
+        "ZoneAlternate" => &["group_a_strips", "group_b_strips", "group_a_color", "group_b_color", "rate", "mode"],
+        "Plasma" => &["speed", "scale", "palette", "color_a", "color_b"],
+        "Ripple" => &["origin_x", "origin_y", "origin_z", "speed", "width", "decay", "trigger", "interval", "color"],
+        "SpectrumBars" => &["color_low", "color_mid", "color_high"],
+        _ => return None,
+    })
+}
+
+/// Whether `kind` reads `key`, directly or as LFO settings of one of its params
+pub fn is_known_param(kind: &str, key: &str) -> bool {
+    param_names(kind).is_some_and(|names| {
+        names.iter().any(|name| key == *name || key.strip_prefix(name).is_some_and(|rest| rest.starts_with("_lfo_")))
+    })
+}
+
+impl EffectParams {
+    /// Params as rendered: anything missing or malformed takes its default.
+    /// None for a kind that doesn't exist.
+    pub fn lenient(kind: &str, params: &HashMap<String, Value>) -> Option<Self> {
+        Reader { params, errors: Vec::new() }.read(kind)
+    }
+
+    /// Params of `kind`, or the first wrong-typed or unknown param. Missing params are fine.
+    pub fn strict(kind: &str, params: &HashMap<String, Value>) -> Result<Self, ParamError> {
+        let mut reader = Reader { params, errors: Vec::new() };
+        let typed = reader.read(kind).ok_or_else(|| ParamError::UnknownKind(kind.to_string()))?;
+        if let Some(err) = reader.errors.into_iter().next() {
+            return Err(err);
+        }
+        let mut unknown: Vec<&String> = params.keys().filter(|key| !is_known_param(kind, key)).collect();
+        unknown.sort();
+        match unknown.first() {
+            Some(key) => Err(ParamError::UnknownParam(key.to_string())),
+            None => Ok(typed),
+        }
+    }
+}
+
+/// Gradient stops from a `[{"pos": 0..1, "color": [r, g, b]}, ...]` param, sorted by position.
+/// Malformed stops are skipped and positions clamped; a single stop is held across the
+/// strip and none at all gives black to white, so there are always at least two.
+pub fn gradient_stops(value: Option<&Value>) -> Vec<(f32, [u8; 3])> {
+    let mut stops: Vec<(f32, [u8; 3])> = value
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(gradient_stop).collect())
+        .unwrap_or_default();
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    match stops.len() {
+        0 => vec![(0.0, [0, 0, 0]), (1.0, [255, 255, 255])],
+        1 => vec![(0.0, stops[0].1), (1.0, stops[0].1)],
+        _ => stops,
+    }
+}
+
+fn gradient_stop(stop: &Value) -> Option<(f32, [u8; 3])> {
+    let pos = stop.get("pos")?.as_f64().filter(|p| p.is_finite())? as f32;
+    let color = parse_color(stop.get("color")?)?;
+    Some((pos.clamp(0.0, 1.0), color))
+}
+
+/// Reads params by name, recording each one present with the wrong type
+struct Reader<'a> {
+    params: &'a HashMap<String, Value>,
+    errors: Vec<ParamError>,
+}
+
+impl Reader<'_> {
+    fn read(&mut self, kind: &str) -> Option<EffectParams> {
+        Some(match kind {
+            "Solid" => EffectParams::Solid { color: self.color("color", [255, 255, 255]) },
+            "Rainbow" => EffectParams::Rainbow { speed: self.f32("speed", 0.2) },
+            "Gradient" => EffectParams::Gradient { stops: self.stops("stops"), scroll_speed: self.f32("scroll_speed", 0.0) },
+            "Flash" => EffectParams::Flash {
+                color: self.color("color", [255, 255, 255]),
+                rate: self.string("rate", "1 Bar"),
+                decay: self.f64("decay", 5.0),
+            },
+            "Strobe" => EffectParams::Strobe {
+                color: self.color("color", [255, 255, 255]),
+                frequency_hz: self.f32("frequency_hz", 10.0),
+                duty_cycle: self.f32("duty_cycle", 0.5),
+            },
+            "Sparkle" => EffectParams::Sparkle {
+                color: self.color("color", [255, 255, 255]),
+                density: self.f32("density", 0.05),
+                life: self.f32("life", 0.2),
+                decay: self.f64("decay", 5.0),
+                sync_mode: self.string("sync_mode", "per_strip"),
+            },
+            "ColorWash" => EffectParams::ColorWash {
+                color_a: self.color("color_a", [255, 0, 0]),
+                color_b: self.color("color_b", [0, 0, 255]),
+                sync_to_beat: self.bool("sync_to_beat", false),
+                rate: self.string("rate", "1 Bar"),
+                period: self.f64("period", 4.0),
+            },
+            "GlitchSparkle" => EffectParams::GlitchSparkle {
+                background_color: self.color("background_color", [0, 0, 0]),
+                sparkle_color: self.color("sparkle_color", [255, 255, 255]),
+                density: self.f32("density", 0.05),
+                fade_time: self.f32("fade_time", 0.3),
+                decay: self.f64("decay", 5.0),
+                sync_mode: self.string("sync_mode", "per_strip"),
+            },
+            "PulseWave" => EffectParams::PulseWave {
+                color: self.color("color", [255, 255, 255]),
+                sync: self.bool("sync", true),
+                rate: self.string("rate", "1/4"),
+                speed: self.f64("speed", 5.0),
+                tail_length: self.f32("tail_length", 10.0),
+                decay: self.f32("decay", 2.0),
+                direction: self.string("direction", "Forward"),
+            },
+            "ZoneAlternate" => EffectParams::ZoneAlternate {
+                group_a_strips: self.strip_ids("group_a_strips"),
+                group_b_strips: self.strip_ids("group_b_strips"),
+                group_a_color: self.color("group_a_color", [255, 0, 0]),
+                group_b_color: self.color("group_b_color", [0, 0, 255]),
+                rate: self.string("rate", "1/4"),
+                mode: self.string("mode", "Swap"),
+            },
+            "Plasma" => EffectParams::Plasma {
+                speed: self.f32("speed", 0.5),
+                scale: self.f32("scale", 6.0),
+                palette: self.string("palette", "Rainbow"),
+                color_a: self.color("color_a", [255, 0, 128]),
+                color_b: self.color("color_b", [0, 64, 255]),
+            },
+            "Ripple" => EffectParams::Ripple {
+                origin_x: self.f32("origin_x", 0.5),
+                origin_y: self.f32("origin_y", 0.5),
+                origin_z: self.value("origin_z", "a number", None, |v| v.as_f64().map(|z| Some(z as f32))), // Unset = rings ignore depth
+                speed: self.f32("speed", 0.5),
+                width: self.f32("width", 0.05),
+                decay: self.f32("decay", 1.0),
+                trigger: self.string("trigger", "Beat"),
+                interval: self.f32("interval", 1.0),
+                color: self.color("color", [0, 128, 255]),
+            },
+            "SpectrumBars" => EffectParams::SpectrumBars {
+                color_low: self.color("color_low", [255, 0, 0]),
+                color_mid: self.color("color_mid", [0, 255, 0]),
+                color_high: self.color("color_high", [0, 0, 255]),
+            },
+            _ => return None,
+        })
+    }
+
+    /// `key` read with `parse`; missing or null gives `default`, and so does a value
+    /// `parse` rejects, which is also recorded
+    fn value<T>(&mut self, key: &str, expected: &'static str, default: T, parse: impl FnOnce(&Value) -> Option<T>) -> T {
+        match self.params.get(key) {
+            None | Some(Value::Null) => default,
+            Some(value) => parse(value).unwrap_or_else(|| {
+                self.errors.push(ParamError::WrongType { param: key.to_string(), expected });
+                default
+            }),
+        }
+    }
+
+    fn f32(&mut self, key: &str, default: f32) -> f32 {
+        self.value(key, "a number", default, |v| v.as_f64().map(|n| n as f32))
+    }
+
+    fn f64(&mut self, key: &str, default: f64) -> f64 {
+        self.value(key, "a number", default, |v| v.as_f64())
+    }
+
+    fn bool(&mut self, key: &str, default: bool) -> bool {
+        self.value(key, "true or false", default, |v| v.as_bool())
+    }
+
+    fn string(&mut self, key: &str, default: &str) -> String {
+        self.value(key, "text", default.to_string(), |v| v.as_str().map(String::from))
+    }
+
+    fn color(&mut self, key: &str, default: [u8; 3]) -> [u8; 3] {
+        self.value(key, "a color [r, g, b]", default, parse_color)
+    }
+
+    fn strip_ids(&mut self, key: &str) -> Vec<u64> {
+        self.value(key, "a list of strip ids", Vec::new(), |v| v.as_array()?.iter().map(|id| id.as_u64()).collect())
+    }
+
+    fn stops(&mut self, key: &str) -> Vec<(f32, [u8; 3])> {
+        let malformed = match self.params.get(key) {
+            None | Some(Value::Null) => false,
+            Some(Value::Array(stops)) => !stops.iter().all(|stop| gradient_stop(stop).is_some()),
+            Some(_) => true,
+        };
+        if malformed {
+            self.errors.push(ParamError::WrongType { param: key.to_string(), expected: "a list of {pos, color} stops" });
+        }
+        gradient_stops(self.params.get(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(json: Value) -> HashMap<String, Value> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_strict_reports_typos_and_wrong_types() {
+        let strobe = params(serde_json::json!({ "color": [255, 0, 0], "frequency_hz": 8, "speed_lfo_enabled": true }));
+        assert_eq!(EffectParams::strict("Strobe", &strobe), Err(ParamError::UnknownParam("speed_lfo_enabled".into())));
+
+        let rainbow = params(serde_json::json!({ "speed": 0.4, "speed_lfo_enabled": true, "speed_lfo_depth": 0.2 }));
+        assert_eq!(EffectParams::strict("Rainbow", &rainbow), Ok(EffectParams::Rainbow { speed: 0.4 }));
+
+        let typo = params(serde_json::json!({ "colour": [255, 0, 0] }));
+        assert_eq!(EffectParams::strict("Solid", &typo), Err(ParamError::UnknownParam("colour".into())));
+
+        let wrong = params(serde_json::json!({ "rate": 4, "decay": "fast" }));
+        assert_eq!(
+            EffectParams::strict("Flash", &wrong),
+            Err(ParamError::WrongType { param: "rate".into(), expected: "text" })
+        );
+        assert_eq!(EffectParams::strict("Laser", &HashMap::new()), Err(ParamError::UnknownKind("Laser".into())));
+    }
+
+    #[test]
+    fn test_lenient_fills_defaults() {
+        let wrong = params(serde_json::json!({ "rate": 4, "decay": "fast", "color": [10, 20, 30] }));
+        assert_eq!(
+            EffectParams::lenient("Flash", &wrong),
+            Some(EffectParams::Flash { color: [10, 20, 30], rate: "1 Bar".into(), decay: 5.0 })
+        );
+        assert_eq!(EffectParams::lenient("Laser", &HashMap::new()), None);
+        assert!(is_known_param("Plasma", "speed_lfo_waveform"));
+        assert!(!is_known_param("Plasma", "speedy"));
+    }
+}
@@ -1,7 +1,16 @@
-use crate::model::{AppState, Mask, PixelStrip, NetworkConfig, GlobalEffect};
-use crate::audio::AudioListener;
+use crate::model::{AppState, AudioModulation, Mask, PixelStrip, NetworkConfig, GlobalEffect, EffectPreset, Scene};
+use crate::audio::{AudioLevels, AudioListener, BeatDetector, BAND_COUNT};
+use crate::effect_params::EffectParams;
+use crate::clock::{Clock, MockClock, SystemClock};
+use crate::output::{OutputPool, SenderSet, SendResult, UniversePacket};
+use crate::preview::PreviewServer;
+use crate::scanner;
 use sacn::source::SacnSource; 
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use rand::{Rng, RngCore, SeedableRng};
+use rand::rngs::StdRng;
 
 use rusty_link::{AblLink, SessionState};
 
@@ -25,10 +34,314 @@ struct GlitchPixel {
     color: [u8; 3],
 }
 
+/// Keeps audio-driven visuals moving when the input stutters or drops out.
+/// While audio frames are missing the level is synthesized from the last known
+/// loudness, pulsed on the current beat, and `confidence` decays toward zero.
+struct AudioFlywheel {
+    peak_level: f32,   // Decaying recent peak of real audio
+    predicting: bool,
+    confidence: f32,   // 0..1, 1 = live audio
+}
+
+/// Audio is considered stalled once no frame has arrived for this long
+const FLYWHEEL_STALL_MS: u128 = 100;
+/// Seconds for confidence to halve while predicting
+const FLYWHEEL_CONFIDENCE_HALF_LIFE: f32 = 2.0;
+
+impl AudioFlywheel {
+    fn new() -> Self {
+        Self { peak_level: 0.0, predicting: false, confidence: 0.0 }
+    }
+
+    /// Feed this frame's measured level; returns the level effects should use.
+    /// `resumed` is set on the first frame real audio comes back after a stall.
+    fn update(&mut self, now: Instant, newest_frame: Option<Instant>, level: f32, beat: f64, enabled: bool, dt: f32) -> (f32, bool) {
+        let fresh = match newest_frame {
+            Some(t) => now.duration_since(t).as_millis() < FLYWHEEL_STALL_MS,
+            None => false,
+        };
+
+        if fresh || !enabled {
+            let resumed = fresh && self.predicting;
+            self.predicting = false;
+            self.peak_level = level.max(self.peak_level * 0.995);
+            // Ramp confidence back up rather than snapping, so effects ease back in
+            self.confidence = if fresh { (self.confidence + dt * 2.0).min(1.0) } else { 0.0 };
+            return (level, resumed);
+        }
+
+        // Stalled: extrapolate a beat-shaped envelope at the last known loudness
+        self.predicting = true;
+        self.confidence *= 0.5f32.powf(dt / FLYWHEEL_CONFIDENCE_HALF_LIFE);
+        let envelope = (1.0 - beat.fract() as f32).powi(3);
+        (self.peak_level * envelope, false)
+    }
+}
+
+/// Engine performance counters, refreshed once per `STATS_WINDOW_SECS`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub render_fps: f32,
+    pub packets_per_sec: f32,
+    // Frame slots missed because a frame ran past its budget (per second / since start)
+    pub skipped_per_sec: f32,
+    pub frames_skipped: u64,
+    // Rendered frames held back by the output cap (`NetworkConfig::max_fps`), per second
+    pub paced_per_sec: f32,
+    // Adaptive quality: 0 = full; n = scenes re-render every (n + 1)th frame
+    pub quality_level: u8,
+}
+
+/// Weight of each new frame in the measured frame interval (see `StatsCounter::frame`)
+const FRAME_INTERVAL_SMOOTHING: f64 = 0.05;
+/// Length of the window `Stats` rates are averaged over
+const STATS_WINDOW_SECS: f64 = 1.0;
+/// A MIDI clock tempo older than this is ignored (the clock cable was pulled without a Stop)
+const MIDI_CLOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Accumulates per-frame counts and publishes them as `Stats` once per window
+struct StatsCounter {
+    window_secs: f64,
+    frames: u32,
+    packets: u32,
+    skipped: u32,
+    total_skipped: u64,
+    paced: u32,
+    frame_interval: f64, // Typical seconds between frames, measured; 0 until the first frame
+    current: Stats,
+}
+
+impl StatsCounter {
+    fn new() -> Self {
+        Self { window_secs: 0.0, frames: 0, packets: 0, skipped: 0, total_skipped: 0, paced: 0, frame_interval: 0.0, current: Stats::default() }
+    }
+
+    /// Record one rendered frame that took `dt` seconds since the previous one.
+    /// Slots are those of the output cap (`max_fps`), or when uncapped the loop's own typical
+    /// frame interval, so a 30 fps loop or a 120 Hz display are each measured at their rate.
+    /// Returns true when a window closed and `current` was refreshed.
+    fn frame(&mut self, dt: f64, max_fps: u16) -> bool {
+        self.frames += 1;
+        let interval = if max_fps > 0 { 1.0 / max_fps as f64 } else { self.frame_interval };
+        let missed = if interval > 0.0 { ((dt / interval).round() as u32).saturating_sub(1) } else { 0 };
+        self.skipped += missed;
+        self.total_skipped += missed as u64;
+        // Stalls stay out of the typical interval so they keep counting as missed slots
+        if self.frame_interval <= 0.0 {
+            self.frame_interval = dt;
+        } else if dt < self.frame_interval * 3.0 {
+            self.frame_interval += (dt - self.frame_interval) * FRAME_INTERVAL_SMOOTHING;
+        }
+
+        self.window_secs += dt;
+        if self.window_secs < STATS_WINDOW_SECS {
+            return false;
+        }
+        let secs = self.window_secs as f32;
+        self.current = Stats {
+            render_fps: self.frames as f32 / secs,
+            packets_per_sec: self.packets as f32 / secs,
+            skipped_per_sec: self.skipped as f32 / secs,
+            frames_skipped: self.total_skipped,
+            paced_per_sec: self.paced as f32 / secs,
+            quality_level: 0, // Filled in live by `LightingEngine::get_stats`
+        };
+        self.window_secs = 0.0;
+        self.frames = 0;
+        self.packets = 0;
+        self.skipped = 0;
+        self.paced = 0;
+        true
+    }
+}
+
+/// Consecutive renders over budget before quality drops a level
+const QUALITY_DOWN_FRAMES: u32 = 10;
+/// Consecutive renders under half the budget before quality comes back a level
+const QUALITY_UP_FRAMES: u32 = 120;
+const MAX_QUALITY_LEVEL: u8 = 3;
+
+/// Lowers the scene update rate while rendering runs over budget and restores it
+/// once renders fit comfortably again. Skipped frames resend the last rendered one.
+#[derive(Default)]
+struct AdaptiveQuality {
+    level: u8,
+    over: u32,
+    under: u32,
+    frame: u64,
+}
+
+impl AdaptiveQuality {
+    /// Whether this frame renders the scenes; otherwise the last rendered frame is reused
+    fn should_render(&mut self) -> bool {
+        self.frame += 1;
+        self.frame % (self.level as u64 + 1) == 0
+    }
+
+    /// Record how long a render took against the budget, moving the level with hysteresis
+    fn observe(&mut self, render_ms: f32, budget_ms: f32) {
+        if render_ms > budget_ms {
+            self.over += 1;
+            self.under = 0;
+        } else if render_ms < budget_ms * 0.5 {
+            self.under += 1;
+            self.over = 0;
+        } else {
+            self.over = 0;
+            self.under = 0;
+        }
+        if self.over >= QUALITY_DOWN_FRAMES && self.level < MAX_QUALITY_LEVEL {
+            self.level += 1;
+            self.over = 0;
+        } else if self.under >= QUALITY_UP_FRAMES && self.level > 0 {
+            self.level -= 1;
+            self.under = 0;
+        }
+    }
+}
+
+/// Crossfades the rendered frame when the active scenes change (`AppState::transition_ms`).
+/// The outgoing look is the last frame shown before the change, held still while it fades
+/// out; a change during a fade starts a new one from the blended frame on screen.
+#[derive(Default)]
+struct Crossfade {
+    scene_ids: Vec<u64>,
+    last_frame: HashMap<u64, Vec<[u8; 3]>>, // Output of the previous frame, blend included
+    fade: Option<(HashMap<u64, Vec<[u8; 3]>>, u64)>, // Outgoing frame and when the fade began
+}
+
+impl Crossfade {
+    fn apply(&mut self, scene_ids: &[u64], transition_ms: u32, now_ms: u64, strips: &mut [PixelStrip]) {
+        if self.scene_ids != scene_ids {
+            self.scene_ids = scene_ids.to_vec();
+            if transition_ms > 0 && !self.last_frame.is_empty() {
+                self.fade = Some((std::mem::take(&mut self.last_frame), now_ms));
+            }
+        }
+        if transition_ms == 0 {
+            self.fade = None;
+            self.last_frame.clear();
+            return;
+        }
+        if let Some((from, started)) = &self.fade {
+            let progress = now_ms.saturating_sub(*started) as f32 / transition_ms as f32;
+            if progress >= 1.0 {
+                self.fade = None;
+            } else {
+                for strip in strips.iter_mut() {
+                    // Strips added or resized since the switch just cut in
+                    if let Some(old) = from.get(&strip.id).filter(|d| d.len() == strip.data.len()) {
+                        for (pixel, old) in strip.data.iter_mut().zip(old) {
+                            *pixel = blend_pixel(*old, *pixel, "Normal", progress);
+                        }
+                    }
+                }
+            }
+        }
+        self.last_frame = strips.iter().map(|s| (s.id, s.data.clone())).collect();
+    }
+}
+
+/// Keeps DMX output under `NetworkConfig::max_fps` and holds back unchanged universes
+/// until `NetworkConfig::keepalive_ms` has passed since they last went out
+#[derive(Default)]
+struct OutputPacer {
+    next_frame_ms: f64,
+    last_sent: HashMap<(u16, Option<std::net::SocketAddr>), (UniversePacket, u64)>, // Universe and destination -> packet and when it went out
+}
+
+impl OutputPacer {
+    /// Whether a frame may go out at `now_ms` (0 fps = uncapped). Early frames are dropped,
+    /// so the next one that is due carries the latest look.
+    fn frame_due(&mut self, now_ms: u64, max_fps: u16) -> bool {
+        if max_fps == 0 {
+            return true;
+        }
+        let now = now_ms as f64;
+        if now < self.next_frame_ms {
+            return false;
+        }
+        // Step from the previous slot so the average rate holds; after a stall start over
+        let interval = 1000.0 / max_fps as f64;
+        self.next_frame_ms = if now - self.next_frame_ms > interval { now + interval } else { self.next_frame_ms + interval };
+        true
+    }
+
+    /// Drop packets identical to what last went out for their universe and destination,
+    /// unless that was `keepalive_ms` or longer ago. None keeps every packet.
+    fn retain_changed(&mut self, packets: &mut Vec<UniversePacket>, now_ms: u64, keepalive_ms: Option<u64>) {
+        let Some(keepalive_ms) = keepalive_ms else { return };
+        packets.retain(|p| match self.last_sent.get(&(p.universe, p.dst)) {
+            Some((last, at)) => last != p || now_ms.saturating_sub(*at) >= keepalive_ms,
+            None => true,
+        });
+        for p in packets.iter() {
+            self.last_sent.insert((p.universe, p.dst), (p.clone(), now_ms));
+        }
+    }
+
+    /// Something other than the paced frames went out (blackout); resend everything next frame
+    fn forget_sent(&mut self) {
+        self.last_sent.clear();
+    }
+}
+
+/// Per-channel output curve `out = (in/255)^gamma * 255`, used when packing DMX.
+/// Rebuilt only when `AppState::gamma` changes.
+struct GammaLut {
+    gamma: f32,
+    table: [u8; 256],
+}
+
+impl GammaLut {
+    fn new(gamma: f32) -> Self {
+        let mut lut = Self { gamma: f32::NAN, table: [0; 256] };
+        lut.set_gamma(gamma);
+        lut
+    }
+
+    fn set_gamma(&mut self, gamma: f32) {
+        let gamma = if gamma.is_finite() { gamma.clamp(crate::model::MIN_GAMMA, crate::model::MAX_GAMMA) } else { 1.0 };
+        if gamma == self.gamma {
+            return;
+        }
+        self.gamma = gamma;
+        for (i, slot) in self.table.iter_mut().enumerate() {
+            // Linear stays exact rather than trusting powf(1.0) to round-trip
+            *slot = if gamma == 1.0 {
+                i as u8
+            } else {
+                ((i as f32 / 255.0).powf(gamma) * 255.0).round() as u8
+            };
+        }
+    }
+
+    fn apply(&self, value: u8) -> u8 {
+        self.table[value as usize]
+    }
+}
+
+struct RippleWave {
+    origin: (f32, f32, Option<f32>),
+    birth_time: f32,
+}
+
+/// Rings and spawn timing of one Ripple effect instance
+#[derive(Default)]
+struct RippleState {
+    waves: Vec<RippleWave>,
+    last_spawn: f32,
+    last_beat: f64,
+    drawn: bool, // Rendered this frame; states left undrawn are dropped by `prune_ripples`
+}
+
 pub struct LightingEngine {
-    sender: SacnSource,
+    // Serial output: one sACN source per local bind address ("" = any interface)
+    senders: SenderSet,
+    // Threaded output when `AppState::output_threads` > 1, rebuilt when the count changes
+    output_pool: Option<OutputPool>,
     link: AblLink,
-    registered_universes: std::collections::HashSet<u16>,
+    sent_universes: std::collections::HashSet<u16>, // Every universe output so far, for blackout
     bind_ip: Option<String>,
     pub speed: f32,
     pub latency_ms: f32,
@@ -36,9 +349,9 @@ pub struct LightingEngine {
     pub hybrid_sync: bool, 
     pub audio_sensitivity: f32,
     audio_listener: Option<AudioListener>,
-    was_peaking: bool, // For edge detection
+    audio_device: Option<Option<String>>, // Input the listener was opened for; None = not opened yet
     pub current_beat: u8, // 1, 2, 3, 4
-    start_time: Instant,
+    clock: Box<dyn Clock>, // Render time; effects only see what this reports
     last_network: NetworkConfig,
     flywheel_beat: f64,
     last_update: std::time::Instant,
@@ -46,8 +359,7 @@ pub struct LightingEngine {
     sync_mode: bool, // true if locked, false if drifting/error
 
     // Audio BPM
-    last_tap_time: Option<Instant>,
-    tap_intervals: Vec<f64>,
+    beat_detector: BeatDetector,
     pub audio_bpm: f64,
 
     // Audio Snap Phase Tracking
@@ -55,15 +367,337 @@ pub struct LightingEngine {
     phase_error: f64, // How far off we are from audio beats (in beats)
     phase_correction_rate: f64, // How fast we correct (beats per second)
 
+    // True only on the frame an audio onset (rising edge) was detected
+    audio_onset: bool,
+    // True only on the frame an audio beat landed (a hit, or one the flywheel predicted)
+    audio_beat: bool,
+    audio_beat_count: u64, // Audio beats since launch
+    // Latency-compensated audio volume for this frame
+    audio_level: f32,
+    // Latency-compensated band energies for this frame, scaled by sensitivity
+    audio_bands: [f32; BAND_COUNT],
+    audio_flywheel: AudioFlywheel,
+    levels_tx: Option<std::sync::mpsc::Sender<AudioLevels>>, // UI meters, see `audio_levels_receiver`
+
+    // Effect/mask state lives in the renderer so it can also run without I/O
+    renderer: SceneRenderer,
+    output_flowing: bool, // Last frame reached the network without send errors
+    tap_bpm: Option<f64>, // Tapped tempo from AppState.bpm
+    midi_clock: Option<(f64, Instant)>, // Latest MIDI clock tempo and when it arrived (not persisted)
+    calibration_level: Option<u8>, // White calibration output; bypasses scenes while set (not persisted)
+    test_pattern_all: bool, // Diagnostic pattern on every strip (not persisted)
+    test_pattern_strips: std::collections::HashSet<u64>, // Diagnostic pattern on these strips only
+    blackout: bool, // Emergency kill: every frame goes out zeroed until released
+    paused: bool, // Output stopped on purpose; frames still render for the preview (not persisted)
+    preview: Option<PreviewServer>,
+    preview_port: Option<u16>, // Port the preview server was last (re)started for
+    stats: StatsCounter,
+    pub log_stats: bool, // Log `Stats` every window
+    // Rendered frame held while `AppState.frozen`, keyed by strip id
+    held_frame: Option<HashMap<u64, Vec<[u8; 3]>>>,
+    frozen_at: Option<f32>, // Engine time the current freeze began
+    effect_time_offset: f32, // Subtracted from effect time; grows by each freeze when resuming from the frozen phase
+    shuffle_rng: StdRng,
+    last_shuffle: Instant,
+    quality: AdaptiveQuality,
+    // Last scene render, reused on frames adaptive quality skips
+    last_rendered: Option<HashMap<u64, Vec<[u8; 3]>>>,
+    // A/B comparison frames keyed by strip id (not persisted)
+    snapshots: [Option<HashMap<u64, Vec<[u8; 3]>>>; SNAPSHOT_SLOTS],
+    showing_snapshot: Option<usize>, // Slot overlaid on the live output
+    pending_snapshot: Option<usize>, // Slot filled from the next rendered frame
+    gamma: GammaLut,
+    pacer: OutputPacer,
+    crossfade: Crossfade,
+    warned_unicast_ips: std::collections::HashSet<String>, // Invalid unicast targets already logged
+}
+
+/// Number of A/B snapshot slots
+pub const SNAPSHOT_SLOTS: usize = 2;
+
+/// Renders scenes into strip pixel data.
+/// Holds all per-effect state but no network, audio device or clock access, so the
+/// same code drives live output and `render_scene_to_frames`.
+pub struct SceneRenderer {
+    pub speed: f32,
+    // Audio features for the current frame, supplied by the caller
+    pub audio_level: f32,
+    pub audio_onset: bool,
+    pub audio_beat_count: u64, // Audio beats so far; effects step on changes (see `LightingEngine::audio_beat`)
+    pub audio_confidence: f32,
+    // Per-band energy (0.0-1.0), low to high; empty when no analysis is available
+    pub audio_bands: Vec<f32>,
+    pub bpm: f64, // Tempo behind `beat`, for converting beat-grid positions to effect time
+    pub quantize: String, // "beat" | "bar": edited effects restart on that grid; anything else = off
+    pub max_flash_hz: Option<f32>, // Flash rate limit from `AppState::max_flash_hz`
+    pub no_strobe: bool, // Flash effects hold a steady `NO_STROBE_LEVEL` instead of flashing
+    // Per mask/effect (scene, mask id or effect index): params fingerprint and the time its phase restarted
+    phase_anchors: HashMap<(Option<u64>, u64), (u64, f32)>,
+    rng: StdRng,
+    seed: u64,
+    // Per-strip streams for `sync_mode: "per_strip"`, seeded with `seed ^ strip id`
+    strip_rngs: HashMap<u64, StdRng>,
+    // (t, beat) when each rendering scene started; None = the loose masks
+    scene_clocks: HashMap<Option<u64>, (f32, f64)>,
+    current_scene: Option<u64>, // Scene being drawn, for `scene_elapsed`
+
     // Sparkle effect state tracking
     sparkle_states: Vec<SparklePixel>,
     // Pulse Wave effect state tracking
     pulse_states: Vec<PulseState>,
     // Glitch Sparkle effect state tracking
     glitch_states: Vec<GlitchPixel>,
-    glitch_sparkle_accumulator: f32,
+    glitch_sparkle_accumulator: f32, // Shared-mode spawn accumulator
+    glitch_strip_accumulators: HashMap<u64, f32>, // Per-strip spawn accumulators
     // Burst effect radius smoothing per-mask
     burst_radius_states: std::collections::HashMap<u64, f32>,
+    // Ripple effect state per (scene, effect index), keyed like `phase_anchors`
+    ripple_states: HashMap<(Option<u64>, u64), RippleState>,
+    current_effect: (Option<u64>, u64), // Global effect being drawn, for per-instance state
+    warned_mirrors: std::collections::HashSet<u64>, // Strips with a broken mirror already logged
+}
+
+impl SceneRenderer {
+    pub fn new() -> Self {
+        let mut rng = StdRng::from_entropy();
+        let seed = rng.next_u64();
+        Self::with_rng(rng, seed)
+    }
+
+    /// Renderer whose random effects (Sparkle, GlitchSparkle) repeat exactly for a given seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed), seed)
+    }
+
+    fn with_rng(rng: StdRng, seed: u64) -> Self {
+        Self {
+            speed: 1.0,
+            audio_level: 0.0,
+            audio_onset: false,
+            audio_beat_count: 0,
+            audio_confidence: 0.0,
+            audio_bands: Vec::new(),
+            bpm: 120.0,
+            quantize: String::new(),
+            max_flash_hz: None,
+            no_strobe: false,
+            phase_anchors: HashMap::new(),
+            rng,
+            seed,
+            strip_rngs: HashMap::new(),
+            scene_clocks: HashMap::new(),
+            current_scene: None,
+            sparkle_states: Vec::new(),
+            pulse_states: Vec::new(),
+            glitch_states: Vec::new(),
+            glitch_sparkle_accumulator: 0.0,
+            glitch_strip_accumulators: HashMap::new(),
+            burst_radius_states: std::collections::HashMap::new(),
+            ripple_states: HashMap::new(),
+            current_effect: (None, 0),
+            warned_mirrors: std::collections::HashSet::new(),
+        }
+    }
+
+    /// Random stream for one strip; depends only on the renderer seed and the strip id,
+    /// so a strip looks the same however many other strips there are
+    fn strip_rng(&mut self, strip_id: u64) -> &mut StdRng {
+        let seed = self.seed;
+        self.strip_rngs.entry(strip_id).or_insert_with(|| StdRng::seed_from_u64(seed ^ strip_id))
+    }
+
+    /// Clear the strips and draw one frame of `scene`.
+    /// Without a scene (or for unknown kinds) the loose `fallback_masks` are drawn instead.
+    /// Global effects referencing a preset are resolved against `presets`.
+    pub fn render(&mut self, scene: Option<&Scene>, fallback_masks: &[Mask], presets: &[EffectPreset], strips: &mut [PixelStrip], t: f32, beat: f64) {
+        let scene_id = scene.map(|s| s.id);
+        self.scene_clocks.retain(|id, _| *id == scene_id);
+        self.draw(scene, fallback_masks, presets, strips, t, beat);
+        self.prune_ripples();
+    }
+
+    /// `render` at the time `clock` reports, with beats derived from a fixed `bpm`
+    pub fn render_at(&mut self, clock: &dyn Clock, bpm: f64, scene: Option<&Scene>, fallback_masks: &[Mask], presets: &[EffectPreset], strips: &mut [PixelStrip]) {
+        let beat = clock.now_ms() as f64 / 1000.0 * bpm / 60.0;
+        self.bpm = bpm;
+        self.render(scene, fallback_masks, presets, strips, clock.now_secs(), beat);
+    }
+
+    /// Draw a stack of active scenes and composite them bottom to top by `Scene::layer`
+    /// (ties keep the given order), each with its opacity and blend mode.
+    /// The bottom layer always goes on normally since there is nothing below it to modulate.
+    /// An empty stack draws the loose `fallback_masks`.
+    pub fn render_layers(&mut self, scenes: &[&Scene], fallback_masks: &[Mask], presets: &[EffectPreset], strips: &mut [PixelStrip], t: f32, beat: f64) {
+        let mut stack = scenes.to_vec();
+        stack.sort_by_key(|s| s.layer);
+        if stack.is_empty() || (stack.len() == 1 && stack[0].opacity >= 1.0) {
+            return self.render(stack.first().copied(), fallback_masks, presets, strips, t, beat);
+        }
+        self.scene_clocks.retain(|id, _| id.is_some_and(|id| stack.iter().any(|s| s.id == id)));
+
+        let mut composite: Vec<Vec<[u8; 3]>> = strips.iter().map(|s| vec![[0, 0, 0]; s.pixel_count]).collect();
+        for (layer, scene) in stack.iter().enumerate() {
+            self.draw(Some(scene), fallback_masks, presets, strips, t, beat);
+            let mode = if layer == 0 { "Normal" } else { scene.effective_blend_mode() };
+            let opacity = scene.opacity.clamp(0.0, 1.0);
+            for (out, strip) in composite.iter_mut().zip(strips.iter()) {
+                for (below, pixel) in out.iter_mut().zip(&strip.data) {
+                    *below = blend_pixel(*below, *pixel, mode, opacity);
+                }
+            }
+        }
+        for (strip, data) in strips.iter_mut().zip(composite) {
+            strip.data = data;
+        }
+        self.prune_ripples();
+    }
+
+    /// Drop ripple state of effects that weren't drawn this frame, and reset the marks for the next
+    fn prune_ripples(&mut self) {
+        self.ripple_states.retain(|_, state| std::mem::take(&mut state.drawn));
+    }
+
+    fn draw(&mut self, scene: Option<&Scene>, fallback_masks: &[Mask], presets: &[EffectPreset], strips: &mut [PixelStrip], t: f32, beat: f64) {
+        let scene_id = scene.map(|s| s.id);
+        self.current_scene = scene_id;
+        self.scene_clocks.entry(scene_id).or_insert((t, beat));
+
+        // 1. Clear all strips (and repair any length drift so effects can index up to pixel_count)
+        for strip in strips.iter_mut() {
+            strip.data.fill([0, 0, 0]);
+            if strip.data.len() != strip.pixel_count {
+                log::debug!("Strip {} data length {} != pixel_count {}, resizing", strip.id, strip.data.len(), strip.pixel_count);
+                strip.set_pixel_count(strip.pixel_count);
+            }
+        }
+
+        // 2. Apply Scene or fallback to raw masks
+        match scene.map(|s| s.kind.as_str()) {
+            Some("Masks") => {
+                for mask in &scene.unwrap().masks {
+                    let t = self.phase_time((scene_id, mask.id), &mask.params, t, beat);
+                    self.apply_mask_to_strips(mask, strips, t, beat);
+                }
+            }
+            Some("Global") => {
+                for (i, config) in scene.unwrap().global_effects.iter().enumerate().filter(|(_, c)| !c.is_disabled()) {
+                    let effect = config.effect.resolve(presets);
+                    // Stateful effects keep absolute time so their particles' ages stay valid
+                    let effect_t = if STATEFUL_EFFECTS.contains(&effect.kind.as_str()) {
+                        t
+                    } else {
+                        self.phase_time((scene_id, i as u64), &effect.params, t, beat)
+                    };
+                    let effect = if effect.automation.is_empty() { effect } else { Cow::Owned(self.automate_effect(&effect, t, beat)) };
+                    self.current_effect = (scene_id, i as u64);
+                    self.apply_global_effect(&effect, strips, effect_t, beat, config.targets.as_ref());
+                }
+            }
+            _ => {
+                for mask in fallback_masks {
+                    let t = self.phase_time((None, mask.id), &mask.params, t, beat);
+                    self.apply_mask_to_strips(mask, strips, t, beat);
+                }
+            }
+        }
+
+        // 3. Mirrored strips take their source's output
+        crate::model::apply_mirrors(strips, &mut self.warned_mirrors);
+    }
+}
+
+impl SceneRenderer {
+    /// Effect time for one mask or global effect. With `quantize` on, an effect whose
+    /// params changed restarts its time-driven phase from the last beat or bar boundary,
+    /// so free-running effects stay locked to the grid across live edits. Beat-synced
+    /// phases already follow `beat` and are unaffected.
+    fn phase_time(&mut self, key: (Option<u64>, u64), params: &HashMap<String, serde_json::Value>, t: f32, beat: f64) -> f32 {
+        if !matches!(self.quantize.as_str(), "beat" | "bar") {
+            self.phase_anchors.clear();
+            return t;
+        }
+        let fingerprint = params_fingerprint(params);
+        let bpm = self.bpm;
+        let quantize = self.quantize.as_str();
+        let anchor = self.phase_anchors.entry(key).or_insert((fingerprint, 0.0));
+        if anchor.0 != fingerprint {
+            let boundary = quantize_beat(beat, quantize);
+            let since_boundary = if bpm > 0.0 { (beat - boundary) * 60.0 / bpm } else { 0.0 };
+            *anchor = (fingerprint, t - since_boundary as f32);
+        }
+        t - anchor.1
+    }
+
+    /// Time and beats elapsed since the scene being drawn started rendering
+    pub fn scene_elapsed(&self, t: f32, beat: f64) -> (f32, f64) {
+        self.scene_elapsed_for(self.current_scene, t, beat)
+    }
+
+    /// Time and beats elapsed since `scene_id` started rendering; zero when it isn't rendering
+    pub fn scene_elapsed_for(&self, scene_id: Option<u64>, t: f32, beat: f64) -> (f32, f64) {
+        match self.scene_clocks.get(&scene_id) {
+            Some((start_t, start_beat)) => ((t - start_t).max(0.0), (beat - start_beat).max(0.0)),
+            None => (0.0, 0.0),
+        }
+    }
+}
+
+/// Brightness flash effects hold when `no_strobe` is on
+const NO_STROBE_LEVEL: f32 = 0.3;
+
+/// Fastest Strobe `frequency_hz` rendered; higher values are clamped
+const MAX_STROBE_HZ: f32 = 25.0;
+
+/// Global effects that track particles by absolute time, so never get a quantized phase
+const STATEFUL_EFFECTS: &[&str] = &["Sparkle", "GlitchSparkle", "PulseWave", "Ripple"];
+
+/// The last beat-grid boundary at or before `beat`: whole beats for "beat", groups of
+/// four for "bar". Any other mode returns `beat` unchanged.
+pub fn quantize_beat(beat: f64, mode: &str) -> f64 {
+    let grid = match mode {
+        "beat" => 1.0,
+        "bar" => 4.0,
+        _ => return beat,
+    };
+    (beat / grid).floor() * grid
+}
+
+/// Hash of a param map that doesn't depend on its iteration order
+fn params_fingerprint(params: &HashMap<String, serde_json::Value>) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut entries: Vec<(&String, String)> = params.iter().map(|(k, v)| (k, v.to_string())).collect();
+    entries.sort();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    entries.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Seed used by `render_scene_to_frames`, so repeated runs produce identical frames
+pub const RENDER_SEED: u64 = 0x5EED_11AE;
+/// Frame rate `render_scene_to_frames` steps stateful effects at
+const RENDER_FPS: u64 = 60;
+/// Tempo assumed by `render_scene_to_frames` (the engine's manual default)
+const RENDER_BPM: f64 = 120.0;
+
+/// Render `scene` at `time_ms` without any network, audio or clock access.
+/// Stateful effects (Sparkle, PulseWave, Ripple...) are stepped from t=0 at a fixed
+/// frame rate, so the result matches a live run that started at t=0 with no audio
+/// input, at 120 BPM and master speed 1.0. Returns pixel data keyed by strip ID.
+pub fn render_scene_to_frames(scene: &Scene, presets: &[EffectPreset], strips: &[PixelStrip], time_ms: u64) -> HashMap<u64, Vec<[u8; 3]>> {
+    let mut renderer = SceneRenderer::with_seed(RENDER_SEED);
+    let mut strips = strips.to_vec();
+
+    let frame_ms = 1000 / RENDER_FPS;
+    let clock = MockClock::new(time_ms % frame_ms);
+    loop {
+        renderer.render_at(&clock, RENDER_BPM, Some(scene), &[], presets, &mut strips);
+        if clock.now_ms() >= time_ms {
+            break;
+        }
+        clock.advance(frame_ms);
+    }
+
+    strips.into_iter().map(|s| (s.id, s.data)).collect()
 }
 
 impl LightingEngine {
@@ -85,51 +719,112 @@ impl LightingEngine {
         link.enable(true);
         
         Self {
-            sender,
+            senders: SenderSet::new(sender),
+            output_pool: None,
             link,
-            registered_universes: std::collections::HashSet::new(),
+            sent_universes: std::collections::HashSet::new(),
             bind_ip: None,
             speed: 1.0,
             latency_ms: 0.0,
             use_flywheel: true,
             hybrid_sync: false,
             audio_sensitivity: 0.5,
-            audio_listener: AudioListener::new(), // Try to init
-            was_peaking: false,
+            audio_listener: None, // Opened on the first update, from `AudioConfig::input_device`
+            audio_device: None,
             current_beat: 1,
-            start_time: Instant::now(),
+            clock: Box::new(SystemClock::new()),
             last_network: NetworkConfig::default(),
             flywheel_beat: 0.0,
             last_update: Instant::now(),
             sync_error_timer: 0.0,
             sync_mode: true,
-            last_tap_time: None,
-            tap_intervals: Vec::new(),
+            beat_detector: BeatDetector::new(),
             audio_bpm: 0.0,
             last_audio_beat_time: None,
             phase_error: 0.0,
             phase_correction_rate: 0.5, // Correct half a beat per second when out of sync
-            sparkle_states: Vec::new(),
-            pulse_states: Vec::new(),
-            glitch_states: Vec::new(),
-            glitch_sparkle_accumulator: 0.0,
-            burst_radius_states: std::collections::HashMap::new(),
+            audio_onset: false,
+            audio_beat: false,
+            audio_beat_count: 0,
+            audio_level: 0.0,
+            audio_bands: [0.0; BAND_COUNT],
+            audio_flywheel: AudioFlywheel::new(),
+            levels_tx: None,
+            renderer: SceneRenderer::new(),
+            output_flowing: false,
+            tap_bpm: None,
+            midi_clock: None,
+            calibration_level: None,
+            test_pattern_all: false,
+            test_pattern_strips: std::collections::HashSet::new(),
+            blackout: false,
+            paused: false,
+            preview: None,
+            preview_port: None,
+            stats: StatsCounter::new(),
+            log_stats: false,
+            held_frame: None,
+            frozen_at: None,
+            effect_time_offset: 0.0,
+            shuffle_rng: StdRng::from_entropy(),
+            last_shuffle: Instant::now(),
+            quality: AdaptiveQuality::default(),
+            last_rendered: None,
+            snapshots: Default::default(),
+            showing_snapshot: None,
+            pending_snapshot: None,
+            gamma: GammaLut::new(crate::model::DEFAULT_GAMMA),
+            pacer: OutputPacer::default(),
+            crossfade: Crossfade::default(),
+            warned_unicast_ips: std::collections::HashSet::new(),
         }
     }
 
     pub fn update(&mut self, state: &mut AppState) {
 
 
+        // (Re)start the live preview server when its port changes
+        if state.preview_port != self.preview_port {
+            self.preview_port = state.preview_port;
+            self.preview = None;
+            if let Some(port) = state.preview_port {
+                match PreviewServer::start(port) {
+                    Ok(server) => self.preview = Some(server),
+                    Err(e) => log::warn!("Failed to start preview server on port {}: {}", port, e),
+                }
+            }
+        }
+
         // Sync Audio Params from State
         self.latency_ms = state.audio.latency_ms;
         self.use_flywheel = state.audio.use_flywheel;
         self.hybrid_sync = state.audio.hybrid_sync;
         self.audio_sensitivity = state.audio.sensitivity;
+        if self.audio_device.as_ref() != Some(&state.audio.input_device) {
+            // Drop the old stream first; some backends open one capture stream at a time
+            self.audio_listener = None;
+            self.audio_listener = AudioListener::new(state.audio.input_device.as_deref());
+            self.audio_device = Some(state.audio.input_device.clone());
+            if self.audio_listener.is_none() {
+                log::warn!("No audio input available, audio-reactive effects stay idle");
+            }
+        }
+        self.tap_bpm = state.bpm.filter(|bpm| *bpm > 0.0);
 
         let now = Instant::now();
+        if let Some(interval) = state.shuffle_interval_ms.filter(|ms| *ms > 0) {
+            if now.duration_since(self.last_shuffle).as_millis() >= interval as u128 {
+                self.shuffle(state);
+            }
+        }
         let dt = now.duration_since(self.last_update).as_secs_f64();
         self.last_update = now;
-        let t = self.start_time.elapsed().as_secs_f32();
+        if self.stats.frame(dt, state.network.max_fps) && self.log_stats {
+            let s = self.stats.current;
+            log::info!("Engine: {:.1} fps, {:.0} packets/s, {:.1} skipped/s ({} total), {:.1} held by Max FPS/s",
+                s.render_fps, s.packets_per_sec, s.skipped_per_sec, s.frames_skipped, s.paced_per_sec);
+        }
+        let t = self.clock.now_secs();
         
         // Capture Link Beat
         let mut session_state = SessionState::new();
@@ -152,68 +847,76 @@ impl LightingEngine {
 
         // Hybrid Sync / Audio logic
         let mut force_snap = false;
+        self.audio_onset = false;
+        self.audio_beat = false;
         if let Some(audio) = &self.audio_listener {
              // Read Volume (handle poisoned mutex gracefully)
-             let vol = audio.current_volume.lock()
+             let raw_vol = audio.current_volume.lock()
                  .map(|v| *v)
                  .unwrap_or_else(|poisoned| {
                      log::warn!("Audio mutex poisoned, recovering");
                      *poisoned.into_inner()
                  });
 
-             // Detect Peak using Sensitivity
-             // Sensitivity 0.0 = Need HUGE volume (Threshold 1.0)
-             // Sensitivity 1.0 = React to silence (Threshold 0.0)
-             // Let's map Sensitivity 0..1 to Threshold 0.5 .. 0.01
-             let threshold = 0.5 - (self.audio_sensitivity * 0.45);
-
-             let is_peaking = vol > threshold;
+             // Sample the feature history `latency_ms` in the past (or ahead, when negative)
+             // so audio-driven visuals line up with what is heard. A NaN/inf latency from a
+             // hand-edited file reads live levels instead of panicking.
+             let sample_time = if self.latency_ms >= 0.0 {
+                 std::time::Duration::try_from_secs_f32(self.latency_ms / 1000.0).ok().and_then(|d| now.checked_sub(d))
+             } else {
+                 std::time::Duration::try_from_secs_f32(-self.latency_ms / 1000.0).ok().and_then(|d| now.checked_add(d))
+             };
+             let (vol, bands, newest_frame) = match audio.features.lock() {
+                 Ok(buffer) => (
+                     sample_time.and_then(|at| buffer.sample(at)).unwrap_or(raw_vol),
+                     sample_time.and_then(|at| buffer.sample_bands(at)).unwrap_or_default(),
+                     buffer.latest_time(),
+                 ),
+                 Err(_) => (raw_vol, [0.0; BAND_COUNT], None),
+             };
+
+             // Flywheel: fill stalls from the last tempo/level, re-sync once audio returns
+             let (vol, resumed) = self.audio_flywheel.update(
+                 now, newest_frame, vol, self.flywheel_beat, self.use_flywheel, dt as f32,
+             );
+             if resumed && self.hybrid_sync {
+                 // Next real hit snaps the phase instead of being compared to stale history
+                 self.last_audio_beat_time = None;
+                 self.phase_error = 0.0;
+             }
+             self.audio_level = vol;
+             // Sensitivity is a gain on the bands (0.5 = unity); stale bands fade with confidence
+             let gain = self.audio_sensitivity * 2.0 * self.audio_flywheel.confidence;
+             self.audio_bands = bands.map(|band| (band * gain).clamp(0.0, 1.0));
+
+             // Beats: hits in the level envelope plus, with the flywheel, predicted beats between them.
+             // Predicted levels never count as hits, so they can't feed the tempo or hybrid sync.
+             let level = (!self.audio_flywheel.predicting).then_some(vol);
+             let update = self.beat_detector.process(now, level, self.audio_sensitivity, self.use_flywheel);
+             if let Some(bpm) = self.beat_detector.bpm() {
+                 self.audio_bpm = bpm;
+             }
+             if update.beat {
+                 self.audio_beat_count += 1;
+                 self.audio_beat = true;
+             }
 
-             // Rising Edge Detection
-             if is_peaking && !self.was_peaking {
+             if update.onset {
                  // AUDIO HIT!
+                 self.audio_onset = true;
 
                  let now_t = Instant::now();
 
-                 // 1. Audio BPM Detection (Tap Tempo)
-                 if let Some(last) = self.last_tap_time {
-                     let delta = now_t.duration_since(last).as_secs_f64();
-                     // Filter reasonable range: 30 BPM (2.0s) to 200 BPM (0.3s)
-                     if delta > 0.3 && delta < 2.0 {
-                         self.tap_intervals.push(delta);
-                         if self.tap_intervals.len() > 8 {
-                             self.tap_intervals.remove(0);
-                         }
-
-                         // Average with outlier filtering
-                         let mut sorted = self.tap_intervals.clone();
-                         sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-                         // Use median or trimmed mean for better accuracy
-                         let mid = sorted.len() / 2;
-                         let avg_interval = if sorted.len() > 3 {
-                             // Use middle 50% of values
-                             let start = sorted.len() / 4;
-                             let end = 3 * sorted.len() / 4;
-                             sorted[start..end].iter().sum::<f64>() / (end - start) as f64
-                         } else {
-                             sorted[mid]
-                         };
-
-                         self.audio_bpm = 60.0 / avg_interval;
-                     } else if delta > 2.0 {
-                         // Reset if too long silence
-                         self.tap_intervals.clear();
-                     }
-                 }
-                 self.last_tap_time = Some(now_t);
-
                  if self.hybrid_sync {
                      // NEW APPROACH: Track expected beat time and calculate phase error
 
-                     // Get current effective BPM
+                     // Get current effective BPM; under a MIDI clock, hits pull the clock-driven beat into phase
                      let current_bpm = if self.link.num_peers() > 0 {
                          tempo
+                     } else if let Some(bpm) = self.midi_clock_bpm() {
+                         bpm
+                     } else if let Some(bpm) = self.tap_bpm {
+                         bpm
                      } else if self.audio_bpm > 30.0 {
                          self.audio_bpm
                      } else {
@@ -275,12 +978,15 @@ impl LightingEngine {
                      }
                  }
              }
-             self.was_peaking = is_peaking;
         }
 
         // Determine effective tempo
         let effective_tempo = if link_peers > 0 {
              tempo // Link Tempo
+        } else if let Some(bpm) = self.midi_clock_bpm() {
+             bpm // MIDI Clock
+        } else if let Some(bpm) = self.tap_bpm {
+             bpm // Tapped Tempo
         } else if self.audio_bpm > 30.0 {
              self.audio_bpm // Audio Tempo
         } else {
@@ -366,139 +1072,525 @@ impl LightingEngine {
             0.0
         };
 
-        // 1. Clear all strips
-        for strip in &mut state.strips {
-            strip.data = vec![[0, 0, 0]; strip.pixel_count];
+        // 1-2. Render the active scene layers (or loose masks) into strip data
+        self.renderer.speed = self.speed;
+        self.renderer.bpm = effective_tempo;
+        self.renderer.quantize.clone_from(&state.quantize);
+        self.renderer.max_flash_hz = state.max_flash_hz;
+        self.renderer.no_strobe = state.no_strobe;
+        self.renderer.audio_level = if self.audio_listener.is_some() { self.audio_level } else { 0.0 };
+        self.renderer.audio_onset = self.audio_onset;
+        self.renderer.audio_beat_count = self.audio_beat_count;
+        self.renderer.audio_confidence = self.get_audio_confidence();
+        self.renderer.audio_bands = if self.audio_listener.is_some() { self.audio_bands.to_vec() } else { Vec::new() };
+        if let Some(tx) = &self.levels_tx {
+            let levels = AudioLevels { rms: self.renderer.audio_level, bands: self.audio_bands };
+            if tx.send(levels).is_err() {
+                self.levels_tx = None; // Receiver dropped
+            }
         }
-
-        // 2. Apply Scene or fallback to raw masks
-        if let Some(sel_id) = state.selected_scene_id {
-            if let Some(scene) = state.scenes.iter().find(|s| s.id == sel_id).cloned() {
-                match scene.kind.as_str() {
-                    "Masks" => {
-                        for mask in &scene.masks {
-                            self.apply_mask_to_strips(mask, &mut state.strips, t, beat);
-                        }
-                    }
-                    "Global" => {
-                        for config in &scene.global_effects {
-                             self.apply_global_effect(&config.effect, &mut state.strips, t, beat, config.targets.as_ref());
-                        }
+        let layers: Vec<&Scene> = state.active_scene_ids.iter()
+            .filter_map(|id| state.scenes.iter().find(|s| s.id == *id))
+            .collect();
+        // Disabled strips sit out rendering; they go back in place, black, right after
+        let parked = park_disabled_strips(&mut state.strips);
+        if let Some(level) = self.calibration_level {
+            // Uniform white on every strip; packing below still applies color order
+            for strip in state.strips.iter_mut() {
+                strip.set_pixel_count(strip.pixel_count);
+                strip.data.fill([level, level, level]);
+            }
+        } else if state.frozen {
+            // Keep re-sending the held frame; effect time and state stop advancing
+            self.frozen_at.get_or_insert(t);
+            let held = self.held_frame.as_ref().filter(|held| {
+                state.strips.iter().all(|s| held.get(&s.id).is_some_and(|d| d.len() == s.pixel_count))
+            });
+            if let Some(held) = held {
+                for strip in state.strips.iter_mut() {
+                    strip.data.clone_from(&held[&strip.id]);
+                }
+            } else {
+                // First frozen frame, or the layout changed under the hold: capture a fresh frame
+                self.renderer.render_layers(&layers, &state.masks, &state.presets, &mut state.strips, t - self.effect_time_offset, beat);
+                self.held_frame = Some(state.strips.iter().map(|s| (s.id, s.data.clone())).collect());
+            }
+        } else {
+            // Unfreezing jumps to the current time unless resuming from the frozen phase
+            if let Some(frozen_at) = self.frozen_at.take() {
+                if state.freeze_resume_phase {
+                    self.effect_time_offset += t - frozen_at;
+                }
+                self.held_frame = None;
+            }
+            let budget = state.render_budget_ms.filter(|ms| *ms > 0.0);
+            if budget.is_none() {
+                self.quality = AdaptiveQuality::default();
+                self.last_rendered = None;
+            }
+            let reusable = self.last_rendered.as_ref().filter(|last| {
+                state.strips.iter().all(|s| last.get(&s.id).is_some_and(|d| d.len() == s.pixel_count))
+            });
+            match reusable {
+                Some(last) if budget.is_some() && !self.quality.should_render() => {
+                    for strip in state.strips.iter_mut() {
+                        strip.data.clone_from(&last[&strip.id]);
                     }
-                    _ => {
-                        for mask in &state.masks {
-                            self.apply_mask_to_strips(mask, &mut state.strips, t, beat);
-                        }
+                }
+                _ => {
+                    let started = Instant::now();
+                    self.renderer.render_layers(&layers, &state.masks, &state.presets, &mut state.strips, t - self.effect_time_offset, beat);
+                    if let Some(budget) = budget {
+                        self.quality.observe(started.elapsed().as_secs_f32() * 1000.0, budget);
+                        self.last_rendered = Some(state.strips.iter().map(|s| (s.id, s.data.clone())).collect());
                     }
                 }
-            } else {
-                // Selected scene not found, fallback
-                for mask in &state.masks {
-                    self.apply_mask_to_strips(mask, &mut state.strips, t, beat);
+            }
+        }
+        if self.calibration_level.is_none() && !state.frozen {
+            self.crossfade.apply(&state.active_scene_ids, state.transition_ms, self.clock.now_ms(), &mut state.strips);
+        }
+        // Snapshots store the rendered look, before overlays, blackout or the master stage touch it
+        if self.calibration_level.is_none() {
+            if let Some(slot) = self.pending_snapshot.take() {
+                self.snapshots[slot] = Some(state.strips.iter().map(|s| (s.id, s.data.clone())).collect());
+            }
+        }
+        // A shown snapshot covers the live render; effects keep running underneath
+        if let Some(snapshot) = self.showing_snapshot.and_then(|slot| self.snapshots[slot].as_ref()) {
+            if self.calibration_level.is_none() {
+                overlay_snapshot(&mut state.strips, snapshot);
+            }
+        }
+        unpark_strips(&mut state.strips, parked);
+        if self.blackout {
+            for strip in state.strips.iter_mut() {
+                strip.data.fill([0, 0, 0]);
+            }
+        }
+
+        // Master multiply stage: scales the whole frame after rendering, master on top of each strip's dimmer.
+        // This is the only place output levels are scaled; any curve correction belongs after it.
+        let master = startup_gain(self.clock.now_ms(), state.startup_fade_ms) * state.master_brightness.clamp(0.0, 1.0);
+        for strip in state.strips.iter_mut() {
+            let gain = master * strip.brightness.clamp(0.0, 1.0);
+            if gain < 1.0 {
+                for pixel in strip.data.iter_mut() {
+                    *pixel = scale_pixel(*pixel, gain);
                 }
             }
-        } else {
-            // No scene selected: use masks directly
-            for mask in &state.masks {
-                self.apply_mask_to_strips(mask, &mut state.strips, t, beat);
+        }
+
+        // Test pattern goes out raw: after the master stage so nothing scales it, but blackout still wins
+        if !self.blackout && (self.test_pattern_all || !self.test_pattern_strips.is_empty()) {
+            for strip in state.strips.iter_mut() {
+                if strip.enabled && (self.test_pattern_all || self.test_pattern_strips.contains(&strip.id)) {
+                    strip.set_pixel_count(strip.pixel_count);
+                    for (i, pixel) in strip.data.iter_mut().enumerate() {
+                        *pixel = test_pattern_pixel(i);
+                    }
+                }
             }
         }
 
+        // Preview clients get the linear frame: after master, blackout and test pattern, but
+        // before gamma, the min-brightness floor, color-order packing and the Max FPS pacer
+        if let Some(preview) = &self.preview {
+            preview.publish(&state.strips);
+        }
+
+        if self.paused {
+            self.output_flowing = false;
+            return;
+        }
+
+        let now_ms = self.clock.now_ms();
+        if !self.pacer.frame_due(now_ms, state.network.max_fps) {
+            self.stats.paced += 1;
+            return;
+        }
+
         // 3. Send to sACN
+        self.gamma.set_gamma(state.gamma);
         // Coalesce data by universe
         let mut universe_data: std::collections::HashMap<u16, Vec<u8>> = std::collections::HashMap::new();
         
         let global_universe_offset = state.network.universe.saturating_sub(1);
 
-        for strip in &state.strips {
+        for strip in state.strips.iter().filter(|s| s.enabled) {
              // specific strip universe + global offset (clamped to valid sACN range 1-63999)
              let u = strip.universe.saturating_add(global_universe_offset).min(63999).max(1);
 
              // sACN allows multiple strips in one universe if channels don't overlap
-             let start = (strip.start_channel as usize).saturating_sub(1);
-             
              // Ensure we have a buffer (512 bytes for DMX)
              let entry = universe_data.entry(u).or_insert_with(|| vec![0; 512]);
-             
-             for (i, pixel) in strip.data.iter().enumerate() {
-                 let idx = start + i * 3;
-                 // Bounds check: ensure idx, idx+1, idx+2 are all valid
-                 if let Some(max_idx) = idx.checked_add(2) {
-                     if max_idx < entry.len() {
-                         match strip.color_order.as_str() {
-                             "GRB" => {
-                                 entry[idx] = pixel[1];   // G
-                                 entry[idx+1] = pixel[0]; // R
-                                 entry[idx+2] = pixel[2]; // B
-                             },
-                             "BGR" => {
-                                 entry[idx] = pixel[2];   // B
-                                 entry[idx+1] = pixel[1]; // G
-                                 entry[idx+2] = pixel[0]; // R
-                             },
-                             _ => { // RGB
-                                 entry[idx] = pixel[0];   // R
-                                 entry[idx+1] = pixel[1]; // G
-                                 entry[idx+2] = pixel[2]; // B
-                             }
-                         }
-                     }
-                 }
-             }
+             pack_strip(strip, &self.gamma, entry);
         }
     
         
         // Debug: Log color data before sending
         static mut LAST_COLOR_LOG: f32 = 0.0;
 
-        for (u, data) in universe_data {
-            if !self.registered_universes.contains(&u) {
-                match self.sender.register_universe(u) {
-                    Ok(_) => {
-                        self.registered_universes.insert(u);
-                        println!("Registered sACN Universe {}", u);
-                    },
-                    Err(e) => {
-                        println!("Failed to register sACN Universe {}: {:?}", u, e);
+        let unicast = self.unicast_destinations(state);
+        let mut packets: Vec<UniversePacket> = universe_data.iter()
+            .flat_map(|(&u, data)| self.universe_packets(state, u, data, &unicast))
+            .collect();
+        let total = packets.len();
+        self.pacer.retain_changed(&mut packets, now_ms, state.network.keepalive_ms);
+        if packets.is_empty() && total > 0 {
+            return; // Nothing changed; nodes still show the last frame
+        }
+        let (sent, failed) = self.send_packets(state, packets);
+        self.output_flowing = sent > 0 && failed == 0;
+    }
+
+    /// Immediately send zeroed frames to every active universe, repeated to survive packet loss.
+    /// Covers universes registered earlier as well as those the current strips map to.
+    pub fn output_all_black(&mut self, state: &AppState) {
+        const REPEATS: usize = 3;
+        let offset = state.network.universe.saturating_sub(1);
+        let mut universes: Vec<u16> = self.sent_universes.iter().copied()
+            .chain(state.strips.iter().map(|s| s.universe.saturating_add(offset).min(63999).max(1)))
+            .collect();
+        universes.sort_unstable();
+        universes.dedup();
+
+        let zeros = vec![0u8; 512];
+        let unicast = self.unicast_destinations(state);
+        self.pacer.forget_sent();
+        for _ in 0..REPEATS {
+            let packets = universes.iter()
+                .flat_map(|&u| self.universe_packets(state, u, &zeros, &unicast))
+                .collect();
+            self.send_packets(state, packets);
+        }
+        log::info!("Sent blackout to {} universe(s)", universes.len());
+    }
+
+    /// Latch blackout on or off. Engaging it sends `output_all_black` right away;
+    /// releasing it lets the active scene render again on the next frame.
+    pub fn set_blackout(&mut self, state: &AppState, on: bool) {
+        self.blackout = on;
+        if on {
+            self.output_all_black(state);
+        }
+    }
+
+    pub fn is_blackout(&self) -> bool {
+        self.blackout
+    }
+
+    /// Stop or resume sACN output. Pausing under `NetworkConfig::pause_policy` "blackout"
+    /// sends `output_all_black` first; otherwise output just stops and nodes keep their
+    /// last frame (or blank on their own data-loss timeout).
+    pub fn set_paused(&mut self, state: &AppState, on: bool) {
+        if on && !self.paused && state.network.pause_policy == "blackout" {
+            self.output_all_black(state);
+        }
+        self.paused = on;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Store the next rendered frame in `slot` (0 = A, 1 = B), replacing its old frame.
+    /// The frame is taken before the master stage, so showing it later is scaled only once.
+    pub fn take_snapshot(&mut self, slot: usize) {
+        if slot < SNAPSHOT_SLOTS {
+            self.pending_snapshot = Some(slot);
+        }
+    }
+
+    /// Overlay `slot` on the output, or switch back to live with None. Empty slots are ignored.
+    pub fn show_snapshot(&mut self, slot: Option<usize>) {
+        self.showing_snapshot = slot.filter(|&slot| self.has_snapshot(slot));
+    }
+
+    /// Show `slot`, or go back to live if it is already showing
+    pub fn toggle_snapshot(&mut self, slot: usize) {
+        let next = if self.showing_snapshot == Some(slot) { None } else { Some(slot) };
+        self.show_snapshot(next);
+    }
+
+    pub fn showing_snapshot(&self) -> Option<usize> {
+        self.showing_snapshot
+    }
+
+    pub fn has_snapshot(&self, slot: usize) -> bool {
+        self.snapshots.get(slot).is_some_and(|s| s.is_some())
+    }
+
+    /// Parse the global unicast targets, skipping entries that aren't IP addresses
+    /// with a warning the first time each one is seen
+    fn unicast_destinations(&mut self, state: &AppState) -> Vec<std::net::SocketAddr> {
+        let port = state.network.protocol.port();
+        state.network.unicast_ips.iter()
+            .filter_map(|ip| match ip.trim().parse::<std::net::IpAddr>() {
+                Ok(addr) => Some(std::net::SocketAddr::new(addr, port)),
+                Err(_) => {
+                    if self.warned_unicast_ips.insert(ip.clone()) {
+                        log::warn!("Skipping unicast target '{}': not an IP address", ip);
                     }
+                    None
                 }
-            }
+            })
+            .collect()
+    }
 
-            let priority = 100; // Default priority
-            let dst_ip: Option<std::net::SocketAddr> = if state.network.use_multicast {
-                None
-            } else {
-                if let Ok(ip) = state.network.unicast_ip.parse::<std::net::IpAddr>() {
-                    Some(std::net::SocketAddr::new(ip, 5568))
-                } else {
-                    None // Fallback
-                }
-            };
+    /// Address one universe (DMX slots without start code) using its route or the global config:
+    /// one packet for multicast, else one per unicast destination (`unicast`, from
+    /// `unicast_destinations`). Empty when it should be skipped (no valid unicast IP).
+    fn universe_packets(&self, state: &AppState, u: u16, data: &[u8], unicast: &[std::net::SocketAddr]) -> Vec<UniversePacket> {
+        // Routed universes pick their own interface/destination, the rest use the global config
+        let route = state.network.route_for(u);
+        let bind = route
+            .and_then(|r| r.bind.clone())
+            .or_else(|| state.bind_address.clone())
+            .unwrap_or_default();
+
+        let dsts: Vec<Option<std::net::SocketAddr>> = match route {
+            Some(r) => match r.dest.as_deref() {
+                None => vec![None],
+                // Unicast selected but the IP is invalid: skip rather than fall back to multicast
+                Some(ip) => ip.trim().parse::<std::net::IpAddr>().ok()
+                    .map(|ip| Some(std::net::SocketAddr::new(ip, state.network.protocol.port())))
+                    .into_iter()
+                    .collect(),
+            },
+            None if state.network.use_multicast => vec![None],
+            None => unicast.iter().copied().map(Some).collect(),
+        };
+
+        let mut fixed_data = vec![0u8]; // Start Code
+        fixed_data.extend_from_slice(data);
+        dsts.into_iter()
+            .map(|dst| UniversePacket { universe: u, bind: bind.clone(), dst, data: fixed_data.clone(), protocol: state.network.protocol })
+            .collect()
+    }
 
-            // Only send if we have a valid config (if Unicast was selected but invalid IP, we might SKIP or fall back)
-            // User code implies we should try to send.
-            // If !multicast and invalid IP -> dst_ip is None -> Sends Multicast?
-            // Let's explicitly check:
-            if !state.network.use_multicast && dst_ip.is_none() {
-                // Invalid Unicast IP, skip or log
-                continue;
+    /// Send a frame's packets, serially or through the output pool, and return (sent, failed).
+    /// Returns once every packet is on the wire, so a sync packet may follow.
+    fn send_packets(&mut self, state: &AppState, packets: Vec<UniversePacket>) -> SendResult {
+        let threads = state.output_threads as usize;
+        let threads = if threads <= 1 { 0 } else { threads };
+        if self.output_pool.as_ref().map_or(0, |p| p.threads()) != threads {
+            // Universes are about to move to other sources: end their old streams first,
+            // so receivers never see the old and new source at once
+            match self.output_pool.take() {
+                Some(pool) => drop(pool), // Workers terminate their universes as they exit
+                None => self.senders.terminate_all(),
+            }
+            if threads > 1 {
+                log::info!("Sending sACN on {} output threads", threads);
+                self.output_pool = Some(OutputPool::new(threads, &self.senders));
             }
-            // let _ = self.sender.send(&[u], &data, Some(priority), dst_ip, None);
-            let mut fixed_data = vec![0u8]; // Start Code
-            fixed_data.extend_from_slice(&data);
+        }
 
-            match self.sender.send(&[u], &fixed_data, Some(200), dst_ip, None) {
-                Ok(_) => {
-                    // Success, verbose logging might flood
-                }
-                Err(e) => {
-                    println!("sACN Error sending to U{} (Dest: {:?}): {:?}", u, dst_ip, e);
+        self.sent_universes.extend(packets.iter().map(|p| p.universe));
+        let (sent, failed) = match &self.output_pool {
+            Some(pool) => pool.send_all(packets),
+            None => {
+                let sent = packets.iter().filter(|p| self.senders.send(p)).count();
+                (sent, packets.len() - sent)
+            }
+        };
+        self.stats.packets += sent as u32;
+        (sent, failed)
+    }
+
+    pub fn get_bpm(&self) -> f64 {
+        let mut session_state = SessionState::new();
+        self.link.capture_app_session_state(&mut session_state);
+        session_state.tempo()
+    }
+
+    pub fn get_beat(&self) -> f64 {
+        if self.use_flywheel {
+            self.flywheel_beat
+        } else {
+            // Need to capture fresh or store last raw beat?
+            // self.flywheel logic already captures raw beat in update.
+            // But update is called once per frame.
+            // Let's store raw_beat in struct or just assume flywheel_beat is kept in sync if disabled?
+            // Better: update() logic should set flywheel_beat = link_beat if disabled.
+            self.flywheel_beat 
+        }
+    }
+    
+    pub fn get_time(&self) -> f32 {
+        self.clock.now_secs()
+    }
+
+    /// Replace the render clock, e.g. with a `MockClock` to step effects deterministically
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+    
+    /// Output uniform white at `level` on all strips for wiring / white balance checks.
+    /// `None` returns to rendering the active scene.
+    pub fn set_calibration(&mut self, level: Option<u8>) {
+        self.calibration_level = level;
+    }
+
+    /// Tempo effects were last rendered at (Link, tapped, audio or manual)
+    pub fn tempo(&self) -> f64 {
+        self.renderer.bpm
+    }
+
+    pub fn get_calibration(&self) -> Option<u8> {
+        self.calibration_level
+    }
+
+    /// Randomize the active scene's effect params now and restart the shuffle timer
+    pub fn shuffle(&mut self, state: &mut AppState) -> usize {
+        self.last_shuffle = Instant::now();
+        state.shuffle_active_scene(&mut self.shuffle_rng)
+    }
+
+    /// Reseed the shuffle RNG so a sequence of shuffles can be reproduced
+    pub fn seed_shuffle(&mut self, seed: u64) {
+        self.shuffle_rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Show the diagnostic pattern (see `test_pattern_pixel`) on one strip, or on all strips with `None`.
+    /// Turning the global pattern off also clears every per-strip one.
+    pub fn set_test_pattern(&mut self, strip_id: Option<u64>, on: bool) {
+        match strip_id {
+            Some(id) if on => { self.test_pattern_strips.insert(id); }
+            Some(id) => { self.test_pattern_strips.remove(&id); }
+            None => {
+                self.test_pattern_all = on;
+                if !on {
+                    self.test_pattern_strips.clear();
                 }
             }
         }
     }
 
+    /// Whether the pattern is showing on `strip_id`, or globally with `None`
+    pub fn is_test_pattern(&self, strip_id: Option<u64>) -> bool {
+        match strip_id {
+            Some(id) => self.test_pattern_all || self.test_pattern_strips.contains(&id),
+            None => self.test_pattern_all,
+        }
+    }
+
+    /// Render/output counters from the last completed stats window
+    pub fn get_stats(&self) -> Stats {
+        Stats { quality_level: self.quality.level, ..self.stats.current }
+    }
+
+    /// True when the last frame was sent on every universe without errors
+    pub fn is_output_flowing(&self) -> bool {
+        self.output_flowing
+    }
+
+    /// How much the current audio level can be trusted (0..1).
+    /// Drops while the flywheel is predicting through an input stall; 0 without audio input.
+    pub fn get_audio_confidence(&self) -> f32 {
+        if self.audio_listener.is_some() { self.audio_flywheel.confidence } else { 0.0 }
+    }
+
+    /// Number of the audio beat that landed this frame (counting from 1), or None. Beats are
+    /// detected hits plus, with the flywheel on, beats predicted between them.
+    pub fn audio_beat(&self) -> Option<u64> {
+        self.audio_beat.then_some(self.audio_beat_count)
+    }
+
+    /// Channel receiving the audio level and band energies effects saw, once per frame.
+    /// Replaces any earlier receiver.
+    pub fn audio_levels_receiver(&mut self) -> std::sync::mpsc::Receiver<AudioLevels> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.levels_tx = Some(tx);
+        rx
+    }
+
+    /// Follow an external MIDI clock's tempo (ahead of tap and audio, behind Link); None when
+    /// the clock stopped. A clock that goes quiet for `MIDI_CLOCK_TIMEOUT` is dropped as well.
+    pub fn set_midi_clock(&mut self, bpm: Option<f64>) {
+        self.midi_clock = bpm.filter(|bpm| bpm.is_finite() && *bpm > 0.0).map(|bpm| (bpm, Instant::now()));
+    }
+
+    fn midi_clock_bpm(&self) -> Option<f64> {
+        self.midi_clock.filter(|(_, at)| at.elapsed() < MIDI_CLOCK_TIMEOUT).map(|(bpm, _)| bpm)
+    }
+
+    /// Restart the beat count at the downbeat, e.g. on MIDI Start
+    pub fn restart_beat(&mut self) {
+        self.flywheel_beat = 0.0;
+        self.phase_error = 0.0;
+    }
+
+    /// Time and beats since `scene_id` started rendering (used by one-shot motion like clamped scanners)
+    pub fn get_scene_elapsed(&self, scene_id: Option<u64>) -> (f32, f64) {
+        self.renderer.scene_elapsed_for(scene_id, self.get_time(), self.get_beat())
+    }
+
+    pub fn get_sync_info(&self) -> (String, f64) {
+        let peers = self.link.num_peers();
+        if peers > 0 {
+             let mut session_state = SessionState::new();
+             self.link.capture_app_session_state(&mut session_state);
+             (format!("LINK ({} Peers)", peers), session_state.tempo())
+        } else if let Some(bpm) = self.midi_clock_bpm() {
+             ("MIDI CLOCK".to_string(), bpm)
+        } else if let Some(bpm) = self.tap_bpm {
+             ("TAP".to_string(), bpm)
+        } else if self.audio_bpm > 30.0 {
+             ("AUDIO".to_string(), self.audio_bpm)
+        } else {
+             ("MANUAL".to_string(), 120.0 * self.speed as f64)
+        }
+    }
+}
+
+impl SceneRenderer {
+    /// Current value of an audio modulation source, 0.0 when unavailable
+    fn modulation_source(&self, m: &AudioModulation) -> f32 {
+        match m.source.as_str() {
+            "Band" => self.audio_bands.get(m.band).copied().unwrap_or(0.0),
+            _ => self.audio_level,
+        }
+    }
+
+    /// Copy of `mask` with its audio modulation bindings applied to the params
+    fn modulate_mask(&self, mask: &Mask) -> Mask {
+        let mut out = mask.clone();
+        for m in &mask.modulation {
+            let Some(base) = out.params.get(&m.param).and_then(|v| v.as_f64()) else { continue };
+            let value = base + (m.offset + m.gain * self.modulation_source(m)) as f64;
+            out.params.insert(m.param.clone(), serde_json::json!(value));
+        }
+        out
+    }
+
+    /// `effect` with each automation's current value written into its params, timed from
+    /// when the scene being drawn started. Unset params ramp from the bottom of their editor range.
+    fn automate_effect(&self, effect: &GlobalEffect, t: f32, beat: f64) -> GlobalEffect {
+        let elapsed_ms = (self.scene_elapsed(t, beat).0 * 1000.0) as u64;
+        let mut out = effect.clone();
+        for automation in &effect.automation {
+            let from = out.params.get(&automation.param).and_then(|v| v.as_f64()).or_else(|| {
+                match crate::model::param_range(&effect.kind, &automation.param) {
+                    Some(crate::model::ParamRange::Float(min, _)) => Some(min),
+                    _ => None,
+                }
+            });
+            let Some(from) = from else { continue };
+            out.params.insert(automation.param.clone(), serde_json::json!(automation.value(from, elapsed_ms)));
+        }
+        out
+    }
+
     fn apply_mask_to_strips(&mut self, mask: &Mask, strips: &mut [PixelStrip], t: f32, beat: f64) {
+        let modulated;
+        let mask = if mask.modulation.is_empty() {
+            mask
+        } else {
+            modulated = self.modulate_mask(mask);
+            &modulated
+        };
         let mx = mask.x;
         let my = mask.y;
         
@@ -586,6 +1678,11 @@ impl LightingEngine {
             let cos_rot = rot_rad.cos();
             let sin_rot = rot_rad.sin();
 
+            // Sweep direction inside the (rotated) rectangle; 0 sweeps along its width
+            let sweep_rad = (mask.params.get("angle").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32).to_radians();
+            let cos_sweep = sweep_rad.cos();
+            let sin_sweep = sweep_rad.sin();
+
 
 
             // Get bar parameters
@@ -594,6 +1691,9 @@ impl LightingEngine {
             let hard_edge = mask.params.get("hard_edge").and_then(|v| v.as_bool()).unwrap_or(false);
 
             // Calculate bar position (scanning animation)
+            let edge_mode = scanner::edge_mode(&mask.params);
+            // A clamped sweep runs once, so it is timed from when the scene started
+            let (t, beat) = if edge_mode == "clamp" { self.scene_elapsed(t, beat) } else { (t, beat) };
             let is_sync = mask.params.get("sync").and_then(|v| v.as_bool()).unwrap_or(false);
             let phase = if is_sync {
                 let rate_str = mask.params.get("rate").and_then(|v| v.as_str()).unwrap_or("1/4");
@@ -611,28 +1711,17 @@ impl LightingEngine {
                 (t * speed * self.speed) as f64
             };
 
-            let unidirectional = mask.params.get("unidirectional").and_then(|v| v.as_bool()).unwrap_or(false);
             let motion = mask.params.get("motion").and_then(|v| v.as_str()).unwrap_or("Smooth");
-            
-            let osc_val = if unidirectional {
-                 // Sawtooth wave: -1.0 to 1.0
-                 let norm_phase = (phase / (std::f64::consts::PI * 2.0)).fract();
-                 // fract() returns [0, 1) (if positive). phase is usually positive (t*speed or beat).
-                 // We want strictly 0..1 then map to -1..1
-                 let p = if norm_phase < 0.0 { norm_phase + 1.0 } else { norm_phase };
-                 p * 2.0 - 1.0
-            } else if motion == "Linear" {
-                (2.0 / std::f64::consts::PI) * (phase.sin().asin())
-            } else {
-                phase.sin()
-            };
+            let osc_val = scanner::bar_position(phase, edge_mode, motion);
 
             // Bar position in local space
             // Sweep the BAR CENTER within ±(width/2 - bar_width) so that
             // the bar's EDGES can exactly reach the mask edges without relying
             // on osc_val hitting perfect ±1.0. This prevents a dark sliver at
             // the mask boundaries, especially noticeable when rotated.
-            let sweep_range = (width / 2.0) - bar_width;
+            // With a sweep angle the travel is the rectangle's extent projected onto that direction.
+            let sweep_half = (width / 2.0) * cos_sweep.abs() + (height / 2.0) * sin_sweep.abs();
+            let sweep_range = sweep_half - bar_width;
             let bar_local_x = sweep_range * osc_val as f32;
 
             // Debug bar position - DETAILED
@@ -659,6 +1748,7 @@ impl LightingEngine {
             for i in 0..strips.len() {
                 let strip = &mut strips[i];
                 let pixel_limit = strip.pixel_count.min(strip.data.len());
+                let footprint = strip.spacing * (rot_rad + sweep_rad).cos().abs();
 
                 for p in 0..pixel_limit {
                     // 1. Calculate pixel position in world space
@@ -675,6 +1765,7 @@ impl LightingEngine {
                     let dy = py - my;
                     let mask_local_x = dx * cos_rot + dy * sin_rot;
                     let mask_local_y = -dx * sin_rot + dy * cos_rot;
+                    let sweep_pos = mask_local_x * cos_sweep + mask_local_y * sin_sweep;
 
                     // 3. Check if pixel is within mask bounds (rectangular boundary)
                     let half_w = width / 2.0;
@@ -705,29 +1796,21 @@ impl LightingEngine {
                             continue;
                         }
 
-                        // 4. Check if pixel is hit by the scanning bar
-                        let dist_to_bar = (mask_local_x - bar_local_x).abs();
+                        // 4. Check if pixel is hit by the scanning bar (anti-aliased, see scanner::bar_intensity)
+                        let dist_to_bar = (sweep_pos - bar_local_x).abs();
+                        let intensity = scanner::bar_intensity(dist_to_bar, bar_width, footprint, hard_edge);
 
-                        if dist_to_bar <= bar_width {
-                            // Pixel is inside mask AND hit by bar
-                            let intensity = if hard_edge {
-                                1.0
-                            } else {
-                                (1.0 - dist_to_bar / bar_width).max(0.0)
-                            };
+                        if intensity > 0.0 {
+                            let r = (final_color[0] as f32 * intensity).round() as u8;
+                            let g = (final_color[1] as f32 * intensity).round() as u8;
+                            let b = (final_color[2] as f32 * intensity).round() as u8;
 
-                            if intensity > 0.0 {
-                                let r = (final_color[0] as f32 * intensity) as u8;
-                                let g = (final_color[1] as f32 * intensity) as u8;
-                                let b = (final_color[2] as f32 * intensity) as u8;
-
-                                let curr = strip.data[p];
-                                strip.data[p] = [
-                                    curr[0].saturating_add(r),
-                                    curr[1].saturating_add(g),
-                                    curr[2].saturating_add(b)
-                                ];
-                            }
+                            let curr = strip.data[p];
+                            strip.data[p] = [
+                                curr[0].saturating_add(r),
+                                curr[1].saturating_add(g),
+                                curr[2].saturating_add(b)
+                            ];
                         }
                     }
                 }
@@ -884,6 +1967,11 @@ impl LightingEngine {
              let base_radius = mask.params.get("radius").and_then(|v| v.as_f64()).unwrap_or(0.2) as f32;
              let radius = apply_lfo_modulation(base_radius, &mask.params, "radius", t, beat);
              let debug_fill = mask.params.get("debug_fill").and_then(|v| v.as_bool()).unwrap_or(false);
+             // `stretch` elongates the circle into an ellipse along `angle` (degrees)
+             let angle_rad = (mask.params.get("angle").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32).to_radians();
+             let stretch = (mask.params.get("stretch").and_then(|v| v.as_f64()).unwrap_or(1.0) as f32).max(0.01);
+             let (sin_angle, cos_angle) = angle_rad.sin_cos();
+             let shaped = angle_rad != 0.0 || stretch != 1.0;
              let m_color = mask.params.get("color").and_then(|v| {
                 let arr = v.as_array()?;
                 Some([
@@ -910,7 +1998,16 @@ impl LightingEngine {
                          (strip.x + local_x, strip.y)
                     };
 
-                    let dist = ((px - mx).powi(2) + (py - my).powi(2)).sqrt();
+                    let (px, py) = if shaped {
+                         let dx = px - mx;
+                         let dy = py - my;
+                         let along = (dx * cos_angle + dy * sin_angle) / stretch;
+                         let across = -dx * sin_angle + dy * cos_angle;
+                         (mx + along, my + across)
+                    } else {
+                         (px, py)
+                    };
+                    let dist = crate::model::spatial_distance((px, py, strip.z), (mx, my, mask.z));
                     if dist < radius {
                          if debug_fill {
                              strip.data[i] = [255, 255, 255];
@@ -928,6 +2025,31 @@ impl LightingEngine {
                     }
                  }
               }
+        } else if mask.mask_type == "wave" {
+            // Wave Mask: sine bands traveling from the mask position along `angle`
+            let wavelength = mask.params.get("wavelength").and_then(|v| v.as_f64()).unwrap_or(0.2) as f32;
+            let wave_speed = mask.params.get("speed").and_then(|v| v.as_f64()).unwrap_or(0.2) as f32;
+            let amplitude = mask.params.get("amplitude").and_then(|v| v.as_f64()).unwrap_or(1.0) as f32;
+            let angle = mask.params.get("angle").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+            let m_color = mask.params.get("color").and_then(|v| {
+                let arr = v.as_array()?;
+                Some([arr.get(0)?.as_u64()? as u8, arr.get(1)?.as_u64()? as u8, arr.get(2)?.as_u64()? as u8])
+            }).unwrap_or([0, 255, 128]);
+            let final_color = get_color(m_color);
+
+            let (dir_x, dir_y) = (angle.to_radians().cos(), angle.to_radians().sin());
+            for strip in strips.iter_mut() {
+                let positions = strip.pixel_positions();
+                for (pixel, (px, py)) in strip.data.iter_mut().zip(positions) {
+                    let distance = (px - mx) * dir_x + (py - my) * dir_y;
+                    let level = wave_brightness(distance, wavelength, wave_speed, amplitude, t);
+                    *pixel = [
+                        pixel[0].saturating_add((final_color[0] as f32 * level) as u8),
+                        pixel[1].saturating_add((final_color[1] as f32 * level) as u8),
+                        pixel[2].saturating_add((final_color[2] as f32 * level) as u8),
+                    ];
+                }
+            }
         } else if mask.mask_type == "burst" {
             // Burst Mask: Audio-reactive radial mask that grows/shrinks with music
             let base_radius = mask.params.get("base_radius").and_then(|v| v.as_f64()).unwrap_or(0.1) as f32;
@@ -941,11 +2063,7 @@ impl LightingEngine {
             }).unwrap_or([255, 100, 0]);
 
             // Get audio volume
-            let audio_vol = if let Some(audio) = &self.audio_listener {
-                audio.current_volume.lock().map(|v| *v).unwrap_or(0.0)
-            } else {
-                0.0
-            };
+            let audio_vol = self.audio_level;
 
             // Calculate target radius
             let expansion = (audio_vol * sensitivity).min(1.0);
@@ -989,53 +2107,10 @@ impl LightingEngine {
         }
     }
 
-    pub fn get_bpm(&self) -> f64 {
-        let mut session_state = SessionState::new();
-        self.link.capture_app_session_state(&mut session_state);
-        session_state.tempo()
-    }
-
-    pub fn get_beat(&self) -> f64 {
-        if self.use_flywheel {
-            self.flywheel_beat
-        } else {
-            // Need to capture fresh or store last raw beat?
-            // self.flywheel logic already captures raw beat in update.
-            // But update is called once per frame.
-            // Let's store raw_beat in struct or just assume flywheel_beat is kept in sync if disabled?
-            // Better: update() logic should set flywheel_beat = link_beat if disabled.
-            self.flywheel_beat 
-        }
-    }
-    
-    pub fn get_time(&self) -> f32 {
-        self.start_time.elapsed().as_secs_f32()
-    }
-    
-    pub fn get_sync_info(&self) -> (String, f64) {
-        let peers = self.link.num_peers();
-        if peers > 0 {
-             let mut session_state = SessionState::new();
-             self.link.capture_app_session_state(&mut session_state);
-             (format!("LINK ({} Peers)", peers), session_state.tempo())
-        } else if self.audio_bpm > 30.0 {
-             ("AUDIO".to_string(), self.audio_bpm)
-        } else {
-             ("MANUAL".to_string(), 120.0 * self.speed as f64)
-        }
-    }
-}
-
-impl LightingEngine {
     fn apply_global_effect(&mut self, effect: &GlobalEffect, strips: &mut [PixelStrip], t: f32, beat: f64, targets: Option<&Vec<u64>>) {
-        match effect.kind.as_str() {
-            "Solid" => {
-                // Use EXACT same color reading as masks
-                let color = effect.params.get("color").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8, arr.get(1)?.as_u64()? as u8, arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([255, 255, 255]);
-
+        let Some(params) = EffectParams::lenient(&effect.kind, &effect.params) else { return };
+        match params {
+            EffectParams::Solid { color } => {
                 // Apply color EXACTLY like scanner masks do - with intensity and saturating_add
                 for s in strips.iter_mut() {
                     if let Some(t) = targets { if !t.contains(&s.id) { continue; } }
@@ -1056,8 +2131,7 @@ impl LightingEngine {
                     }
                 }
             }
-            "Rainbow" => {
-                let base_speed = effect.params.get("speed").and_then(|v| v.as_f64()).unwrap_or(0.2) as f32;
+            EffectParams::Rainbow { speed: base_speed } => {
                 let speed = apply_lfo_modulation(base_speed, &effect.params, "speed", t, beat);
                 let hue = (t * speed * self.speed).fract();
                 let c = hsv_to_rgb(hue, 1.0, 1.0);
@@ -1068,26 +2142,36 @@ impl LightingEngine {
                     for i in 0..cnt { s.data[i] = c; }
                 }
             }
-            "Flash" => {
-                // Use EXACT same color reading as masks
-                let color = effect.params.get("color").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8, arr.get(1)?.as_u64()? as u8, arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([255, 255, 255]);
+            EffectParams::Gradient { stops, scroll_speed } => {
+                let offset = t * scroll_speed * self.speed;
 
-                let rate_str = effect.params.get("rate").and_then(|v| v.as_str()).unwrap_or("1 Bar");
-                let divisor = match rate_str {
-                    "4 Bar" => 16.0, "1 Bar" => 4.0, "1/2" => 2.0, "1/4" => 1.0, "1/8" => 0.5, _ => 4.0,
-                };
+                for s in strips.iter_mut() {
+                    if let Some(t) = targets { if !t.contains(&s.id) { continue; } }
 
-                let decay = effect.params.get("decay").and_then(|v| v.as_f64()).unwrap_or(5.0);
+                    let cnt = s.pixel_count.min(s.data.len());
+                    for i in 0..cnt {
+                        // Run along the physical strip, so flipped strips start from their far end
+                        let index = if s.flipped { cnt - 1 - i } else { i };
+                        let mut pos = if cnt > 1 { index as f32 / (cnt - 1) as f32 } else { 0.0 };
+                        if scroll_speed != 0.0 {
+                            pos = (pos + offset).rem_euclid(1.0);
+                        }
+                        s.data[i] = sample_gradient(&stops, pos);
+                    }
+                }
+            }
+            EffectParams::Flash { color, rate: rate_str, decay } => {
+                let rate_str = rate_str.as_str();
+                // Strobe safety: too fast a rate is slowed to the limit, in whole-beat steps
+                let divisor = crate::model::limit_flash_divisor(crate::model::flash_divisor(rate_str), self.bpm, self.max_flash_hz);
 
                 // Calculate phase 0..1
                 let phase = (beat / divisor).fract();
 
                 // Exponential decay: starts at 1.0, drops quickly
                 // To make it flash *on the beat*, we want peak at phase=0.
-                let intensity = (1.0 - phase).powf(decay) as f32;
+                // With strobing turned off, hold a steady dimmed color instead.
+                let intensity = if self.no_strobe { NO_STROBE_LEVEL } else { (1.0 - phase).powf(decay) as f32 };
 
                 // Clamp to ensure valid range
                 let intensity = intensity.clamp(0.0, 1.0);
@@ -1106,19 +2190,46 @@ impl LightingEngine {
                     }
                 }
             }
-            "Sparkle" => {
-                let density = effect.params.get("density").and_then(|v| v.as_f64()).unwrap_or(0.05) as f32;
-                let life = effect.params.get("life").and_then(|v| v.as_f64()).unwrap_or(0.2) as f32;
-                let decay = effect.params.get("decay").and_then(|v| v.as_f64()).unwrap_or(5.0);
-                let color = effect.params.get("color").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8, arr.get(1)?.as_u64()? as u8, arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([255, 255, 255]);
+            EffectParams::Strobe { color, frequency_hz: frequency, duty_cycle: duty } => {
+                // Strobe safety: never faster than MAX_STROBE_HZ or the configured flash limit
+                let max_hz = self.max_flash_hz.filter(|hz| *hz > 0.0).map_or(MAX_STROBE_HZ, |hz| hz.min(MAX_STROBE_HZ));
+                let frequency = if frequency.is_finite() { frequency.clamp(0.0, max_hz) } else { 0.0 };
+                let duty = if duty.is_finite() { duty.clamp(0.0, 1.0) } else { 0.5 };
+
+                let intensity = if self.no_strobe {
+                    NO_STROBE_LEVEL
+                } else if (t * frequency).fract() < duty {
+                    1.0
+                } else {
+                    0.0
+                };
+
+                for s in strips.iter_mut() {
+                    if let Some(t) = targets { if !t.contains(&s.id) { continue; } }
 
+                    let cnt = s.pixel_count.min(s.data.len());
+                    for i in 0..cnt {
+                        s.data[i] = [
+                            (color[0] as f32 * intensity) as u8,
+                            (color[1] as f32 * intensity) as u8,
+                            (color[2] as f32 * intensity) as u8,
+                        ];
+                    }
+                }
+            }
+            EffectParams::Sparkle { color, density, life, decay, sync_mode } => {
+                let sync_mode = sync_mode.as_str();
                 const MAX_SPARKLES: usize = 500;
 
                 // Spawn new sparkles
                 if self.sparkle_states.len() < MAX_SPARKLES {
+                    // "shared": one roll per pixel index, so every strip sparkles in the same places
+                    let shared_rolls: Vec<f32> = if sync_mode == "shared" {
+                        let longest = strips.iter().map(|s| s.pixel_count.min(s.data.len())).max().unwrap_or(0);
+                        (0..longest).map(|_| self.rng.gen()).collect()
+                    } else {
+                        Vec::new()
+                    };
                     for strip in strips.iter() {
                         if let Some(t) = targets { if !t.contains(&strip.id) { continue; } }
                         
@@ -1127,7 +2238,11 @@ impl LightingEngine {
                             if self.sparkle_states.len() >= MAX_SPARKLES {
                                 break;
                             }
-                            if rand::random::<f32>() < density {
+                            let roll = match shared_rolls.get(i) {
+                                Some(roll) => *roll,
+                                None => self.strip_rng(strip.id).gen::<f32>(),
+                            };
+                            if roll < density {
                                 self.sparkle_states.push(SparklePixel {
                                     strip_id: strip.id,
                                     pixel_index: i,
@@ -1164,31 +2279,13 @@ impl LightingEngine {
                                 strip.data[sparkle.pixel_index][2].saturating_add(b),
                             ];
                         }
-                    }
-
-                    true
-                });
-            }
-            "ColorWash" => {
-                // Parse parameters
-                let color_a = effect.params.get("color_a").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8,
-                          arr.get(1)?.as_u64()? as u8,
-                          arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([255, 0, 0]);
-
-                let color_b = effect.params.get("color_b").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8,
-                          arr.get(1)?.as_u64()? as u8,
-                          arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([0, 0, 255]);
-
-                let sync_to_beat = effect.params.get("sync_to_beat").and_then(|v| v.as_bool()).unwrap_or(false);
-                let rate_str = effect.params.get("rate").and_then(|v| v.as_str()).unwrap_or("1 Bar");
-                let period = effect.params.get("period").and_then(|v| v.as_f64()).unwrap_or(4.0);
+                    }
 
+                    true
+                });
+            }
+            EffectParams::ColorWash { color_a, color_b, sync_to_beat, rate: rate_str, period } => {
+                let rate_str = rate_str.as_str();
                 // Calculate phase (0.0 to 1.0)
                 let phase = if sync_to_beat {
                     let divisor = match rate_str {
@@ -1226,26 +2323,8 @@ impl LightingEngine {
                     }
                 }
             }
-            "GlitchSparkle" => {
-                // Parse parameters
-                let background_color = effect.params.get("background_color").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8,
-                          arr.get(1)?.as_u64()? as u8,
-                          arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([0, 0, 0]);
-
-                let sparkle_color = effect.params.get("sparkle_color").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8,
-                          arr.get(1)?.as_u64()? as u8,
-                          arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([255, 255, 255]);
-
-                let density = effect.params.get("density").and_then(|v| v.as_f64()).unwrap_or(0.05) as f32;
-                let fade_time = effect.params.get("fade_time").and_then(|v| v.as_f64()).unwrap_or(0.3) as f32;
-                let decay = effect.params.get("decay").and_then(|v| v.as_f64()).unwrap_or(5.0);
-
+            EffectParams::GlitchSparkle { background_color, sparkle_color, density, fade_time, decay, sync_mode } => {
+                let sync_mode = sync_mode.as_str();
                 const MAX_GLITCH_SPARKLES: usize = 500;
 
                 // Step 1: Fill background color on all targeted strips
@@ -1261,57 +2340,48 @@ impl LightingEngine {
                     }
                 }
 
-                // Step 2: Spawn new sparkles using accumulator for constant rate
-                if self.glitch_states.len() < MAX_GLITCH_SPARKLES {
-                    // Count total pixels in targeted strips
-                    let mut total_pixels = 0;
-                    let mut eligible_pixels = Vec::new();
-
-                    for strip in strips.iter() {
-                        if let Some(t) = targets {
-                            if !t.contains(&strip.id) {
-                                continue;
-                            }
-                        }
-
-                        let pixel_count = strip.pixel_count.min(strip.data.len());
-                        for i in 0..pixel_count {
-                            eligible_pixels.push((strip.id, i));
-                            total_pixels += 1;
-                        }
+                // Step 2: Spawn new sparkles using accumulators for a constant rate.
+                // Density represents target percentage of pixels sparkling at any time;
+                // the spawn rate is adjusted by fade_time to maintain constant coverage.
+                let spawn_per_pixel = density / fade_time.max(0.1) / 60.0; // Assume 60fps
+                let targeted: Vec<(u64, usize)> = strips.iter()
+                    .filter(|s| targets.map_or(true, |t| t.contains(&s.id)))
+                    .map(|s| (s.id, s.pixel_count.min(s.data.len())))
+                    .collect();
+                let mut spawned: Vec<(u64, usize)> = Vec::new();
+                if sync_mode == "shared" {
+                    // One stream for all strips: each spawn lights the same index on every strip
+                    let longest = targeted.iter().map(|(_, n)| *n).max().unwrap_or(0);
+                    self.glitch_sparkle_accumulator += longest as f32 * spawn_per_pixel;
+                    let count = self.glitch_sparkle_accumulator.floor() as usize;
+                    self.glitch_sparkle_accumulator -= count as f32;
+                    for _ in 0..count.min(longest) {
+                        let pixel_index = self.rng.gen_range(0..longest);
+                        spawned.extend(targeted.iter().filter(|(_, n)| pixel_index < *n).map(|(id, _)| (*id, pixel_index)));
                     }
-
-                    // Calculate expected sparkles this frame and accumulate
-                    // Density represents target percentage of pixels sparkling at any time
-                    // Adjust spawn rate based on fade_time to maintain constant coverage
-                    let target_coverage = total_pixels as f32 * density;
-                    let spawn_rate_per_second = target_coverage / fade_time.max(0.1);
-                    let fps_estimate = 60.0; // Assume 60fps for spawn rate calculation
-                    let expected_sparkles = spawn_rate_per_second / fps_estimate;
-                    self.glitch_sparkle_accumulator += expected_sparkles;
-
-                    // Spawn whole number of sparkles, keep fractional part
-                    let sparkles_to_spawn = self.glitch_sparkle_accumulator.floor() as usize;
-                    self.glitch_sparkle_accumulator -= sparkles_to_spawn as f32;
-
-                    // Spawn sparkles at random positions
-                    for _ in 0..sparkles_to_spawn.min(MAX_GLITCH_SPARKLES - self.glitch_states.len()) {
-                        if eligible_pixels.is_empty() {
-                            break;
+                } else {
+                    // Each strip runs its own stream and accumulator
+                    for &(strip_id, pixel_count) in &targeted {
+                        if pixel_count == 0 {
+                            continue;
+                        }
+                        let acc = self.glitch_strip_accumulators.entry(strip_id).or_insert(0.0);
+                        *acc += pixel_count as f32 * spawn_per_pixel;
+                        let count = acc.floor() as usize;
+                        *acc -= count as f32;
+                        for _ in 0..count {
+                            let pixel_index = self.strip_rng(strip_id).gen_range(0..pixel_count);
+                            spawned.push((strip_id, pixel_index));
                         }
-
-                        // Pick a random pixel
-                        let idx = (rand::random::<f32>() * eligible_pixels.len() as f32) as usize % eligible_pixels.len();
-                        let (strip_id, pixel_index) = eligible_pixels[idx];
-
-                        self.glitch_states.push(GlitchPixel {
-                            strip_id,
-                            pixel_index,
-                            birth_time: t,
-                            color: sparkle_color,
-                        });
                     }
                 }
+                let room = MAX_GLITCH_SPARKLES.saturating_sub(self.glitch_states.len());
+                self.glitch_states.extend(spawned.into_iter().take(room).map(|(strip_id, pixel_index)| GlitchPixel {
+                    strip_id,
+                    pixel_index,
+                    birth_time: t,
+                    color: sparkle_color,
+                }));
 
                 // Step 3: Render and cleanup sparkles
                 self.glitch_states.retain(|sparkle| {
@@ -1350,22 +2420,9 @@ impl LightingEngine {
                     true // Keep sparkle alive
                 });
             }
-            "PulseWave" => {
-                // Parse parameters
-                let color = effect.params.get("color").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8,
-                          arr.get(1)?.as_u64()? as u8,
-                          arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([255, 255, 255]);
-
-                let sync = effect.params.get("sync").and_then(|v| v.as_bool()).unwrap_or(true);
-                let rate_str = effect.params.get("rate").and_then(|v| v.as_str()).unwrap_or("1/4");
-                let manual_speed = effect.params.get("speed").and_then(|v| v.as_f64()).unwrap_or(5.0);
-                let tail_length = effect.params.get("tail_length").and_then(|v| v.as_f64()).unwrap_or(10.0) as f32;
-                let decay = effect.params.get("decay").and_then(|v| v.as_f64()).unwrap_or(2.0) as f32;
-                let direction = effect.params.get("direction").and_then(|v| v.as_str()).unwrap_or("Forward");
-
+            EffectParams::PulseWave { color, sync, rate: rate_str, speed: manual_speed, tail_length, decay, direction } => {
+                let rate_str = rate_str.as_str();
+                let direction = direction.as_str();
                 // Calculate speed (pixels per second)
                 let speed = if sync {
                     // Beat-synced: use beat phase to calculate position
@@ -1462,33 +2519,9 @@ impl LightingEngine {
                     }
                 }
             }
-            "ZoneAlternate" => {
-                // Parse parameters
-                let group_a: Vec<u64> = effect.params.get("group_a_strips")
-                    .and_then(|v| serde_json::from_value(v.clone()).ok())
-                    .unwrap_or_default();
-
-                let group_b: Vec<u64> = effect.params.get("group_b_strips")
-                    .and_then(|v| serde_json::from_value(v.clone()).ok())
-                    .unwrap_or_default();
-
-                let group_a_color = effect.params.get("group_a_color").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8,
-                          arr.get(1)?.as_u64()? as u8,
-                          arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([255, 0, 0]);
-
-                let group_b_color = effect.params.get("group_b_color").and_then(|v| {
-                    let arr = v.as_array()?;
-                    Some([arr.get(0)?.as_u64()? as u8,
-                          arr.get(1)?.as_u64()? as u8,
-                          arr.get(2)?.as_u64()? as u8])
-                }).unwrap_or([0, 0, 255]);
-
-                let rate_str = effect.params.get("rate").and_then(|v| v.as_str()).unwrap_or("1/4");
-                let mode = effect.params.get("mode").and_then(|v| v.as_str()).unwrap_or("Swap");
-
+            EffectParams::ZoneAlternate { group_a_strips: group_a, group_b_strips: group_b, group_a_color, group_b_color, rate: rate_str, mode } => {
+                let rate_str = rate_str.as_str();
+                let mode = mode.as_str();
                 // Calculate beat phase and determine active group
                 let divisor = match rate_str {
                     "4 Bar" => 16.0,
@@ -1499,8 +2532,12 @@ impl LightingEngine {
                     "1/8" => 0.5,
                     _ => 1.0,
                 };
-                let phase = (beat / divisor).fract();
-                let a_is_active = phase < 0.5;
+                // "Audio" swaps on every audio beat instead of following the beat clock
+                let a_is_active = if rate_str == "Audio" {
+                    self.audio_beat_count % 2 == 0
+                } else {
+                    (beat / divisor).fract() < 0.5
+                };
 
                 // Determine colors based on mode
                 let (color_when_a_active, color_when_b_active) = match mode {
@@ -1543,9 +2580,253 @@ impl LightingEngine {
                     }
                 }
             }
-            _ => {}
+            EffectParams::Plasma { speed: base_speed, scale, palette, color_a, color_b } => {
+                let palette = palette.as_str();
+                let speed = apply_lfo_modulation(base_speed, &effect.params, "speed", t, beat);
+
+                let time = t * speed * self.speed;
+
+                // The plasma is sampled in canvas space, so neighbouring strips share one field
+                for strip in strips.iter_mut() {
+                    if let Some(t) = targets {
+                        if !t.contains(&strip.id) {
+                            continue;
+                        }
+                    }
+
+                    let pz = strip.z.unwrap_or(0.0) * scale;
+                    let positions = strip.pixel_positions();
+                    for (pixel, (px, py)) in strip.data.iter_mut().zip(positions) {
+                        let value = plasma_value(px * scale, py * scale, pz, time);
+                        *pixel = plasma_palette(palette, value, color_a, color_b);
+                    }
+                }
+            }
+            EffectParams::Ripple { origin_x, origin_y, origin_z, speed, width, decay, trigger, interval, color } => {
+                let trigger = trigger.as_str();
+                const MAX_RIPPLES: usize = 32;
+
+                let (audio_confidence, audio_onset) = (self.audio_confidence, self.audio_onset);
+                let state = self.ripple_states.entry(self.current_effect).or_default();
+                state.drawn = true;
+
+                // Step 1: Spawn new rings
+                let spawn = match trigger {
+                    "Timer" => t - state.last_spawn >= interval.max(0.05),
+                    _ => {
+                        // "Beat": audio onsets, falling back to the beat clock when audio is missing or stalled
+                        if audio_confidence > 0.5 {
+                            audio_onset
+                        } else {
+                            beat.floor() > state.last_beat.floor()
+                        }
+                    }
+                };
+                state.last_beat = beat;
+
+                if spawn && state.waves.len() < MAX_RIPPLES {
+                    state.waves.push(RippleWave {
+                        origin: (origin_x, origin_y, origin_z),
+                        birth_time: t,
+                    });
+                    state.last_spawn = t;
+                }
+
+                // Step 2: Drop rings that have faded out or left the canvas
+                let speed = speed * self.speed;
+                state.waves.retain(|ripple| {
+                    let age = t - ripple.birth_time;
+                    (-decay * age).exp() > 0.01 && age * speed < 3.0
+                });
+
+                // Step 3: Render rings at each pixel's mapped position
+                for strip in strips.iter_mut() {
+                    if let Some(t) = targets {
+                        if !t.contains(&strip.id) {
+                            continue;
+                        }
+                    }
+
+                    let positions = strip.pixel_positions_3d();
+                    for (pixel, position) in strip.data.iter_mut().zip(positions) {
+                        let mut intensity = 0.0f32;
+                        for ripple in &state.waves {
+                            let age = t - ripple.birth_time;
+                            let radius = age * speed;
+                            let dist = crate::model::spatial_distance(position, ripple.origin);
+                            let ring_dist = (dist - radius).abs();
+                            if ring_dist < width {
+                                intensity += (1.0 - ring_dist / width) * (-decay * age).exp();
+                            }
+                        }
+                        let intensity = intensity.clamp(0.0, 1.0);
+
+                        let r = (color[0] as f32 * intensity) as u8;
+                        let g = (color[1] as f32 * intensity) as u8;
+                        let b = (color[2] as f32 * intensity) as u8;
+
+                        *pixel = [
+                            pixel[0].saturating_add(r),
+                            pixel[1].saturating_add(g),
+                            pixel[2].saturating_add(b),
+                        ];
+                    }
+                }
+            }
+            EffectParams::SpectrumBars { color_low, color_mid, color_high } => {
+                let colors = [color_low, color_mid, color_high];
+                let bands: Vec<f32> = (0..BAND_COUNT).map(|i| self.audio_bands.get(i).copied().unwrap_or(0.0)).collect();
+
+                for s in strips.iter_mut() {
+                    if let Some(t) = targets { if !t.contains(&s.id) { continue; } }
+
+                    let cnt = s.pixel_count.min(s.data.len());
+                    for i in 0..cnt {
+                        // Bars rise from the strip's physical start, low band first
+                        let index = if s.flipped { cnt - 1 - i } else { i };
+                        s.data[i] = spectrum_bar_pixel(&bands, &colors, index, cnt);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pixel `index` of `count` when the strip is split into one equal section per band, each
+/// filled from its start in proportion to the band's level. The pixel the bar ends in is
+/// partly lit, so levels move smoothly on short strips.
+fn spectrum_bar_pixel(bands: &[f32], colors: &[[u8; 3]], index: usize, count: usize) -> [u8; 3] {
+    if bands.is_empty() || count == 0 {
+        return [0, 0, 0];
+    }
+    let section = (index * bands.len() / count).min(bands.len() - 1);
+    let start = section as f32 * count as f32 / bands.len() as f32;
+    let len = count as f32 / bands.len() as f32;
+    let lit = bands[section].clamp(0.0, 1.0) * len;
+    let cover = (lit - (index as f32 - start)).clamp(0.0, 1.0);
+    let color = colors.get(section).copied().unwrap_or([255, 255, 255]);
+    color.map(|c| (c as f32 * cover).round() as u8)
+}
+
+/// Take disabled strips out of `strips`, remembering where they were
+fn park_disabled_strips(strips: &mut Vec<PixelStrip>) -> Vec<(usize, PixelStrip)> {
+    if strips.iter().all(|s| s.enabled) {
+        return Vec::new();
+    }
+    let mut parked = Vec::new();
+    for (i, strip) in std::mem::take(strips).into_iter().enumerate() {
+        if strip.enabled {
+            strips.push(strip);
+        } else {
+            parked.push((i, strip));
         }
     }
+    parked
+}
+
+/// Copy snapshot data onto strips it still fits; strips added or resized since keep their live data
+fn overlay_snapshot(strips: &mut [PixelStrip], snapshot: &HashMap<u64, Vec<[u8; 3]>>) {
+    for strip in strips.iter_mut() {
+        if let Some(data) = snapshot.get(&strip.id).filter(|d| d.len() == strip.pixel_count) {
+            strip.data.clone_from(data);
+        }
+    }
+}
+
+/// Put parked strips back at their original positions, blacked out
+fn unpark_strips(strips: &mut Vec<PixelStrip>, parked: Vec<(usize, PixelStrip)>) {
+    for (i, mut strip) in parked {
+        strip.data.fill([0, 0, 0]);
+        strips.insert(i.min(strips.len()), strip);
+    }
+}
+
+/// Combine a layer pixel with the composite below it using `mode` (see `model::BLEND_MODES`),
+/// then mix the result over `below` by `opacity` (0.0-1.0)
+fn blend_pixel(below: [u8; 3], layer: [u8; 3], mode: &str, opacity: f32) -> [u8; 3] {
+    let coverage = layer.iter().copied().max().unwrap_or(0) as u16;
+    let blended: [u8; 3] = std::array::from_fn(|i| {
+        let (a, b) = (below[i] as u16, layer[i] as u16);
+        (match mode {
+            "Normal" => b,
+            "Add" => (a + b).min(255),
+            "Multiply" => a * b / 255,
+            "Screen" => 255 - (255 - a) * (255 - b) / 255,
+            "Mask" => a * coverage / 255, // Lower layers show through where the mask is lit
+            _ => a.max(b),                 // "Max"
+        }) as u8
+    });
+    if opacity >= 1.0 {
+        return blended;
+    }
+    std::array::from_fn(|i| (below[i] as f32 + (blended[i] as f32 - below[i] as f32) * opacity).round() as u8)
+}
+
+fn scale_pixel(pixel: [u8; 3], gain: f32) -> [u8; 3] {
+    let gain = gain.clamp(0.0, 1.0);
+    pixel.map(|c| (c as f32 * gain) as u8)
+}
+
+/// Write `strip` into its universe's DMX slots (`universe[0]` is channel 1): pre channels
+/// at `start_channel`, then the pixels through gamma and the brightness floor, then post
+/// channels. Fixture channels skip gamma and the floor. Slots past the universe are dropped.
+fn pack_strip(strip: &PixelStrip, gamma: &GammaLut, universe: &mut [u8]) {
+    debug_assert_eq!(strip.data.len(), strip.pixel_count, "strip {} rendered with stale data length", strip.id);
+    let fixture_start = (strip.start_channel as usize).saturating_sub(1);
+    let start = fixture_start + strip.pre_channels.len();
+    let stride = strip.pixel_stride();
+    let post_start = start + strip.data.len() * stride;
+
+    // Level-driven fixture channels follow the brightest channel of the rendered frame
+    let level = strip.data.iter().flatten().copied().max().unwrap_or(0);
+    let fixture = strip.pre_channels.iter().enumerate().map(|(k, c)| (fixture_start + k, c))
+        .chain(strip.post_channels.iter().enumerate().map(|(k, c)| (post_start + k, c)));
+    for (idx, channel) in fixture {
+        if let Some(slot) = universe.get_mut(idx) {
+            *slot = channel.value(level);
+        }
+    }
+
+    for (i, pixel) in strip.data.iter().enumerate() {
+        let pixel = pixel.map(|c| lift_min_brightness(gamma.apply(c), strip.min_brightness));
+        let idx = start + i * stride;
+        // Bounds check: the pixel's slots must all fit in the universe
+        if let Some(slots) = universe.get_mut(idx..idx + stride) {
+            crate::model::pack_pixel(pixel, &strip.color_order, &strip.white_mode, slots);
+        }
+    }
+}
+
+/// Raise a non-zero output channel to at least `floor`; zero stays off.
+/// Runs last in the output stage, so the floor is in output (post-gamma) space.
+fn lift_min_brightness(value: u8, floor: u8) -> u8 {
+    if value == 0 {
+        0
+    } else {
+        value.max(floor)
+    }
+}
+
+/// Output gain `elapsed_ms` into a startup fade of `fade_ms` (0 = no fade)
+fn startup_gain(elapsed_ms: u64, fade_ms: u64) -> f32 {
+    if fade_ms == 0 {
+        return 1.0;
+    }
+    (elapsed_ms as f32 / fade_ms as f32).min(1.0)
+}
+
+/// Diagnostic pattern: red, green, blue repeating from pixel 0, with every 10th pixel
+/// (index 9, 19, ...) white. Wrong colors mean a wrong `color_order`; a shifted
+/// marker means a wrong pixel count or start channel.
+fn test_pattern_pixel(index: usize) -> [u8; 3] {
+    if index % 10 == 9 {
+        return [255, 255, 255];
+    }
+    match index % 3 {
+        0 => [255, 0, 0],
+        1 => [0, 255, 0],
+        _ => [0, 0, 255],
+    }
 }
 
 pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [u8; 3] {
@@ -1567,6 +2848,75 @@ pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [u8; 3] {
     [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8]
 }
 
+/// Classic four-term sine plasma, normalized to 0..1
+fn plasma_value(x: f32, y: f32, z: f32, time: f32) -> f32 {
+    // Moving centre for the radial term so the pattern drifts around the rig
+    let cx = x + 0.5 * (time / 5.0).sin() * 4.0;
+    let cy = y + 0.5 * (time / 3.0).cos() * 4.0;
+
+    // z = 0 gives the flat 2D field
+    let v = (x + time).sin()
+        + ((y + time) * 0.5).sin()
+        + ((x + y + z + time) * 0.5).sin()
+        + ((cx * cx + cy * cy + z * z).sqrt() + time).sin();
+
+    (v + 4.0) / 8.0
+}
+
+/// Wave mask level (0..1) at `distance` along the wave direction from its origin: 1 on the
+/// crests and `1 - amplitude` in the troughs. `speed` is in canvas units per second.
+fn wave_brightness(distance: f32, wavelength: f32, speed: f32, amplitude: f32, t: f32) -> f32 {
+    let cycles = (distance - speed * t) / wavelength.max(0.001);
+    let sine = 0.5 + 0.5 * (cycles * std::f32::consts::TAU).sin();
+    1.0 - amplitude.clamp(0.0, 1.0) * (1.0 - sine)
+}
+
+/// Color at `pos` (0..1) interpolating linearly in RGB between the surrounding `stops`;
+/// positions outside the first/last stop hold that stop's color
+fn sample_gradient(stops: &[(f32, [u8; 3])], pos: f32) -> [u8; 3] {
+    let Some(&(first_pos, first_color)) = stops.first() else { return [0, 0, 0] };
+    if pos <= first_pos {
+        return first_color;
+    }
+    for pair in stops.windows(2) {
+        let ((a_pos, a), (b_pos, b)) = (pair[0], pair[1]);
+        if pos <= b_pos {
+            let span = b_pos - a_pos;
+            let mix = if span > 0.0 { (pos - a_pos) / span } else { 1.0 };
+            return [
+                (a[0] as f32 + (b[0] as f32 - a[0] as f32) * mix).round() as u8,
+                (a[1] as f32 + (b[1] as f32 - a[1] as f32) * mix).round() as u8,
+                (a[2] as f32 + (b[2] as f32 - a[2] as f32) * mix).round() as u8,
+            ];
+        }
+    }
+    stops[stops.len() - 1].1
+}
+
+/// Map a 0..1 plasma value to a color
+fn plasma_palette(palette: &str, value: f32, color_a: [u8; 3], color_b: [u8; 3]) -> [u8; 3] {
+    let value = value.clamp(0.0, 1.0);
+    match palette {
+        "Fire" => {
+            let r = (value * 3.0).min(1.0);
+            let g = (value * 3.0 - 1.0).clamp(0.0, 1.0);
+            let b = (value * 3.0 - 2.0).clamp(0.0, 1.0);
+            [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8]
+        }
+        "Ocean" => hsv_to_rgb(0.5 + value * 0.2, 1.0, 0.3 + value * 0.7),
+        "Duo" => {
+            // Ping-pong between the two colors so the palette has no hard seam
+            let mix = ((value * std::f32::consts::TAU).sin() + 1.0) / 2.0;
+            [
+                (color_a[0] as f32 * (1.0 - mix) + color_b[0] as f32 * mix) as u8,
+                (color_a[1] as f32 * (1.0 - mix) + color_b[1] as f32 * mix) as u8,
+                (color_a[2] as f32 * (1.0 - mix) + color_b[2] as f32 * mix) as u8,
+            ]
+        }
+        _ => hsv_to_rgb(value.fract(), 1.0, 1.0), // "Rainbow"
+    }
+}
+
 /// Apply LFO modulation to a parameter value
 fn apply_lfo_modulation(
     base_value: f32,
@@ -1629,3 +2979,721 @@ fn apply_lfo_modulation(
     let modulation = wave_value * depth;
     base_value * (1.0 + modulation)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effect_params::gradient_stops;
+    use crate::model::GlobalEffectConfig;
+
+    /// Helper to create a horizontal test strip
+    fn create_test_strip(id: u64, x: f32, y: f32, pixel_count: usize) -> PixelStrip {
+        PixelStrip {
+            id,
+            pixel_count,
+            x,
+            y,
+            spacing: 0.01,
+            data: vec![[0, 0, 0]; pixel_count],
+            ..Default::default()
+        }
+    }
+
+    /// Helper to create a Global scene running a single effect on all strips
+    fn create_global_scene(kind: &str, params: serde_json::Value) -> Scene {
+        Scene {
+            id: 1,
+            name: "Test".to_string(),
+            kind: "Global".to_string(),
+            category: "Uncategorized".to_string(),
+            masks: Vec::new(),
+            global: None,
+            global_effects: vec![GlobalEffectConfig {
+                effect: GlobalEffect {
+                    kind: kind.to_string(),
+                    params: serde_json::from_value(params).unwrap(),
+                    preset_id: None,
+                    automation: Vec::new(),
+                },
+                targets: None,
+            }],
+            launchpad_btn: None,
+            launchpad_is_cc: false,
+            launchpad_color: None,
+            launchpad_momentary: false,
+            notes: String::new(),
+            exclusive_group: crate::model::default_exclusive_group(),
+            layer: 0,
+            opacity: 1.0,
+            blend_mode: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_solid_fills_every_strip() {
+        let strips = vec![create_test_strip(1, 0.0, 0.2, 10), create_test_strip(2, 0.0, 0.8, 5)];
+        let scene = create_global_scene("Solid", serde_json::json!({ "color": [10, 20, 30] }));
+
+        let frames = render_scene_to_frames(&scene, &[], &strips, 0);
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[&1], vec![[10, 20, 30]; 10]);
+        assert_eq!(frames[&2], vec![[10, 20, 30]; 5]);
+    }
+
+    #[test]
+    fn test_park_disabled_strips_restores_order() {
+        let mut strips: Vec<PixelStrip> = (1..=4).map(|id| create_test_strip(id, 0.0, 0.5, 2)).collect();
+        strips[1].enabled = false;
+        strips[3].enabled = false;
+        strips[3].data[0] = [9, 9, 9];
+
+        let parked = park_disabled_strips(&mut strips);
+        assert_eq!(strips.iter().map(|s| s.id).collect::<Vec<_>>(), vec![1, 3]);
+
+        unpark_strips(&mut strips, parked);
+        assert_eq!(strips.iter().map(|s| s.id).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(strips[3].data, vec![[0, 0, 0]; 2]);
+    }
+
+    #[test]
+    fn test_overlay_snapshot_skips_changed_strips() {
+        let mut strips: Vec<PixelStrip> = (1..=3).map(|id| create_test_strip(id, 0.0, 0.5, 2)).collect();
+        let snapshot: HashMap<u64, Vec<[u8; 3]>> = HashMap::from([
+            (1, vec![[10, 20, 30]; 2]),
+            (2, vec![[40, 50, 60]; 3]), // Strip 2 has been shortened since
+        ]);
+        strips[1].data.fill([1, 1, 1]);
+
+        overlay_snapshot(&mut strips, &snapshot);
+        assert_eq!(strips[0].data, vec![[10, 20, 30]; 2]);
+        assert_eq!(strips[1].data, vec![[1, 1, 1]; 2]);
+        assert_eq!(strips[2].data, vec![[0, 0, 0]; 2]);
+    }
+
+    #[test]
+    fn test_snapshot_replays_at_master_brightness_once() {
+        let mut engine = LightingEngine::new();
+        engine.set_clock(Box::new(MockClock::new(0)));
+        let mut state = AppState {
+            strips: vec![create_test_strip(1, 0.0, 0.5, 4)],
+            scenes: vec![create_global_scene("Solid", serde_json::json!({ "color": [200, 100, 40] }))],
+            active_scene_ids: vec![1],
+            master_brightness: 0.5,
+            ..Default::default()
+        };
+        engine.set_paused(&state, true);
+
+        engine.take_snapshot(0);
+        engine.update(&mut state);
+        assert_eq!(state.strips[0].data[0], [100, 50, 20]);
+
+        // Live output goes dark; the snapshot comes back at the same master level, not scaled twice
+        state.active_scene_ids.clear();
+        engine.show_snapshot(Some(0));
+        engine.update(&mut state);
+        assert_eq!(state.strips[0].data, vec![[100, 50, 20]; 4]);
+    }
+
+    #[test]
+    fn test_pause_policy_holds_or_blacks_out() {
+        for (policy, blackout_packets) in [("hold", 0), ("blackout", 3)] {
+            let clock = MockClock::new(0);
+            let mut engine = LightingEngine::new();
+            engine.set_clock(Box::new(clock.clone()));
+            let mut state = AppState { strips: vec![create_test_strip(1, 0.0, 0.5, 4)], ..Default::default() };
+            state.network.use_multicast = false;
+            state.network.unicast_ips = vec!["127.0.0.1".into()];
+            state.network.pause_policy = policy.into();
+
+            engine.update(&mut state);
+            assert_eq!(engine.stats.packets, 1, "{}", policy);
+
+            // Blackout sends its repeated black frame once, on pausing; hold sends nothing
+            engine.set_paused(&state, true);
+            assert_eq!(engine.stats.packets, 1 + blackout_packets, "{}", policy);
+            for _ in 0..3 {
+                clock.advance(100);
+                engine.update(&mut state);
+            }
+            assert_eq!(engine.stats.packets, 1 + blackout_packets, "{}", policy);
+            assert!(!engine.is_output_flowing());
+
+            // Resuming sends the next frame whatever the policy
+            engine.set_paused(&state, false);
+            clock.advance(100);
+            engine.update(&mut state);
+            assert_eq!(engine.stats.packets, 2 + blackout_packets, "{}", policy);
+            assert!(engine.is_output_flowing());
+        }
+    }
+
+    #[test]
+    fn test_blend_pixel_modes() {
+        let below = [200, 100, 0];
+        let layer = [100, 200, 255];
+        assert_eq!(blend_pixel(below, layer, "Max", 1.0), [200, 200, 255]);
+        assert_eq!(blend_pixel(below, layer, "Normal", 1.0), [100, 200, 255]);
+        assert_eq!(blend_pixel(below, layer, "Add", 1.0), [255, 255, 255]);
+        assert_eq!(blend_pixel(below, layer, "Multiply", 1.0), [78, 78, 0]);
+        assert_eq!(blend_pixel(below, layer, "Screen", 1.0), [222, 222, 255]);
+        assert_eq!(blend_pixel(below, layer, "Mask", 1.0), [200, 100, 0]);
+        assert_eq!(blend_pixel(below, [0, 0, 51], "Mask", 1.0), [40, 20, 0]);
+        assert_eq!(blend_pixel(below, layer, "Normal", 0.5), [150, 150, 128]);
+        assert_eq!(blend_pixel(below, layer, "Add", 0.0), below);
+    }
+
+    #[test]
+    fn test_render_layers_composites_by_layer_index() {
+        let mut strips = vec![create_test_strip(1, 0.0, 0.5, 4)];
+        let mut red = create_global_scene("Solid", serde_json::json!({ "color": [200, 0, 0] }));
+        let mut blue = create_global_scene("Solid", serde_json::json!({ "color": [0, 0, 100] }));
+        red.id = 1;
+        blue.id = 2;
+        let mut renderer = SceneRenderer::with_seed(RENDER_SEED);
+
+        // Max (global default): both show
+        renderer.render_layers(&[&red, &blue], &[], &[], &mut strips, 0.0, 0.0);
+        assert_eq!(strips[0].data, vec![[200, 0, 100]; 4]);
+
+        // Normal at half opacity over red; listing order doesn't matter, layer index does
+        blue.blend_mode = "Normal".into();
+        blue.opacity = 0.5;
+        blue.layer = 1;
+        renderer.render_layers(&[&blue, &red], &[], &[], &mut strips, 0.0, 0.0);
+        assert_eq!(strips[0].data, vec![[100, 0, 50]; 4]);
+
+        // Red on top now fully covers blue
+        red.blend_mode = "Normal".into();
+        red.layer = 2;
+        renderer.render_layers(&[&blue, &red], &[], &[], &mut strips, 0.0, 0.0);
+        assert_eq!(strips[0].data, vec![[200, 0, 0]; 4]);
+
+        // A lone layer still respects its opacity
+        renderer.render_layers(&[&blue], &[], &[], &mut strips, 0.0, 0.0);
+        assert_eq!(strips[0].data, vec![[0, 0, 50]; 4]);
+    }
+
+    #[test]
+    fn test_render_does_not_modify_input_strips() {
+        let strips = vec![create_test_strip(1, 0.0, 0.5, 10)];
+        let scene = create_global_scene("Solid", serde_json::json!({ "color": [255, 0, 0] }));
+
+        render_scene_to_frames(&scene, &[], &strips, 500);
+
+        assert_eq!(strips[0].data, vec![[0, 0, 0]; 10]);
+    }
+
+    #[test]
+    fn test_startup_gain_ramps_to_full() {
+        assert_eq!(startup_gain(0, 0), 1.0);
+        assert_eq!(startup_gain(0, 2000), 0.0);
+        assert_eq!(startup_gain(500, 2000), 0.25);
+        assert_eq!(startup_gain(5000, 2000), 1.0);
+    }
+
+    #[test]
+    fn test_pack_strip_places_fixture_channels_around_pixels() {
+        use crate::model::FixtureChannel;
+        let mut strip = create_test_strip(1, 0.0, 0.5, 2);
+        strip.start_channel = 10;
+        strip.color_order = "GRB".into();
+        strip.pre_channels = vec![255.into(), FixtureChannel::Level { level: 200 }];
+        strip.post_channels = vec![7.into()];
+        strip.data = vec![[100, 50, 0], [0, 0, 0]];
+        let mut universe = vec![0u8; 512];
+
+        pack_strip(&strip, &GammaLut::new(1.0), &mut universe);
+        // Channel 10 = index 9: dimmer, level (200 * 100/255), two GRB pixels, trailing fixed channel
+        assert_eq!(universe[9..18], [255, 78, 50, 100, 0, 0, 0, 0, 7]);
+        assert_eq!(universe[8], 0);
+        assert_eq!(universe[18], 0);
+
+        // Near the end of the universe, whatever doesn't fit is dropped
+        strip.start_channel = 508;
+        let mut universe = vec![0u8; 512];
+        pack_strip(&strip, &GammaLut::new(1.0), &mut universe);
+        assert_eq!(universe[507..], [255, 78, 50, 100, 0]);
+    }
+
+    #[test]
+    fn test_min_brightness_lifts_only_non_zero() {
+        assert_eq!(lift_min_brightness(0, 8), 0);
+        assert_eq!(lift_min_brightness(1, 8), 8);
+        assert_eq!(lift_min_brightness(7, 8), 8);
+        assert_eq!(lift_min_brightness(8, 8), 8);
+        assert_eq!(lift_min_brightness(9, 8), 9);
+        assert_eq!(lift_min_brightness(255, 8), 255);
+        assert_eq!(lift_min_brightness(1, 0), 1);
+    }
+
+    #[test]
+    fn test_crossfade_blends_frames_and_restarts_mid_fade() {
+        let mut fade = Crossfade::default();
+        let frame = |value: u8| vec![PixelStrip { data: vec![[value; 3]; 2], ..create_test_strip(1, 0.0, 0.5, 2) }];
+
+        let mut strips = frame(200);
+        fade.apply(&[1], 1000, 0, &mut strips);
+        assert_eq!(strips[0].data[0], [200; 3]); // Nothing to fade from yet
+
+        // Switching scenes blends from the last frame shown
+        let mut strips = frame(0);
+        fade.apply(&[2], 1000, 100, &mut strips);
+        assert_eq!(strips[0].data[0], [200; 3]);
+        let mut strips = frame(0);
+        fade.apply(&[2], 1000, 600, &mut strips);
+        assert_eq!(strips[0].data[0], [100; 3]);
+
+        // Another switch mid-fade starts over from the blended frame
+        let mut strips = frame(50);
+        fade.apply(&[3], 1000, 700, &mut strips);
+        assert_eq!(strips[0].data[0], [100; 3]);
+        let mut strips = frame(50);
+        fade.apply(&[3], 1000, 1200, &mut strips);
+        assert_eq!(strips[0].data[0], [75; 3]);
+        let mut strips = frame(50);
+        fade.apply(&[3], 1000, 1700, &mut strips);
+        assert_eq!(strips[0].data[0], [50; 3]);
+
+        // 0 ms cuts straight over
+        let mut strips = frame(10);
+        fade.apply(&[4], 0, 1800, &mut strips);
+        assert_eq!(strips[0].data[0], [10; 3]);
+    }
+
+    #[test]
+    fn test_output_pacer_caps_rate_and_skips_unchanged() {
+        // A 60 fps render loop capped at 44 fps sends 44 frames a second on average
+        let mut pacer = OutputPacer::default();
+        let sent = (0..600u64).filter(|frame| pacer.frame_due(frame * 1000 / 60, 44)).count();
+        assert!((438..=442).contains(&sent), "sent {} frames in 10 s", sent);
+        assert!((0..10u64).all(|frame| pacer.frame_due(frame, 0)), "0 fps is uncapped");
+
+        // Unchanged universes wait for the keep-alive; changed ones go out at once
+        let packet = |universe, value| UniversePacket {
+            universe,
+            bind: String::new(),
+            dst: None,
+            data: vec![0, value],
+            protocol: crate::model::DmxProtocol::Sacn,
+        };
+        let mut packets = vec![packet(1, 10), packet(2, 20)];
+        pacer.retain_changed(&mut packets, 0, Some(1000));
+        assert_eq!(packets.len(), 2);
+        let mut packets = vec![packet(1, 10), packet(2, 21)];
+        pacer.retain_changed(&mut packets, 500, Some(1000));
+        assert_eq!(packets, vec![packet(2, 21)]);
+        let mut packets = vec![packet(1, 10), packet(2, 21)];
+        pacer.retain_changed(&mut packets, 1000, Some(1000));
+        assert_eq!(packets, vec![packet(1, 10)]);
+        // Without a keep-alive everything goes out every frame
+        let mut packets = vec![packet(1, 10), packet(2, 21)];
+        pacer.retain_changed(&mut packets, 1100, None);
+        assert_eq!(packets.len(), 2);
+    }
+
+    #[test]
+    fn test_gamma_lut() {
+        let linear = GammaLut::new(1.0);
+        assert!((0..=255u8).all(|c| linear.apply(c) == c), "Gamma 1.0 must leave output untouched");
+
+        let mut lut = GammaLut::new(2.2);
+        assert_eq!(lut.apply(0), 0);
+        assert_eq!(lut.apply(255), 255);
+        assert_eq!(lut.apply(128), 56);
+        assert!((1..=255u8).all(|c| lut.apply(c) >= lut.apply(c - 1)), "Curve must be monotonic");
+
+        // Unset (0.0) or broken values fall back to a sane curve instead of saturating
+        lut.set_gamma(0.0);
+        assert_eq!(lut.apply(128), 128);
+        lut.set_gamma(f32::NAN);
+        assert_eq!(lut.apply(128), 128);
+    }
+
+    #[test]
+    fn test_scale_pixel_stays_in_range() {
+        assert_eq!(scale_pixel([255, 128, 0], 1.0), [255, 128, 0]);
+        assert_eq!(scale_pixel([255, 128, 0], 0.5), [127, 64, 0]);
+        assert_eq!(scale_pixel([255, 128, 0], 0.0), [0, 0, 0]);
+        // Out-of-range gains from hand-edited files are clamped rather than wrapping
+        assert_eq!(scale_pixel([255, 128, 0], 4.0), [255, 128, 0]);
+        assert_eq!(scale_pixel([255, 128, 0], -1.0), [0, 0, 0]);
+        // Master and strip dimmers compose by multiplying
+        assert_eq!(scale_pixel([200, 200, 200], 0.5 * 0.5), [50, 50, 50]);
+    }
+
+    #[test]
+    fn test_test_pattern_sequence_and_markers() {
+        let pixels: Vec<[u8; 3]> = (0..21).map(test_pattern_pixel).collect();
+        assert_eq!(pixels[0], [255, 0, 0]);
+        assert_eq!(pixels[1], [0, 255, 0]);
+        assert_eq!(pixels[2], [0, 0, 255]);
+        assert_eq!(pixels[3], [255, 0, 0]);
+        assert_eq!(pixels[9], [255, 255, 255]);
+        assert_eq!(pixels[10], [0, 255, 0]);
+        assert_eq!(pixels[19], [255, 255, 255]);
+        assert_eq!(pixels.iter().filter(|p| **p == [255, 255, 255]).count(), 2);
+    }
+
+    #[test]
+    fn test_stats_counts_frames_and_overruns() {
+        let mut stats = StatsCounter::new();
+        stats.packets = 30;
+        // 58 on-budget frames, then one frame that overran by two slots
+        for _ in 0..58 {
+            assert!(!stats.frame(1.0 / 60.0, 0));
+        }
+        assert!(stats.frame(3.0 / 60.0, 0));
+
+        let s = stats.current;
+        let window = 61.0 / 60.0;
+        assert!((s.render_fps - 59.0 / window).abs() < 0.01);
+        assert!((s.packets_per_sec - 30.0 / window).abs() < 0.01);
+        assert_eq!(s.frames_skipped, 2);
+        assert_eq!(stats.packets, 0);
+    }
+
+    #[test]
+    fn test_stats_follow_the_frame_rate() {
+        // A 120 Hz loop on time misses nothing; a stall of three frames misses two slots
+        let mut stats = StatsCounter::new();
+        for _ in 0..120 {
+            stats.frame(1.0 / 120.0, 0);
+        }
+        assert_eq!(stats.current.frames_skipped, 0);
+        stats.frame(3.0 / 120.0, 0);
+        assert_eq!(stats.total_skipped, 2);
+
+        // A steady 30 fps loop isn't an overrun; with a 44 fps output cap its slots are
+        let mut slow = StatsCounter::new();
+        for _ in 0..30 {
+            slow.frame(1.0 / 30.0, 0);
+        }
+        assert_eq!(slow.total_skipped, 0);
+        slow.frame(1.0 / 22.0, 44);
+        assert_eq!(slow.total_skipped, 1);
+    }
+
+    #[test]
+    fn test_adaptive_quality_drops_and_recovers() {
+        let mut quality = AdaptiveQuality::default();
+        assert!((0..4).all(|_| quality.should_render()));
+
+        for _ in 0..QUALITY_DOWN_FRAMES {
+            quality.observe(20.0, 10.0);
+        }
+        assert_eq!(quality.level, 1);
+        let rendered = (0..4).filter(|_| quality.should_render()).count();
+        assert_eq!(rendered, 2);
+
+        // Within budget but not under half of it: hold the level
+        for _ in 0..QUALITY_UP_FRAMES {
+            quality.observe(7.0, 10.0);
+        }
+        assert_eq!(quality.level, 1);
+
+        for _ in 0..QUALITY_UP_FRAMES {
+            quality.observe(2.0, 10.0);
+        }
+        assert_eq!(quality.level, 0);
+
+        for _ in 0..QUALITY_DOWN_FRAMES * 10 {
+            quality.observe(50.0, 10.0);
+        }
+        assert_eq!(quality.level, MAX_QUALITY_LEVEL);
+    }
+
+    #[test]
+    fn test_radial_mask_uses_depth_when_set() {
+        let mut renderer = SceneRenderer::with_seed(1);
+        let strip = |id, z| PixelStrip { z, ..create_test_strip(id, 0.5, 0.5, 1) };
+        let render = |renderer: &mut SceneRenderer, mask_z| {
+            let mut strips = vec![strip(1, Some(0.0)), strip(2, Some(1.0)), strip(3, None)];
+            let mask = Mask { id: 1, mask_type: "radial".to_string(), x: 0.5, y: 0.5, z: mask_z, params: HashMap::new(), modulation: Vec::new() };
+            renderer.apply_mask_to_strips(&mask, &mut strips, 0.0, 0.0);
+            strips.iter().map(|s| s.data[0] != [0, 0, 0]).collect::<Vec<_>>()
+        };
+
+        // The strip one unit deep is outside the default 0.2 radius; strips without z stay 2D
+        assert_eq!(render(&mut renderer, Some(0.0)), vec![true, false, true]);
+        // Without a mask depth, every strip is measured on the canvas plane
+        assert_eq!(render(&mut renderer, None), vec![true, true, true]);
+    }
+
+    #[test]
+    fn test_spectrum_bar_pixel_fills_band_sections() {
+        let colors = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
+        let render = |bands: &[f32]| (0..12).map(|i| spectrum_bar_pixel(bands, &colors, i, 12)).collect::<Vec<_>>();
+
+        let frame = render(&[1.0, 0.5, 0.625]);
+        assert_eq!(&frame[0..4], &[[255, 0, 0]; 4]);
+        assert_eq!(&frame[4..8], &[[0, 255, 0], [0, 255, 0], [0, 0, 0], [0, 0, 0]]);
+        // 2.5 pixels of the high band: the last one half lit
+        assert_eq!(&frame[8..12], &[[0, 0, 255], [0, 0, 255], [0, 0, 128], [0, 0, 0]]);
+
+        assert!(render(&[0.0, 0.0, 0.0]).iter().all(|p| *p == [0, 0, 0]));
+        assert_eq!(spectrum_bar_pixel(&[], &colors, 0, 12), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_modulate_mask_applies_gain_and_offset() {
+        let mut renderer = SceneRenderer::with_seed(1);
+        renderer.audio_level = 0.5;
+        renderer.audio_bands = vec![0.0, 1.0];
+
+        let mask = Mask {
+            id: 1,
+            mask_type: "radial".to_string(),
+            x: 0.0,
+            y: 0.0,
+            z: None,
+            params: serde_json::from_value(serde_json::json!({ "radius": 0.2, "speed": 1.0 })).unwrap(),
+            modulation: vec![
+                AudioModulation { param: "radius".to_string(), source: "Level".to_string(), band: 0, gain: 0.4, offset: 0.1 },
+                AudioModulation { param: "speed".to_string(), source: "Band".to_string(), band: 1, gain: 2.0, offset: 0.0 },
+                AudioModulation { param: "intensity".to_string(), source: "Level".to_string(), band: 0, gain: 1.0, offset: 0.0 },
+            ],
+        };
+
+        let out = renderer.modulate_mask(&mask);
+        assert!((out.params["radius"].as_f64().unwrap() - 0.5).abs() < 1e-6);
+        assert!((out.params["speed"].as_f64().unwrap() - 3.0).abs() < 1e-6);
+        assert!(!out.params.contains_key("intensity"));
+    }
+
+    #[test]
+    fn test_wave_mask_follows_sine() {
+        let mut renderer = SceneRenderer::with_seed(1);
+        let mut strips = vec![create_test_strip(1, 0.0, 0.5, 10)];
+        let mask = Mask {
+            id: 1,
+            mask_type: "wave".to_string(),
+            x: 0.0,
+            y: 0.5,
+            z: None,
+            params: serde_json::from_value(serde_json::json!({
+                "wavelength": 0.1, "speed": 0.1, "amplitude": 1.0, "angle": 0.0, "color": [200, 0, 0],
+            })).unwrap(),
+            modulation: Vec::new(),
+        };
+
+        // At t = 0.25s the wave has moved a quarter wavelength (0.025) along x
+        renderer.apply_mask_to_strips(&mask, &mut strips, 0.25, 0.0);
+        for (i, pixel) in strips[0].data.iter().enumerate() {
+            let x = i as f32 * 0.01;
+            let expected = 200.0 * (0.5 + 0.5 * ((x - 0.025) / 0.1 * std::f32::consts::TAU).sin());
+            assert!((pixel[0] as f32 - expected).abs() <= 1.0, "pixel {}: {} vs {}", i, pixel[0], expected);
+        }
+        assert!(strips[0].data[5][0] >= 199); // Crest
+        assert!(strips[0].data[0][0] <= 1); // Trough
+
+        // Half amplitude keeps the troughs at half brightness
+        assert!((wave_brightness(-0.025, 0.1, 0.0, 0.5, 0.0) - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_mask_angle_rotates_scanner_and_shapes_radial() {
+        let mut renderer = SceneRenderer::with_seed(1);
+        let scanner = |angle: f64| Mask {
+            id: 1,
+            mask_type: "scanner".to_string(),
+            x: 0.5,
+            y: 0.5,
+            z: None,
+            params: serde_json::from_value(serde_json::json!({
+                "width": 0.4, "height": 0.4, "bar_width": 0.05, "hard_edge": true, "speed": 0.0, "angle": angle,
+            })).unwrap(),
+            modulation: Vec::new(),
+        };
+        let lit = |strips: &[PixelStrip]| strips[0].data.iter().filter(|p| **p != [0, 0, 0]).count();
+
+        // Angle 0 matches a mask without the param: a narrow vertical bar at the centre
+        let mut plain = vec![create_test_strip(1, 0.3, 0.5, 41)];
+        let mut unrotated = plain.clone();
+        let mut no_angle = scanner(0.0);
+        no_angle.params.remove("angle");
+        renderer.apply_mask_to_strips(&no_angle, &mut plain, 0.0, 0.0);
+        renderer.apply_mask_to_strips(&scanner(0.0), &mut unrotated, 0.0, 0.0);
+        assert_eq!(plain[0].data, unrotated[0].data);
+        assert!(lit(&unrotated) <= 11);
+
+        // At 90° the bar lies along the strip, lighting the whole width of the mask
+        let mut rotated = vec![create_test_strip(1, 0.3, 0.5, 41)];
+        renderer.apply_mask_to_strips(&scanner(90.0), &mut rotated, 0.0, 0.0);
+        assert_eq!(lit(&rotated), 41);
+
+        // A radial mask stretched along x reaches further along a horizontal strip
+        let radial = |angle: f64| Mask {
+            id: 2,
+            mask_type: "radial".to_string(),
+            x: 0.5,
+            y: 0.5,
+            z: None,
+            params: serde_json::from_value(serde_json::json!({
+                "radius": 0.2, "stretch": 2.0, "angle": angle,
+            })).unwrap(),
+            modulation: Vec::new(),
+        };
+        let mut along = vec![create_test_strip(1, 0.0, 0.5, 100)];
+        renderer.apply_mask_to_strips(&radial(0.0), &mut along, 0.0, 0.0);
+        assert_ne!(along[0].data[80], [0, 0, 0]);
+        let mut across = vec![create_test_strip(1, 0.0, 0.5, 100)];
+        renderer.apply_mask_to_strips(&radial(90.0), &mut across, 0.0, 0.0);
+        assert_eq!(across[0].data[80], [0, 0, 0]);
+        assert_ne!(across[0].data[60], [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_render_is_deterministic() {
+        let strips = vec![create_test_strip(1, 0.0, 0.5, 100)];
+        let scene = create_global_scene("Sparkle", serde_json::json!({ "density": 0.1, "life": 0.5 }));
+
+        let a = render_scene_to_frames(&scene, &[], &strips, 1234);
+        let b = render_scene_to_frames(&scene, &[], &strips, 1234);
+
+        assert_eq!(a, b, "Same scene, strips and time should render identical frames");
+        assert!(a[&1].iter().any(|p| *p != [0, 0, 0]), "Sparkle should light some pixels");
+    }
+
+    #[test]
+    fn test_ripple_effects_keep_separate_state() {
+        let mut scene = create_global_scene("Ripple", serde_json::json!({ "origin_x": 0.2 }));
+        let mut second = scene.global_effects[0].clone();
+        second.effect.params.insert("origin_x".into(), serde_json::json!(0.8));
+        scene.global_effects.push(second);
+        let mut renderer = SceneRenderer::with_seed(1);
+        let mut strips = vec![create_test_strip(1, 0.0, 0.5, 10)];
+        let waves = |renderer: &SceneRenderer, i: u64| renderer.ripple_states.get(&(Some(1), i)).map(|s| s.waves.iter().map(|w| w.origin.0).collect::<Vec<_>>());
+
+        // No audio: both effects spawn on the beat clock, each with only its own ring
+        renderer.render(Some(&scene), &[], &[], &mut strips, 0.0, 0.5);
+        renderer.render(Some(&scene), &[], &[], &mut strips, 0.5, 1.0);
+        assert_eq!(waves(&renderer, 0), Some(vec![0.2]));
+        assert_eq!(waves(&renderer, 1), Some(vec![0.8]));
+
+        // A removed effect's state is dropped
+        scene.global_effects.pop();
+        renderer.render(Some(&scene), &[], &[], &mut strips, 1.0, 1.5);
+        assert_eq!(waves(&renderer, 0), Some(vec![0.2]));
+        assert_eq!(waves(&renderer, 1), None);
+    }
+
+    #[test]
+    fn test_sparkle_sync_mode() {
+        let strips = vec![create_test_strip(1, 0.0, 0.2, 60), create_test_strip(2, 0.0, 0.8, 60)];
+        for kind in ["Sparkle", "GlitchSparkle"] {
+            let shared = create_global_scene(kind, serde_json::json!({ "density": 0.1, "sync_mode": "shared" }));
+            let frames = render_scene_to_frames(&shared, &[], &strips, 1000);
+            assert_eq!(frames[&1], frames[&2], "{} shared mode should light the same pixels on every strip", kind);
+            assert!(frames[&1].iter().any(|p| *p != [0, 0, 0]));
+
+            // Per strip: each strip's stream depends only on its id, not on the other strips
+            let per_strip = create_global_scene(kind, serde_json::json!({ "density": 0.1, "sync_mode": "per_strip" }));
+            let both = render_scene_to_frames(&per_strip, &[], &strips, 1000);
+            let alone = render_scene_to_frames(&per_strip, &[], &strips[1..], 1000);
+            assert_eq!(both[&2], alone[&2], "{} per-strip stream should not depend on other strips", kind);
+            assert_ne!(both[&1], both[&2]);
+        }
+    }
+
+    #[test]
+    fn test_quantize_restarts_phase_on_param_change() {
+        assert_eq!(quantize_beat(10.75, "beat"), 10.0);
+        assert_eq!(quantize_beat(10.75, "bar"), 8.0);
+        assert_eq!(quantize_beat(10.75, "none"), 10.75);
+
+        // 60 BPM, so beats and seconds coincide
+        let hue_after_edit = |quantize: &str| {
+            let mut scene = create_global_scene("Rainbow", serde_json::json!({ "speed": 0.2 }));
+            let mut strips = vec![create_test_strip(1, 0.0, 0.5, 4)];
+            let mut renderer = SceneRenderer::with_seed(RENDER_SEED);
+            renderer.bpm = 60.0;
+            renderer.quantize = quantize.to_string();
+            renderer.render(Some(&scene), &[], &[], &mut strips, 10.0, 10.0);
+            scene.global_effects[0].effect.params.insert("speed".into(), serde_json::json!(0.35));
+            renderer.render(Some(&scene), &[], &[], &mut strips, 10.25, 10.25);
+            let edited = strips[0].data[0];
+            renderer.render(Some(&scene), &[], &[], &mut strips, 11.0, 11.0);
+            (edited, strips[0].data[0])
+        };
+        assert_eq!(hue_after_edit("none").0, hsv_to_rgb((10.25f32 * 0.35).fract(), 1.0, 1.0));
+        // The edit restarts the phase from beat 10 (or bar start at beat 8) and then runs on
+        assert_eq!(hue_after_edit("beat"), (hsv_to_rgb(0.25 * 0.35, 1.0, 1.0), hsv_to_rgb(0.35, 1.0, 1.0)));
+        assert_eq!(hue_after_edit("bar").0, hsv_to_rgb((2.25f32 * 0.35).fract(), 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_render_flash_decays_within_beat() {
+        let strips = vec![create_test_strip(1, 0.0, 0.5, 4)];
+        let scene = create_global_scene("Flash", serde_json::json!({ "color": [200, 200, 200], "rate": "1/4" }));
+
+        // 120 BPM: beats land every 500ms
+        let on_beat = render_scene_to_frames(&scene, &[], &strips, 1000);
+        let mid_beat = render_scene_to_frames(&scene, &[], &strips, 1250);
+
+        assert_eq!(on_beat[&1][0], [200, 200, 200]);
+        assert!(mid_beat[&1][0][0] < on_beat[&1][0][0]);
+    }
+
+    #[test]
+    fn test_render_strobe_duty_cycle_and_clamp() {
+        let strips = vec![create_test_strip(1, 0.0, 0.5, 4)];
+        let scene = create_global_scene("Strobe", serde_json::json!({ "color": [0, 200, 0], "frequency_hz": 4.0, "duty_cycle": 0.25 }));
+        // 250ms period, on for the first 62.5ms of each
+        assert_eq!(render_scene_to_frames(&scene, &[], &strips, 1000)[&1][0], [0, 200, 0]);
+        assert_eq!(render_scene_to_frames(&scene, &[], &strips, 1100)[&1][0], [0, 0, 0]);
+
+        // 1000 Hz is clamped to 25 Hz: 30ms in is past the half-period
+        let scene = create_global_scene("Strobe", serde_json::json!({ "frequency_hz": 1000.0 }));
+        assert_eq!(render_scene_to_frames(&scene, &[], &strips, 1000)[&1][0], [255, 255, 255]);
+        assert_eq!(render_scene_to_frames(&scene, &[], &strips, 1030)[&1][0], [0, 0, 0]);
+
+        // Malformed params fall back to defaults instead of panicking
+        let scene = create_global_scene("Strobe", serde_json::json!({ "frequency_hz": "fast", "duty_cycle": null, "color": "red" }));
+        assert_eq!(render_scene_to_frames(&scene, &[], &strips, 1000)[&1][0], [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_gradient_stops_coerced_and_interpolated() {
+        // Unsorted, one malformed stop, positions out of range
+        let stops = gradient_stops(Some(&serde_json::json!([
+            { "pos": 1.5, "color": [0, 0, 255] },
+            { "pos": "mid", "color": [9, 9, 9] },
+            { "pos": -1.0, "color": [255, 0, 0] },
+        ])));
+        assert_eq!(stops, vec![(0.0, [255, 0, 0]), (1.0, [0, 0, 255])]);
+        assert_eq!(sample_gradient(&stops, 0.5), [128, 0, 128]);
+
+        assert_eq!(gradient_stops(Some(&serde_json::json!([{ "pos": 0.3, "color": [1, 2, 3] }]))), vec![(0.0, [1, 2, 3]), (1.0, [1, 2, 3])]);
+        assert_eq!(gradient_stops(None).len(), 2);
+
+        let strips = vec![create_test_strip(1, 0.0, 0.5, 5)];
+        let scene = create_global_scene("Gradient", serde_json::json!({
+            "stops": [{ "pos": 0.0, "color": [0, 0, 0] }, { "pos": 1.0, "color": [200, 0, 0] }],
+        }));
+        let frame = &render_scene_to_frames(&scene, &[], &strips, 1000)[&1];
+        assert_eq!(frame[0], [0, 0, 0]);
+        assert_eq!(frame[2], [100, 0, 0]);
+        assert_eq!(frame[4], [200, 0, 0]);
+
+        let mut flipped = strips.clone();
+        flipped[0].flipped = true;
+        assert_eq!(render_scene_to_frames(&scene, &[], &flipped, 1000)[&1][0], [200, 0, 0]);
+    }
+
+    #[test]
+    fn test_render_at_follows_mock_clock() {
+        let scene = create_global_scene("Rainbow", serde_json::json!({ "speed": 1.0 }));
+        let clock = MockClock::new(0);
+        let frame_at = |ms: u64| {
+            let mut strips = vec![create_test_strip(1, 0.0, 0.5, 8)];
+            clock.set(ms);
+            SceneRenderer::with_seed(RENDER_SEED).render_at(&clock, 120.0, Some(&scene), &[], &[], &mut strips);
+            strips.remove(0).data
+        };
+
+        // Same timestamp, same frame; the rainbow moves as the clock advances
+        assert_eq!(frame_at(1500), frame_at(1500));
+        assert_ne!(frame_at(1500), frame_at(1750));
+    }
+}
@@ -1,4 +1,34 @@
-
+//! Lightspeed application entry point and UI.
+//!
+//! # Threads and state ownership
+//!
+//! `AppState` has exactly one owner: `MyApp` on the UI thread. Nothing else
+//! holds a reference to it, so it needs no lock. Every other thread talks to the
+//! UI thread through channels or small shared cells, never through `AppState`:
+//!
+//! - **UI thread** (`eframe::App::update`): owns `AppState`, the `Database` and the
+//!   `LightingEngine`. Renders and sends DMX (sACN or Art-Net) once per frame via
+//!   `LightingEngine::update` and is the only place state is mutated or saved.
+//! - **Output workers** (`output::OutputPool`, threads `sacn-out-N`): only when
+//!   `AppState::output_threads` is above 1. Each owns its own sockets and receives the
+//!   frame's packets (already rendered and packed) over a channel, universes sharded
+//!   across workers; the engine waits for all of them before the frame ends.
+//! - **Node monitor** (`reachability::NodeMonitor`): while `network.probe_interval_ms`
+//!   is set, probes the unicast targets it is handed and reports `NodeEvent`s over a
+//!   channel the UI thread drains. Stops when the monitor is dropped.
+//! - **MIDI service** (`midi::start_midi_service`): owns the MIDI ports. Receives
+//!   `MidiCommand`s over a `Sender` and reports `MidiEvent`s back; the UI thread drains
+//!   those at the start of each frame and applies them to `AppState`. Stopped through
+//!   its `midi::MidiServiceHandle` when MIDI is disabled or the app exits.
+//! - **Audio callback** (`audio::AudioListener`): writes level/peak/features into
+//!   `Arc<Mutex<_>>` / atomics that the engine reads each frame. It never sees `AppState`.
+//! - **Preview server** (`preview::PreviewServer`): client threads only read encoded
+//!   frames the engine publishes into a single shared slot.
+//!
+//! Control surfaces express what they want as `command::AppCommand`s pushed into the
+//! shared `Sender` from `command_sender()`; the UI thread applies them after draining
+//! MIDI events. New input sources (OSC, TCP, WebSocket remotes) should run their own
+//! thread and push commands the same way rather than touching `AppState`.
 
 mod model;
 mod engine;
@@ -6,9 +36,19 @@ mod audio;
 mod scanner;
 mod midi;
 mod db;
+mod preview;
+mod strip_csv;
+mod command;
+mod clock;
+mod output;
+mod reachability;
+mod pixel_map;
+mod easing;
+mod state_file;
+mod effect_params;
 
 use eframe::egui;
-use model::{AppState, PixelStrip, Mask};
+use model::{AppState, PixelStrip, Mask, UniverseRoute};
 use engine::LightingEngine;
 use db::Database;
 use std::fs;
@@ -124,17 +164,43 @@ struct MyApp {
     import_file_path: Option<PathBuf>,
     // MIDI
     midi_sender: Sender<midi::MidiCommand>,
+    midi_service: Option<midi::MidiServiceHandle>, // Present while the service thread runs
     midi_receiver: Receiver<midi::MidiEvent>,
+    midi_event_sender: Sender<midi::MidiEvent>, // Main-thread detection reports through the service's event stream
+    midi_ports_available: (Vec<String>, Vec<String>), // Inputs and outputs from the last detection pass
     midi_connected: bool,
+    midi_model: midi::LaunchpadModel, // Profile of the last detected Launchpad
+    midi_status: String, // Last connection state reported by the MIDI service
+    midi_last_error: Option<String>,
+    midi_unavailable: bool, // Backend can't be opened on this machine; detection stops until MIDI is re-enabled
+    midi_service_running: bool, // Service thread is up; follows `AppState.midi_enabled`
+    save_blocked: Option<String>, // Set when the database holds a newer format we must not overwrite
+    logo_output_flowing: Option<bool>, // sACN state last shown on the Launchpad logo; None = needs refresh
+    pulsing_pads: Option<Vec<u8>>, // Pads of active scenes currently pulsing; None = needs refresh
+    cc_learn: bool, // The next CC received becomes a new binding instead of acting
     last_midi_detection: Option<Instant>,
     // Scene Reordering
     dragged_scene_id: Option<u64>,
+    effect_clipboard: Option<(u64, usize)>, // Copied global effect as (scene id, effect index)
+    // Unified control input; see `command_sender`
+    command_sender: Sender<command::AppCommand>,
+    command_receiver: Receiver<command::AppCommand>,
+    // Idle fallback
+    last_activity: Instant, // Last MIDI or UI input
+    idle_restore: Option<(Vec<u64>, Option<u64>)>, // Scenes to bring back when input ends idle mode; Some while idle
+    node_monitor: Option<reachability::NodeMonitor>, // Running while `network.probe_interval_ms` is set
+    unreachable_nodes: std::collections::BTreeSet<std::net::IpAddr>,
+    // Audio meters, fed by the engine each frame
+    audio_levels_receiver: Receiver<audio::AudioLevels>,
+    audio_levels: audio::AudioLevels,
+    audio_input_names: Vec<String>, // Capture devices from the last scan, for the input picker
 }
 
 impl Default for MyApp {
     fn default() -> Self {
         let mut state = AppState::default();
         let mut status = "Ready".to_owned();
+        let mut save_blocked = None;
 
         // Open database
         let db_path = user_db_path();
@@ -154,7 +220,9 @@ impl Default for MyApp {
                     mask_type: "scanner".into(),
                     x: 0.5,
                     y: 0.5,
+                    z: None,
                     params: std::collections::HashMap::new(),
+                    modulation: Vec::new(),
                 });
 
                 // Create a dummy database (will retry on next launch)
@@ -167,22 +235,14 @@ impl Default for MyApp {
         // Load state from database
         match db.load_state() {
             Ok(loaded) => {
-                state = loaded;
-                // MIGRATION: Move deprecated `global` into `global_effects` if needed
-                for scene in &mut state.scenes {
-                    if scene.kind == "Global" && scene.global_effects.is_empty() && scene.global.is_some() {
-                         if let Some(old_global) = scene.global.take() {
-                             scene.global_effects.push(model::GlobalEffectConfig {
-                                 effect: old_global,
-                                 targets: None, // Apply to all
-                             });
-                             println!("Migrated scene '{}' global effect", scene.name);
-                         }
-                    }
-                }
+                state = loaded; // Already migrated to the current format version
             }
             Err(e) => {
                 eprintln!("Failed to load state from database: {}", e);
+                if e.downcast_ref::<model::NewerVersionError>().is_some() {
+                    // Don't overwrite data from a newer build with the defaults seeded below
+                    save_blocked = Some(e.to_string());
+                }
                 if status == "Ready" {
                     status = format!("Load error: {}", e);
                 }
@@ -193,33 +253,44 @@ impl Default for MyApp {
                     mask_type: "scanner".into(),
                     x: 0.5,
                     y: 0.5,
+                    z: None,
                     params: std::collections::HashMap::new(),
+                    modulation: Vec::new(),
                 });
             }
         }
         
-        // Init MIDI
+        // Init MIDI (service only runs when enabled; commands to a stopped service are dropped)
         let (tx_event, rx_event) = std::sync::mpsc::channel();
-        let tx_cmd = midi::start_midi_service(tx_event);
+        let midi_event_sender = tx_event.clone();
+        let midi_service_running = state.midi_enabled;
+        let (tx_cmd, midi_service) = if midi_service_running {
+            let (tx_cmd, handle) = midi::start_midi_service(tx_event);
+            if let Some(ports) = &state.midi_ports {
+                let _ = tx_cmd.send(midi::MidiCommand::SelectPorts { input: ports.input.clone(), output: ports.output.clone() });
+            }
+            (tx_cmd, Some(handle))
+        } else {
+            (std::sync::mpsc::channel().0, None)
+        };
+
+        let (command_sender, command_receiver) = std::sync::mpsc::channel();
+
+        // Come back up as the rig was left: saved active scenes render from the first frame
+        // (under the startup fade), and a latched blackout stays dark
+        let mut engine = LightingEngine::new();
+        if state.blackout {
+            engine.set_blackout(&state, true);
+        }
+        let audio_levels_receiver = engine.audio_levels_receiver();
 
         // Send initial colors
         let _ = tx_cmd.send(midi::MidiCommand::ClearAll);
-        // Small delay to ensure clear processes if needed, but channel order is preserved usually.
-        
-        for s in &state.scenes {
-            if let (Some(btn), Some(col)) = (s.launchpad_btn, s.launchpad_color) {
-                 let cmd = if s.launchpad_is_cc {
-                     midi::MidiCommand::SetButtonColor { cc: btn, color: col }
-                 } else {
-                     midi::MidiCommand::SetPadColor { note: btn, color: col }
-                 };
-                 let _ = tx_cmd.send(cmd);
-            }
-        }
+        let _ = tx_cmd.send(midi::MidiCommand::SetGrid { colors: state.launchpad_colors() });
 
         Self {
             state,
-            engine: LightingEngine::new(),
+            engine,
             view: ViewState::default(),
             status,
             is_first_frame: true,
@@ -238,16 +309,42 @@ impl Default for MyApp {
             import_merge_mode: false,
             import_file_path: None,
             midi_sender: tx_cmd,
+            midi_service,
             midi_receiver: rx_event,
+            midi_event_sender,
+            midi_ports_available: (Vec::new(), Vec::new()),
             midi_connected: false,
+            midi_model: midi::LaunchpadModel::MiniMk3,
+            midi_status: if midi_service_running { "Searching..." } else { "Disabled" }.into(),
+            midi_last_error: None,
+            midi_unavailable: false,
+            midi_service_running,
+            save_blocked,
+            logo_output_flowing: None,
+            pulsing_pads: None,
+            cc_learn: false,
             last_midi_detection: None,
             dragged_scene_id: None,
+            effect_clipboard: None,
+            command_sender,
+            command_receiver,
+            last_activity: Instant::now(),
+            idle_restore: None,
+            node_monitor: None,
+            unreachable_nodes: std::collections::BTreeSet::new(),
+            audio_levels_receiver,
+            audio_levels: audio::AudioLevels::default(),
+            audio_input_names: audio::input_device_names(),
         }
     }
 }
 
 impl MyApp {
     fn save_state(&mut self) {
+        if let Some(reason) = &self.save_blocked {
+            self.status = format!("Not saved: {}", reason);
+            return;
+        }
         match self.db.save_state(&self.state) {
             Ok(_) => {
                 self.status = "Saved to database".into();
@@ -260,26 +357,201 @@ impl MyApp {
         }
     }
 
+    /// Spawn the MIDI service with fresh channels (when MIDI is enabled at runtime)
+    fn start_midi(&mut self) {
+        let (tx_event, rx_event) = std::sync::mpsc::channel();
+        self.midi_event_sender = tx_event.clone();
+        let (tx_cmd, handle) = midi::start_midi_service(tx_event);
+        self.midi_sender = tx_cmd;
+        self.midi_service = Some(handle);
+        self.midi_receiver = rx_event;
+        self.select_midi_ports();
+        self.midi_service_running = true;
+        self.midi_unavailable = false;
+        self.midi_connected = false;
+        self.midi_status = "Searching...".into();
+        self.last_midi_detection = None;
+    }
+
+    /// Look for the Launchpad on the next frame instead of waiting out the detection interval
+    fn rescan_midi(&mut self) {
+        let _ = self.midi_sender.send(midi::MidiCommand::Rescan);
+        self.last_midi_detection = None;
+    }
+
+    /// Pulse the pads of active scenes; pads of scenes that stopped go back to their static color
+    fn sync_active_pads(&mut self) {
+        let active: Vec<(u8, u8)> = self.state.active_scene_ids.iter()
+            .filter_map(|id| self.state.scenes.iter().find(|s| s.id == *id))
+            .filter(|s| !s.launchpad_is_cc)
+            .filter_map(|s| Some((s.launchpad_btn.filter(|b| *b != 0)?, s.launchpad_color?)))
+            .collect();
+        let notes: Vec<u8> = active.iter().map(|(note, _)| *note).collect();
+        if self.pulsing_pads.as_ref() == Some(&notes) {
+            return;
+        }
+        let previous = self.pulsing_pads.take().unwrap_or_default();
+        for note in previous.iter().filter(|n| !notes.contains(n)) {
+            // Pulsing only stops when the pad gets a static color again
+            let color = self.state.scene_for_pad(*note, false).and_then(|s| s.launchpad_color).unwrap_or(0);
+            let _ = self.midi_sender.send(midi::MidiCommand::SetPadColor { note: *note, color });
+        }
+        for (note, color) in &active {
+            if !previous.contains(note) {
+                let _ = self.midi_sender.send(midi::MidiCommand::PulsePad { note: *note, color: *color });
+            }
+        }
+        self.pulsing_pads = Some(notes);
+    }
+
+    /// Add a binding for `controller` to the first numeric param of the selected scene's first
+    /// effect (or the first Global scene's), spanning the param's slider range
+    fn learn_cc_binding(&mut self, controller: u8) {
+        self.cc_learn = false;
+        let scene = self.state.selected_scene_id
+            .and_then(|id| self.state.scenes.iter().find(|s| s.id == id && !s.global_effects.is_empty()))
+            .or_else(|| self.state.scenes.iter().find(|s| !s.global_effects.is_empty()));
+        let Some(scene) = scene else {
+            self.status = format!("CC {} learned, but there is no effect to bind it to", controller);
+            return;
+        };
+        let kind = scene.global_effects[0].effect.resolve(&self.state.presets).kind.clone();
+        let Some((param, min, max)) = model::PARAM_RANGES.iter().find_map(|(k, p, r)| match r {
+            model::ParamRange::Float(min, max) if *k == kind => Some((p.to_string(), *min, *max)),
+            _ => None,
+        }) else {
+            self.status = format!("CC {} learned, but {} has no numeric params", controller, kind);
+            return;
+        };
+        self.status = format!("CC {} bound to {} {}", controller, scene.name, param);
+        self.state.midi_cc_bindings.push(model::CcBinding { controller, scene_id: scene.id, effect: 0, param, min, max });
+        self.mark_state_changed();
+    }
+
+    /// Tell the service which ports to use (pinned or automatic) and detect again right away
+    fn select_midi_ports(&mut self) {
+        let cmd = match &self.state.midi_ports {
+            Some(ports) => midi::MidiCommand::SelectPorts { input: ports.input.clone(), output: ports.output.clone() },
+            None => midi::MidiCommand::AutoSelectPorts,
+        };
+        let _ = self.midi_sender.send(cmd);
+        self.last_midi_detection = None;
+    }
+
+    /// Shut the MIDI service down, darkening the pads and closing the device connection
+    /// (when MIDI is disabled). Doesn't wait for the thread.
+    fn stop_midi(&mut self) {
+        if let Some(service) = self.midi_service.take() {
+            service.shutdown(true);
+        }
+        // Drop the old event stream so late events from the exiting thread can't touch the status
+        self.midi_receiver = std::sync::mpsc::channel().1;
+        self.midi_service_running = false;
+        self.midi_connected = false;
+        self.midi_status = "Disabled".into();
+    }
+
+    /// Handle for control surfaces to push `AppCommand`s; applied on the next frame
+    fn command_sender(&self) -> Sender<command::AppCommand> {
+        self.command_sender.clone()
+    }
+
+    /// Apply every queued command in arrival order
+    fn apply_commands(&mut self) {
+        while let Ok(cmd) = self.command_receiver.try_recv() {
+            log::debug!("Command: {:?}", cmd);
+            if cmd.apply(&mut self.state, &mut self.engine) {
+                self.mark_state_changed();
+            }
+        }
+    }
+
+    /// Start, stop or retarget the unicast node prober to match the network config,
+    /// then take in its status changes
+    fn poll_nodes(&mut self) {
+        let interval = self.state.network.probe_interval_ms.map(|ms| Duration::from_millis(ms.max(500)));
+        if self.node_monitor.as_ref().map(|m| m.interval()) != interval {
+            self.node_monitor = interval.map(reachability::NodeMonitor::start);
+            self.unreachable_nodes.clear();
+        }
+        let Some(monitor) = &self.node_monitor else { return };
+
+        let targets = reachability::unicast_targets(&self.state.network);
+        self.unreachable_nodes.retain(|ip| targets.contains(ip));
+        monitor.set_targets(targets);
+        for event in monitor.events() {
+            match event {
+                reachability::NodeEvent::Unreachable(ip) => {
+                    log::warn!("sACN node {} is unreachable", ip);
+                    self.status = format!("Node {} unreachable", ip);
+                    self.unreachable_nodes.insert(ip);
+                }
+                reachability::NodeEvent::Reachable(ip) => {
+                    log::info!("sACN node {} is reachable again", ip);
+                    self.status = format!("Node {} back online", ip);
+                    self.unreachable_nodes.remove(&ip);
+                }
+            }
+        }
+    }
+
+    /// Record user input; leaves idle mode, restoring the scenes that were active before it
+    fn note_activity(&mut self) {
+        self.last_activity = Instant::now();
+        if let Some((active, selected)) = self.idle_restore.take() {
+            self.state.active_scene_ids = active.into_iter()
+                .filter(|id| self.state.scenes.iter().any(|s| s.id == *id))
+                .collect();
+            self.state.selected_scene_id = selected.filter(|id| self.state.active_scene_ids.contains(id));
+            self.status = "Idle ended".into();
+        }
+    }
+
+    /// Switch to the idle scene once `idle_timeout_ms` has passed without input
+    fn check_idle(&mut self) {
+        let Some(timeout) = self.state.idle_timeout_ms else { return };
+        if self.idle_restore.is_some() || self.last_activity.elapsed() < Duration::from_millis(timeout) {
+            return;
+        }
+        if let Some(previous) = self.state.activate_idle_scene() {
+            log::info!("No input for {} ms, switching to the idle scene", timeout);
+            self.idle_restore = Some(previous);
+            self.status = "Idle".into();
+        }
+    }
+
     fn mark_state_changed(&mut self) {
         self.last_change_time = Some(Instant::now());
     }
 
+    /// Repack strip addresses and report the outcome in the status line
+    fn reassign_addresses(&mut self) {
+        self.status = match self.state.reassign_dmx_addresses() {
+            Ok(assigned) => {
+                let universes = assigned.iter().map(|(_, u, _)| *u).max().unwrap_or(0);
+                format!("Addressed {} strips across {} universes", assigned.len(), universes)
+            }
+            Err(e) => format!("Auto-address failed: {}", e),
+        };
+    }
+
     fn export_to_json(&mut self) {
         // Use native file dialog to choose save location
         if let Some(path) = rfd::FileDialog::new()
             .set_file_name("lightspeed_export.json")
             .add_filter("JSON", &["json"])
+            .add_filter("Binary", &[state_file::BINARY_EXTENSION])
             .save_file()
         {
-            match self.db.export_to_json() {
-                Ok(json) => {
-                    match fs::write(&path, json) {
+            match self.db.load_state() {
+                Ok(state) => {
+                    match state_file::save(&state, &path) {
                         Ok(_) => {
                             self.status = format!("Exported to {}", path.display());
                         }
                         Err(e) => {
                             self.status = format!("Export failed: {}", e);
-                            eprintln!("Failed to write export file: {}", e);
+                            eprintln!("Failed to write export file: {:#}", e);
                         }
                     }
                 }
@@ -291,10 +563,61 @@ impl MyApp {
         }
     }
 
+    fn export_strips_csv(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .set_file_name("lightspeed_strips.csv")
+            .add_filter("CSV", &["csv"])
+            .save_file()
+        {
+            match strip_csv::export_strips_csv(&self.state, &path) {
+                Ok(_) => self.status = format!("Exported {} strips to {}", self.state.strips.len(), path.display()),
+                Err(e) => {
+                    self.status = format!("Strip export failed: {}", e);
+                    eprintln!("Failed to export strips: {}", e);
+                }
+            }
+        }
+    }
+
+    fn export_pixel_map(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .set_file_name("lightspeed_pixel_map.csv")
+            .add_filter("CSV", &["csv"])
+            .add_filter("JSON", &["json"])
+            .save_file()
+        {
+            match pixel_map::export_pixel_map(&self.state, &path) {
+                Ok(count) => self.status = format!("Exported {} pixel positions to {}", count, path.display()),
+                Err(e) => {
+                    self.status = format!("Pixel map export failed: {}", e);
+                    eprintln!("Failed to export pixel map: {}", e);
+                }
+            }
+        }
+    }
+
+    fn import_strips_csv(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .pick_file()
+        {
+            match strip_csv::import_strips_csv(&mut self.state, &path) {
+                Ok(count) => {
+                    self.status = format!("Imported {} strips from {}", count, path.display());
+                    self.mark_state_changed();
+                }
+                Err(e) => {
+                    self.status = format!("Strip import failed: {}", e);
+                    eprintln!("Failed to import strips: {}", e);
+                }
+            }
+        }
+    }
+
     fn import_from_json(&mut self) {
         // Use native file dialog to choose file
         if let Some(path) = rfd::FileDialog::new()
-            .add_filter("JSON", &["json"])
+            .add_filter("Lightspeed", &["json", state_file::BINARY_EXTENSION])
             .pick_file()
         {
             self.import_file_path = Some(path);
@@ -304,14 +627,15 @@ impl MyApp {
 
     fn do_import(&mut self) {
         if let Some(path) = &self.import_file_path {
-            match fs::read_to_string(path) {
-                Ok(json) => {
-                    match self.db.import_from_json(&json, self.import_merge_mode) {
+            match state_file::load(path) {
+                Ok(import_state) => {
+                    match self.db.import_state(import_state, self.import_merge_mode) {
                         Ok(_) => {
                             // Reload state from database
                             match self.db.load_state() {
                                 Ok(state) => {
                                     self.state = state;
+                                    self.save_blocked = None;
                                     self.status = "Import successful".into();
                                     // Restart engine with new state
                                     self.engine = LightingEngine::new();
@@ -329,8 +653,8 @@ impl MyApp {
                     }
                 }
                 Err(e) => {
-                    self.status = format!("Failed to read file: {}", e);
-                    eprintln!("Failed to read import file: {}", e);
+                    self.status = format!("Failed to read file: {:#}", e);
+                    eprintln!("Failed to read import file: {:#}", e);
                 }
             }
         }
@@ -448,6 +772,10 @@ fn reveal_in_file_manager(path: &Path) {
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if ctx.input(|i| !i.events.is_empty() || i.pointer.any_down()) {
+            self.note_activity();
+        }
+
         // Handle keyboard shortcuts
         ctx.input(|i| {
             // Command+S (Mac) or Ctrl+S (Windows/Linux) to save
@@ -456,9 +784,18 @@ impl eframe::App for MyApp {
             }
         });
 
+        // Start/stop the service thread when MIDI is toggled
+        if self.state.midi_enabled != self.midi_service_running {
+            if self.state.midi_enabled {
+                self.start_midi();
+            } else {
+                self.stop_midi();
+            }
+        }
+
         // 1. Detection Logic (Runs on Main Thread)
-        // Only run MIDI detection if midi_enabled is true
-        if self.state.midi_enabled {
+        // Only run MIDI detection if midi_enabled is true and the backend exists
+        if self.state.midi_enabled && !self.midi_unavailable {
             let now = std::time::Instant::now();
             let should_check = self.last_midi_detection
                 .map(|t| now.duration_since(t) > std::time::Duration::from_secs(2))
@@ -469,8 +806,13 @@ impl eframe::App for MyApp {
 
                 if !self.midi_connected {
                     // DETECT
-                    if let Some(payload) = midi::detect_launchpad() {
-                        println!("Launchpad detected on Main Thread! Handing off to worker...");
+                    if let Some(mut payload) = midi::detect_launchpad(self.state.midi_ports.as_ref(), self.state.midi_port_filter.as_ref(), &self.midi_event_sender) {
+                        log::info!("Launchpad detected, handing off to MIDI worker");
+                        if let Some(init) = self.state.midi_init.get(payload.model.name()) {
+                            payload.init = init.clone();
+                        }
+                        payload.heartbeat_ms = self.state.midi_heartbeat_ms;
+                        self.midi_model = payload.model;
                         let _ = self.midi_sender.send(midi::MidiCommand::Connect(Box::new(payload)));
                         self.midi_connected = true;
                     }
@@ -482,64 +824,166 @@ impl eframe::App for MyApp {
                         watcher.ignore(midir::Ignore::None);
                         watcher.ports().iter().any(|p| {
                             let name = watcher.port_name(p).unwrap_or_default();
-                            name.contains("Launchpad")
+                            match (&self.state.midi_ports, &self.state.midi_port_filter) {
+                                (Some(ports), _) => name == ports.input,
+                                (None, Some(filter)) => filter.matches(&name),
+                                (None, None) => name.contains("Launchpad") || midi::is_launchpad_midi_port(&name),
+                            }
                         })
                     } else {
                         false
                     };
 
                     if !is_present {
-                        println!("Watchdog: Launchpad disappeared from port list. Disconnecting...");
+                        log::warn!("Watchdog: Launchpad disappeared from port list, disconnecting");
                         let _ = self.midi_sender.send(midi::MidiCommand::Disconnect);
                         self.midi_connected = false;
                     }
                 }
             }
-        } else if self.midi_connected {
-            // MIDI was disabled while connected - disconnect
-            let _ = self.midi_sender.send(midi::MidiCommand::Disconnect);
-            self.midi_connected = false;
         }
 
-        // Handle MIDI Input
+        if let Some(levels) = self.audio_levels_receiver.try_iter().last() {
+            self.audio_levels = levels;
+        }
+
+        // Handle MIDI Input; control events become commands
+        let commands = self.command_sender();
         while let Ok(event) = self.midi_receiver.try_recv() {
+            if matches!(event, midi::MidiEvent::NoteOn { .. } | midi::MidiEvent::ControlChange { .. } | midi::MidiEvent::TapTempo { .. }) {
+                self.note_activity();
+            }
+            // CC bindings ride effect params, releases (value 0) included, ahead of pad triggers
+            if let midi::MidiEvent::ControlChange { controller, value } = event {
+                if self.cc_learn {
+                    self.learn_cc_binding(controller);
+                    continue;
+                }
+                let mut bound = false;
+                for binding in self.state.cc_bindings_for(controller) {
+                    let _ = commands.send(command::AppCommand::SetEffectParam {
+                        scene_id: binding.scene_id,
+                        effect: binding.effect,
+                        param: binding.param.clone(),
+                        value: binding.value(value).into(),
+                    });
+                    bound = true;
+                }
+                if bound {
+                    continue;
+                }
+            }
             match event {
+                // Pads and buttons act on press; releases only end momentary scene holds
+                midi::MidiEvent::NoteOff { note } => {
+                    if let Some(id) = self.state.scene_for_pad(note, false).filter(|s| s.launchpad_momentary).map(|s| s.id) {
+                        let _ = commands.send(command::AppCommand::ReleaseScene(id));
+                    }
+                }
+                midi::MidiEvent::ControlChange { controller, value: 0 } => {
+                    if let Some(id) = self.state.scene_for_pad(controller, true).filter(|s| s.launchpad_momentary).map(|s| s.id) {
+                        let _ = commands.send(command::AppCommand::ReleaseScene(id));
+                    }
+                }
+                midi::MidiEvent::NoteOn { note, velocity: _ } if !self.state.panic_is_cc && self.state.panic_btn == Some(note) => {
+                    let _ = commands.send(command::AppCommand::SetBlackout(None));
+                }
+                midi::MidiEvent::ControlChange { controller, value: _ } if self.state.panic_is_cc && self.state.panic_btn == Some(controller) => {
+                    let _ = commands.send(command::AppCommand::SetBlackout(None));
+                }
+                midi::MidiEvent::NoteOn { note, velocity: _ } if !self.state.freeze_is_cc && self.state.freeze_btn == Some(note) => {
+                    let _ = commands.send(command::AppCommand::SetFrozen(None));
+                }
+                midi::MidiEvent::ControlChange { controller, value: _ } if self.state.freeze_is_cc && self.state.freeze_btn == Some(controller) => {
+                    let _ = commands.send(command::AppCommand::SetFrozen(None));
+                }
                 midi::MidiEvent::NoteOn { note, velocity: _ } => {
                      // Check for scene mapped to this note (and is NOT cc)
-                     if let Some(s) = self.state.scenes.iter().find(|s| !s.launchpad_is_cc && s.launchpad_btn == Some(note)) {
-                         self.state.selected_scene_id = Some(s.id);
+                     if let Some(scene) = self.state.scene_for_pad(note, false) {
+                         let _ = commands.send(scene_pad_command(scene));
                      }
                 }
                 midi::MidiEvent::ControlChange { controller, value: _ } => {
                      // Check for scene mapped to this CC
-                     if let Some(s) = self.state.scenes.iter().find(|s| s.launchpad_is_cc && s.launchpad_btn == Some(controller)) {
-                         self.state.selected_scene_id = Some(s.id);
+                     if let Some(scene) = self.state.scene_for_pad(controller, true) {
+                         let _ = commands.send(scene_pad_command(scene));
                      }
                 }
-                midi::MidiEvent::Connected => {
-                    println!("Launchpad connected! Refreshing button colors...");
+                midi::MidiEvent::Clock { bpm } => {
+                    self.engine.set_midi_clock(Some(bpm));
+                }
+                midi::MidiEvent::ClockStart => {
+                    log::debug!("MIDI clock start, restarting the beat");
+                    self.engine.restart_beat();
+                }
+                midi::MidiEvent::ClockStop => {
+                    self.engine.set_midi_clock(None);
+                }
+                midi::MidiEvent::TapTempo { bpm } => {
+                    log::debug!("Tap tempo: {:.1} BPM", bpm);
+                    let _ = commands.send(command::AppCommand::SetBpm(Some(bpm)));
+                }
+                midi::MidiEvent::Connecting { input, output } => {
+                    self.midi_status = format!("Connecting to {} / {}...", input, output);
+                }
+                midi::MidiEvent::Connected | midi::MidiEvent::Reconnected => {
+                    log::info!("Launchpad connected, refreshing button colors");
                     self.midi_connected = true;
                     // Clear all buttons
                     let _ = self.midi_sender.send(midi::MidiCommand::ClearAll);
-                    // Resend all scene button colors
-                    for s in &self.state.scenes {
-                        if let (Some(btn), Some(col)) = (s.launchpad_btn, s.launchpad_color) {
-                            let cmd = if s.launchpad_is_cc {
-                                midi::MidiCommand::SetButtonColor { cc: btn, color: col }
-                            } else {
-                                midi::MidiCommand::SetPadColor { note: btn, color: col }
-                            };
-                            let _ = self.midi_sender.send(cmd);
-                        }
+                    self.logo_output_flowing = None;
+                    self.pulsing_pads = None;
+                    let _ = self.midi_sender.send(midi::MidiCommand::SetTapPad {
+                        btn: self.state.tap_btn,
+                        is_cc: self.state.tap_is_cc,
+                    });
+                    let _ = self.midi_sender.send(midi::MidiCommand::SetMinVelocity(self.state.midi_min_velocity));
+                    let _ = self.midi_sender.send(midi::MidiCommand::SetDebounceMs(self.state.midi_debounce_ms));
+                    // Resend all scene button colors in one update
+                    let _ = self.midi_sender.send(midi::MidiCommand::SetGrid { colors: self.state.launchpad_colors() });
+                }
+                midi::MidiEvent::Error(message) => {
+                    self.midi_last_error = Some(message);
+                }
+                midi::MidiEvent::PortsAvailable { inputs, outputs } => {
+                    self.midi_ports_available = (inputs, outputs);
+                }
+                midi::MidiEvent::Phase(phase) => {
+                    // Connecting is refined by the Connecting { input, output } event that follows
+                    if phase != midi::ConnectionPhase::Connecting {
+                        self.midi_status = phase.to_string();
                     }
+                    if phase == midi::ConnectionPhase::Connected {
+                        self.midi_last_error = None;
+                    }
+                }
+                midi::MidiEvent::Unavailable(reason) => {
+                    self.midi_unavailable = true;
+                    self.midi_connected = false;
+                    self.midi_status = "Unavailable".into();
+                    self.midi_last_error = Some(reason);
                 }
                 midi::MidiEvent::Disconnected => {
-                    println!("Launchpad disconnected. Will retry connection...");
+                    log::info!("Launchpad disconnected, will retry connection");
                     self.midi_connected = false;
                     self.last_midi_detection = Some(std::time::Instant::now()); // Delay retry slightly
                 }
             }
         }
+        self.apply_commands();
+        self.poll_nodes();
+        self.check_idle();
+
+        // Logo LED mirrors output health: green while sACN is flowing, pulsing red otherwise (or during blackout)
+        let flowing = self.engine.is_output_flowing() && !self.engine.is_blackout();
+        if self.midi_connected && self.logo_output_flowing != Some(flowing) {
+            let (color, mode) = if flowing { (21, midi::LedMode::Static) } else { (5, midi::LedMode::Pulse) };
+            let _ = self.midi_sender.send(midi::MidiCommand::SetStatusLed { led: midi::StatusLed::Logo, color, mode });
+            self.logo_output_flowing = Some(flowing);
+        }
+        if self.midi_connected {
+            self.sync_active_pads();
+        }
 
         // Import confirmation dialog
         if self.import_dialog_open {
@@ -646,9 +1090,7 @@ impl eframe::App for MyApp {
                         for row in 0..8 {
                             ui.horizontal(|ui| {
                                 for col in 0..8 {
-                                    // Launchpad layout: bottom row = 11-18, top row = 81-88
-                                    // row 0 = top (81-88), row 7 = bottom (11-18)
-                                    let note = ((8 - row) * 10 + 1 + col) as u8;
+                                    let note = midi::xy_to_note((col, row), self.midi_model);
                                     let (rect, response) = ui.allocate_exact_size(
                                         egui::vec2(cell_size, cell_size),
                                         egui::Sense::hover()
@@ -721,10 +1163,11 @@ impl eframe::App for MyApp {
                         .collect();
 
                     // Display in grid
+                    let mut triggered_scene = None;
                     for row_scenes in filtered_scenes.chunks(cols) {
                         ui.horizontal(|ui| {
                             for (_idx, scene) in row_scenes {
-                                    let is_selected = self.state.selected_scene_id == Some(scene.id);
+                                    let is_selected = self.state.is_scene_active(scene.id);
 
                                     let (rect, response) = ui.allocate_exact_size(
                                         egui::vec2(card_width, card_height),
@@ -732,7 +1175,7 @@ impl eframe::App for MyApp {
                                     );
 
                                     if response.clicked() {
-                                        self.state.selected_scene_id = Some(scene.id);
+                                        triggered_scene = Some(scene.id);
                                     }
 
                                     let visuals = if is_selected {
@@ -798,6 +1241,9 @@ impl eframe::App for MyApp {
                             ui.label("No scenes in this category");
                         });
                     }
+                    if let Some(id) = triggered_scene {
+                        let _ = self.command_sender.send(command::AppCommand::TriggerScene(id));
+                    }
                     }); // End of main scroll area
                 });
         }
@@ -814,16 +1260,31 @@ impl eframe::App for MyApp {
 
                     ui.separator();
 
-                    if ui.button("Export to JSON...").clicked() {
+                    if ui.button("Export State...").clicked() {
                         self.export_to_json();
                         ui.close_menu();
                     }
 
-                    if ui.button("Import from JSON...").clicked() {
+                    if ui.button("Import State...").clicked() {
                         self.import_from_json();
                         ui.close_menu();
                     }
 
+                    if ui.button("Import Strips from CSV...").clicked() {
+                        self.import_strips_csv();
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Export Strips to CSV...").clicked() {
+                        self.export_strips_csv();
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Export Pixel Map...").clicked() {
+                        self.export_pixel_map();
+                        ui.close_menu();
+                    }
+
                     ui.separator();
 
                     if ui.button("Reveal Config in Finder").clicked() {
@@ -839,11 +1300,62 @@ impl eframe::App for MyApp {
         // Update Loop (Physics/Networking)
         self.engine.update(&mut self.state);
 
+        // Audio downbeat feedback: the beat pad flashes for every fourth beat and is dark for the rest
+        if let (Some(note), Some(count)) = (self.state.beat_pad, self.engine.audio_beat()) {
+            let command = if count % 4 == 1 {
+                midi::MidiCommand::FlashPad { note, color_a: BEAT_PAD_COLOR, color_b: 0 }
+            } else {
+                midi::MidiCommand::SetPadColor { note, color: 0 }
+            };
+            let _ = self.midi_sender.send(command);
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             // HEADER AND STATUS
             ui.horizontal(|ui| {
                 ui.heading("Lightspeed");
                 ui.separator();
+
+                let blackout = self.engine.is_blackout();
+                let blackout_btn = egui::Button::new(egui::RichText::new("BLACKOUT").strong())
+                    .fill(if blackout { egui::Color32::DARK_RED } else { egui::Color32::from_gray(40) });
+                if ui.add(blackout_btn).on_hover_text("Send zeroed frames to every universe and hold output dark").clicked() {
+                    let _ = self.command_sender.send(command::AppCommand::SetBlackout(Some(!blackout)));
+                }
+                let freeze_btn = egui::Button::new(egui::RichText::new("FREEZE").strong())
+                    .fill(if self.state.frozen { egui::Color32::from_rgb(0, 90, 140) } else { egui::Color32::from_gray(40) });
+                if ui.add(freeze_btn).on_hover_text("Hold the current frame on the output; effects pause until released").clicked() {
+                    let _ = self.command_sender.send(command::AppCommand::SetFrozen(Some(!self.state.frozen)));
+                }
+                let paused = self.engine.is_paused();
+                let pause_btn = egui::Button::new(egui::RichText::new(if paused { "RESUME" } else { "PAUSE" }).strong())
+                    .fill(if paused { egui::Color32::from_rgb(120, 80, 0) } else { egui::Color32::from_gray(40) });
+                let pause_hint = if self.state.network.pause_policy == "blackout" {
+                    "Stop sending sACN after a final black frame (Network Output → On Pause)"
+                } else {
+                    "Stop sending sACN so nodes hold their last frame (Network Output → On Pause)"
+                };
+                if ui.add(pause_btn).on_hover_text(pause_hint).clicked() {
+                    let _ = self.command_sender.send(command::AppCommand::SetPaused(Some(!paused)));
+                }
+                for (slot, name) in ["A", "B"].into_iter().enumerate() {
+                    let filled = self.engine.has_snapshot(slot);
+                    let showing = self.engine.showing_snapshot() == Some(slot);
+                    let text = egui::RichText::new(name).strong();
+                    let btn = egui::Button::new(if filled { text } else { text.weak() })
+                        .fill(if showing { egui::Color32::from_rgb(120, 80, 0) } else { egui::Color32::from_gray(40) });
+                    let resp = ui.add(btn).on_hover_text(format!(
+                        "Snapshot {}: shift-click stores the current output, click shows it over the live output / back to live", name));
+                    if resp.clicked() {
+                        if ui.input(|i| i.modifiers.shift) || !filled {
+                            self.engine.take_snapshot(slot);
+                            self.status = format!("Stored snapshot {}", name);
+                        } else {
+                            self.engine.toggle_snapshot(slot);
+                        }
+                    }
+                }
+                ui.separator();
                 
                 // Unified Sync Status
                 let (source, bpm) = self.engine.get_sync_info();
@@ -852,6 +1364,12 @@ impl eframe::App for MyApp {
                                    else { egui::Color32::LIGHT_GRAY };
                 
                 ui.label(egui::RichText::new(source).color(source_color).strong());
+
+                let confidence = self.engine.get_audio_confidence();
+                if self.state.audio.use_flywheel && confidence > 0.0 && confidence < 0.99 {
+                    ui.label(egui::RichText::new(format!("Audio {:.0}%", confidence * 100.0)).color(egui::Color32::YELLOW))
+                        .on_hover_text("Flywheel confidence: audio input stalled or missing, visuals are predicted from the last tempo");
+                }
                 
                 let beat = self.engine.get_beat();
                 let beat_in_bar = ((beat % 4.0).floor() as i32) + 1;
@@ -872,6 +1390,25 @@ impl eframe::App for MyApp {
 
                 ui.separator();
 
+                // Engine performance
+                let stats = self.engine.get_stats();
+                let fps_color = if stats.skipped_per_sec > 1.0 || stats.quality_level > 0 { egui::Color32::YELLOW } else { egui::Color32::LIGHT_GRAY };
+                let quality = if stats.quality_level == 0 {
+                    "full".to_string()
+                } else {
+                    format!("reduced (scenes update every {} frames)", stats.quality_level + 1)
+                };
+                let stats_label = ui.label(egui::RichText::new(format!("{:.0} FPS", stats.render_fps)).color(fps_color))
+                    .on_hover_text(format!(
+                        "Packets/s: {:.0}\nSkipped frames/s: {:.1}\nSkipped since start: {}\nHeld by Max FPS/s: {:.1}\nQuality: {}\nClick to toggle periodic logging ({})",
+                        stats.packets_per_sec, stats.skipped_per_sec, stats.frames_skipped, stats.paced_per_sec, quality,
+                        if self.engine.log_stats { "on" } else { "off" }));
+                if stats_label.interact(egui::Sense::click()).clicked() {
+                    self.engine.log_stats = !self.engine.log_stats;
+                }
+
+                ui.separator();
+
                 if ui.button("Save Config").clicked() {
                     self.save_state();
                     self.status = "Saved".into();
@@ -889,7 +1426,238 @@ impl eframe::App for MyApp {
                         ui.collapsing("Global Settings", |ui| {
                             ui.horizontal(|ui| {
                                  ui.label("Master Speed");
-                                 ui.add(egui::Slider::new(&mut self.engine.speed, 0.1..=5.0));
+                                 let mut speed = self.engine.speed;
+                                 if ui.add(egui::Slider::new(&mut speed, command::SPEED_RANGE)).changed() {
+                                     let _ = self.command_sender.send(command::AppCommand::SetSpeed(speed));
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 ui.label("Master Brightness");
+                                 let mut level = self.state.master_brightness;
+                                 if ui.add(egui::Slider::new(&mut level, 0.0..=1.0)).changed() {
+                                     let _ = self.command_sender.send(command::AppCommand::SetMasterBrightness(level));
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 ui.label("Crossfade");
+                                 ui.add(egui::DragValue::new(&mut self.state.transition_ms).speed(10).clamp_range(0..=60_000).suffix(" ms"))
+                                     .on_hover_text("Fade from the previous look whenever the active scenes change; 0 = instant cut");
+                            });
+                            ui.horizontal(|ui| {
+                                 ui.label("Gamma");
+                                 ui.add(egui::Slider::new(&mut self.state.gamma, model::MIN_GAMMA..=model::MAX_GAMMA))
+                                     .on_hover_text("Output curve for the LEDs; 1.0 sends levels unchanged, higher values deepen low levels");
+                            });
+                            ui.horizontal(|ui| {
+                                 ui.label("Startup Fade");
+                                 ui.add(egui::DragValue::new(&mut self.state.startup_fade_ms).speed(50).clamp_range(0..=30000).suffix(" ms"))
+                                     .on_hover_text("Ramp all output up from black after launch (0 = off)");
+                            });
+                            ui.horizontal(|ui| {
+                                 let mut adaptive = self.state.render_budget_ms.is_some();
+                                 if ui.checkbox(&mut adaptive, "Adaptive Quality")
+                                     .on_hover_text("Update scenes less often while rendering takes longer than the budget; full rate returns when there is headroom")
+                                     .changed()
+                                 {
+                                     self.state.render_budget_ms = if adaptive { Some(8.0) } else { None };
+                                 }
+                                 if let Some(budget) = &mut self.state.render_budget_ms {
+                                     ui.add(egui::DragValue::new(budget).speed(0.5).clamp_range(1.0..=50.0).prefix("Budget: ").suffix(" ms"));
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 let mut idle = self.state.idle_timeout_ms.is_some();
+                                 if ui.checkbox(&mut idle, "Idle Scene")
+                                     .on_hover_text("Switch to this scene after a while without MIDI or UI input; any input brings the previous scenes back")
+                                     .changed()
+                                 {
+                                     self.state.idle_timeout_ms = if idle { Some(300_000) } else { None };
+                                 }
+                                 if let Some(timeout) = &mut self.state.idle_timeout_ms {
+                                     let mut secs = *timeout / 1000;
+                                     if ui.add(egui::DragValue::new(&mut secs).speed(1).clamp_range(5..=86400).prefix("After: ").suffix(" s")).changed() {
+                                         *timeout = secs * 1000;
+                                     }
+                                     let selected = self.state.idle_scene_id
+                                         .and_then(|id| self.state.scenes.iter().find(|s| s.id == id))
+                                         .map_or("(none)".to_string(), |s| s.name.clone());
+                                     egui::ComboBox::from_id_source("idle_scene")
+                                         .selected_text(selected)
+                                         .show_ui(ui, |ui| {
+                                             for scene in &self.state.scenes {
+                                                 ui.selectable_value(&mut self.state.idle_scene_id, Some(scene.id), &scene.name);
+                                             }
+                                         });
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 let mut calibrating = self.engine.get_calibration().is_some();
+                                 if ui.checkbox(&mut calibrating, "White Calibration").changed() {
+                                     self.engine.set_calibration(if calibrating { Some(255) } else { None });
+                                 }
+                                 if let Some(mut level) = self.engine.get_calibration() {
+                                     if ui.add(egui::Slider::new(&mut level, 0..=255).text("Level")).changed() {
+                                         self.engine.set_calibration(Some(level));
+                                     }
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 let mut pattern = self.engine.is_test_pattern(None);
+                                 if ui.checkbox(&mut pattern, "Test Pattern").on_hover_text(
+                                     "R, G, B repeating from pixel 0 with a white marker every 10th pixel. Wrong colors = wrong color order; shifted marker = wrong pixel count or channel"
+                                 ).changed() {
+                                     self.engine.set_test_pattern(None, pattern);
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 let mut panic_on = self.state.panic_btn.is_some();
+                                 if ui.checkbox(&mut panic_on, "Blackout Pad").changed() {
+                                     self.state.panic_btn = if panic_on { Some(29) } else { None };
+                                 }
+                                 if let Some(btn) = &mut self.state.panic_btn {
+                                     ui.add(egui::DragValue::new(btn).prefix("Note: ").clamp_range(0..=127));
+                                     ui.checkbox(&mut self.state.panic_is_cc, "CC");
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 let mut freeze_on = self.state.freeze_btn.is_some();
+                                 if ui.checkbox(&mut freeze_on, "Freeze Pad").changed() {
+                                     self.state.freeze_btn = if freeze_on { Some(39) } else { None };
+                                 }
+                                 if let Some(btn) = &mut self.state.freeze_btn {
+                                     ui.add(egui::DragValue::new(btn).prefix("Note: ").clamp_range(0..=127));
+                                     ui.checkbox(&mut self.state.freeze_is_cc, "CC");
+                                 }
+                                 ui.checkbox(&mut self.state.freeze_resume_phase, "Resume Phase")
+                                     .on_hover_text("On unfreeze, continue effects from the frozen moment instead of jumping to the current time. Beat-synced motion still follows the live beat.");
+                            });
+                            ui.horizontal(|ui| {
+                                 let mut limited = self.state.max_flash_hz.is_some();
+                                 if ui.checkbox(&mut limited, "Max Flash Rate")
+                                     .on_hover_text("Slow flash effects that would strobe faster than this (photosensitivity and LED stress)")
+                                     .changed()
+                                 {
+                                     self.state.max_flash_hz = if limited { Some(3.0) } else { None };
+                                 }
+                                 if let Some(hz) = &mut self.state.max_flash_hz {
+                                     ui.add(egui::DragValue::new(hz).speed(0.1).clamp_range(0.5..=20.0).suffix(" Hz"));
+                                 }
+                                 ui.checkbox(&mut self.state.no_strobe, "No Strobe")
+                                     .on_hover_text("Render flash effects as a steady dimmed color");
+                            });
+                            for warning in self.state.strobe_warnings(self.engine.tempo()) {
+                                 ui.label(egui::RichText::new(warning).small().color(egui::Color32::YELLOW));
+                            }
+                            ui.horizontal(|ui| {
+                                 if ui.button("🎲 Shuffle").on_hover_text("Randomize the active scene's effect params within their ranges").clicked() {
+                                     self.engine.shuffle(&mut self.state);
+                                     self.mark_state_changed();
+                                 }
+                                 let mut timed = self.state.shuffle_interval_ms.is_some();
+                                 if ui.checkbox(&mut timed, "Every").changed() {
+                                     self.state.shuffle_interval_ms = if timed { Some(30_000) } else { None };
+                                 }
+                                 if let Some(ms) = &mut self.state.shuffle_interval_ms {
+                                     let mut secs = *ms as f64 / 1000.0;
+                                     if ui.add(egui::DragValue::new(&mut secs).speed(1.0).clamp_range(1.0..=3600.0).suffix(" s")).changed() {
+                                         *ms = (secs * 1000.0) as u64;
+                                     }
+                                 }
+                                 let hover = "Comma-separated params to shuffle, e.g. palette, speed, direction";
+                                 if let Some(only) = comma_list_edit(ui, self.state.shuffle_params.join(", "), "shuffle_params", "all params", hover) {
+                                     self.state.shuffle_params = only.split(',')
+                                         .map(|p| p.trim().to_string())
+                                         .filter(|p| !p.is_empty())
+                                         .collect();
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 ui.label("Tap Tempo");
+                                 match self.state.bpm {
+                                     Some(bpm) => {
+                                         ui.label(format!("{:.1} BPM", bpm));
+                                         if ui.small_button("Clear").clicked() {
+                                             self.state.bpm = None;
+                                         }
+                                     }
+                                     None => { ui.label(egui::RichText::new("off").weak()); }
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 let mut tap_on = self.state.tap_btn.is_some();
+                                 let mut changed = ui.checkbox(&mut tap_on, "Tap Pad").changed();
+                                 if changed {
+                                     self.state.tap_btn = if tap_on { Some(19) } else { None };
+                                 }
+                                 if let Some(btn) = &mut self.state.tap_btn {
+                                     changed |= ui.add(egui::DragValue::new(btn).prefix("Note: ").clamp_range(0..=127)).changed();
+                                     changed |= ui.checkbox(&mut self.state.tap_is_cc, "CC").changed();
+                                 }
+                                 if changed {
+                                     let _ = self.midi_sender.send(midi::MidiCommand::SetTapPad {
+                                         btn: self.state.tap_btn,
+                                         is_cc: self.state.tap_is_cc,
+                                     });
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 let mut beat_on = self.state.beat_pad.is_some();
+                                 if ui.checkbox(&mut beat_on, "Beat Pad")
+                                     .on_hover_text("Flash a Launchpad pad on every fourth detected audio beat")
+                                     .changed() {
+                                     if let Some(old) = self.state.beat_pad {
+                                         let _ = self.midi_sender.send(midi::MidiCommand::SetPadColor { note: old, color: 0 });
+                                     }
+                                     self.state.beat_pad = if beat_on { Some(29) } else { None };
+                                 }
+                                 if let Some(btn) = &mut self.state.beat_pad {
+                                     let old = *btn;
+                                     if ui.add(egui::DragValue::new(btn).prefix("Note: ").clamp_range(0..=127)).changed() && *btn != old {
+                                         // Don't leave the previous pad lit from its last flash
+                                         let _ = self.midi_sender.send(midi::MidiCommand::SetPadColor { note: old, color: 0 });
+                                     }
+                                 }
+                                 if self.engine.audio_bpm > 0.0 {
+                                     ui.label(egui::RichText::new(format!("{:.1} BPM heard", self.engine.audio_bpm)).small().weak());
+                                 }
+                            });
+                            ui.horizontal(|ui| {
+                                 ui.label("Quantize Edits");
+                                 let selected = match self.state.quantize.as_str() {
+                                     "beat" => "Beat",
+                                     "bar" => "Bar",
+                                     _ => "None",
+                                 };
+                                 egui::ComboBox::from_id_source("quantize")
+                                     .selected_text(selected)
+                                     .show_ui(ui, |ui| {
+                                         for (value, label) in [("none", "None"), ("beat", "Beat"), ("bar", "Bar")] {
+                                             ui.selectable_value(&mut self.state.quantize, value.to_string(), label);
+                                         }
+                                     })
+                                     .response
+                                     .on_hover_text("When an effect's params change, restart its phase from the last beat or bar so it stays on the grid");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Audio Input");
+                                let selected = match &self.state.audio.input_device {
+                                    Some(name) if !self.audio_input_names.contains(name) => format!("{} (missing)", name),
+                                    Some(name) => name.clone(),
+                                    None => "System Default".to_string(),
+                                };
+                                egui::ComboBox::from_id_source("audio_input")
+                                    .selected_text(selected)
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut self.state.audio.input_device, None, "System Default");
+                                        for name in &self.audio_input_names {
+                                            ui.selectable_value(&mut self.state.audio.input_device, Some(name.clone()), name);
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("Capture device for audio reactivity; a missing device falls back to the system default");
+                                if ui.small_button("Rescan").on_hover_text("List audio inputs again").clicked() {
+                                    self.audio_input_names = audio::input_device_names();
+                                }
                             });
                             ui.horizontal(|ui| {
                                  ui.label("Audio Latency (ms)");
@@ -898,6 +1666,13 @@ impl eframe::App for MyApp {
                             ui.horizontal(|ui| {
                                  ui.checkbox(&mut self.state.audio.use_flywheel, "Beat Smoothing (Flywheel)");
                             });
+                            ui.horizontal(|ui| {
+                                ui.label("Levels").on_hover_text("Audio level and band energies effects see, after latency and sensitivity");
+                                let levels = self.audio_levels;
+                                for (name, value) in std::iter::once(("RMS", levels.rms)).chain(audio::BAND_NAMES.into_iter().zip(levels.bands)) {
+                                    ui.add(egui::ProgressBar::new(value.clamp(0.0, 1.0)).desired_width(50.0).text(name));
+                                }
+                            });
                             ui.separator();
                             ui.label("Hybrid Sync (Audio)");
                             ui.horizontal(|ui| {
@@ -909,23 +1684,341 @@ impl eframe::App for MyApp {
                             ui.separator();
                             ui.horizontal(|ui| {
                                 ui.checkbox(&mut self.state.midi_enabled, "Enable MIDI (Launchpad)");
+                                if self.state.midi_enabled {
+                                    let status = ui.label(egui::RichText::new(format!("MIDI: {}", self.midi_status)).small().weak());
+                                    if let Some(err) = &self.midi_last_error {
+                                        status.on_hover_text(format!("Last error: {}", err));
+                                    }
+                                    if !self.midi_unavailable && ui.small_button("Rescan").on_hover_text("Look for MIDI devices now").clicked() {
+                                        self.rescan_midi();
+                                    }
+                                }
                             });
+                            if self.node_monitor.is_some() {
+                                if self.unreachable_nodes.is_empty() {
+                                    ui.label(egui::RichText::new("Nodes: all reachable").small().weak());
+                                } else {
+                                    let down: Vec<String> = self.unreachable_nodes.iter().map(|ip| ip.to_string()).collect();
+                                    ui.label(egui::RichText::new(format!("Nodes down: {}", down.join(", "))).small().color(egui::Color32::RED));
+                                }
+                            }
+                            if self.state.midi_enabled {
+                                // Manual port choice for controllers the automatic detection misses
+                                let mut ports_changed = false;
+                                ui.horizontal(|ui| {
+                                    let mut manual = self.state.midi_ports.is_some();
+                                    if ui.checkbox(&mut manual, "Manual Ports")
+                                        .on_hover_text("Connect to the MIDI input/output chosen here instead of detecting a Launchpad by name")
+                                        .changed()
+                                    {
+                                        let (inputs, outputs) = &self.midi_ports_available;
+                                        self.state.midi_ports = manual.then(|| model::MidiPortSelection {
+                                            input: inputs.first().cloned().unwrap_or_default(),
+                                            output: outputs.first().cloned().unwrap_or_default(),
+                                        });
+                                        ports_changed = true;
+                                    }
+                                });
+                                if let Some(ports) = &mut self.state.midi_ports {
+                                    let (inputs, outputs) = &self.midi_ports_available;
+                                    for (label, selected, names) in [("In", &mut ports.input, inputs), ("Out", &mut ports.output, outputs)] {
+                                        ui.horizontal(|ui| {
+                                            ui.label(label);
+                                            egui::ComboBox::from_id_source(format!("midi_port_{}", label))
+                                                .selected_text(selected.as_str())
+                                                .show_ui(ui, |ui| {
+                                                    for name in names {
+                                                        if ui.selectable_value(selected, name.clone(), name.as_str()).changed() {
+                                                            ports_changed = true;
+                                                        }
+                                                    }
+                                                });
+                                        });
+                                    }
+                                }
+                                if ports_changed {
+                                    self.select_midi_ports();
+                                }
+                                if self.state.midi_ports.is_none() {
+                                    let mut use_filter = self.state.midi_port_filter.is_some();
+                                    if ui.checkbox(&mut use_filter, "Port Filter")
+                                        .on_hover_text("Detect ports by these name substrings instead of looking for a Launchpad")
+                                        .changed()
+                                    {
+                                        self.state.midi_port_filter = use_filter.then(|| model::MidiPortFilter {
+                                            include: "Launchpad".into(),
+                                            exclude: "DAW".into(),
+                                        });
+                                        self.last_midi_detection = None;
+                                    }
+                                    if let Some(filter) = &mut self.state.midi_port_filter {
+                                        let mut filter_changed = false;
+                                        ui.horizontal(|ui| {
+                                            ui.label("Contains");
+                                            filter_changed |= ui.text_edit_singleline(&mut filter.include).changed();
+                                        });
+                                        ui.horizontal(|ui| {
+                                            ui.label("Excludes");
+                                            filter_changed |= ui.text_edit_singleline(&mut filter.exclude).changed();
+                                        });
+                                        if filter_changed {
+                                            self.last_midi_detection = None;
+                                        }
+                                    }
+                                }
+
+                                // Init timing for the detected model (applies on next connect)
+                                let model = self.midi_model;
+                                let mut init = self.state.midi_init.get(model.name()).cloned()
+                                    .unwrap_or_else(|| model.default_init());
+                                let before = init.clone();
+                                ui.horizontal(|ui| {
+                                    ui.label("Init Settle");
+                                    ui.add(egui::DragValue::new(&mut init.settle_ms).speed(10).clamp_range(0..=5000).suffix(" ms"));
+                                    ui.label("Attempts");
+                                    ui.add(egui::DragValue::new(&mut init.max_attempts).speed(1).clamp_range(1..=10));
+                                });
+                                ui.horizontal(|ui| {
+                                    let mut heartbeat = self.state.midi_heartbeat_ms.is_some();
+                                    if ui.checkbox(&mut heartbeat, "Heartbeat")
+                                        .on_hover_text("Probe the Launchpad periodically so a silently frozen connection reconnects (applies on next connect)")
+                                        .changed()
+                                    {
+                                        self.state.midi_heartbeat_ms = if heartbeat { Some(5000) } else { None };
+                                    }
+                                    if let Some(ms) = &mut self.state.midi_heartbeat_ms {
+                                        ui.add(egui::DragValue::new(ms).speed(100).clamp_range(500..=60000).suffix(" ms"));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Min Velocity");
+                                    if ui.add(egui::DragValue::new(&mut self.state.midi_min_velocity).speed(1).clamp_range(1..=127))
+                                        .on_hover_text("Ignore pad presses softer than this, so brushing a pad doesn't trigger a scene")
+                                        .changed()
+                                    {
+                                        let _ = self.midi_sender.send(midi::MidiCommand::SetMinVelocity(self.state.midi_min_velocity));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Debounce");
+                                    if ui.add(egui::DragValue::new(&mut self.state.midi_debounce_ms).speed(1).clamp_range(0..=500).suffix(" ms"))
+                                        .on_hover_text("Ignore a repeat press of the same pad within this window (0 = off)")
+                                        .changed()
+                                    {
+                                        let _ = self.midi_sender.send(midi::MidiCommand::SetDebounceMs(self.state.midi_debounce_ms));
+                                    }
+                                });
+                                // CC bindings: faders and knobs riding effect params
+                                ui.horizontal(|ui| {
+                                    ui.label("CC Bindings");
+                                    let learn_text = if self.cc_learn { "Move a control..." } else { "Learn" };
+                                    if ui.selectable_label(self.cc_learn, learn_text)
+                                        .on_hover_text("Bind the next CC received to the selected scene's effect")
+                                        .clicked()
+                                    {
+                                        self.cc_learn = !self.cc_learn;
+                                    }
+                                });
+                                let mut delete_binding_idx = None;
+                                for (idx, binding) in self.state.midi_cc_bindings.iter_mut().enumerate() {
+                                    let scene = self.state.scenes.iter().find(|s| s.id == binding.scene_id);
+                                    let kind = scene
+                                        .and_then(|s| s.global_effects.get(binding.effect))
+                                        .map(|c| c.effect.resolve(&self.state.presets).kind.clone())
+                                        .unwrap_or_default();
+                                    ui.horizontal(|ui| {
+                                        ui.label("CC");
+                                        ui.add(egui::DragValue::new(&mut binding.controller).clamp_range(0..=127));
+                                        ui.label(format!("→ {} #{}", scene.map_or("(missing scene)", |s| s.name.as_str()), binding.effect + 1));
+                                        egui::ComboBox::from_id_source(format!("cc_param_{}", idx))
+                                            .selected_text(&binding.param)
+                                            .show_ui(ui, |ui| {
+                                                for (_, p, _) in model::PARAM_RANGES.iter()
+                                                    .filter(|(k, _, r)| *k == kind && matches!(r, model::ParamRange::Float(..)))
+                                                {
+                                                    ui.selectable_value(&mut binding.param, p.to_string(), *p);
+                                                }
+                                            });
+                                        ui.add(egui::DragValue::new(&mut binding.min).speed(0.01).prefix("min "));
+                                        ui.add(egui::DragValue::new(&mut binding.max).speed(0.01).prefix("max "));
+                                        if ui.small_button("🗑").clicked() {
+                                            delete_binding_idx = Some(idx);
+                                        }
+                                    });
+                                }
+                                if let Some(idx) = delete_binding_idx {
+                                    self.state.midi_cc_bindings.remove(idx);
+                                }
+                                if init != before {
+                                    self.state.midi_init.insert(model.name().to_string(), init);
+                                }
+                            }
                         });
                         
+                        ui.collapsing("Effect Presets", |ui| {
+                            if self.state.presets.is_empty() {
+                                ui.label(egui::RichText::new("Save a global effect with ⭐ to reuse it across scenes.").weak());
+                            }
+                            let mut delete_preset_id = None;
+                            for preset in &mut self.state.presets {
+                                let uses = self.state.scenes.iter()
+                                    .flat_map(|s| &s.global_effects)
+                                    .filter(|c| c.effect.preset_id == Some(preset.id))
+                                    .count();
+                                ui.horizontal(|ui| {
+                                    ui.text_edit_singleline(&mut preset.name);
+                                    ui.label(egui::RichText::new(format!("{} · {} use(s)", preset.kind, uses)).small().weak());
+                                    if ui.button("🗑").clicked() {
+                                        delete_preset_id = Some(preset.id);
+                                    }
+                                });
+                            }
+                            if let Some(id) = delete_preset_id {
+                                // Bake the preset into every effect using it so those scenes keep their look
+                                for config in self.state.scenes.iter_mut().flat_map(|s| s.global_effects.iter_mut()) {
+                                    if config.effect.preset_id == Some(id) {
+                                        config.effect = config.effect.resolve(&self.state.presets).into_owned();
+                                        config.effect.preset_id = None;
+                                    }
+                                }
+                                self.state.presets.retain(|p| p.id != id);
+                            }
+                        });
+
                         ui.collapsing("Network Output", |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Protocol");
+                                egui::ComboBox::from_id_source("dmx_protocol")
+                                    .selected_text(self.state.network.protocol.label())
+                                    .show_ui(ui, |ui| {
+                                        for protocol in model::DmxProtocol::ALL {
+                                            ui.selectable_value(&mut self.state.network.protocol, protocol, protocol.label());
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("Art-Net sends universe 1 as port-address 0 and broadcasts when Multicast is on");
+                            });
                             ui.horizontal(|ui| {
                                 ui.label("Universe");
-                                ui.add(egui::DragValue::new(&mut self.state.network.universe).speed(1).clamp_range(1..=63999));
+                                let max_universe = self.state.network.protocol.max_universe();
+                                ui.add(egui::DragValue::new(&mut self.state.network.universe).speed(1).clamp_range(1..=max_universe));
                             });
                             
                             ui.checkbox(&mut self.state.network.use_multicast, "Multicast (Broadcast)");
                             
                             if !self.state.network.use_multicast {
-                                ui.horizontal(|ui| {
-                                    ui.label("IP Address");
-                                    ui.text_edit_singleline(&mut self.state.network.unicast_ip);
-                                });
+                                let mut remove_ip = None;
+                                for (idx, ip) in self.state.network.unicast_ips.iter_mut().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        ui.label("IP Address");
+                                        ui.text_edit_singleline(ip);
+                                        if ui.small_button("✖").clicked() {
+                                            remove_ip = Some(idx);
+                                        }
+                                    });
+                                }
+                                if let Some(idx) = remove_ip {
+                                    self.state.network.unicast_ips.remove(idx);
+                                }
+                                if ui.button("➕ Add IP").on_hover_text("Every universe without a route is sent to each IP; use Routes to split universes between controllers").clicked() {
+                                    self.state.network.unicast_ips.push(String::new());
+                                }
                             }
+
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut self.state.network.max_fps).speed(1).clamp_range(0..=1000).prefix("Max FPS: "))
+                                    .on_hover_text("Cap on frames sent per second; extra rendered frames are dropped. 0 = uncapped");
+                                let mut keepalive = self.state.network.keepalive_ms.is_some();
+                                if ui.checkbox(&mut keepalive, "Skip Unchanged")
+                                    .on_hover_text("Only resend a universe whose data hasn't changed once the keep-alive interval has passed")
+                                    .changed()
+                                {
+                                    self.state.network.keepalive_ms = if keepalive { Some(1000) } else { None };
+                                }
+                                if let Some(ms) = &mut self.state.network.keepalive_ms {
+                                    ui.add(egui::DragValue::new(ms).speed(10).clamp_range(model::MIN_KEEPALIVE_MS..=model::MAX_KEEPALIVE_MS).prefix("Keep-alive: ").suffix(" ms"))
+                                        .on_hover_text("At most 1 s: receivers drop a source that sends nothing for 2.5 s");
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                let mut probe = self.state.network.probe_interval_ms.is_some();
+                                if ui.checkbox(&mut probe, "Check Nodes")
+                                    .on_hover_text("Periodically check that every unicast destination answers on the network; down nodes show next to the MIDI status")
+                                    .changed()
+                                {
+                                    self.state.network.probe_interval_ms = if probe { Some(5000) } else { None };
+                                }
+                                if let Some(ms) = &mut self.state.network.probe_interval_ms {
+                                    ui.add(egui::DragValue::new(ms).speed(100).clamp_range(500..=60000).prefix("Every: ").suffix(" ms"));
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("On Pause");
+                                let selected = if self.state.network.pause_policy == "blackout" { "Blackout" } else { "Hold" };
+                                egui::ComboBox::from_id_source("pause_policy")
+                                    .selected_text(selected)
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut self.state.network.pause_policy, "hold".to_string(), "Hold")
+                                            .on_hover_text("Stop sending; most nodes keep the last frame");
+                                        ui.selectable_value(&mut self.state.network.pause_policy, "blackout".to_string(), "Blackout")
+                                            .on_hover_text("Send a black frame, then stop sending");
+                                    });
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("Output Threads");
+                                ui.add(egui::DragValue::new(&mut self.state.output_threads).speed(0.1).clamp_range(1..=16))
+                                    .on_hover_text("Send universes from several threads (each universe always uses the same one); helps with 32+ universes");
+                            });
+
+                            ui.horizontal(|ui| {
+                                let mut preview_on = self.state.preview_port.is_some();
+                                if ui.checkbox(&mut preview_on, "Live Preview (WebSocket)").changed() {
+                                    self.state.preview_port = if preview_on { Some(8765) } else { None };
+                                }
+                                if let Some(port) = &mut self.state.preview_port {
+                                    ui.add(egui::DragValue::new(port).prefix("Port: ").clamp_range(1024..=65535));
+                                }
+                            });
+
+                            ui.collapsing("Universe Routes", |ui| {
+                                ui.label("Send universe ranges out a specific interface / destination. Unmapped universes use the settings above.");
+                                let mut delete_route_idx = None;
+                                let max_universe = self.state.network.protocol.max_universe();
+                                for (idx, route) in self.state.network.routes.iter_mut().enumerate() {
+                                    ui.push_id(format!("route_{}", idx), |ui| {
+                                        ui.horizontal(|ui| {
+                                            ui.add(egui::DragValue::new(&mut route.universes.0).prefix("From: ").clamp_range(1..=max_universe));
+                                            ui.add(egui::DragValue::new(&mut route.universes.1).prefix("To: ").clamp_range(1..=max_universe));
+                                            if ui.button("🗑").clicked() {
+                                                delete_route_idx = Some(idx);
+                                            }
+                                        });
+                                        ui.horizontal(|ui| {
+                                            ui.label("Bind IP");
+                                            let mut bind = route.bind.clone().unwrap_or_default();
+                                            if ui.text_edit_singleline(&mut bind).changed() {
+                                                route.bind = if bind.trim().is_empty() { None } else { Some(bind) };
+                                            }
+                                        });
+                                        ui.horizontal(|ui| {
+                                            ui.label("Dest IP");
+                                            let mut dest = route.dest.clone().unwrap_or_default();
+                                            if ui.text_edit_singleline(&mut dest).on_hover_text("Leave empty for multicast").changed() {
+                                                route.dest = if dest.trim().is_empty() { None } else { Some(dest) };
+                                            }
+                                        });
+                                    });
+                                    ui.separator();
+                                }
+                                if let Some(idx) = delete_route_idx {
+                                    self.state.network.routes.remove(idx);
+                                }
+                                if ui.button("➕ Add Route").clicked() {
+                                    self.state.network.routes.push(UniverseRoute::default());
+                                }
+                            });
                         });
                         
                         ui.separator();
@@ -939,11 +2032,40 @@ impl eframe::App for MyApp {
                                 let mut s = PixelStrip::default();
                                 s.id = rand::random();
                                 self.state.strips.push(s);
+                                if !self.state.manual_addressing {
+                                    self.reassign_addresses();
+                                }
+                                self.mark_state_changed();
+                            }
+                            if ui.button("Auto-Address").on_hover_text("Pack every unchained strip into universes in list order").clicked() {
+                                self.reassign_addresses();
+                                self.mark_state_changed();
+                            }
+                            if ui.checkbox(&mut self.state.manual_addressing, "Manual")
+                                .on_hover_text("Keep typed universe/channel when adding strips instead of repacking all strips")
+                                .changed()
+                            {
                                 self.mark_state_changed();
                             }
+                            let mut canvas_width = self.state.canvas_width();
+                            if ui.add(egui::DragValue::new(&mut canvas_width).speed(0.1).clamp_range(0.1..=1000.0).prefix("Canvas: ").suffix(" m"))
+                                .on_hover_text("Real-world width of the canvas, used to place strips that have LEDs/m or a length set")
+                                .changed()
+                            {
+                                self.state.canvas_width_m = Some(canvas_width);
+                                self.state.apply_physical_spacing();
+                            }
                         });
                         
+                        let canvas_width = self.state.canvas_width();
+                        let chained: std::collections::HashSet<u64> = self.state.chains.iter()
+                            .flat_map(|c| c.strip_ids.iter().copied())
+                            .collect();
                         let mut delete_strip_idx = None;
+                        let strip_ids: Vec<u64> = self.state.strips.iter().map(|s| s.id).collect();
+                        let mut set_mirror = None;
+                        let mut fix_color_order = None;
+                        let max_universe = self.state.network.protocol.max_universe();
                         for (idx, s) in self.state.strips.iter_mut().enumerate() {
                             ui.push_id(s.id, |ui| {
                                 ui.collapsing(format!("Strip::{}", s.id), |ui| {
@@ -951,30 +2073,146 @@ impl eframe::App for MyApp {
                                         ui.label("Position:");
                                         ui.add(egui::DragValue::new(&mut s.x).speed(0.01).prefix("X: "));
                                         ui.add(egui::DragValue::new(&mut s.y).speed(0.01).prefix("Y: "));
+                                        let mut has_z = s.z.is_some();
+                                        if ui.checkbox(&mut has_z, "Z")
+                                            .on_hover_text("Depth for volumetric rigs; Plasma, Ripple and radial masks measure 3D distance when set")
+                                            .changed()
+                                        {
+                                            s.z = if has_z { Some(0.0) } else { None };
+                                        }
+                                        if let Some(z) = &mut s.z {
+                                            ui.add(egui::DragValue::new(z).speed(0.01));
+                                        }
                                     });
                                     ui.horizontal(|ui| {
                                         ui.label("Direction:");
                                         ui.checkbox(&mut s.flipped, "Flip 180°");
+                                        let mut pattern = self.engine.is_test_pattern(Some(s.id));
+                                        if ui.checkbox(&mut pattern, "Test Pattern").changed() {
+                                            self.engine.set_test_pattern(Some(s.id), pattern);
+                                        }
                                     });
                                     ui.horizontal(|ui| {
                                         ui.label("Config:");
-                                        ui.add(egui::DragValue::new(&mut s.universe).prefix("Uni: ").clamp_range(1..=63999));
-                                        ui.add(egui::DragValue::new(&mut s.start_channel).prefix("Ch: "));
+                                        ui.checkbox(&mut s.enabled, "Enabled")
+                                            .on_hover_text("Disabled strips are not rendered and send no DMX");
+                                        let addressable = !chained.contains(&s.id);
+                                        ui.add_enabled(addressable, egui::DragValue::new(&mut s.universe).prefix("Uni: ").clamp_range(1..=max_universe));
+                                        ui.add_enabled(addressable, egui::DragValue::new(&mut s.start_channel).prefix("Ch: "));
+                                        if !addressable {
+                                            ui.label("(chained)");
+                                        }
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Fixture:");
+                                        for (label, channels) in [("Pre: ", &mut s.pre_channels), ("Post: ", &mut s.post_channels)] {
+                                            ui.label(label);
+                                            let current = channels.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ");
+                                            let hover = "Comma-separated DMX values (0-255), e.g. a dimmer at full: 255.\nL<value> follows the effect: the strip's brightest pixel, scaled to <value> at full (L = L255)";
+                                            if let Some(text) = comma_list_edit(ui, current, (s.id, label), "none", hover) {
+                                                *channels = text.split(',').filter_map(model::FixtureChannel::parse).collect();
+                                            }
+                                        }
                                     });
                                     ui.horizontal(|ui| {
                                         ui.label("Layout:");
-                                        ui.add(egui::DragValue::new(&mut s.pixel_count).prefix("Count: "));
-                                        ui.add(egui::Slider::new(&mut s.spacing, 0.001..=0.05).text("Spacing"));
+                                        let mut count = s.pixel_count;
+                                        if ui.add(egui::DragValue::new(&mut count).prefix("Count: ")).changed() {
+                                            s.set_pixel_count(count);
+                                        }
+                                        ui.add_enabled(s.pixel_pitch_m().is_none(), egui::Slider::new(&mut s.spacing, 0.001..=0.05).text("Spacing"));
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Physical:");
+                                        let mut has_density = s.leds_per_meter.is_some();
+                                        if ui.checkbox(&mut has_density, "LEDs/m").changed() {
+                                            s.leds_per_meter = if has_density { Some(60.0) } else { None };
+                                        }
+                                        if let Some(lpm) = &mut s.leds_per_meter {
+                                            ui.add(egui::DragValue::new(lpm).clamp_range(1.0..=1000.0));
+                                        }
+                                        let mut has_length = s.length_m.is_some();
+                                        if ui.checkbox(&mut has_length, "Length").changed() {
+                                            s.length_m = if has_length { Some(1.0) } else { None };
+                                        }
+                                        if let Some(len) = &mut s.length_m {
+                                            ui.add(egui::DragValue::new(len).speed(0.01).clamp_range(0.01..=1000.0).suffix(" m"));
+                                        }
                                     });
+                                    s.apply_physical_spacing(canvas_width);
                                     ui.horizontal(|ui| {
                                         ui.label("Protocol:");
                                         egui::ComboBox::from_id_source(format!("proto_{}", s.id))
                                             .selected_text(&s.color_order)
                                             .show_ui(ui, |ui| {
-                                                ui.selectable_value(&mut s.color_order, "RGB".to_string(), "RGB");
-                                                ui.selectable_value(&mut s.color_order, "GRB".to_string(), "GRB");
-                                                ui.selectable_value(&mut s.color_order, "BGR".to_string(), "BGR");
-                                            });
+                                                for order in model::COLOR_ORDERS.iter().chain(model::EXTENDED_COLOR_ORDERS) {
+                                                    if ui.selectable_label(s.color_order == *order, *order).clicked() {
+                                                        if ui.input(|i| i.modifiers.shift) {
+                                                            s.set_color_order_preserving_look(order);
+                                                        } else {
+                                                            s.color_order = order.to_string();
+                                                        }
+                                                    }
+                                                }
+                                            })
+                                            .response
+                                            .on_hover_text("Shift-click an order to switch without changing what the strip shows right now. W sends derived white, I full intensity");
+                                        ui.add(egui::DragValue::new(&mut s.channels_per_pixel).clamp_range(0..=16).prefix("Ch/px: "))
+                                            .on_hover_text("DMX channels per pixel; 0 = one per letter of the order. Extra channels are sent as 0");
+                                        if s.color_order.contains('W') {
+                                            egui::ComboBox::from_id_source(format!("white_{}", s.id))
+                                                .selected_text(format!("W: {}", s.white_mode))
+                                                .show_ui(ui, |ui| {
+                                                    for mode in model::WHITE_MODES {
+                                                        ui.selectable_value(&mut s.white_mode, mode.to_string(), *mode);
+                                                    }
+                                                })
+                                                .response
+                                                .on_hover_text("Add: white on top of RGB. Subtract: the common part of RGB moves to the white LED. Off: white LED dark");
+                                        }
+                                        ui.menu_button("Fix…", |ui| {
+                                            ui.label("With the test pattern on, red and green showed as:");
+                                            let name = |c: char| match c { 'R' => "red", 'G' => "green", _ => "blue" };
+                                            for (red, green) in [('R', 'G'), ('R', 'B'), ('G', 'R'), ('G', 'B'), ('B', 'R'), ('B', 'G')] {
+                                                let Some(order) = model::infer_color_order(&s.color_order, red, green) else { continue };
+                                                let resp = ui.button(format!("{} / {}  →  {}", name(red), name(green), order))
+                                                    .on_hover_text("Shift-click to fix every strip currently set to the same order");
+                                                if resp.clicked() {
+                                                    let all = ui.input(|i| i.modifiers.shift);
+                                                    fix_color_order = Some((s.id, s.color_order.clone(), order, all));
+                                                    ui.close_menu();
+                                                }
+                                            }
+                                        });
+                                        ui.add(egui::DragValue::new(&mut s.min_brightness).prefix("Min: ").clamp_range(0..=64))
+                                            .on_hover_text("Lift any lit channel to at least this value to avoid low-PWM flicker; off stays off");
+                                        ui.add(egui::DragValue::new(&mut s.brightness).prefix("Dim: ").speed(0.01).clamp_range(0.0..=1.0))
+                                            .on_hover_text("Scale this strip's output; Master Brightness multiplies on top");
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Mirror:");
+                                        let selected = s.mirror_source.map_or("Off".to_string(), |id| format!("Strip::{}", id));
+                                        egui::ComboBox::from_id_source("mirror_source")
+                                            .selected_text(selected)
+                                            .show_ui(ui, |ui| {
+                                                if ui.selectable_label(s.mirror_source.is_none(), "Off").clicked() {
+                                                    set_mirror = Some((s.id, None));
+                                                }
+                                                for id in strip_ids.iter().filter(|id| **id != s.id) {
+                                                    if ui.selectable_label(s.mirror_source == Some(*id), format!("Strip::{}", id)).clicked() {
+                                                        set_mirror = Some((s.id, Some(*id)));
+                                                    }
+                                                }
+                                            })
+                                            .response
+                                            .on_hover_text("Show another strip's output here instead of rendering effects");
+                                        if s.mirror_source.is_some() {
+                                            ui.checkbox(&mut s.mirror_reversed, "Reversed");
+                                        }
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Notes:");
+                                        ui.text_edit_multiline(&mut s.notes);
                                     });
                                     
                                     if ui.button("🗑 Delete Strip").clicked() {
@@ -986,6 +2224,85 @@ impl eframe::App for MyApp {
                         if let Some(idx) = delete_strip_idx {
                             self.state.strips.remove(idx);
                         }
+                        if let Some((id, current, order, all)) = fix_color_order {
+                            let ids: Vec<u64> = if all {
+                                self.state.strips.iter().filter(|s| s.color_order == current).map(|s| s.id).collect()
+                            } else {
+                                vec![id]
+                            };
+                            let changed = self.state.set_color_order(&ids, &order, false);
+                            self.status = format!("Set {} strip(s) to {}", changed, order);
+                            self.mark_state_changed();
+                        }
+                        if let Some((id, source)) = set_mirror {
+                            if self.state.set_mirror_source(id, source) {
+                                self.mark_state_changed();
+                            } else {
+                                self.status = "Mirror rejected: the source already mirrors this strip".into();
+                            }
+                        }
+
+                        ui.collapsing(format!("Chains ({})", self.state.chains.len()), |ui| {
+                            ui.label("Strips wired in series on one output. Segments are addressed back to back in this order but keep their own placement.");
+                            let strip_ids: Vec<u64> = self.state.strips.iter().map(|s| s.id).collect();
+                            let mut delete_chain_idx = None;
+                            let max_universe = self.state.network.protocol.max_universe();
+                            for (ci, chain) in self.state.chains.iter_mut().enumerate() {
+                                ui.push_id(chain.id, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.text_edit_singleline(&mut chain.name);
+                                        ui.add(egui::DragValue::new(&mut chain.universe).prefix("Uni: ").clamp_range(1..=max_universe));
+                                        ui.add(egui::DragValue::new(&mut chain.start_channel).prefix("Ch: ").clamp_range(1..=512));
+                                        if ui.button("🗑").clicked() {
+                                            delete_chain_idx = Some(ci);
+                                        }
+                                    });
+                                    let mut move_up = None;
+                                    let mut remove = None;
+                                    for (si, id) in chain.strip_ids.iter().enumerate() {
+                                        ui.horizontal(|ui| {
+                                            ui.label(format!("{}. Strip::{}", si + 1, id));
+                                            if si > 0 && ui.small_button("⬆").clicked() {
+                                                move_up = Some(si);
+                                            }
+                                            if ui.small_button("x").clicked() {
+                                                remove = Some(si);
+                                            }
+                                        });
+                                    }
+                                    if let Some(si) = move_up {
+                                        chain.strip_ids.swap(si - 1, si);
+                                    }
+                                    if let Some(si) = remove {
+                                        chain.strip_ids.remove(si);
+                                    }
+                                    egui::ComboBox::from_id_source("add_segment")
+                                        .selected_text("➕ Add Segment")
+                                        .show_ui(ui, |ui| {
+                                            for id in strip_ids.iter().filter(|id| !chained.contains(id)) {
+                                                if ui.selectable_label(false, format!("Strip::{}", id)).clicked() {
+                                                    chain.strip_ids.push(*id);
+                                                }
+                                            }
+                                        });
+                                });
+                                ui.separator();
+                            }
+                            if let Some(ci) = delete_chain_idx {
+                                self.state.chains.remove(ci);
+                            }
+                            if ui.button("➕ Add Chain").clicked() {
+                                self.state.chains.push(model::StripChain {
+                                    id: rand::random(),
+                                    name: format!("Chain {}", self.state.chains.len() + 1),
+                                    universe: 1,
+                                    start_channel: 1,
+                                    strip_ids: Vec::new(),
+                                });
+                            }
+                        });
+                        // Chain edits and pixel count changes re-address chained segments every frame
+                        self.state.apply_chains();
 
                         ui.separator();
                         // STRIPS are shown above; now show Scenes with embedded Masks editors
@@ -1001,7 +2318,19 @@ impl eframe::App for MyApp {
                                 self.scene_manager_open = true;
                             }
                             if !self.state.scenes.is_empty() {
-                                if ui.button("Select None").clicked() { self.state.selected_scene_id = None; }
+                                if ui.button("Select None").clicked() { let _ = self.command_sender.send(command::AppCommand::ClearScenes); }
+                            }
+                            let conflicts = self.state.binding_conflicts();
+                            if !conflicts.is_empty() {
+                                let details: Vec<String> = conflicts.iter().map(|(note, is_cc, ids)| {
+                                    let names: Vec<&str> = ids.iter()
+                                        .filter_map(|id| self.state.scenes.iter().find(|s| s.id == *id))
+                                        .map(|s| s.name.as_str())
+                                        .collect();
+                                    format!("{} {}: {}", if *is_cc { "CC" } else { "Note" }, note, names.join(", "))
+                                }).collect();
+                                ui.colored_label(egui::Color32::YELLOW, format!("⚠ {} pad conflict(s)", conflicts.len()))
+                                    .on_hover_text(format!("Only the first scene on each pad is triggered:\n{}", details.join("\n")));
                             }
                         });
 
@@ -1084,7 +2413,13 @@ impl eframe::App for MyApp {
                                                 global_effects: vec![],
                                                 launchpad_btn: None,
                                                 launchpad_color: None,
-                                                launchpad_is_cc: false
+                                                launchpad_is_cc: false,
+                                                launchpad_momentary: false,
+                                                notes: String::new(),
+                                                exclusive_group: model::default_exclusive_group(),
+                                                layer: 0,
+                                                opacity: 1.0,
+                                                blend_mode: String::new(),
                                             }
                                         } else {
                                             let mut ge = model::GlobalEffect::default();
@@ -1102,11 +2437,17 @@ impl eframe::App for MyApp {
                                                  }],
                                                  launchpad_btn: None,
                                                  launchpad_color: None,
-                                                 launchpad_is_cc: false
+                                                 launchpad_is_cc: false,
+                                                 launchpad_momentary: false,
+                                                 notes: String::new(),
+                                                 exclusive_group: model::default_exclusive_group(),
+                                                 layer: 0,
+                                                 opacity: 1.0,
+                                                 blend_mode: String::new(),
                                             }
                                         };
                                         self.state.scenes.push(scene);
-                                        self.state.selected_scene_id = Some(id);
+                                        let _ = self.command_sender.send(command::AppCommand::TriggerScene(id));
                                         self.new_scene_open = false;
                                     }
                                     if ui.button("Cancel").clicked() { self.new_scene_open = false; }
@@ -1118,6 +2459,9 @@ impl eframe::App for MyApp {
                         let mut delete_scene_idx: Option<usize> = None;
                         let mut duplicate_scene_idx: Option<usize> = None;
                         let mut swap_request: Option<(usize, usize)> = None;
+                        let mut triggered_scene: Option<u64> = None;
+                        let effect_clipboard = self.effect_clipboard;
+                        let mut paste_effect: Option<((u64, usize), bool)> = None;
                         let mut floating_scene: Option<model::Scene> = None;
                         let mut needs_save = false;
                         let sender = self.midi_sender.clone();
@@ -1190,8 +2534,10 @@ impl eframe::App for MyApp {
                                              // Next frame it will be caught by is_being_dragged above
                                         }
 
-                                        if ui.selectable_label(selected, &scene.name).clicked() {
-                                            self.state.selected_scene_id = Some(scene.id);
+                                        let active = self.state.active_scene_ids.contains(&scene.id);
+                                        let label = if active && !selected { format!("◉ {}", scene.name) } else { scene.name.clone() };
+                                        if ui.selectable_label(selected, label).on_hover_text("Active layers are marked ◉").clicked() {
+                                            triggered_scene = Some(scene.id);
                                         }
                                         ui.text_edit_singleline(&mut scene.name);
                                         if ui.button("📋").on_hover_text("Duplicate").clicked() { duplicate_scene_idx = Some(si); }
@@ -1228,6 +2574,44 @@ impl eframe::App for MyApp {
                                         needs_save = true;
                                     }
                                 });
+                                ui.horizontal(|ui| {
+                                    ui.label("Group:");
+                                    if ui.add(egui::TextEdit::singleline(&mut scene.exclusive_group).hint_text("none (overlay)").desired_width(100.0))
+                                        .on_hover_text("Active scenes in the same group replace each other; scenes in different groups stack. Leave empty for an overlay that toggles on and off.")
+                                        .changed()
+                                    {
+                                        needs_save = true;
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Layer:");
+                                    if ui.add(egui::DragValue::new(&mut scene.layer)).on_hover_text("Stacking order among active scenes; higher is on top").changed() {
+                                        needs_save = true;
+                                    }
+                                    if ui.add(egui::Slider::new(&mut scene.opacity, 0.0..=1.0).text("Opacity")).changed() {
+                                        needs_save = true;
+                                    }
+                                    let auto_label = format!("Auto ({})", if scene.kind == "Masks" { "Mask" } else { "Max" });
+                                    let selected_text = if scene.blend_mode.is_empty() { auto_label.clone() } else { scene.blend_mode.clone() };
+                                    egui::ComboBox::from_id_source(format!("blend_{}", scene.id))
+                                        .selected_text(selected_text)
+                                        .show_ui(ui, |ui| {
+                                            if ui.selectable_value(&mut scene.blend_mode, String::new(), auto_label).changed() {
+                                                needs_save = true;
+                                            }
+                                            for mode in model::BLEND_MODES {
+                                                if ui.selectable_value(&mut scene.blend_mode, mode.to_string(), *mode).changed() {
+                                                    needs_save = true;
+                                                }
+                                            }
+                                        });
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Notes:");
+                                    if ui.text_edit_multiline(&mut scene.notes).changed() {
+                                        needs_save = true;
+                                    }
+                                });
                                 // Launchpad Config
                                 ui.horizontal(|ui| {
                                     ui.label("Launchpad Pad:");
@@ -1237,9 +2621,9 @@ impl eframe::App for MyApp {
 
                                     // Generate valid note values (8 rows, 8 columns)
                                     let mut valid_notes = Vec::new();
-                                    for row in 0..8 {
-                                        for col in 0..8 {
-                                            let note = ((8 - row) * 10 + 1 + col) as u8;
+                                    for row in 0..midi::GRID_SIZE {
+                                        for col in 0..midi::GRID_SIZE {
+                                            let note = midi::xy_to_note((col, row), self.midi_model);
                                             valid_notes.push(note);
                                         }
                                     }
@@ -1267,7 +2651,7 @@ impl eframe::App for MyApp {
                                             for row in 0..8 {
                                                 ui.horizontal(|ui| {
                                                     for col in 0..8 {
-                                                        let note = ((8 - row) * 10 + 1 + col) as u8;
+                                                        let note = midi::xy_to_note((col, row), self.midi_model);
                                                         // Check if already used by another scene
                                                         let is_used = if let Some(&owner) = used_ids.get(&(false, note)) {
                                                             owner != scene.id
@@ -1303,6 +2687,8 @@ impl eframe::App for MyApp {
                                         if let (Some(note), Some(col)) = (scene.launchpad_btn, scene.launchpad_color) {
                                             let _ = sender.send(midi::MidiCommand::SetPadColor { note, color: col });
                                         }
+                                        // The sends above bypass the pulse cache; rebuild it next frame
+                                        self.pulsing_pads = None;
 
                                         needs_save = true;
                                     }
@@ -1314,6 +2700,12 @@ impl eframe::App for MyApp {
                                         if let Some(note) = scene.launchpad_btn {
                                             let _ = sender.send(midi::MidiCommand::SetPadColor { note, color: col });
                                         }
+                                        self.pulsing_pads = None;
+                                        needs_save = true;
+                                    }
+                                    if ui.checkbox(&mut scene.launchpad_momentary, "Momentary")
+                                        .on_hover_text("Scene is active only while the pad is held; releasing it restores the previous scenes")
+                                        .changed() {
                                         needs_save = true;
                                     }
                                 });
@@ -1340,7 +2732,7 @@ impl eframe::App for MyApp {
                                                     let label_text = match &config.targets {
                                                         None => "Targets: All Strips".to_string(),
                                                         Some(t) => {
-                                                            if t.is_empty() { "Targets: None".to_string() }
+                                                            if t.is_empty() { "Targets: None (disabled)".to_string() }
                                                             else { format!("Targets: {} Strips", t.len()) }
                                                         }
                                                     };
@@ -1351,6 +2743,12 @@ impl eframe::App for MyApp {
                                                             if ui.selectable_label(config.targets.is_none(), "All Strips").clicked() {
                                                                 config.targets = None;
                                                             }
+                                                            if ui.selectable_label(config.is_disabled(), "No Strips (disabled)")
+                                                                .on_hover_text("Keep this effect but stop rendering it")
+                                                                .clicked()
+                                                            {
+                                                                config.targets = Some(Vec::new());
+                                                            }
                                                             ui.separator();
                                                             
                                                             for (sid, sidx) in &available_strips {
@@ -1383,19 +2781,96 @@ impl eframe::App for MyApp {
                                                         });
                                                         
                                                     // Type Selector
+                                                    let kind_before = config.effect.kind.clone();
                                                     egui::ComboBox::from_id_source("kind_sel")
                                                         .selected_text(&config.effect.kind)
                                                         .show_ui(ui, |ui| {
                                                             ui.selectable_value(&mut config.effect.kind, "Rainbow".into(), "Rainbow");
                                                             ui.selectable_value(&mut config.effect.kind, "Solid".into(), "Solid");
+                                                            ui.selectable_value(&mut config.effect.kind, "Gradient".into(), "Gradient");
                                                             ui.selectable_value(&mut config.effect.kind, "Flash".into(), "Flash");
+                                                            ui.selectable_value(&mut config.effect.kind, "Strobe".into(), "Strobe");
                                                             ui.selectable_value(&mut config.effect.kind, "Sparkle".into(), "Sparkle");
                                                             ui.selectable_value(&mut config.effect.kind, "ColorWash".into(), "Color Wash");
                                                             ui.selectable_value(&mut config.effect.kind, "GlitchSparkle".into(), "Glitch Sparkle");
                                                             ui.selectable_value(&mut config.effect.kind, "PulseWave".into(), "Pulse Wave");
                                                             ui.selectable_value(&mut config.effect.kind, "ZoneAlternate".into(), "Zone Alternate");
+                                                            ui.selectable_value(&mut config.effect.kind, "Plasma".into(), "Plasma");
+                                                            ui.selectable_value(&mut config.effect.kind, "Ripple".into(), "Ripple");
+                                                            ui.selectable_value(&mut config.effect.kind, "SpectrumBars".into(), "Spectrum Bars");
+                                                        });
+
+                                                    if config.effect.kind != kind_before {
+                                                        model::prune_unknown_params(&config.effect.kind, &mut config.effect.params);
+                                                    }
+
+                                                    // Picking another kind unlinks the preset (its params belong to its own kind)
+                                                    let linked_kind = config.effect.preset_id
+                                                        .and_then(|id| self.state.presets.iter().find(|p| p.id == id))
+                                                        .map(|p| p.kind.clone());
+                                                    if linked_kind.is_some_and(|k| k != config.effect.kind) {
+                                                        config.effect.preset_id = None;
+                                                        config.effect.params.clear();
+                                                    }
+
+                                                    // Preset link
+                                                    let preset_name = config.effect.preset_id
+                                                        .and_then(|id| self.state.presets.iter().find(|p| p.id == id))
+                                                        .map(|p| p.name.clone())
+                                                        .unwrap_or_else(|| "No Preset".into());
+                                                    egui::ComboBox::from_id_source("preset_sel")
+                                                        .selected_text(preset_name)
+                                                        .show_ui(ui, |ui| {
+                                                            if ui.selectable_label(config.effect.preset_id.is_none(), "No Preset").clicked() {
+                                                                // Detach but keep the current look as plain params
+                                                                config.effect = config.effect.resolve(&self.state.presets).into_owned();
+                                                                config.effect.preset_id = None;
+                                                            }
+                                                            for preset in &self.state.presets {
+                                                                if ui.selectable_label(config.effect.preset_id == Some(preset.id), &preset.name).clicked() {
+                                                                    config.effect.preset_id = Some(preset.id);
+                                                                    config.effect.kind = preset.kind.clone();
+                                                                    config.effect.params.clear();
+                                                                }
+                                                            }
                                                         });
+                                                    if config.effect.preset_id.is_none() {
+                                                        if ui.button("⭐").on_hover_text("Save as preset").clicked() {
+                                                            let id = rand::random();
+                                                            self.state.presets.push(model::EffectPreset {
+                                                                id,
+                                                                name: format!("{} Preset {}", config.effect.kind, self.state.presets.len() + 1),
+                                                                kind: config.effect.kind.clone(),
+                                                                params: std::mem::take(&mut config.effect.params),
+                                                            });
+                                                            config.effect.preset_id = Some(id);
+                                                        }
+                                                    } else if !config.effect.params.is_empty() {
+                                                        let overrides = config.effect.params.len();
+                                                        if ui.button(format!("⬆ {}", overrides))
+                                                            .on_hover_text("Push this scene's overrides into the preset (updates every scene using it)")
+                                                            .clicked()
+                                                        {
+                                                            if let Some(preset) = self.state.presets.iter_mut().find(|p| Some(p.id) == config.effect.preset_id) {
+                                                                preset.params.extend(config.effect.params.drain());
+                                                            }
+                                                        }
+                                                        if ui.button("↺").on_hover_text("Clear this scene's overrides").clicked() {
+                                                            config.effect.params.clear();
+                                                        }
+                                                    }
                                                         
+                                                    if ui.button("📋").on_hover_text("Copy this effect").clicked() {
+                                                        self.effect_clipboard = Some((scene.id, eff_idx));
+                                                    }
+                                                    if effect_clipboard.is_some_and(|src| src != (scene.id, eff_idx)) {
+                                                        if ui.button("📥").on_hover_text("Paste the copied effect (kind and params)").clicked() {
+                                                            paste_effect = Some(((scene.id, eff_idx), false));
+                                                        }
+                                                        if ui.button("📥 Params").on_hover_text("Paste only the copied params, keeping this effect's kind").clicked() {
+                                                            paste_effect = Some(((scene.id, eff_idx), true));
+                                                        }
+                                                    }
                                                     if ui.button("🗑").clicked() {
                                                         delete_effect_idx = Some(eff_idx);
                                                     }
@@ -1407,7 +2882,11 @@ impl eframe::App for MyApp {
                                                 // See below for fix.
                                                 
                                                 // Render Effect Params
-                                                let ge = &mut config.effect;
+                                                // Edits apply to the resolved look; only changed values are stored
+                                                // (as scene overrides when a preset is linked)
+                                                let base = config.effect.resolve(&self.state.presets).into_owned();
+                                                let mut resolved = base.clone();
+                                                let ge = &mut resolved;
                                                 // ... (Reusing existing UI logic, but refactored to check `ge`)
                                                 // INLINED FOR NOW:
                                                 if ge.kind == "Solid" {
@@ -1443,6 +2922,22 @@ impl eframe::App for MyApp {
                                                     if ui.add(egui::Slider::new(&mut decay, 0.1..=20.0).text("Decay")).changed() {
                                                         ge.params.insert("decay".into(), decay.into());
                                                     }
+                                                } else if ge.kind == "Strobe" {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label("Color:");
+                                                        let mut color = ge.params.get("color").and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or([255u8,255,255]);
+                                                        if color_picker(ui, &mut color, format!("ge_stb_{}_{}", scene.id, eff_idx)) {
+                                                            ge.params.insert("color".into(), serde_json::json!([color[0], color[1], color[2]]));
+                                                        }
+                                                    });
+                                                    let mut frequency = ge.params.get("frequency_hz").and_then(|v| v.as_f64()).unwrap_or(10.0);
+                                                    if ui.add(egui::Slider::new(&mut frequency, 0.5..=25.0).text("Frequency").suffix(" Hz")).changed() {
+                                                        ge.params.insert("frequency_hz".into(), frequency.into());
+                                                    }
+                                                    let mut duty = ge.params.get("duty_cycle").and_then(|v| v.as_f64()).unwrap_or(0.5);
+                                                    if ui.add(egui::Slider::new(&mut duty, 0.05..=0.95).text("Duty Cycle")).changed() {
+                                                        ge.params.insert("duty_cycle".into(), duty.into());
+                                                    }
                                                 } else if ge.kind == "Sparkle" {
                                                     ui.horizontal(|ui| {
                                                         ui.label("Color:");
@@ -1463,6 +2958,7 @@ impl eframe::App for MyApp {
                                                     if ui.add(egui::Slider::new(&mut decay, 0.1..=20.0).text("Decay")).changed() {
                                                         ge.params.insert("decay".into(), decay.into());
                                                     }
+                                                    sync_mode_combo(ui, &mut ge.params, format!("spk_sync_{}_{}", scene.id, eff_idx));
                                                 } else if ge.kind == "ColorWash" {
                                                     ui.horizontal(|ui| {
                                                         ui.label("Color A:");
@@ -1531,6 +3027,7 @@ impl eframe::App for MyApp {
                                                     if ui.add(egui::Slider::new(&mut decay, 0.1..=20.0).text("Decay")).changed() {
                                                         ge.params.insert("decay".into(), decay.into());
                                                     }
+                                                    sync_mode_combo(ui, &mut ge.params, format!("gs_sync_{}_{}", scene.id, eff_idx));
                                                 } else if ge.kind == "PulseWave" {
                                                     ui.horizontal(|ui| {
                                                         ui.label("Color:");
@@ -1612,6 +3109,8 @@ impl eframe::App for MyApp {
                                                                 ui.selectable_value(&mut rate, "1/2".into(), "1/2");
                                                                 ui.selectable_value(&mut rate, "1/4".into(), "1/4");
                                                                 ui.selectable_value(&mut rate, "1/8".into(), "1/8");
+                                                                ui.selectable_value(&mut rate, "Audio".into(), "Audio Beat")
+                                                                    .on_hover_text("Swap on every detected audio beat");
                                                             });
                                                         ge.params.insert("rate".into(), serde_json::json!(rate));
                                                     });
@@ -1672,6 +3171,147 @@ impl eframe::App for MyApp {
                                                             ge.params.insert("group_a_strips".into(), serde_json::json!(group_a));
                                                             ge.params.insert("group_b_strips".into(), serde_json::json!(group_b));
                                                         });
+                                                } else if ge.kind == "Gradient" {
+                                                    let mut stops = effect_params::gradient_stops(ge.params.get("stops"));
+                                                    let mut stops_changed = false;
+                                                    let mut remove = None;
+                                                    for (stop_idx, (pos, color)) in stops.iter_mut().enumerate() {
+                                                        ui.horizontal(|ui| {
+                                                            stops_changed |= color_picker(ui, color, format!("ge_grad_{}_{}_{}", scene.id, eff_idx, stop_idx));
+                                                            stops_changed |= ui.add(egui::Slider::new(pos, 0.0..=1.0).text("Pos")).changed();
+                                                            if ui.small_button("🗑").clicked() {
+                                                                remove = Some(stop_idx);
+                                                            }
+                                                        });
+                                                    }
+                                                    if let Some(stop_idx) = remove {
+                                                        stops.remove(stop_idx);
+                                                        stops_changed = true;
+                                                    }
+                                                    if ui.button("+ Stop").clicked() {
+                                                        stops.push((1.0, [255, 255, 255]));
+                                                        stops_changed = true;
+                                                    }
+                                                    if stops_changed {
+                                                        let json: Vec<serde_json::Value> = stops.iter()
+                                                            .map(|(pos, color)| serde_json::json!({ "pos": pos, "color": [color[0], color[1], color[2]] }))
+                                                            .collect();
+                                                        ge.params.insert("stops".into(), serde_json::Value::Array(json));
+                                                    }
+                                                    let mut scroll_speed = ge.params.get("scroll_speed").and_then(|v| v.as_f64()).unwrap_or(0.0);
+                                                    if ui.add(egui::Slider::new(&mut scroll_speed, -2.0..=2.0).text("Scroll Speed")).changed() {
+                                                        ge.params.insert("scroll_speed".into(), scroll_speed.into());
+                                                    }
+                                                } else if ge.kind == "Plasma" {
+                                                    let mut speed = ge.params.get("speed").and_then(|v| v.as_f64()).unwrap_or(0.5);
+                                                    if ui.add(egui::Slider::new(&mut speed, 0.05..=5.0).text("Speed")).changed() {
+                                                        ge.params.insert("speed".into(), speed.into());
+                                                    }
+                                                    lfo_controls(ui, &mut ge.params, "speed", format!("pl_spd_lfo_{}_{}", scene.id, eff_idx));
+                                                    let mut scale = ge.params.get("scale").and_then(|v| v.as_f64()).unwrap_or(6.0);
+                                                    if ui.add(egui::Slider::new(&mut scale, 0.5..=30.0).text("Scale")).changed() {
+                                                        ge.params.insert("scale".into(), scale.into());
+                                                    }
+                                                    let mut palette = ge.params.get("palette").and_then(|v| v.as_str().map(String::from)).unwrap_or("Rainbow".into());
+                                                    ui.horizontal(|ui| {
+                                                        ui.label("Palette:");
+                                                        egui::ComboBox::from_id_source(format!("pl_pal_{}_{}", scene.id, eff_idx))
+                                                            .selected_text(&palette)
+                                                            .show_ui(ui, |ui| {
+                                                                ui.selectable_value(&mut palette, "Rainbow".into(), "Rainbow");
+                                                                ui.selectable_value(&mut palette, "Fire".into(), "Fire");
+                                                                ui.selectable_value(&mut palette, "Ocean".into(), "Ocean");
+                                                                ui.selectable_value(&mut palette, "Duo".into(), "Duo");
+                                                            });
+                                                    });
+                                                    if palette == "Duo" {
+                                                        ui.horizontal(|ui| {
+                                                            ui.label("Color A:");
+                                                            let mut color_a = ge.params.get("color_a").and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or([255u8,0,128]);
+                                                            if color_picker(ui, &mut color_a, format!("ge_pl_a_{}_{}", scene.id, eff_idx)) {
+                                                                ge.params.insert("color_a".into(), serde_json::json!([color_a[0], color_a[1], color_a[2]]));
+                                                            }
+                                                        });
+                                                        ui.horizontal(|ui| {
+                                                            ui.label("Color B:");
+                                                            let mut color_b = ge.params.get("color_b").and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or([0u8,64,255]);
+                                                            if color_picker(ui, &mut color_b, format!("ge_pl_b_{}_{}", scene.id, eff_idx)) {
+                                                                ge.params.insert("color_b".into(), serde_json::json!([color_b[0], color_b[1], color_b[2]]));
+                                                            }
+                                                        });
+                                                    }
+                                                    ge.params.insert("palette".into(), serde_json::json!(palette));
+                                                } else if ge.kind == "Ripple" {
+                                                    ui.horizontal(|ui| {
+                                                        ui.label("Color:");
+                                                        let mut color = ge.params.get("color").and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or([0u8,128,255]);
+                                                        if color_picker(ui, &mut color, format!("ge_rp_{}_{}", scene.id, eff_idx)) {
+                                                            ge.params.insert("color".into(), serde_json::json!([color[0], color[1], color[2]]));
+                                                        }
+                                                    });
+                                                    let mut origin_x = ge.params.get("origin_x").and_then(|v| v.as_f64()).unwrap_or(0.5);
+                                                    if ui.add(egui::Slider::new(&mut origin_x, 0.0..=1.0).text("Origin X")).changed() {
+                                                        ge.params.insert("origin_x".into(), origin_x.into());
+                                                    }
+                                                    let mut origin_y = ge.params.get("origin_y").and_then(|v| v.as_f64()).unwrap_or(0.5);
+                                                    if ui.add(egui::Slider::new(&mut origin_y, 0.0..=1.0).text("Origin Y")).changed() {
+                                                        ge.params.insert("origin_y".into(), origin_y.into());
+                                                    }
+                                                    ui.horizontal(|ui| {
+                                                        let mut origin_z = ge.params.get("origin_z").and_then(|v| v.as_f64());
+                                                        let mut has_z = origin_z.is_some();
+                                                        if ui.checkbox(&mut has_z, "Origin Z").on_hover_text("Rings expand as spheres through strips that have a Z").changed() {
+                                                            origin_z = has_z.then_some(0.0);
+                                                            match origin_z {
+                                                                Some(z) => { ge.params.insert("origin_z".into(), z.into()); }
+                                                                None => { ge.params.remove("origin_z"); }
+                                                            }
+                                                        }
+                                                        if let Some(mut z) = origin_z {
+                                                            if ui.add(egui::DragValue::new(&mut z).speed(0.01)).changed() {
+                                                                ge.params.insert("origin_z".into(), z.into());
+                                                            }
+                                                        }
+                                                    });
+                                                    let mut speed = ge.params.get("speed").and_then(|v| v.as_f64()).unwrap_or(0.5);
+                                                    if ui.add(egui::Slider::new(&mut speed, 0.05..=3.0).text("Speed")).changed() {
+                                                        ge.params.insert("speed".into(), speed.into());
+                                                    }
+                                                    let mut width = ge.params.get("width").and_then(|v| v.as_f64()).unwrap_or(0.05);
+                                                    if ui.add(egui::Slider::new(&mut width, 0.005..=0.5).text("Width")).changed() {
+                                                        ge.params.insert("width".into(), width.into());
+                                                    }
+                                                    let mut decay = ge.params.get("decay").and_then(|v| v.as_f64()).unwrap_or(1.0);
+                                                    if ui.add(egui::Slider::new(&mut decay, 0.1..=10.0).text("Decay")).changed() {
+                                                        ge.params.insert("decay".into(), decay.into());
+                                                    }
+                                                    ui.horizontal(|ui| {
+                                                        ui.label("Trigger:");
+                                                        let mut trigger = ge.params.get("trigger").and_then(|v| v.as_str().map(String::from)).unwrap_or("Beat".into());
+                                                        egui::ComboBox::from_id_source(format!("rp_trig_{}_{}", scene.id, eff_idx))
+                                                            .selected_text(&trigger)
+                                                            .show_ui(ui, |ui| {
+                                                                ui.selectable_value(&mut trigger, "Beat".into(), "Beat");
+                                                                ui.selectable_value(&mut trigger, "Timer".into(), "Timer");
+                                                            });
+                                                        ge.params.insert("trigger".into(), serde_json::json!(trigger));
+                                                    });
+                                                    if ge.params.get("trigger").and_then(|v| v.as_str()) == Some("Timer") {
+                                                        let mut interval = ge.params.get("interval").and_then(|v| v.as_f64()).unwrap_or(1.0);
+                                                        if ui.add(egui::Slider::new(&mut interval, 0.1..=10.0).text("Interval (s)")).changed() {
+                                                            ge.params.insert("interval".into(), interval.into());
+                                                        }
+                                                    }
+                                                } else if ge.kind == "SpectrumBars" {
+                                                    for (key, label, default) in [("color_low", "Low:", [255u8, 0, 0]), ("color_mid", "Mid:", [0, 255, 0]), ("color_high", "High:", [0, 0, 255])] {
+                                                        ui.horizontal(|ui| {
+                                                            ui.label(label);
+                                                            let mut color = ge.params.get(key).and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or(default);
+                                                            if color_picker(ui, &mut color, format!("ge_sb_{}_{}_{}", key, scene.id, eff_idx)) {
+                                                                ge.params.insert(key.into(), serde_json::json!([color[0], color[1], color[2]]));
+                                                            }
+                                                        });
+                                                    }
                                                 } else { // Rainbow / Default
                                                     let mut speed = ge.params.get("speed").and_then(|v| v.as_f64()).unwrap_or(0.2);
                                                     if ui.add(egui::Slider::new(&mut speed, 0.05..=2.0).text("Speed")).changed() {
@@ -1679,11 +3319,76 @@ impl eframe::App for MyApp {
                                                     }
                                                     lfo_controls(ui, &mut ge.params, "speed", format!("spd_lfo"));
                                                 }
+                                                if let Err(err) = resolved.typed_params() {
+                                                    ui.colored_label(egui::Color32::RED, format!("⚠ {}", err));
+                                                }
+                                                for (key, value) in resolved.params {
+                                                    if base.params.get(&key) != Some(&value) {
+                                                        config.effect.params.insert(key, value);
+                                                    }
+                                                }
+                                                // Param automation: ramp a numeric param toward a target over time
+                                                let float_params: Vec<&str> = model::PARAM_RANGES.iter()
+                                                    .filter(|(k, _, r)| *k == base.kind && matches!(r, model::ParamRange::Float(..)))
+                                                    .map(|(_, p, _)| *p)
+                                                    .collect();
+                                                if !float_params.is_empty() {
+                                                    let mut delete_ramp_idx = None;
+                                                    for (ramp_idx, ramp) in config.effect.automation.iter_mut().enumerate() {
+                                                        ui.horizontal(|ui| {
+                                                            egui::ComboBox::from_id_source(format!("auto_param_{}_{}_{}", scene.id, eff_idx, ramp_idx))
+                                                                .selected_text(&ramp.param)
+                                                                .show_ui(ui, |ui| {
+                                                                    for p in &float_params {
+                                                                        ui.selectable_value(&mut ramp.param, p.to_string(), *p);
+                                                                    }
+                                                                });
+                                                            ui.label("→");
+                                                            ui.add(egui::DragValue::new(&mut ramp.target).speed(0.01));
+                                                            ui.label("over");
+                                                            let mut secs = ramp.duration_ms as f64 / 1000.0;
+                                                            if ui.add(egui::DragValue::new(&mut secs).speed(0.1).clamp_range(0.1..=600.0).suffix(" s")).changed() {
+                                                                ramp.duration_ms = (secs * 1000.0).round() as u64;
+                                                            }
+                                                            egui::ComboBox::from_id_source(format!("auto_curve_{}_{}_{}", scene.id, eff_idx, ramp_idx))
+                                                                .selected_text(if ramp.curve.is_empty() { "linear" } else { ramp.curve.as_str() })
+                                                                .show_ui(ui, |ui| {
+                                                                    for c in easing::CURVES {
+                                                                        ui.selectable_value(&mut ramp.curve, c.to_string(), *c);
+                                                                    }
+                                                                });
+                                                            egui::ComboBox::from_id_source(format!("auto_repeat_{}_{}_{}", scene.id, eff_idx, ramp_idx))
+                                                                .selected_text(if ramp.repeat.is_empty() { "loop" } else { ramp.repeat.as_str() })
+                                                                .show_ui(ui, |ui| {
+                                                                    for r in model::AUTOMATION_REPEATS {
+                                                                        ui.selectable_value(&mut ramp.repeat, r.to_string(), *r);
+                                                                    }
+                                                                });
+                                                            if ui.small_button("🗑").clicked() {
+                                                                delete_ramp_idx = Some(ramp_idx);
+                                                            }
+                                                        });
+                                                    }
+                                                    if let Some(idx) = delete_ramp_idx {
+                                                        config.effect.automation.remove(idx);
+                                                    }
+                                                    if ui.small_button("+ Automate").on_hover_text("Ramp a param toward a target, timed from scene start").clicked() {
+                                                        let param = float_params[0].to_string();
+                                                        let target = match model::param_range(&base.kind, &param) {
+                                                            Some(model::ParamRange::Float(_, max)) => max,
+                                                            _ => 1.0,
+                                                        };
+                                                        config.effect.automation.push(model::ParamAutomation { param, target, duration_ms: 4000, curve: String::new(), repeat: String::new() });
+                                                    }
+                                                }
                                             });
                                         });
                                     }
                                     if let Some(idx) = delete_effect_idx {
                                         scene.global_effects.remove(idx);
+                                        if self.effect_clipboard.is_some_and(|(id, _)| id == scene.id) {
+                                            self.effect_clipboard = None; // Indices shifted
+                                        }
                                     }
                                 } else {
                                     // Embedded Masks editor for this scene
@@ -1693,7 +3398,7 @@ impl eframe::App for MyApp {
                                             .selected_text("Add Mask...")
                                             .show_ui(ui, |ui| {
                                                 if ui.selectable_label(false, "Scanner").clicked() {
-                                                    let mut m = Mask { id: rand::random(), mask_type: "scanner".into(), x: 0.5, y: 0.5, params: std::collections::HashMap::new() };
+                                                    let mut m = Mask { id: rand::random(), mask_type: "scanner".into(), x: 0.5, y: 0.5, z: None, params: std::collections::HashMap::new(), modulation: Vec::new() };
                                                     m.params.insert("width".into(), 0.3.into());
                                                     m.params.insert("height".into(), 0.3.into());
                                                     m.params.insert("speed".into(), 1.0.into());
@@ -1701,13 +3406,22 @@ impl eframe::App for MyApp {
                                                     scene.masks.push(m);
                                                 }
                                                 if ui.selectable_label(false, "Radial").clicked() {
-                                                    let mut m = Mask { id: rand::random(), mask_type: "radial".into(), x: 0.5, y: 0.5, params: std::collections::HashMap::new() };
+                                                    let mut m = Mask { id: rand::random(), mask_type: "radial".into(), x: 0.5, y: 0.5, z: None, params: std::collections::HashMap::new(), modulation: Vec::new() };
                                                     m.params.insert("radius".into(), 0.2.into());
                                                     m.params.insert("color".into(), serde_json::json!([255, 0, 0]));
                                                     scene.masks.push(m);
                                                 }
+                                                if ui.selectable_label(false, "Wave").clicked() {
+                                                    let mut m = Mask { id: rand::random(), mask_type: "wave".into(), x: 0.5, y: 0.5, z: None, params: std::collections::HashMap::new(), modulation: Vec::new() };
+                                                    m.params.insert("wavelength".into(), 0.2.into());
+                                                    m.params.insert("speed".into(), 0.2.into());
+                                                    m.params.insert("amplitude".into(), 1.0.into());
+                                                    m.params.insert("angle".into(), 0.0.into());
+                                                    m.params.insert("color".into(), serde_json::json!([0, 255, 128]));
+                                                    scene.masks.push(m);
+                                                }
                                                 if ui.selectable_label(false, "Burst").clicked() {
-                                                    let mut m = Mask { id: rand::random(), mask_type: "burst".into(), x: 0.5, y: 0.5, params: std::collections::HashMap::new() };
+                                                    let mut m = Mask { id: rand::random(), mask_type: "burst".into(), x: 0.5, y: 0.5, z: None, params: std::collections::HashMap::new(), modulation: Vec::new() };
                                                     m.params.insert("base_radius".into(), 0.1.into());
                                                     m.params.insert("max_radius".into(), 0.5.into());
                                                     m.params.insert("sensitivity".into(), 0.5.into());
@@ -1716,7 +3430,7 @@ impl eframe::App for MyApp {
                                                     scene.masks.push(m);
                                                 }
                                                 if ui.selectable_label(false, "Orbit").clicked() {
-                                                    let mut m = Mask { id: rand::random(), mask_type: "orbit".into(), x: 0.5, y: 0.5, params: std::collections::HashMap::new() };
+                                                    let mut m = Mask { id: rand::random(), mask_type: "orbit".into(), x: 0.5, y: 0.5, z: None, params: std::collections::HashMap::new(), modulation: Vec::new() };
                                                     m.params.insert("width".into(), 0.3.into());
                                                     m.params.insert("height".into(), 0.3.into());
                                                     m.params.insert("bar_width".into(), 0.1.into());
@@ -1758,6 +3472,12 @@ impl eframe::App for MyApp {
                                             m.params.insert("rotation".into(), rotation.into());
                                             needs_save = true;
                                         }
+                                        // Sweep angle within the rectangle
+                                        let mut angle = m.params.get("angle").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+                                        if ui.add(egui::Slider::new(&mut angle, 0.0..=360.0).text("Sweep Angle").suffix("°")).changed() {
+                                            m.params.insert("angle".into(), angle.into());
+                                            needs_save = true;
+                                        }
                                     } else if m.mask_type == "radial" {
                                         let mut r = m.params.get("radius").and_then(|v| v.as_f64()).unwrap_or(0.2) as f32;
                                         if ui.add(egui::Slider::new(&mut r, 0.0..=5.0).text("Radius")).changed() {
@@ -1767,6 +3487,49 @@ impl eframe::App for MyApp {
                                         if lfo_controls(ui, &mut m.params, "radius", format!("radius_lfo_{}", m.id)) {
                                             needs_save = true;
                                         }
+                                        let mut stretch = m.params.get("stretch").and_then(|v| v.as_f64()).unwrap_or(1.0) as f32;
+                                        if ui.add(egui::Slider::new(&mut stretch, 0.1..=5.0).text("Stretch")).on_hover_text("Elongate the circle into an ellipse along Angle").changed() {
+                                            m.params.insert("stretch".into(), stretch.into());
+                                            needs_save = true;
+                                        }
+                                        let mut angle = m.params.get("angle").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+                                        if ui.add(egui::Slider::new(&mut angle, 0.0..=360.0).text("Angle").suffix("°")).changed() {
+                                            m.params.insert("angle".into(), angle.into());
+                                            needs_save = true;
+                                        }
+                                        ui.horizontal(|ui| {
+                                            let mut has_z = m.z.is_some();
+                                            if ui.checkbox(&mut has_z, "Depth").on_hover_text("Measure 3D distance to strips that have a Z").changed() {
+                                                m.z = if has_z { Some(0.0) } else { None };
+                                                needs_save = true;
+                                            }
+                                            if let Some(z) = &mut m.z {
+                                                if ui.add(egui::DragValue::new(z).speed(0.01).prefix("Z: ")).changed() {
+                                                    needs_save = true;
+                                                }
+                                            }
+                                        });
+                                    } else if m.mask_type == "wave" {
+                                        let mut wavelength = m.params.get("wavelength").and_then(|v| v.as_f64()).unwrap_or(0.2) as f32;
+                                        if ui.add(egui::Slider::new(&mut wavelength, 0.01..=2.0).text("Wavelength")).changed() {
+                                            m.params.insert("wavelength".into(), wavelength.into());
+                                            needs_save = true;
+                                        }
+                                        let mut speed = m.params.get("speed").and_then(|v| v.as_f64()).unwrap_or(0.2) as f32;
+                                        if ui.add(egui::Slider::new(&mut speed, -2.0..=2.0).text("Speed")).changed() {
+                                            m.params.insert("speed".into(), speed.into());
+                                            needs_save = true;
+                                        }
+                                        let mut amplitude = m.params.get("amplitude").and_then(|v| v.as_f64()).unwrap_or(1.0) as f32;
+                                        if ui.add(egui::Slider::new(&mut amplitude, 0.0..=1.0).text("Amplitude")).changed() {
+                                            m.params.insert("amplitude".into(), amplitude.into());
+                                            needs_save = true;
+                                        }
+                                        let mut angle = m.params.get("angle").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+                                        if ui.add(egui::Slider::new(&mut angle, 0.0..=360.0).text("Angle").suffix("°")).changed() {
+                                            m.params.insert("angle".into(), angle.into());
+                                            needs_save = true;
+                                        }
                                     } else if m.mask_type == "burst" {
                                         let mut base_r = m.params.get("base_radius").and_then(|v| v.as_f64()).unwrap_or(0.1) as f32;
                                         if ui.add(egui::Slider::new(&mut base_r, 0.0..=2.0).text("Base Radius")).changed() {
@@ -1916,6 +3679,72 @@ impl eframe::App for MyApp {
                                             needs_save = true;
                                         }
                                     }
+
+                                    // Audio Modulation
+                                    ui.collapsing(format!("Audio Modulation ({})", m.modulation.len()), |ui| {
+                                        let mut numeric: Vec<String> = m.params.iter()
+                                            .filter(|(_, v)| v.is_number())
+                                            .map(|(k, _)| k.clone())
+                                            .collect();
+                                        numeric.sort();
+
+                                        let mut remove_mod = None;
+                                        for (mi, md) in m.modulation.iter_mut().enumerate() {
+                                            ui.push_id(format!("mod_{}_{}", m.id, mi), |ui| {
+                                                ui.horizontal(|ui| {
+                                                    egui::ComboBox::from_id_source("param")
+                                                        .selected_text(md.param.clone())
+                                                        .show_ui(ui, |ui| {
+                                                            for p in &numeric {
+                                                                if ui.selectable_value(&mut md.param, p.clone(), p).changed() {
+                                                                    needs_save = true;
+                                                                }
+                                                            }
+                                                        });
+                                                    egui::ComboBox::from_id_source("source")
+                                                        .selected_text(md.source.clone())
+                                                        .show_ui(ui, |ui| {
+                                                            if ui.selectable_value(&mut md.source, "Level".into(), "Level").changed() { needs_save = true; }
+                                                            if ui.selectable_value(&mut md.source, "Band".into(), "Band").changed() { needs_save = true; }
+                                                        });
+                                                    if md.source == "Band" {
+                                                        egui::ComboBox::from_id_source("band")
+                                                            .selected_text(audio::BAND_NAMES.get(md.band).copied().unwrap_or("?"))
+                                                            .show_ui(ui, |ui| {
+                                                                for (band, name) in audio::BAND_NAMES.iter().enumerate() {
+                                                                    if ui.selectable_value(&mut md.band, band, *name).changed() {
+                                                                        needs_save = true;
+                                                                    }
+                                                                }
+                                                            });
+                                                    }
+                                                    if ui.small_button("-").clicked() {
+                                                        remove_mod = Some(mi);
+                                                    }
+                                                });
+                                                ui.horizontal(|ui| {
+                                                    if ui.add(egui::DragValue::new(&mut md.gain).speed(0.01).prefix("Gain: ")).changed() { needs_save = true; }
+                                                    if ui.add(egui::DragValue::new(&mut md.offset).speed(0.01).prefix("Offset: ")).changed() { needs_save = true; }
+                                                });
+                                            });
+                                        }
+                                        if let Some(mi) = remove_mod {
+                                            m.modulation.remove(mi);
+                                            needs_save = true;
+                                        }
+                                        if let Some(first) = numeric.first() {
+                                            if ui.button("+ Add Binding").clicked() {
+                                                m.modulation.push(model::AudioModulation {
+                                                    param: first.clone(),
+                                                    source: "Level".into(),
+                                                    band: 0,
+                                                    gain: 0.5,
+                                                    offset: 0.0,
+                                                });
+                                                needs_save = true;
+                                            }
+                                        }
+                                    });
                                     
                                     // Speed / Sync
                                     ui.horizontal(|ui| {
@@ -1927,9 +3756,17 @@ impl eframe::App for MyApp {
                                                         m.params.insert("sync".into(), is_sync.into());
                                                         needs_save = true;
                                                     }
-                                                    let mut uni = m.params.get("unidirectional").and_then(|v| v.as_bool()).unwrap_or(false);
-                                                    if ui.checkbox(&mut uni, "Uni").changed() {
-                                                        m.params.insert("unidirectional".into(), uni.into());
+                                                    // Edge behavior (replaces the old "Uni" toggle, which maps to Wrap)
+                                                    let mut edge_mode = scanner::edge_mode(&m.params).to_string();
+                                                    egui::ComboBox::from_id_source(format!("edge_{}", m.id))
+                                                        .selected_text(match edge_mode.as_str() { "wrap" => "Wrap", "clamp" => "Clamp", _ => "Bounce" })
+                                                        .show_ui(ui, |ui| {
+                                                            ui.selectable_value(&mut edge_mode, "bounce".into(), "Bounce");
+                                                            ui.selectable_value(&mut edge_mode, "wrap".into(), "Wrap");
+                                                            ui.selectable_value(&mut edge_mode, "clamp".into(), "Clamp");
+                                                        });
+                                                    if edge_mode != scanner::edge_mode(&m.params) {
+                                                        m.params.insert("edge_mode".into(), serde_json::json!(edge_mode));
                                                         needs_save = true;
                                                     }
                                                     
@@ -2065,6 +3902,18 @@ impl eframe::App for MyApp {
                             self.mark_state_changed();
                         }
 
+                        if let Some(id) = triggered_scene {
+                            let _ = self.command_sender.send(command::AppCommand::TriggerScene(id));
+                        }
+
+                        if let (Some(source), Some((target, params_only))) = (effect_clipboard, paste_effect) {
+                            if self.state.copy_effect(source, target, params_only) {
+                                self.mark_state_changed();
+                            } else {
+                                self.effect_clipboard = None; // Source effect was deleted
+                            }
+                        }
+
                         if let Some(i) = delete_scene_idx {
                             // Clear MIDI button before deleting scene
                             if let Some(scene) = self.state.scenes.get(i) {
@@ -2079,7 +3928,8 @@ impl eframe::App for MyApp {
                                     }
                                 }
                             }
-                            self.state.scenes.remove(i);
+                            let removed = self.state.scenes.remove(i);
+                            let _ = self.command_sender.send(command::AppCommand::DeactivateScene(removed.id));
                             self.mark_state_changed();
                         }
 
@@ -3246,10 +5096,16 @@ impl eframe::App for MyApp {
                              
                              // VISUALIZE SCANNER BAR
                              let t = self.engine.get_time();
+                             let edge_mode = scanner::edge_mode(&m.params);
+                             // A clamped sweep is timed from when the scene started
+                             let (bar_t, bar_beat) = if edge_mode == "clamp" {
+                                 self.engine.get_scene_elapsed(self.state.selected_scene_id)
+                             } else {
+                                 (t, self.engine.get_beat())
+                             };
                              
                              let is_sync = m.params.get("sync").and_then(|v| v.as_bool()).unwrap_or(false);
                              let phase = if is_sync {
-                                 let beat = self.engine.get_beat();
                                  let rate_str = m.params.get("rate").and_then(|v| v.as_str()).unwrap_or("1/4");
                                  let divisor = match rate_str {
                                      "4 Bar" => 16.0,
@@ -3266,25 +5122,14 @@ impl eframe::App for MyApp {
                                     "Left" => 0.75,
                                     _ => 0.0,
                                  };
-                                 (beat / divisor + offset) * std::f64::consts::PI * 2.0
+                                 (bar_beat / divisor + offset) * std::f64::consts::PI * 2.0
                              } else {
-                                 (t * speed_param * self.engine.speed) as f64
+                                 (bar_t * speed_param * self.engine.speed) as f64
                              };
                              
-                             // Motion Easing
                              // Motion Easing
                              let motion = m.params.get("motion").and_then(|v| v.as_str()).unwrap_or("Smooth");
-                             let unidirectional = m.params.get("unidirectional").and_then(|v| v.as_bool()).unwrap_or(false);
-
-                             let osc_val = if unidirectional {
-                                  let norm_phase = (phase / (std::f64::consts::PI * 2.0)).fract();
-                                  let p = if norm_phase < 0.0 { norm_phase + 1.0 } else { norm_phase };
-                                  p * 2.0 - 1.0
-                             } else if motion == "Linear" {
-                                 (2.0 / std::f64::consts::PI) * (phase.sin().asin())
-                             } else {
-                                 phase.sin()
-                             };
+                             let osc_val = scanner::bar_position(phase, edge_mode, motion);
 
                              // Offset of bar center in NORMALIZED units
                              let offset_x_n = (w / 2.0) * osc_val as f32;
@@ -3510,6 +5355,13 @@ impl eframe::App for MyApp {
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
         // Save state when app is closing
         self.save_state();
+        // Leave the Launchpad dark; give the service a moment to send that before the process ends
+        if let Some(service) = self.midi_service.take() {
+            service.shutdown(true);
+            if !service.join_timeout(Duration::from_millis(500)) {
+                log::warn!("MIDI service still running at exit");
+            }
+        }
     }
 }
 // Simple RGB color picker helper with Hex Input
@@ -3554,6 +5406,44 @@ fn color_picker(ui: &mut egui::Ui, rgb: &mut [u8; 3], id_source: impl std::hash:
     changed
 }
 
+/// Single-line editor for a comma-separated list. Keeps the raw text while focused so
+/// separators can be typed; returns the new text whenever it changes.
+fn comma_list_edit(ui: &mut egui::Ui, current: String, id_source: impl std::hash::Hash, hint: &str, hover: &str) -> Option<String> {
+    let id = ui.make_persistent_id(id_source);
+    let mut text = ui.data_mut(|d| d.get_temp::<String>(id)).unwrap_or(current);
+    let resp = ui.add(egui::TextEdit::singleline(&mut text).id(id).hint_text(hint).desired_width(100.0))
+        .on_hover_text(hover);
+    if resp.has_focus() {
+        ui.data_mut(|d| d.insert_temp(id, text.clone()));
+    } else {
+        ui.data_mut(|d| d.remove::<String>(id));
+    }
+    resp.changed().then_some(text)
+}
+
+/// Picker for a random effect's `sync_mode`: "per_strip" (each strip its own random
+/// stream) or "shared" (every targeted strip shows the same pattern)
+fn sync_mode_combo(
+    ui: &mut egui::Ui,
+    params: &mut std::collections::HashMap<String, serde_json::Value>,
+    id_source: impl std::hash::Hash,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Strips:");
+        let mut mode = params.get("sync_mode").and_then(|v| v.as_str().map(String::from)).unwrap_or("per_strip".into());
+        let before = mode.clone();
+        egui::ComboBox::from_id_source(id_source)
+            .selected_text(if mode == "shared" { "Shared" } else { "Per Strip" })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut mode, "per_strip".into(), "Per Strip").on_hover_text("Every strip sparkles independently");
+                ui.selectable_value(&mut mode, "shared".into(), "Shared").on_hover_text("All targeted strips show the same sparkles in sync");
+            });
+        if mode != before {
+            params.insert("sync_mode".into(), mode.into());
+        }
+    });
+}
+
 /// Renders LFO controls for a given parameter
 /// Returns true if any value changed
 fn lfo_controls(
@@ -3660,6 +5550,18 @@ fn lfo_controls(
     changed
 }
 
+/// Launchpad palette color of the beat pad's downbeat flash (green)
+const BEAT_PAD_COLOR: u8 = 21;
+
+/// Command for a scene pad press: momentary scenes hold until release, others latch
+fn scene_pad_command(scene: &model::Scene) -> command::AppCommand {
+    if scene.launchpad_momentary {
+        command::AppCommand::HoldScene(scene.id)
+    } else {
+        command::AppCommand::TriggerScene(scene.id)
+    }
+}
+
 // Helper for Launchpad Color Picker
 fn launchpad_color_picker_ui(ui: &mut egui::Ui, current_color: &mut u8) -> bool {
     let mut changed = false;
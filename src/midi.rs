@@ -1,14 +1,75 @@
-use midir::{Ignore, MidiInput, MidiOutput, MidiInputPort, MidiOutputPort};
+use crate::model::{MidiInitConfig, MidiPortFilter, MidiPortSelection};
+use midir::{Ignore, MidiInput, MidiOutput, MidiInputPort, MidiOutputConnection, MidiOutputPort, SendError};
 use std::error::Error;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
 use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the mode readback after each init attempt
+const INIT_ACK_TIMEOUT_MS: u64 = 500;
+/// Taps further apart than this start a new estimate
+const TAP_RESET_US: u64 = 2_000_000;
+/// Number of recent inter-tap intervals averaged into the estimate
+const TAP_HISTORY: usize = 4;
+/// Largest SysEx packet composed for bulk LED updates
+const MAX_SYSEX_LEN: usize = 320;
+/// MIDI clock pulses per quarter note
+const CLOCK_PPQN: usize = 24;
+/// Clock pulses further apart than this (under 5 BPM) mean the clock paused
+const CLOCK_RESET_US: u64 = 500_000;
 
 pub enum MidiEvent {
     NoteOn { note: u8, velocity: u8 },
+    /// Pad released: NoteOff, or NoteOn with velocity 0
+    NoteOff { note: u8 },
+    /// Button press (value > 0) or release (value 0)
     ControlChange { controller: u8, value: u8 },
+    /// The tap-tempo pad produced a new estimate
+    TapTempo { bpm: f64 },
+    /// MIDI clock tempo, reported once per quarter note while clock pulses arrive
+    Clock { bpm: f64 },
+    /// MIDI Start (FAh): the sender's song restarted at its first beat
+    ClockStart,
+    /// MIDI Stop (FCh)
+    ClockStop,
+    /// Ports were handed to the worker and it is opening them
+    Connecting { input: String, output: String },
+    /// First successful connection since the service started
     Connected,
+    /// Connected again after an earlier connection dropped or was closed
+    Reconnected,
     Disconnected,
+    /// The connection failed or dropped; the message is suitable for display
+    Error(String),
+    /// Connection phase changed; drives the UI status indicator
+    Phase(ConnectionPhase),
+    /// Port names found by the latest enumeration, for manual selection
+    PortsAvailable { inputs: Vec<String>, outputs: Vec<String> },
+    /// The MIDI backend itself cannot be opened (e.g. no ALSA sequencer on a headless box).
+    /// Terminal: the service has exited and no further events follow.
+    Unavailable(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionPhase {
+    Searching,
+    Connecting,
+    Connected,
+    /// The previous connection failed or dropped; `attempt` counts consecutive failures
+    Retrying { attempt: u32 },
+}
+
+impl std::fmt::Display for ConnectionPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConnectionPhase::Searching => write!(f, "Searching..."),
+            ConnectionPhase::Connecting => write!(f, "Connecting..."),
+            ConnectionPhase::Connected => write!(f, "Connected"),
+            ConnectionPhase::Retrying { attempt } => write!(f, "Retrying (attempt {})", attempt),
+        }
+    }
 }
 
 pub struct MidiConnectionPayload {
@@ -16,18 +77,413 @@ pub struct MidiConnectionPayload {
     pub midi_out: MidiOutput,
     pub in_port: MidiInputPort,
     pub out_port: MidiOutputPort,
+    pub model: LaunchpadModel,
+    pub init: MidiInitConfig,
+    /// Probe the output this often so a silently dead device errors and reconnects; None = off
+    pub heartbeat_ms: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchpadModel {
+    MiniMk3,
+    X,
+    ProMk3,
+}
+
+impl LaunchpadModel {
+    pub const ALL: [LaunchpadModel; 3] = [LaunchpadModel::MiniMk3, LaunchpadModel::X, LaunchpadModel::ProMk3];
+
+    /// Stable key used for per-model settings in `AppState::midi_init`
+    pub fn name(&self) -> &'static str {
+        match self {
+            LaunchpadModel::MiniMk3 => "MiniMk3",
+            LaunchpadModel::X => "X",
+            LaunchpadModel::ProMk3 => "ProMk3",
+        }
+    }
+
+    /// Novation device ID byte in this model's SysEx header (F0h 00h 20h 29h 02h <id> ...)
+    fn sysex_device_id(&self) -> u8 {
+        match self {
+            LaunchpadModel::MiniMk3 => 0x0D,
+            LaunchpadModel::X => 0x0C,
+            LaunchpadModel::ProMk3 => 0x0E,
+        }
+    }
+
+    /// Fragment of the model's MIDI (non-DAW) port name, e.g. "LPX MIDI"
+    fn port_tag(&self) -> &'static str {
+        match self {
+            LaunchpadModel::MiniMk3 => "LPMiniMK3 MIDI",
+            LaunchpadModel::X => "LPX MIDI",
+            LaunchpadModel::ProMk3 => "LPProMK3 MIDI",
+        }
+    }
+
+    /// Whether the model takes the bulk "Light LED" SysEx (F0h 00h 20h 29h 02h <id> 03h
+    /// <colorspec>... F7h); every supported model does
+    pub fn supports_bulk_lighting(&self) -> bool {
+        true
+    }
+
+    /// Bulk SysEx packets setting each `(led, color)` to a static palette color, split to
+    /// stay within `MAX_SYSEX_LEN`. `led` is the pad note or button CC. None if the model
+    /// has no bulk lighting.
+    pub fn led_sysex(&self, leds: &[(u8, u8)]) -> Option<Vec<Vec<u8>>> {
+        if !self.supports_bulk_lighting() {
+            return None;
+        }
+        // Colorspec: lighting type (0 = static), LED index, palette color. The device takes
+        // at most one colorspec per LED it has in a single message.
+        let per_packet = ((MAX_SYSEX_LEN - 8) / 3).min(self.all_leds().len());
+        let packets = leds
+            .chunks(per_packet)
+            .map(|chunk| {
+                let specs: Vec<u8> = chunk.iter().flat_map(|(led, color)| [0x00, *led, *color]).collect();
+                self.led_packet(&specs)
+            })
+            .collect();
+        Some(packets)
+    }
+
+    /// Whether the model takes RGB colorspecs (lighting type 3) in the bulk LED SysEx
+    pub fn supports_rgb_lighting(&self) -> bool {
+        match self {
+            LaunchpadModel::MiniMk3 | LaunchpadModel::X | LaunchpadModel::ProMk3 => true,
+        }
+    }
+
+    /// SysEx lighting `led` with an arbitrary 8-bit RGB color, or None if the model has no
+    /// RGB lighting
+    pub fn rgb_led_sysex(&self, led: u8, [r, g, b]: [u8; 3]) -> Option<Vec<u8>> {
+        if !self.supports_rgb_lighting() {
+            return None;
+        }
+        // Colorspec: lighting type 3 (RGB), LED index, 7-bit red, green, blue
+        Some(self.led_packet(&[0x03, led, rgb_to_7bit(r), rgb_to_7bit(g), rgb_to_7bit(b)]))
+    }
+
+    /// SysEx pulsing `led` in a palette color (lighting type 2), or None without bulk lighting.
+    /// Pulsing runs until the LED gets a static color again.
+    pub fn pulse_led_sysex(&self, led: u8, color: u8) -> Option<Vec<u8>> {
+        self.supports_bulk_lighting().then(|| self.led_packet(&[0x02, led, color]))
+    }
+
+    /// SysEx flashing `led` between palette colors `color_a` and `color_b` (lighting type 1),
+    /// or None without bulk lighting. Runs until the LED gets a static color again.
+    pub fn flash_led_sysex(&self, led: u8, color_a: u8, color_b: u8) -> Option<Vec<u8>> {
+        // Colorspec data is color B (shown off-beat) then color A
+        self.supports_bulk_lighting().then(|| self.led_packet(&[0x01, led, color_b, color_a]))
+    }
+
+    /// One "Light LED" SysEx carrying `specs` (concatenated colorspecs)
+    fn led_packet(&self, specs: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0xF0, 0x00, 0x20, 0x29, 0x02, self.sysex_device_id(), 0x03];
+        packet.extend_from_slice(specs);
+        packet.push(0xF7);
+        packet
+    }
+
+    /// Every pad note and button CC, i.e. all LEDs the model lights in programmer mode
+    pub fn all_leds(&self) -> Vec<u8> {
+        (0..=127).filter(|i| self.is_pad_note(*i) || self.is_button_cc(*i)).collect()
+    }
+
+    /// Pads across and down. Every supported model has an 8x8 grid.
+    pub fn grid_size(&self) -> (u8, u8) {
+        (GRID_SIZE, GRID_SIZE)
+    }
+
+    /// CC number of a status LED, or None if this model doesn't have it
+    pub fn status_led_cc(&self, led: StatusLed) -> Option<u8> {
+        match (self, led) {
+            (LaunchpadModel::MiniMk3 | LaunchpadModel::X, StatusLed::Logo) => Some(99),
+            (_, StatusLed::TopRow(i)) if i < 8 => Some(91 + i),
+            _ => None,
+        }
+    }
+
+    /// Whether `note` addresses a grid pad in programmer mode
+    pub fn is_pad_note(&self, note: u8) -> bool {
+        // All models: rows 1-8 bottom to top, columns 1-8 left to right: 11..=88
+        (1..=8).contains(&(note / 10)) && (1..=8).contains(&(note % 10))
+    }
+
+    /// Whether `cc` addresses a button or LED outside the grid in programmer mode
+    pub fn is_button_cc(&self, cc: u8) -> bool {
+        let right_column = (1..=8).contains(&(cc / 10)) && cc % 10 == 9;
+        match self {
+            // Top row 91-98, right column 19-89, logo 99
+            LaunchpadModel::MiniMk3 | LaunchpadModel::X => (91..=99).contains(&cc) || right_column,
+            // Setup 90, top row 91-98, left column 10-80, right column 19-89,
+            // bottom rows 101-108 and 1-8
+            LaunchpadModel::ProMk3 => {
+                let left_column = (1..=8).contains(&(cc / 10)) && cc % 10 == 0;
+                (90..=98).contains(&cc) || right_column || left_column || (101..=108).contains(&cc) || (1..=8).contains(&cc)
+            }
+        }
+    }
+
+    /// Profile for a detected port name. Unknown Launchpads get the Mini MK3 profile.
+    pub fn from_port_name(name: &str) -> Self {
+        let model = if name.contains("LPProMK3") || name.contains("Pro MK3") {
+            Some(LaunchpadModel::ProMk3)
+        } else if name.contains("LPX") || name.contains("Launchpad X") {
+            Some(LaunchpadModel::X)
+        } else if name.contains("LPMiniMK3") || name.contains("Mini MK3") {
+            Some(LaunchpadModel::MiniMk3)
+        } else {
+            None
+        };
+        match model {
+            Some(model) => {
+                log::info!("Using {} profile for '{}'", model.name(), name);
+                model
+            }
+            None => {
+                log::warn!("Unrecognized Launchpad '{}', falling back to the MiniMk3 profile", name);
+                LaunchpadModel::MiniMk3
+            }
+        }
+    }
+
+    /// Programmer-mode handshake for this model
+    pub fn default_init(&self) -> MidiInitConfig {
+        let id = self.sysex_device_id();
+        MidiInitConfig {
+            // Select programmer layout: F0h 00h 20h 29h 02h <id> 0Eh 01h F7h
+            sysex: vec![0xF0, 0x00, 0x20, 0x29, 0x02, id, 0x0E, 0x01, 0xF7],
+            // Mode readback; the device answers with the programmer-mode sysex above
+            query: vec![0xF0, 0x00, 0x20, 0x29, 0x02, id, 0x0E, 0xF7],
+            settle_ms: 200,
+            max_attempts: 3,
+        }
+    }
+}
+
+/// Scale an 8-bit color channel to the 0-127 range of SysEx data bytes
+pub fn rgb_to_7bit(value: u8) -> u8 {
+    value >> 1
+}
+
+/// Grid size of every supported model
+pub const GRID_SIZE: u8 = 8;
+
+/// Grid coordinates of a pad note: x = column left to right, y = row top to bottom, both 0..8.
+/// None for notes outside the grid.
+pub fn note_to_xy(note: u8, model: LaunchpadModel) -> Option<(u8, u8)> {
+    if !model.is_pad_note(note) {
+        return None;
+    }
+    // Programmer mode numbers rows bottom to top: row*10+col, both 1-based
+    let (_, rows) = model.grid_size();
+    Some((note % 10 - 1, rows - note / 10))
+}
+
+/// Pad note at grid coordinates (see `note_to_xy`); coordinates past the edge are clamped onto it
+pub fn xy_to_note((x, y): (u8, u8), model: LaunchpadModel) -> u8 {
+    let (cols, rows) = model.grid_size();
+    let (x, y) = (x.min(cols - 1), y.min(rows - 1));
+    (rows - y) * 10 + x + 1
+}
+
+/// LEDs outside the 8x8 grid, addressed per model in programmer mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatusLed {
+    Logo,
+    TopRow(u8), // 0..8, left to right
+}
+
+/// Lighting mode, selected by the MIDI channel of the color message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedMode {
+    Static,
+    Flash, // Alternates with the static color, synced to MIDI clock
+    Pulse, // Breathes the given color
+}
+
+impl LedMode {
+    fn channel(&self) -> u8 {
+        match self {
+            LedMode::Static => 0,
+            LedMode::Flash => 1,
+            LedMode::Pulse => 2,
+        }
+    }
 }
 
 pub enum MidiCommand {
     SetPadColor { note: u8, color: u8 },
+    /// Light a pad with an arbitrary RGB color; ignored on models without RGB lighting
+    SetPadRgb { note: u8, r: u8, g: u8, b: u8 },
+    /// Pulse a pad in a palette color. Pulsing and flashing follow the Launchpad's own clock
+    /// (MIDI clock it receives, else 120 BPM) and keep going until the pad is given a static
+    /// color again with `SetPadColor`/`SetGrid`.
+    PulsePad { note: u8, color: u8 },
+    /// Flash a pad between two palette colors, timed like `PulsePad`
+    FlashPad { note: u8, color_a: u8, color_b: u8 },
     SetButtonColor { cc: u8, color: u8 },
+    SetStatusLed { led: StatusLed, color: u8, mode: LedMode },
+    /// Set many LEDs at once: (pad note or button CC, palette color) pairs. Sent as bulk
+    /// SysEx where the model supports it, so the layout changes in one go.
+    SetGrid { colors: Vec<(u8, u8)> },
+    /// Designate the pad (note, or CC when `is_cc`) used for tap tempo; None disables it
+    SetTapPad { btn: Option<u8>, is_cc: bool },
+    /// Drop NoteOn events softer than this before they reach the app (0/1 = pass all)
+    SetMinVelocity(u8),
+    /// Drop a pad's NoteOn repeating its previous press within this many ms (0 = off)
+    SetDebounceMs(u64),
     ClearAll,
     Connect(Box<MidiConnectionPayload>),
     Disconnect,
+    /// Pin the service to these ports (exact names) instead of detecting a Launchpad.
+    /// A connection to other ports is closed.
+    SelectPorts { input: String, output: String },
+    /// Drop the pinned ports and go back to automatic detection
+    AutoSelectPorts,
+    /// The user asked for a device refresh: forget past failures and report `Searching`
+    /// so the app's next detection pass runs right away. An open connection is kept.
+    Rescan,
+    /// Close any open connection and end the service thread; the sender is dead afterwards.
+    /// With `blackout`, the device's pads and buttons are switched off first.
+    Shutdown { blackout: bool },
+}
+
+/// Owner's side of a running MIDI service, for stopping it when MIDI is disabled or the app exits
+pub struct MidiServiceHandle {
+    tx_cmd: Sender<MidiCommand>,
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl MidiServiceHandle {
+    /// Ask the service to exit without waiting for it. Also cuts a connection handshake short.
+    pub fn shutdown(&self, blackout: bool) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = self.tx_cmd.send(MidiCommand::Shutdown { blackout });
+    }
+
+    /// Wait up to `timeout` for the service thread to exit; true if it did
+    pub fn join_timeout(self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while !self.thread.is_finished() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        let _ = self.thread.join();
+        true
+    }
+}
+
+/// Per-note press times for dropping double-fired pad presses
+pub struct NoteDebounce {
+    last_press: [Option<u64>; 128], // MIDI timestamp (µs) of the last accepted NoteOn
+}
+
+impl Default for NoteDebounce {
+    fn default() -> Self {
+        Self { last_press: [None; 128] }
+    }
+}
+
+impl NoteDebounce {
+    /// False for a NoteOn arriving within `window_ms` of the previous accepted press of the
+    /// same note. Releases clear the note, so the next press always passes.
+    pub fn accept(&mut self, message: &[u8], stamp: u64, window_ms: u64) -> bool {
+        let [status, note, velocity, ..] = *message else { return true };
+        let last = &mut self.last_press[(note & 0x7F) as usize];
+        match status & 0xF0 {
+            0x80 => *last = None,
+            0x90 if velocity == 0 => *last = None,
+            0x90 => {
+                if window_ms > 0 && last.is_some_and(|t| stamp.saturating_sub(t) < window_ms * 1000) {
+                    return false;
+                }
+                *last = Some(stamp);
+            }
+            _ => {}
+        }
+        true
+    }
+}
+
+/// BPM estimate from repeated taps, averaged over the last few intervals
+#[derive(Default)]
+pub struct TapTempo {
+    last_tap_us: Option<u64>,
+    intervals: Vec<u64>,
+}
+
+impl TapTempo {
+    /// Register a tap at `stamp_us` (microseconds, monotonic).
+    /// Returns the new estimate once at least two taps are close enough together.
+    pub fn tap(&mut self, stamp_us: u64) -> Option<f64> {
+        let last = self.last_tap_us.replace(stamp_us);
+        let gap = stamp_us.saturating_sub(last?);
+        if gap == 0 || gap > TAP_RESET_US {
+            self.intervals.clear();
+            return None;
+        }
+        self.intervals.push(gap);
+        if self.intervals.len() > TAP_HISTORY {
+            self.intervals.remove(0);
+        }
+        let avg_us = self.intervals.iter().sum::<u64>() as f64 / self.intervals.len() as f64;
+        Some(60_000_000.0 / avg_us)
+    }
+}
+
+/// A Launchpad's MIDI (non-DAW) port. Some platforms drop the "Launchpad" prefix
+/// and name it only by the model tag, e.g. "LPX MIDI".
+pub fn is_launchpad_midi_port(name: &str) -> bool {
+    (name.contains("Launchpad") && name.contains("MIDI")) || LaunchpadModel::ALL.iter().any(|m| name.contains(m.port_tag()))
+}
+
+/// BPM estimate from MIDI clock (F8h) pulses, averaged over the last quarter note
+#[derive(Default)]
+pub struct MidiClock {
+    last_pulse_us: Option<u64>,
+    intervals: Vec<u64>,
+    pulses: usize, // Since the last reset; estimates are reported on quarter-note boundaries
+}
+
+impl MidiClock {
+    /// Register a clock pulse at `stamp_us` (microseconds, monotonic). Returns the new
+    /// estimate on each quarter note once a full quarter note of pulses has been timed.
+    pub fn pulse(&mut self, stamp_us: u64) -> Option<f64> {
+        if let Some(last) = self.last_pulse_us.replace(stamp_us) {
+            let gap = stamp_us.saturating_sub(last);
+            if gap == 0 || gap > CLOCK_RESET_US {
+                self.intervals.clear();
+                self.pulses = 0;
+            } else {
+                self.intervals.push(gap);
+                if self.intervals.len() > CLOCK_PPQN {
+                    self.intervals.remove(0);
+                }
+            }
+        }
+        self.pulses += 1;
+        if self.intervals.len() < CLOCK_PPQN || self.pulses % CLOCK_PPQN != 0 {
+            return None;
+        }
+        let avg_us = self.intervals.iter().sum::<u64>() as f64 / self.intervals.len() as f64;
+        Some(60_000_000.0 / (avg_us * CLOCK_PPQN as f64))
+    }
+
+    /// Forget all timing, on Start/Stop
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
 }
 
 // Detection Function (Runs on Main Thread)
-pub fn detect_launchpad() -> Option<MidiConnectionPayload> {
+/// Find the ports to connect to: the `pinned` pair when set (reporting an `Error` when either
+/// is missing), otherwise the first ports passing `filter`, or the best-matching Launchpad when
+/// there is none. Reports the ports seen as `PortsAvailable`.
+pub fn detect_launchpad(pinned: Option<&MidiPortSelection>, filter: Option<&MidiPortFilter>, tx_event: &Sender<MidiEvent>) -> Option<MidiConnectionPayload> {
     // Create new instances (Safe to do on Main Thread)
     // Using a more generic name for reuse if needed, or specific to detection
     let mut midi_in = MidiInput::new("Lightspeed Input").ok()?;
@@ -36,41 +492,87 @@ pub fn detect_launchpad() -> Option<MidiConnectionPayload> {
 
     let in_ports = midi_in.ports();
     let out_ports = midi_out.ports();
+    let _ = tx_event.send(MidiEvent::PortsAvailable {
+        inputs: in_ports.iter().filter_map(|p| midi_in.port_name(p).ok()).collect(),
+        outputs: out_ports.iter().filter_map(|p| midi_out.port_name(p).ok()).collect(),
+    });
 
-    // Find Input - STRICT: only use ports with valid, readable names
-    // 1. Prefer "Launchpad" AND "MIDI"
-    // 2. Prefer "Launchpad" AND NOT "DAW"
-    // 3. Fallback to any "Launchpad"
+    if log::log_enabled!(log::Level::Debug) {
+        for p in &in_ports {
+            log::debug!("MIDI input port: {}", midi_in.port_name(p).unwrap_or_else(|_| "<unreadable>".into()));
+        }
+        for p in &out_ports {
+            log::debug!("MIDI output port: {}", midi_out.port_name(p).unwrap_or_else(|_| "<unreadable>".into()));
+        }
+    }
 
-    let lp_in = in_ports.iter().find(|p| {
-        let Ok(name) = midi_in.port_name(p) else { return false; };
-        name.contains("Launchpad") && (name.contains("MIDI") || name.contains("LPMiniMK3 MIDI"))
-    }).or_else(|| {
-        in_ports.iter().find(|p| {
-            let Ok(name) = midi_in.port_name(p) else { return false; };
-            name.contains("Launchpad") && !name.contains("DAW")
-        })
-    }).or_else(|| {
-        in_ports.iter().find(|p| {
+    if let Some(pinned) = pinned {
+        let in_port = in_ports.iter().find(|p| midi_in.port_name(p).is_ok_and(|name| name == pinned.input));
+        let out_port = out_ports.iter().find(|p| midi_out.port_name(p).is_ok_and(|name| name == pinned.output));
+        let (in_port, out_port) = match (in_port, out_port) {
+            (Some(in_port), Some(out_port)) => (in_port.clone(), out_port.clone()),
+            (None, _) => {
+                let _ = tx_event.send(MidiEvent::Error(format!("Selected MIDI input '{}' not found", pinned.input)));
+                return None;
+            }
+            (_, None) => {
+                let _ = tx_event.send(MidiEvent::Error(format!("Selected MIDI output '{}' not found", pinned.output)));
+                return None;
+            }
+        };
+        let model = LaunchpadModel::from_port_name(&pinned.output);
+        return Some(MidiConnectionPayload {
+            midi_in,
+            midi_out,
+            in_port,
+            out_port,
+            model,
+            init: model.default_init(),
+            heartbeat_ms: None,
+        });
+    }
+
+    let (lp_in, lp_out) = if let Some(filter) = filter {
+        let lp_in = in_ports.iter().find(|p| midi_in.port_name(p).is_ok_and(|name| filter.matches(&name)));
+        let lp_out = out_ports.iter().find(|p| midi_out.port_name(p).is_ok_and(|name| filter.matches(&name)));
+        (lp_in, lp_out)
+    } else {
+        // Find Input - STRICT: only use ports with valid, readable names
+        // 1. Prefer "Launchpad" AND "MIDI"
+        // 2. Prefer "Launchpad" AND NOT "DAW"
+        // 3. Fallback to any "Launchpad"
+
+        let lp_in = in_ports.iter().find(|p| {
             let Ok(name) = midi_in.port_name(p) else { return false; };
-            name.contains("Launchpad")
-        })
-    });
+            is_launchpad_midi_port(&name)
+        }).or_else(|| {
+            in_ports.iter().find(|p| {
+                let Ok(name) = midi_in.port_name(p) else { return false; };
+                name.contains("Launchpad") && !name.contains("DAW")
+            })
+        }).or_else(|| {
+            in_ports.iter().find(|p| {
+                let Ok(name) = midi_in.port_name(p) else { return false; };
+                name.contains("Launchpad")
+            })
+        });
 
-    let lp_out = out_ports.iter().find(|p| {
-        let Ok(name) = midi_out.port_name(p) else { return false; };
-        name.contains("Launchpad") && (name.contains("MIDI") || name.contains("LPMiniMK3 MIDI"))
-    }).or_else(|| {
-        out_ports.iter().find(|p| {
-            let Ok(name) = midi_out.port_name(p) else { return false; };
-            name.contains("Launchpad") && !name.contains("DAW")
-        })
-    }).or_else(|| {
-        out_ports.iter().find(|p| {
+        let lp_out = out_ports.iter().find(|p| {
             let Ok(name) = midi_out.port_name(p) else { return false; };
-            name.contains("Launchpad")
-        })
-    });
+            is_launchpad_midi_port(&name)
+        }).or_else(|| {
+            out_ports.iter().find(|p| {
+                let Ok(name) = midi_out.port_name(p) else { return false; };
+                name.contains("Launchpad") && !name.contains("DAW")
+            })
+        }).or_else(|| {
+            out_ports.iter().find(|p| {
+                let Ok(name) = midi_out.port_name(p) else { return false; };
+                name.contains("Launchpad")
+            })
+        });
+        (lp_in, lp_out)
+    };
 
     if let (Some(in_port), Some(out_port)) = (lp_in, lp_out) {
         // Clone ports because we need to move them into the payload
@@ -79,96 +581,297 @@ pub fn detect_launchpad() -> Option<MidiConnectionPayload> {
         // Checking docs or assumption: MidiPort usually implements Clone.
         // If not, we have a problem because iter returns references.
         // But midir::MidiPort IS Clone.
+        let model = LaunchpadModel::from_port_name(&midi_out.port_name(out_port).unwrap_or_default());
         return Some(MidiConnectionPayload {
             midi_in,
             midi_out,
             in_port: in_port.clone(),
             out_port: out_port.clone(),
+            model,
+            init: model.default_init(),
+            heartbeat_ms: None,
         });
     }
 
     None
 }
 
-pub fn start_midi_service(tx_to_app: Sender<MidiEvent>) -> Sender<MidiCommand> {
+pub fn start_midi_service(tx_to_app: Sender<MidiEvent>) -> (Sender<MidiCommand>, MidiServiceHandle) {
     let (tx_cmd, rx_cmd) = std::sync::mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_service = stop.clone();
 
-    thread::spawn(move || {
-        println!("MIDI Background Service Started");
+    let thread = thread::spawn(move || {
+        log::info!("MIDI background service started");
+        // No device is a normal, retryable state; no backend at all will never recover
+        if let Err(reason) = probe_backend() {
+            log::warn!("MIDI backend unavailable, stopping MIDI service: {}", reason);
+            let _ = tx_to_app.send(MidiEvent::Unavailable(reason));
+            return;
+        }
+        let _ = tx_to_app.send(MidiEvent::Phase(ConnectionPhase::Searching));
+        let mut failures = 0;
+        let mut pinned: Option<MidiPortSelection> = None;
+        // Links that reached Connected; all but the first are reported as Reconnected
+        let mut connections: u32 = 0;
         
         loop {
             // Wait for a Connect command
             // We can block here because we have nothing else to do until we connect
             match rx_cmd.recv() {
                 Ok(MidiCommand::Connect(payload)) => {
-                    println!("Received MIDI connection payload. Connecting...");
+                    log::debug!("Received MIDI connection payload");
+                    if let Some(pinned) = &pinned {
+                        if !payload_matches(&payload, pinned) {
+                            // Detected before the selection changed; the next detection uses the new ports
+                            log::debug!("Ignoring connection to ports other than the selected {} / {}", pinned.input, pinned.output);
+                            let _ = tx_to_app.send(MidiEvent::Disconnected);
+                            continue;
+                        }
+                    }
                     
                     // Unbox and run the loop with the PRE-EXISTING instances
+                    let connections_before = connections;
+                    let mut shutdown = false;
                     let res = run_midi_loop(
                         &tx_to_app, 
                         &rx_cmd, 
-                        *payload 
+                        *payload,
+                        &stop_service,
+                        &mut pinned,
+                        &mut connections,
+                        &mut shutdown,
                     );
+                    if connections > connections_before {
+                        failures = 0;
+                    }
                     
-                    if let Err(e) = res {
-                        println!("MIDI Loop ended with error: {:?}", e);
-                        let _ = tx_to_app.send(MidiEvent::Disconnected);
+                    let phase = match res {
+                        Ok(()) => {
+                            log::info!("MIDI connection closed");
+                            ConnectionPhase::Searching
+                        }
+                        Err(e) => {
+                            log::warn!("MIDI loop ended with error: {}", e);
+                            let _ = tx_to_app.send(MidiEvent::Error(e.to_string()));
+                            failures += 1;
+                            ConnectionPhase::Retrying { attempt: failures }
+                        }
+                    };
+                    let _ = tx_to_app.send(MidiEvent::Disconnected);
+                    if shutdown {
+                        break;
                     }
+                    let _ = tx_to_app.send(MidiEvent::Phase(phase));
                     
                     // After disconnect/error, go back to top of loop waiting for new connection
                 },
+                Ok(MidiCommand::Shutdown { .. }) => break,
+                Ok(MidiCommand::SelectPorts { input, output }) => {
+                    log::info!("MIDI ports pinned to {} / {}", input, output);
+                    pinned = Some(MidiPortSelection { input, output });
+                    failures = 0;
+                },
+                Ok(MidiCommand::AutoSelectPorts) => {
+                    log::info!("MIDI ports back to automatic detection");
+                    pinned = None;
+                },
+                Ok(MidiCommand::Rescan) => {
+                    log::debug!("MIDI rescan requested");
+                    failures = 0;
+                    let _ = tx_to_app.send(MidiEvent::Phase(ConnectionPhase::Searching));
+                },
                 Ok(_) => {
                     // Ignore other commands while disconnected
                 },
                 Err(_) => break, // Channel closed
             }
         }
+        log::info!("MIDI background service stopped");
     });
 
-    tx_cmd
+    let handle = MidiServiceHandle { tx_cmd: tx_cmd.clone(), stop, thread };
+    (tx_cmd, handle)
+}
+
+/// Check that MIDI clients can be created at all, independent of any device being present
+fn probe_backend() -> Result<(), String> {
+    MidiInput::new("Lightspeed Probe").map_err(|e| format!("MIDI input: {}", e))?;
+    MidiOutput::new("Lightspeed Probe").map_err(|e| format!("MIDI output: {}", e))?;
+    Ok(())
+}
+
+/// True for a NoteOn press softer than `min_velocity`. Releases (velocity 0) always pass.
+fn is_accidental_touch(message: &[u8], min_velocity: u8) -> bool {
+    match message {
+        [status, _, velocity, ..] => status & 0xF0 == 0x90 && *velocity > 0 && *velocity < min_velocity,
+        _ => false,
+    }
+}
+
+/// Whether `payload` opens exactly the `pinned` ports
+fn payload_matches(payload: &MidiConnectionPayload, pinned: &MidiPortSelection) -> bool {
+    payload.midi_in.port_name(&payload.in_port).is_ok_and(|name| name == pinned.input)
+        && payload.midi_out.port_name(&payload.out_port).is_ok_and(|name| name == pinned.output)
+}
+
+/// Sleep for `duration` in short steps, returning early (true) once `stop` is set
+fn sleep_unless_stopped(stop: &AtomicBool, duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+        if stop.load(Ordering::SeqCst) {
+            return true;
+        }
+        thread::sleep(deadline.saturating_duration_since(Instant::now()).min(Duration::from_millis(10)));
+    }
+    stop.load(Ordering::SeqCst)
+}
+
+/// Switch off every pad and button LED the model has
+fn clear_all(conn_out: &mut MidiOutputConnection, model: LaunchpadModel) -> Result<(), SendError> {
+    let leds: Vec<(u8, u8)> = model.all_leds().into_iter().map(|led| (led, 0)).collect();
+    send_leds(conn_out, model, &leds)
+}
+
+/// Light each `(led, color)`: bulk SysEx when the model has it, otherwise one note or CC
+/// message per LED. LEDs the model doesn't have are skipped.
+fn send_leds(conn_out: &mut MidiOutputConnection, model: LaunchpadModel, leds: &[(u8, u8)]) -> Result<(), SendError> {
+    let leds: Vec<(u8, u8)> = leds
+        .iter()
+        .filter(|(led, _)| {
+            let known = model.is_pad_note(*led) || model.is_button_cc(*led);
+            if !known {
+                log::warn!("Dropping color for LED {}: not a {} pad or button", led, model.name());
+            }
+            known
+        })
+        .map(|(led, color)| (*led, checked_color(*color)))
+        .collect();
+    if let Some(packets) = model.led_sysex(&leds) {
+        for packet in packets {
+            conn_out.send(&packet)?;
+        }
+        return Ok(());
+    }
+    for (led, color) in leds {
+        let status = if model.is_pad_note(led) { 0x90 } else { 0xB0 };
+        conn_out.send(&[status, led, color])?;
+    }
+    Ok(())
+}
+
+/// Pad and button event for a note or CC message. NoteOn with velocity 0 is a release,
+/// as many devices send it instead of NoteOff.
+fn channel_event(message: &[u8]) -> Option<MidiEvent> {
+    let [status, data1, data2, ..] = *message else { return None };
+    match status & 0xF0 {
+        0x80 => Some(MidiEvent::NoteOff { note: data1 }),
+        0x90 if data2 == 0 => Some(MidiEvent::NoteOff { note: data1 }),
+        0x90 => Some(MidiEvent::NoteOn { note: data1, velocity: data2 }),
+        0xB0 => Some(MidiEvent::ControlChange { controller: data1, value: data2 }),
+        _ => None,
+    }
+}
+
+/// Clamp a palette index to the 7-bit range the device accepts
+fn checked_color(color: u8) -> u8 {
+    if color > 127 {
+        log::warn!("Palette color {} out of range, clamping to 127", color);
+    }
+    color.min(127)
 }
 
 fn run_midi_loop(
     tx_event: &Sender<MidiEvent>,
     rx_cmd: &Receiver<MidiCommand>,
     payload: MidiConnectionPayload,
+    stop: &AtomicBool,
+    pinned: &mut Option<MidiPortSelection>,
+    connections: &mut u32,
+    shutdown: &mut bool,
 ) -> Result<(), Box<dyn Error>> {
     
     // Deconstruct the payload
-    let MidiConnectionPayload { midi_in, midi_out, in_port, out_port } = payload;
+    let MidiConnectionPayload { midi_in, midi_out, in_port, out_port, model, init, heartbeat_ms } = payload;
 
     let in_name = midi_in.port_name(&in_port).unwrap_or_else(|_| "Unknown".to_string());
     let out_name = midi_out.port_name(&out_port).unwrap_or_else(|_| "Unknown".to_string());
-    println!("Connecting to Launched Ports: In={}, Out={}", in_name, out_name);
+    log::info!("Connecting to Launchpad ports: in={}, out={}", in_name, out_name);
+    let _ = tx_event.send(MidiEvent::Phase(ConnectionPhase::Connecting));
+    let _ = tx_event.send(MidiEvent::Connecting { input: in_name.clone(), output: out_name.clone() });
 
     let tx = tx_event.clone();
+    let acked = Arc::new(AtomicBool::new(false));
+    let acked_in = acked.clone();
+    let ack_reply = init.sysex.clone();
+    let tap_pad: Arc<Mutex<Option<(u8, bool)>>> = Arc::new(Mutex::new(None));
+    let tap_pad_in = tap_pad.clone();
+    let min_velocity = Arc::new(AtomicU8::new(1));
+    let min_velocity_in = min_velocity.clone();
+    let debounce_ms = Arc::new(AtomicU64::new(0));
+    let debounce_ms_in = debounce_ms.clone();
+    let mut debounce = NoteDebounce::default();
+    let mut tap_tempo = TapTempo::default();
+    let mut midi_clock = MidiClock::default();
 
     // Connect using the instances passed from Main Thread
     let _conn_in = midi_in.connect(
         &in_port,
         "launchpad-in",
-        move |_stamp, message, _| {
+        move |stamp, message, _| {
+            // Single-byte real-time messages: clock pulses and transport
+            match message.first() {
+                Some(0xF8) => {
+                    if let Some(bpm) = midi_clock.pulse(stamp) {
+                        let _ = tx.send(MidiEvent::Clock { bpm });
+                    }
+                    return;
+                }
+                Some(0xFA) => {
+                    midi_clock.reset();
+                    let _ = tx.send(MidiEvent::ClockStart);
+                    return;
+                }
+                Some(0xFC) => {
+                    midi_clock.reset();
+                    let _ = tx.send(MidiEvent::ClockStop);
+                    return;
+                }
+                Some(0xF9..=0xFF) => return, // Continue, active sensing, reset
+                _ => {}
+            }
+            if message.first() == Some(&0xF0) {
+                if message == ack_reply.as_slice() {
+                    acked_in.store(true, Ordering::SeqCst);
+                }
+                return;
+            }
+            if is_accidental_touch(message, min_velocity_in.load(Ordering::Relaxed)) {
+                return;
+            }
+            if !debounce.accept(message, stamp, debounce_ms_in.load(Ordering::Relaxed)) {
+                log::debug!("Dropping double-fired press of note {}", message[1]);
+                return;
+            }
             if message.len() >= 3 {
                 let status = message[0] & 0xF0;
-                match status {
-                    0x90 => {
-                        let note = message[1];
-                        let vel = message[2];
-                        if vel > 0 {
-                            let _ = tx.send(MidiEvent::NoteOn { note, velocity: vel });
-                        }
-                    }
-                    0xB0 => {
-                        let cc = message[1];
-                        let val = message[2];
-                        if val > 0 {
-                            let _ = tx.send(MidiEvent::ControlChange {
-                                controller: cc,
-                                value: val,
-                            });
+                // Tap pad presses feed the tempo estimate instead of triggering anything;
+                // its releases are swallowed too
+                let pad = match status {
+                    0x80 | 0x90 => Some((message[1], false)),
+                    0xB0 => Some((message[1], true)),
+                    _ => None,
+                };
+                if pad.is_some() && pad == *tap_pad_in.lock().unwrap_or_else(|p| p.into_inner()) {
+                    if status != 0x80 && message[2] > 0 {
+                        if let Some(bpm) = tap_tempo.tap(stamp) {
+                            let _ = tx.send(MidiEvent::TapTempo { bpm });
                         }
                     }
-                    _ => {}
+                    return;
+                }
+                if let Some(event) = channel_event(message) {
+                    let _ = tx.send(event);
                 }
             }
         },
@@ -179,54 +882,375 @@ fn run_midi_loop(
         .map_err(|e| format!("Failed to connect output: {}", e))?;
 
     // === CRITICAL HANDSHAKE ===
-    thread::sleep(Duration::from_millis(200)); 
-    
-    // Enter Programmer Mode
-    // F0h 00h 20h 29h 02h 0Dh 0Eh 01h F7h
-    let sysex = &[0xF0, 0x00, 0x20, 0x29, 0x02, 0x0D, 0x0E, 0x01, 0xF7];
-    conn_out.send(sysex)?;
-    
-    println!("Launchpad Programmer Mode Enabled");
-    
-    thread::sleep(Duration::from_millis(200)); // WAIT for mode switch
-    
+    // Pad colors sent before the mode switch lands are lost (or worse), so
+    // don't report Connected until the device confirms or we run out of attempts.
+    // A shutdown during the handshake ends it right away instead of after every attempt.
+    let settle = Duration::from_millis(init.settle_ms);
+    if sleep_unless_stopped(stop, settle) {
+        *shutdown = true;
+        return Ok(());
+    }
+
+    let attempts = init.max_attempts.max(1);
+    for attempt in 1..=attempts {
+        conn_out.send(&init.sysex)?;
+        log::debug!("{} programmer mode requested (attempt {}/{})", model.name(), attempt, attempts);
+
+        // WAIT for mode switch
+        if sleep_unless_stopped(stop, settle) {
+            *shutdown = true;
+            return Ok(());
+        }
+
+        if init.query.is_empty() {
+            break;
+        }
+        conn_out.send(&init.query)?;
+        let deadline = Instant::now() + Duration::from_millis(INIT_ACK_TIMEOUT_MS);
+        while !acked.load(Ordering::SeqCst) && Instant::now() < deadline {
+            if sleep_unless_stopped(stop, Duration::from_millis(10)) {
+                *shutdown = true;
+                return Ok(());
+            }
+        }
+        if acked.load(Ordering::SeqCst) {
+            log::debug!("{} acknowledged programmer mode", model.name());
+            break;
+        }
+        log::warn!("{} did not acknowledge programmer mode (attempt {}/{})", model.name(), attempt, attempts);
+    }
+    if !init.query.is_empty() && !acked.load(Ordering::SeqCst) {
+        log::warn!("Continuing without programmer mode acknowledgement; pad LEDs may not respond");
+    }
+
     // Now send connected event
-    let _ = tx_event.send(MidiEvent::Connected);
+    let _ = tx_event.send(if *connections > 0 { MidiEvent::Reconnected } else { MidiEvent::Connected });
+    let _ = tx_event.send(MidiEvent::Phase(ConnectionPhase::Connected));
+    *connections += 1;
+
+    log::info!("Launchpad ready, listening for commands");
 
-    println!("Device Ready. Listening for commands...");
+    let heartbeat = heartbeat_ms.filter(|ms| *ms > 0).map(Duration::from_millis);
+    let mut next_heartbeat = heartbeat.map(|every| Instant::now() + every);
 
     // Process Commands
     loop {
-        match rx_cmd.recv_timeout(Duration::from_secs(1)) {
+        let wait = next_heartbeat.map_or(Duration::from_secs(1), |at| at.saturating_duration_since(Instant::now()));
+        match rx_cmd.recv_timeout(wait) {
             Ok(cmd) => match cmd {
                 MidiCommand::SetPadColor { note, color } => {
-                    conn_out.send(&[0x90, note, color])?; 
+                    if model.is_pad_note(note) {
+                        conn_out.send(&[0x90, note, checked_color(color)])?;
+                    } else {
+                        log::warn!("Dropping color for note {}: not a {} pad", note, model.name());
+                    }
+                },
+                MidiCommand::SetPadRgb { note, r, g, b } => {
+                    if !model.is_pad_note(note) {
+                        log::warn!("Dropping RGB color for note {}: not a {} pad", note, model.name());
+                    } else if let Some(packet) = model.rgb_led_sysex(note, [r, g, b]) {
+                        conn_out.send(&packet)?;
+                    } else {
+                        log::info!("{} has no RGB pad lighting, ignoring RGB color for note {}", model.name(), note);
+                    }
+                },
+                MidiCommand::PulsePad { note, color } => {
+                    if model.is_pad_note(note) {
+                        let color = checked_color(color);
+                        match model.pulse_led_sysex(note, color) {
+                            Some(packet) => conn_out.send(&packet)?,
+                            None => conn_out.send(&[0x90 | LedMode::Pulse.channel(), note, color])?,
+                        }
+                    } else {
+                        log::warn!("Dropping pulse for note {}: not a {} pad", note, model.name());
+                    }
+                },
+                MidiCommand::FlashPad { note, color_a, color_b } => {
+                    if model.is_pad_note(note) {
+                        let (color_a, color_b) = (checked_color(color_a), checked_color(color_b));
+                        match model.flash_led_sysex(note, color_a, color_b) {
+                            Some(packet) => conn_out.send(&packet)?,
+                            None => {
+                                // Channel flash alternates with the pad's static color
+                                conn_out.send(&[0x90, note, color_b])?;
+                                conn_out.send(&[0x90 | LedMode::Flash.channel(), note, color_a])?;
+                            }
+                        }
+                    } else {
+                        log::warn!("Dropping flash for note {}: not a {} pad", note, model.name());
+                    }
                 },
                 MidiCommand::SetButtonColor { cc, color } => {
-                     conn_out.send(&[0xB0, cc, color])?; 
+                    if model.is_button_cc(cc) {
+                        conn_out.send(&[0xB0, cc, checked_color(color)])?;
+                    } else {
+                        log::warn!("Dropping color for CC {}: not a {} button", cc, model.name());
+                    }
                 },
-                MidiCommand::ClearAll => {
-                    for i in 0..127 {
-                         conn_out.send(&[0x90, i, 0])?;
-                         conn_out.send(&[0xB0, i, 0])?;
+                MidiCommand::SetStatusLed { led, color, mode } => {
+                    match model.status_led_cc(led) {
+                        Some(cc) => conn_out.send(&[0xB0 | mode.channel(), cc, checked_color(color)])?,
+                        None => log::debug!("{} has no {:?} LED", model.name(), led),
                     }
                 },
+                MidiCommand::SetGrid { colors } => {
+                    send_leds(&mut conn_out, model, &colors)?;
+                },
+                MidiCommand::SetTapPad { btn, is_cc } => {
+                    *tap_pad.lock().unwrap_or_else(|p| p.into_inner()) = btn.map(|b| (b, is_cc));
+                },
+                MidiCommand::SetMinVelocity(velocity) => {
+                    min_velocity.store(velocity, Ordering::Relaxed);
+                },
+                MidiCommand::SetDebounceMs(ms) => {
+                    debounce_ms.store(ms, Ordering::Relaxed);
+                },
+                MidiCommand::ClearAll => {
+                    clear_all(&mut conn_out, model)?;
+                },
                 MidiCommand::Connect(_) => {
-                    println!("Received Connect command while already connected. Ignoring.");
+                    log::debug!("Ignoring Connect command while already connected");
+                },
+                MidiCommand::Rescan => {
+                    log::debug!("Ignoring rescan while connected");
+                },
+                MidiCommand::SelectPorts { input, output } => {
+                    let reconnect = input != in_name || output != out_name;
+                    log::info!("MIDI ports pinned to {} / {}", input, output);
+                    *pinned = Some(MidiPortSelection { input, output });
+                    if reconnect {
+                        log::info!("Closing connection to switch to the selected ports");
+                        break;
+                    }
+                },
+                MidiCommand::AutoSelectPorts => {
+                    log::info!("MIDI ports back to automatic detection");
+                    *pinned = None;
                 },
                 MidiCommand::Disconnect => {
-                    println!("Disconnect requested by Watchdog.");
+                    log::info!("Disconnect requested");
+                    break;
+                }
+                MidiCommand::Shutdown { blackout } => {
+                    log::info!("Shutdown requested");
+                    *shutdown = true;
+                    if blackout {
+                        // Best effort: the device may already be gone
+                        if let Err(e) = clear_all(&mut conn_out, model) {
+                            log::debug!("Blackout on shutdown failed: {}", e);
+                        }
+                    }
                     break;
                 }
             },
-            Err(RecvTimeoutError::Timeout) => {
-                // Heartbeat: Send a dummy message to check connection health
-                // Note Off on Channel 1, Note 0, Velocity 0
-                conn_out.send(&[0x80, 0, 0])?;
-            },
+            Err(RecvTimeoutError::Timeout) => {},
             Err(RecvTimeoutError::Disconnected) => break,
         }
+
+        // Heartbeat: some systems stop delivering to the device without midir noticing.
+        // A failed send ends the loop with an error, which sends the service back to reconnecting.
+        if let (Some(every), Some(at)) = (heartbeat, next_heartbeat) {
+            if Instant::now() >= at {
+                // The mode readback is harmless; without one, re-select programmer mode
+                let probe = if init.query.is_empty() { &init.sysex } else { &init.query };
+                conn_out.send(probe).map_err(|e| format!("MIDI heartbeat failed: {}", e))?;
+                next_heartbeat = Some(Instant::now() + every);
+            }
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_velocity_gates_soft_note_on_only() {
+        assert!(is_accidental_touch(&[0x90, 11, 10], 20));
+        assert!(!is_accidental_touch(&[0x90, 11, 20], 20));
+        assert!(!is_accidental_touch(&[0x90, 11, 0], 20)); // Release
+        assert!(!is_accidental_touch(&[0xB0, 91, 10], 20)); // Buttons are CCs
+        assert!(!is_accidental_touch(&[0x91, 11, 5], 1));
+        assert!(!is_accidental_touch(&[0x90, 11], 20));
+    }
+
+    #[test]
+    fn test_debounce_drops_repeat_press_within_window() {
+        let mut debounce = NoteDebounce::default();
+        assert!(debounce.accept(&[0x90, 11, 100], 1_000_000, 30));
+        assert!(!debounce.accept(&[0x90, 11, 100], 1_010_000, 30)); // 10ms later
+        assert!(debounce.accept(&[0x90, 12, 100], 1_010_000, 30)); // Other pads unaffected
+        assert!(debounce.accept(&[0x90, 11, 100], 1_040_000, 30)); // Past the window
+        assert!(debounce.accept(&[0x90, 11, 0], 1_045_000, 30)); // Release clears the note
+        assert!(debounce.accept(&[0x90, 11, 100], 1_050_000, 30));
+        assert!(debounce.accept(&[0x80, 11, 0], 1_055_000, 30));
+        assert!(debounce.accept(&[0x90, 11, 100], 1_056_000, 30));
+        assert!(debounce.accept(&[0x90, 11, 100], 1_057_000, 0)); // 0 disables
+        assert!(debounce.accept(&[0xB0, 91, 127], 1_057_000, 30)); // CCs pass
+    }
+
+    #[test]
+    fn test_mini_mk3_pad_and_button_ranges() {
+        let model = LaunchpadModel::MiniMk3;
+        assert!(model.is_pad_note(11));
+        assert!(model.is_pad_note(88));
+        assert!(!model.is_pad_note(0));
+        assert!(!model.is_pad_note(10));
+        assert!(!model.is_pad_note(19));
+        assert!(!model.is_pad_note(91));
+        assert!(model.is_button_cc(91));
+        assert!(model.is_button_cc(99));
+        assert!(model.is_button_cc(19));
+        assert!(!model.is_button_cc(11));
+        assert!(!model.is_button_cc(100));
+        assert_eq!(checked_color(127), 127);
+        assert_eq!(checked_color(200), 127);
+    }
+
+    #[test]
+    fn test_mini_mk3_grid_coordinates() {
+        let model = LaunchpadModel::MiniMk3;
+        assert_eq!(note_to_xy(81, model), Some((0, 0)));
+        assert_eq!(note_to_xy(18, model), Some((7, 7)));
+        assert_eq!(note_to_xy(53, model), Some((2, 3)));
+        assert_eq!(note_to_xy(19, model), None);
+        assert_eq!(note_to_xy(0, model), None);
+        for note in (0..=127).filter(|n| model.is_pad_note(*n)) {
+            assert_eq!(xy_to_note(note_to_xy(note, model).unwrap(), model), note);
+        }
+        assert_eq!(xy_to_note((9, 9), model), 18);
+    }
+
+    #[test]
+    fn test_model_profiles_from_port_names() {
+        assert_eq!(LaunchpadModel::from_port_name("Launchpad X LPX MIDI"), LaunchpadModel::X);
+        assert_eq!(LaunchpadModel::from_port_name("LPX MIDI"), LaunchpadModel::X);
+        assert_eq!(LaunchpadModel::from_port_name("Launchpad Pro MK3 LPProMK3 MIDI"), LaunchpadModel::ProMk3);
+        assert_eq!(LaunchpadModel::from_port_name("Launchpad Mini MK3 LPMiniMK3 MIDI"), LaunchpadModel::MiniMk3);
+        assert_eq!(LaunchpadModel::from_port_name("Launchpad S"), LaunchpadModel::MiniMk3);
+        assert!(is_launchpad_midi_port("LPProMK3 MIDI"));
+        assert!(!is_launchpad_midi_port("LPX DAW"));
+
+        // Each model selects programmer mode with its own device ID
+        assert_eq!(LaunchpadModel::MiniMk3.default_init().sysex, vec![0xF0, 0x00, 0x20, 0x29, 0x02, 0x0D, 0x0E, 0x01, 0xF7]);
+        assert_eq!(LaunchpadModel::X.default_init().sysex[5], 0x0C);
+        assert_eq!(LaunchpadModel::ProMk3.default_init().query, vec![0xF0, 0x00, 0x20, 0x29, 0x02, 0x0E, 0x0E, 0xF7]);
+
+        // The Pro MK3 has extra button rows and columns but no addressable logo
+        let pro = LaunchpadModel::ProMk3;
+        assert!(pro.is_button_cc(10) && pro.is_button_cc(105) && pro.is_button_cc(3) && pro.is_button_cc(90));
+        assert!(!LaunchpadModel::X.is_button_cc(10));
+        assert_eq!(pro.status_led_cc(StatusLed::Logo), None);
+        assert_eq!(pro.status_led_cc(StatusLed::TopRow(0)), Some(91));
+        for model in LaunchpadModel::ALL {
+            assert_eq!(note_to_xy(81, model), Some((0, 0)));
+            assert!(!model.is_button_cc(11));
+        }
+    }
+
+    #[test]
+    fn test_sleep_unless_stopped_returns_early() {
+        let stop = AtomicBool::new(true);
+        let start = Instant::now();
+        assert!(sleep_unless_stopped(&stop, Duration::from_secs(2)));
+        assert!(start.elapsed() < Duration::from_millis(100));
+        stop.store(false, Ordering::SeqCst);
+        assert!(!sleep_unless_stopped(&stop, Duration::from_millis(20)));
+    }
+
+    #[test]
+    fn test_bulk_led_sysex_packing() {
+        let model = LaunchpadModel::MiniMk3;
+        let leds: Vec<(u8, u8)> = model.all_leds().into_iter().map(|led| (led, 5)).collect();
+        assert_eq!(leds.len(), 81); // 64 pads, 8 top row, 8 right column, logo
+
+        // A full Mini MK3 clear fits in one packet
+        let packets = model.led_sysex(&leds).unwrap();
+        assert_eq!(packets.len(), 1);
+        let packet = &packets[0];
+        assert_eq!(&packet[..7], &[0xF0, 0x00, 0x20, 0x29, 0x02, 0x0D, 0x03]);
+        assert_eq!(&packet[7..10], &[0x00, 11, 5]);
+        assert_eq!(packet.last(), Some(&0xF7));
+        assert_eq!(packet.len(), 8 + 81 * 3);
+
+        // Larger updates split at the buffer size
+        let many: Vec<(u8, u8)> = (0..150).map(|i| (i as u8, 1)).collect();
+        let packets = LaunchpadModel::ProMk3.led_sysex(&many).unwrap();
+        assert_eq!(packets.iter().map(|p| (p.len() - 8) / 3).collect::<Vec<_>>(), vec![104, 46]);
+        assert!(packets.iter().all(|p| p.len() <= MAX_SYSEX_LEN && p[5] == 0x0E));
+    }
+
+    #[test]
+    fn test_rgb_led_sysex() {
+        assert_eq!(rgb_to_7bit(255), 127);
+        assert_eq!(rgb_to_7bit(128), 64);
+        assert_eq!(rgb_to_7bit(1), 0);
+        assert_eq!(
+            LaunchpadModel::MiniMk3.rgb_led_sysex(11, [255, 128, 0]),
+            Some(vec![0xF0, 0x00, 0x20, 0x29, 0x02, 0x0D, 0x03, 0x03, 11, 127, 64, 0, 0xF7])
+        );
+        assert_eq!(LaunchpadModel::X.rgb_led_sysex(88, [0, 0, 255]).unwrap()[5], 0x0C);
+    }
+
+    #[test]
+    fn test_pulse_and_flash_sysex() {
+        let model = LaunchpadModel::MiniMk3;
+        assert_eq!(model.pulse_led_sysex(11, 5), Some(vec![0xF0, 0x00, 0x20, 0x29, 0x02, 0x0D, 0x03, 0x02, 11, 5, 0xF7]));
+        assert_eq!(model.flash_led_sysex(11, 5, 9), Some(vec![0xF0, 0x00, 0x20, 0x29, 0x02, 0x0D, 0x03, 0x01, 11, 9, 5, 0xF7]));
+    }
+
+    #[test]
+    fn test_channel_events_include_releases() {
+        assert!(matches!(channel_event(&[0x90, 11, 100]), Some(MidiEvent::NoteOn { note: 11, velocity: 100 })));
+        assert!(matches!(channel_event(&[0x90, 11, 0]), Some(MidiEvent::NoteOff { note: 11 })));
+        assert!(matches!(channel_event(&[0x80, 11, 64]), Some(MidiEvent::NoteOff { note: 11 })));
+        assert!(matches!(channel_event(&[0xB0, 91, 127]), Some(MidiEvent::ControlChange { controller: 91, value: 127 })));
+        assert!(matches!(channel_event(&[0xB0, 91, 0]), Some(MidiEvent::ControlChange { controller: 91, value: 0 })));
+        assert!(channel_event(&[0xE0, 0, 64]).is_none());
+        assert!(channel_event(&[0x90, 11]).is_none());
+    }
+
+    #[test]
+    fn test_tap_tempo_averages_intervals() {
+        let mut tap = TapTempo::default();
+        assert_eq!(tap.tap(0), None);
+        // 500ms apart = 120 BPM
+        assert_eq!(tap.tap(500_000), Some(120.0));
+        // 400ms (150 BPM) averaged with 500ms = 450ms
+        let bpm = tap.tap(900_000).unwrap();
+        assert!((bpm - 60.0 / 0.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_midi_clock_reports_each_quarter_note() {
+        let mut clock = MidiClock::default();
+        // 120 BPM = 24 pulses per 500ms
+        let pulse_us = 500_000 / 24;
+        let reports: Vec<(u64, f64)> = (0..73u64)
+            .filter_map(|i| clock.pulse(i * pulse_us).map(|bpm| (i, bpm)))
+            .collect();
+        // The first quarter note only collects intervals
+        assert_eq!(reports.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![47, 71]);
+        for (_, bpm) in reports {
+            assert!((bpm - 60_000_000.0 / (pulse_us as f64 * 24.0)).abs() < 1e-9);
+        }
+
+        // A paused clock starts collecting again
+        clock.pulse(10_000_000);
+        assert_eq!((0..24).filter_map(|i| clock.pulse(10_000_000 + (i + 1) * pulse_us)).count(), 0);
+        clock.reset();
+        assert_eq!(clock.pulse(0), None);
+    }
+
+    #[test]
+    fn test_tap_tempo_resets_after_long_gap() {
+        let mut tap = TapTempo::default();
+        tap.tap(0);
+        tap.tap(1_000_000);
+        // A 3s gap starts over instead of dragging the average down
+        assert_eq!(tap.tap(4_000_000), None);
+        assert_eq!(tap.tap(4_250_000), Some(240.0));
+    }
+}
@@ -1,21 +1,282 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use crate::effect_params::{self, EffectParams, ParamError};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GlobalEffect {
-    pub kind: String,                   // "Solid" | "Rainbow"
+    pub kind: String,                   // "Solid" | "Rainbow" | ...; see `effect_params`
+    pub params: HashMap<String, serde_json::Value>, // With a preset: per-scene overrides
+    #[serde(default)]
+    pub preset_id: Option<u64>,         // EffectPreset supplying kind and base params
+    #[serde(default)]
+    pub automation: Vec<ParamAutomation>, // Param ramps, applied on top of params while rendering
+}
+
+/// Ramps one numeric param from its set value to `target` over `duration_ms`, timed from
+/// when the scene started rendering. Afterwards the ramp starts over ("loop"), runs back
+/// and forth ("bounce") or stays at the target ("once").
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ParamAutomation {
+    pub param: String,
+    pub target: f64,
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub curve: String, // One of `easing::CURVES`; empty = linear
+    #[serde(default)]
+    pub repeat: String, // "loop" | "bounce" | "once"; empty = loop
+}
+
+/// Repeat modes for `ParamAutomation::repeat`, in editor order
+pub const AUTOMATION_REPEATS: &[&str] = &["loop", "bounce", "once"];
+
+impl ParamAutomation {
+    /// Ramp position (0.0 = set value, 1.0 = target) `elapsed_ms` into the automation
+    pub fn progress(&self, elapsed_ms: u64) -> f64 {
+        if self.duration_ms == 0 {
+            return 1.0;
+        }
+        let cycles = elapsed_ms as f64 / self.duration_ms as f64;
+        let linear = match self.repeat.as_str() {
+            "once" => cycles.min(1.0),
+            "bounce" => {
+                let phase = cycles % 2.0;
+                if phase <= 1.0 { phase } else { 2.0 - phase }
+            }
+            _ => cycles.fract(),
+        };
+        crate::easing::ease(&self.curve, linear)
+    }
+
+    /// The param value `elapsed_ms` in, ramping from `from`
+    pub fn value(&self, from: f64, elapsed_ms: u64) -> f64 {
+        from + (self.target - from) * self.progress(elapsed_ms)
+    }
+}
+
+/// Named, reusable effect parameter set referenced by `GlobalEffect::preset_id`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EffectPreset {
+    pub id: u64,
+    pub name: String,
+    pub kind: String,
     pub params: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GlobalEffectConfig {
     pub effect: GlobalEffect,
-    pub targets: Option<Vec<u64>>, // List of Strip IDs. None or Empty implies "All"
+    pub targets: Option<Vec<u64>>, // Strip IDs. None = all strips, Some(empty) = none (config disabled)
+}
+
+impl GlobalEffectConfig {
+    /// Whether this config draws on strip `id`
+    pub fn targets_strip(&self, id: u64) -> bool {
+        self.targets.as_ref().map_or(true, |t| t.contains(&id))
+    }
+
+    /// Targets no strips at all, so the effect is kept but not rendered
+    pub fn is_disabled(&self) -> bool {
+        self.targets.as_ref().is_some_and(|t| t.is_empty())
+    }
 }
 
 impl Default for GlobalEffect {
     fn default() -> Self {
-        Self { kind: "Rainbow".into(), params: HashMap::new() }
+        Self { kind: "Rainbow".into(), params: HashMap::new(), preset_id: None, automation: Vec::new() }
+    }
+}
+
+impl GlobalEffect {
+    /// The effect as rendered: the referenced preset's kind and params with this
+    /// effect's own params layered on top. Missing presets fall back to the effect itself.
+    pub fn resolve(&self, presets: &[EffectPreset]) -> Cow<'_, GlobalEffect> {
+        let Some(preset) = self.preset_id.and_then(|id| presets.iter().find(|p| p.id == id)) else {
+            return Cow::Borrowed(self);
+        };
+        let mut params = preset.params.clone();
+        params.extend(self.params.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Cow::Owned(GlobalEffect { kind: preset.kind.clone(), params, preset_id: self.preset_id, automation: self.automation.clone() })
+    }
+
+    /// Params read as this effect's kind; wrong-typed and unknown params are errors.
+    /// Call on the resolved effect so a preset's params are included.
+    pub fn typed_params(&self) -> Result<EffectParams, ParamError> {
+        EffectParams::strict(&self.kind, &self.params)
+    }
+
+    /// Replace this effect's params with `other`'s, keeping kind and preset link.
+    /// With a preset linked the copied params become its overrides.
+    pub fn copy_params_from(&mut self, other: &GlobalEffect) {
+        self.params = other.params.clone();
+    }
+}
+
+/// Valid values for a tunable effect param, matching the editor's controls
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamRange {
+    Float(f64, f64), // Inclusive
+    Choice(&'static [&'static str]),
+}
+
+const BEAT_RATES: &[&str] = &["4 Bar", "2 Bar", "1 Bar", "1/2", "1/4", "1/8"];
+
+/// (effect kind, param, range) for every param the editor exposes as a slider or choice.
+/// Colors and strip lists have no range and are never shuffled.
+pub const PARAM_RANGES: &[(&str, &str, ParamRange)] = &[
+    ("Rainbow", "speed", ParamRange::Float(0.05, 2.0)),
+    ("Flash", "rate", ParamRange::Choice(&["4 Bar", "1 Bar", "1/2", "1/4", "1/8"])),
+    ("Flash", "decay", ParamRange::Float(0.1, 20.0)),
+    ("Gradient", "scroll_speed", ParamRange::Float(-2.0, 2.0)),
+    ("Strobe", "frequency_hz", ParamRange::Float(0.5, 25.0)),
+    ("Strobe", "duty_cycle", ParamRange::Float(0.05, 0.95)),
+    ("Sparkle", "density", ParamRange::Float(0.001, 0.2)),
+    ("Sparkle", "life", ParamRange::Float(0.05, 2.0)),
+    ("Sparkle", "decay", ParamRange::Float(0.1, 20.0)),
+    ("Sparkle", "sync_mode", ParamRange::Choice(&["per_strip", "shared"])),
+    ("ColorWash", "rate", ParamRange::Choice(BEAT_RATES)),
+    ("ColorWash", "period", ParamRange::Float(0.5, 20.0)),
+    ("GlitchSparkle", "density", ParamRange::Float(0.001, 0.2)),
+    ("GlitchSparkle", "fade_time", ParamRange::Float(0.05, 2.0)),
+    ("GlitchSparkle", "decay", ParamRange::Float(0.1, 20.0)),
+    ("GlitchSparkle", "sync_mode", ParamRange::Choice(&["per_strip", "shared"])),
+    ("PulseWave", "rate", ParamRange::Choice(BEAT_RATES)),
+    ("PulseWave", "speed", ParamRange::Float(0.1, 50.0)),
+    ("PulseWave", "tail_length", ParamRange::Float(3.0, 50.0)),
+    ("PulseWave", "decay", ParamRange::Float(0.1, 5.0)),
+    ("PulseWave", "direction", ParamRange::Choice(&["Forward", "Reverse", "Bounce"])),
+    ("ZoneAlternate", "rate", ParamRange::Choice(BEAT_RATES)),
+    ("ZoneAlternate", "mode", ParamRange::Choice(&["Swap", "Pulse"])),
+    ("Plasma", "speed", ParamRange::Float(0.05, 5.0)),
+    ("Plasma", "scale", ParamRange::Float(0.5, 30.0)),
+    ("Plasma", "palette", ParamRange::Choice(&["Rainbow", "Fire", "Ocean", "Duo"])),
+    ("Ripple", "origin_x", ParamRange::Float(0.0, 1.0)),
+    ("Ripple", "origin_y", ParamRange::Float(0.0, 1.0)),
+    ("Ripple", "speed", ParamRange::Float(0.05, 3.0)),
+    ("Ripple", "width", ParamRange::Float(0.005, 0.5)),
+    ("Ripple", "decay", ParamRange::Float(0.1, 10.0)),
+    ("Ripple", "trigger", ParamRange::Choice(&["Beat", "Timer"])),
+    ("Ripple", "interval", ParamRange::Float(0.1, 10.0)),
+];
+
+pub fn param_range(kind: &str, param: &str) -> Option<ParamRange> {
+    PARAM_RANGES.iter().find(|(k, p, _)| *k == kind && *p == param).map(|(_, _, r)| *r)
+}
+
+/// Global effects that flash the whole rig, subject to `AppState::max_flash_hz` and `no_strobe`
+pub const STROBE_EFFECTS: &[&str] = &["Flash", "Strobe"];
+
+/// Beats per flash for a Flash `rate`; unknown rates flash once a bar
+pub fn flash_divisor(rate: &str) -> f64 {
+    match rate {
+        "4 Bar" => 16.0, "1 Bar" => 4.0, "1/2" => 2.0, "1/4" => 1.0, "1/8" => 0.5, _ => 4.0,
+    }
+}
+
+/// Double `divisor` (beats per flash) until flashing at `bpm` stays at or under `max_hz`,
+/// so a limited flash still lands on the beat grid. None = no limit.
+pub fn limit_flash_divisor(divisor: f64, bpm: f64, max_hz: Option<f32>) -> f64 {
+    let Some(max_hz) = max_hz.filter(|hz| *hz > 0.0) else { return divisor };
+    if !bpm.is_finite() || bpm <= 0.0 {
+        return divisor;
+    }
+    let mut divisor = divisor.max(f64::EPSILON);
+    while bpm / 60.0 / divisor > max_hz as f64 {
+        divisor *= 2.0;
+    }
+    divisor
+}
+
+/// Randomize the params of `effect` (rendered as `kind`) within their ranges.
+/// `only` limits which params change; empty means every param with a range.
+/// Returns the number of params written.
+pub fn shuffle_effect(effect: &mut GlobalEffect, kind: &str, only: &[String], rng: &mut impl rand::Rng) -> usize {
+    let mut changed = 0;
+    for (_, param, range) in PARAM_RANGES.iter().filter(|(k, _, _)| *k == kind) {
+        if !only.is_empty() && !only.iter().any(|p| p == param) {
+            continue;
+        }
+        let value = match range {
+            ParamRange::Float(min, max) => serde_json::json!(rng.gen_range(*min..=*max)),
+            ParamRange::Choice(options) => serde_json::json!(options[rng.gen_range(0..options.len())]),
+        };
+        effect.params.insert(param.to_string(), value);
+        changed += 1;
+    }
+    changed
+}
+
+/// Parse a color written as `[r, g, b]`, `"#RRGGBB"` or `{"h": 0-360, "s": 0-1, "v": 0-1}`.
+/// Returns None for anything malformed, including out-of-range components.
+pub fn parse_color(value: &serde_json::Value) -> Option<[u8; 3]> {
+    use serde_json::Value;
+    match value {
+        Value::Array(arr) => {
+            if arr.len() != 3 {
+                return None;
+            }
+            let mut rgb = [0u8; 3];
+            for (c, v) in rgb.iter_mut().zip(arr) {
+                *c = u8::try_from(v.as_u64()?).ok()?;
+            }
+            Some(rgb)
+        }
+        Value::String(s) => {
+            let hex = s.trim().trim_start_matches('#');
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let val = u32::from_str_radix(hex, 16).ok()?;
+            Some([(val >> 16) as u8, (val >> 8) as u8, val as u8])
+        }
+        Value::Object(obj) => {
+            let h = obj.get("h")?.as_f64()?;
+            let s = obj.get("s")?.as_f64()?;
+            let v = obj.get("v")?.as_f64()?;
+            if !h.is_finite() || !(0.0..=1.0).contains(&s) || !(0.0..=1.0).contains(&v) {
+                return None;
+            }
+            Some(crate::engine::hsv_to_rgb((h / 360.0).rem_euclid(1.0) as f32, s as f32, v as f32))
+        }
+        _ => None,
+    }
+}
+
+/// Drop params `kind` doesn't read. Unknown kinds keep all their params.
+pub fn prune_unknown_params(kind: &str, params: &mut HashMap<String, serde_json::Value>) {
+    if effect_params::param_names(kind).is_none() {
+        return;
+    }
+    params.retain(|key, _| {
+        let known = effect_params::is_known_param(kind, key);
+        if !known {
+            log::info!("Dropping unused {} param '{}'", kind, key);
+        }
+        known
+    });
+}
+
+/// Rewrite hex / HSV color params as `[r, g, b]`, the only form effects read.
+/// Covers every key containing "color"; color lists such as `gradient_colors` are
+/// normalized per entry. Malformed values are left for the effect's default to cover.
+pub fn normalize_color_params(params: &mut HashMap<String, serde_json::Value>) {
+    fn normalize(value: &mut serde_json::Value) {
+        if !value.is_array() {
+            if let Some(rgb) = parse_color(value) {
+                *value = serde_json::json!(rgb);
+            }
+        }
+    }
+    for (key, value) in params.iter_mut() {
+        if !key.contains("color") {
+            continue;
+        }
+        match value {
+            serde_json::Value::Array(entries) if !entries.iter().any(|e| e.is_number()) => {
+                entries.iter_mut().for_each(normalize);
+            }
+            _ => normalize(value),
+        }
     }
 }
 
@@ -37,82 +298,518 @@ pub struct Scene {
     pub launchpad_is_cc: bool,
     #[serde(default)]
     pub launchpad_color: Option<u8>,
+    #[serde(default)]
+    pub launchpad_momentary: bool,     // Pad activates the scene only while held; release restores the previous scenes
+    #[serde(default)]
+    pub notes: String,                 // Free-form build/documentation notes
+    #[serde(default = "default_exclusive_group")]
+    pub exclusive_group: String,       // Active scenes in one group replace each other; "" = overlay that stacks freely
+    #[serde(default)]
+    pub layer: i32,                    // Compositing order among active scenes, bottom (low) to top; ties keep activation order
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,                  // 0.0-1.0 mix of this layer over the ones below
+    #[serde(default)]
+    pub blend_mode: String,            // One of BLEND_MODES; "" = "Mask" for mask scenes, "Max" for global ones
+}
+
+/// How a scene layer combines with the composite below it (see `Scene::effective_blend_mode`)
+pub const BLEND_MODES: &[&str] = &["Max", "Normal", "Add", "Multiply", "Screen", "Mask"];
+
+impl Scene {
+    /// Blend mode used when compositing: the explicit mode, or by kind when unset.
+    /// Mask scenes modulate the layers below; global scenes add their effect output.
+    pub fn effective_blend_mode(&self) -> &str {
+        match self.blend_mode.as_str() {
+            "" if self.kind == "Masks" => "Mask",
+            "" => "Max",
+            mode => mode,
+        }
+    }
+}
+
+fn default_opacity() -> f32 {
+    1.0
 }
 
 fn default_category() -> String {
     "Uncategorized".to_string()
 }
 
+pub fn default_exclusive_group() -> String {
+    "Main".to_string()
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PixelStrip {
     pub id: u64,
+    #[serde(default = "default_true")]
+    pub enabled: bool, // false = left out of rendering and DMX output entirely (e.g. unplugged)
     pub universe: u16,
     pub start_channel: u16,
     pub pixel_count: usize,
     pub x: f32, // Normalized 0..1
     pub y: f32, // Normalized 0..1
-    pub spacing: f32, // Relative spacing 0..1
+    #[serde(default)]
+    pub z: Option<f32>, // Depth for volumetric rigs, same units as x/y; None = on the canvas plane
+    pub spacing: f32, // Relative spacing 0..1; derived from the physical fields below when set
+    #[serde(default)]
+    pub leds_per_meter: Option<f32>,
+    #[serde(default)]
+    pub length_m: Option<f32>, // Physical length first to last pixel; used when leds_per_meter is unset
+    #[serde(default)]
+    pub min_brightness: u8, // Output floor for non-zero channels; 0 = off
+    #[serde(default = "default_strip_brightness")]
+    pub brightness: f32, // 0.0-1.0 dimmer on this strip's output, applied with the master stage
+    #[serde(default)]
+    pub pre_channels: Vec<FixtureChannel>, // Fixture channels (dimmer, strobe, ...) sent at start_channel, before the pixels
+    #[serde(default)]
+    pub post_channels: Vec<FixtureChannel>, // Fixture channels sent right after the last pixel
     #[serde(default)]
     pub flipped: bool, // true = 180 deg (Left), false = 0 deg (Right)
     #[serde(default = "default_color_order")]
-    pub color_order: String, // "RGB", "GRB", "BGR"
+    pub color_order: String, // Wire order spec, see `is_valid_color_order` (RGB, GRB, RGBW, IRGB, ...)
+    #[serde(default = "default_white_mode")]
+    pub white_mode: String, // How a W slot is derived, one of `WHITE_MODES`; ignored without W in the order
+    #[serde(default)]
+    pub channels_per_pixel: u8, // Wire slots per pixel; 0 = one per letter of `color_order`. Slots past the spec send 0
+    #[serde(default)]
+    pub notes: String, // Free-form install notes ("left pillar, fragile connector")
+    #[serde(default)]
+    pub mirror_source: Option<u64>, // Show this strip's rendered output instead of our own
+    #[serde(default)]
+    pub mirror_reversed: bool, // Mirror end to end (pixel 0 shows the source's last pixel)
     #[serde(skip)]
     pub data: Vec<[u8; 3]>, // RGB Data
 }
 
+/// One controller output that snakes through several strips in order.
+/// Segments keep their own placement but are addressed back to back from `start_channel`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StripChain {
+    pub id: u64,
+    pub name: String,
+    pub universe: u16,
+    pub start_channel: u16,
+    pub strip_ids: Vec<u64>, // Segments in wiring order
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_strip_brightness() -> f32 {
+    1.0
+}
+
+/// Supported wire orders; each letter names the color sent in that DMX slot
+pub const COLOR_ORDERS: &[&str] = &["RGB", "RBG", "GRB", "GBR", "BRG", "BGR"];
+
+/// Common specs for fixtures with more than three channels per pixel
+pub const EXTENDED_COLOR_ORDERS: &[&str] = &["RGBW", "GRBW", "WRGB", "IRGB"];
+
+/// How the W slot is derived for orders that have one:
+/// Add sends min(R, G, B) on top of unchanged RGB (brightest), Subtract moves that common
+/// part from RGB to the white LED (truest color), Off leaves the white LED dark
+pub const WHITE_MODES: &[&str] = &["Add", "Subtract", "Off"];
+
+/// A wire order spec has R, G and B exactly once, plus optionally one W (white, derived
+/// from R, G and B per `WHITE_MODES`) and one I (intensity, sent at full) anywhere
+pub fn is_valid_color_order(order: &str) -> bool {
+    let count = |c: char| order.chars().filter(|o| *o == c).count();
+    order.chars().all(|c| "RGBWI".contains(c))
+        && "RGB".chars().all(|c| count(c) == 1)
+        && count('W') <= 1
+        && count('I') <= 1
+}
+
+/// For each color slot, in wire order, the `data` channel (0 = R, 1 = G, 2 = B) sent in it.
+/// W and I slots are skipped; unknown orders fall back to RGB.
+pub fn color_order_slots(order: &str) -> [usize; 3] {
+    let mut slots = [0, 1, 2];
+    if is_valid_color_order(order) {
+        for (slot, color) in order.chars().filter(|c| "RGB".contains(*c)).enumerate() {
+            slots[slot] = "RGB".find(color).unwrap_or(slot);
+        }
+    }
+    slots
+}
+
+/// Write one pixel's wire bytes into `out` (one byte per slot) following `order`,
+/// deriving any W slot per `white_mode` (see `WHITE_MODES`; unknown modes act as Add).
+/// Slots beyond the spec are zeroed; unknown orders pack as RGB.
+pub fn pack_pixel(rgb: [u8; 3], order: &str, white_mode: &str, out: &mut [u8]) {
+    let order = if is_valid_color_order(order) { order } else { "RGB" };
+    let common = rgb[0].min(rgb[1]).min(rgb[2]);
+    let (rgb, white) = match white_mode {
+        _ if !order.contains('W') => (rgb, 0),
+        "Subtract" => (rgb.map(|c| c - common), common),
+        "Off" => (rgb, 0),
+        _ => (rgb, common),
+    };
+    out.fill(0);
+    for (slot, spec) in out.iter_mut().zip(order.chars()) {
+        *slot = match spec {
+            'R' => rgb[0],
+            'G' => rgb[1],
+            'B' => rgb[2],
+            'W' => white,
+            _ => 255, // 'I'
+        };
+    }
+}
+
+/// The order that fixes a strip, read off the test pattern: with the strip set to
+/// `current`, pure red showed as `red_shown` and pure green as `green_shown` ('R', 'G' or 'B').
+/// None when the observations can't come from a single strip (same or unknown colors).
+pub fn infer_color_order(current: &str, red_shown: char, green_shown: char) -> Option<String> {
+    if red_shown == green_shown || ![red_shown, green_shown].iter().all(|c| "RGB".contains(*c)) {
+        return None;
+    }
+    let blue_shown = "RGB".chars().find(|c| *c != red_shown && *c != green_shown)?;
+    // The hardware shows slot k's byte as its own color k: whatever red landed on is really that color
+    let mut shown = ['R'; 3];
+    for (slot, channel) in color_order_slots(current).into_iter().enumerate() {
+        shown[slot] = [red_shown, green_shown, blue_shown][channel];
+    }
+    // W and I slots stay where they are
+    let mut colors = shown.into_iter();
+    Some(current.chars().map(|c| if "RGB".contains(c) { colors.next().unwrap_or(c) } else { c }).collect())
+}
+
+/// One extra fixture channel packed before or after a strip's pixels
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum FixtureChannel {
+    /// Constant value, e.g. a dimmer at full. Saved as a plain number.
+    Fixed(u8),
+    /// Follows the effect: the strip's brightest rendered channel this frame, scaled so
+    /// full output sends `level`. Saved as `{"level": n}`.
+    Level { level: u8 },
+}
+
+impl FixtureChannel {
+    /// DMX value when the strip's brightest rendered channel is `strip_level`
+    pub fn value(self, strip_level: u8) -> u8 {
+        match self {
+            FixtureChannel::Fixed(value) => value,
+            FixtureChannel::Level { level } => ((level as u32 * strip_level as u32 + 127) / 255) as u8,
+        }
+    }
+
+    /// Parse the editor syntax: `255` is fixed, `L255` follows the strip level (`L` alone = `L255`)
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        match text.strip_prefix(['L', 'l']) {
+            Some("") => Some(FixtureChannel::Level { level: 255 }),
+            Some(level) => level.trim().parse().ok().map(|level| FixtureChannel::Level { level }),
+            None => text.parse().ok().map(FixtureChannel::Fixed),
+        }
+    }
+}
+
+impl From<u8> for FixtureChannel {
+    fn from(value: u8) -> Self {
+        FixtureChannel::Fixed(value)
+    }
+}
+
+impl std::fmt::Display for FixtureChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixtureChannel::Fixed(value) => write!(f, "{}", value),
+            FixtureChannel::Level { level } => write!(f, "L{}", level),
+        }
+    }
+}
+
 fn default_color_order() -> String {
     "RGB".to_string()
 }
 
+fn default_white_mode() -> String {
+    "Add".to_string()
+}
+
 impl Default for PixelStrip {
     fn default() -> Self {
         Self {
             id: 0,
+            enabled: true,
             universe: 1,
             start_channel: 1,
             pixel_count: 50,
             x: 0.5,
             y: 0.5,
+            z: None,
             spacing: 0.05,
+            leds_per_meter: None,
+            length_m: None,
+            min_brightness: 0,
+            brightness: 1.0,
+            pre_channels: Vec::new(),
+            post_channels: Vec::new(),
             flipped: false,
             color_order: "RGB".to_string(),
+            white_mode: default_white_mode(),
+            channels_per_pixel: 0,
+            notes: String::new(),
+            mirror_source: None,
+            mirror_reversed: false,
             data: vec![[0, 0, 0]; 50],
         }
     }
 }
 
+impl PixelStrip {
+    /// Change the pixel count, truncating `data` or padding it with black
+    pub fn set_pixel_count(&mut self, n: usize) {
+        self.pixel_count = n;
+        self.data.resize(n, [0, 0, 0]);
+    }
+
+    /// Wire slots per pixel: `channels_per_pixel`, or one per letter of `color_order`
+    pub fn pixel_stride(&self) -> usize {
+        let spec = if is_valid_color_order(&self.color_order) { self.color_order.len() } else { 3 };
+        (self.channels_per_pixel as usize).max(spec)
+    }
+
+    /// DMX channels the strip occupies from `start_channel`: pre channels, pixels, post channels
+    pub fn channel_count(&self) -> usize {
+        self.pre_channels.len() + self.pixel_count * self.pixel_stride() + self.post_channels.len()
+    }
+
+    /// Real-world distance between adjacent pixels, from `leds_per_meter` or else `length_m`
+    pub fn pixel_pitch_m(&self) -> Option<f32> {
+        if let Some(lpm) = self.leds_per_meter.filter(|v| *v > 0.0) {
+            return Some(1.0 / lpm);
+        }
+        let length = self.length_m.filter(|v| *v > 0.0)?;
+        (self.pixel_count > 1).then(|| length / (self.pixel_count - 1) as f32)
+    }
+
+    /// Switch to `order` but permute `data` so every pixel still sends the same bytes,
+    /// i.e. what the strip currently shows doesn't change. False for unknown orders.
+    pub fn set_color_order_preserving_look(&mut self, order: &str) -> bool {
+        if !is_valid_color_order(order) {
+            return false;
+        }
+        let (old, new) = (color_order_slots(&self.color_order), color_order_slots(order));
+        for pixel in self.data.iter_mut() {
+            let wire = old.map(|channel| pixel[channel]);
+            for (slot, channel) in new.into_iter().enumerate() {
+                pixel[channel] = wire[slot];
+            }
+        }
+        self.color_order = order.to_string();
+        true
+    }
+
+    /// Derive `spacing` from physical units on a canvas `canvas_width_m` wide.
+    /// Strips without physical units keep their relative spacing.
+    pub fn apply_physical_spacing(&mut self, canvas_width_m: f32) {
+        if let Some(pitch) = self.pixel_pitch_m() {
+            if canvas_width_m > 0.0 {
+                self.spacing = pitch / canvas_width_m;
+            }
+        }
+    }
+
+    /// Mapped (x, y) canvas position of every pixel, indexed like `data`.
+    /// Flipped strips run right-to-left: index 0 sits at the far end.
+    pub fn pixel_positions(&self) -> Vec<(f32, f32)> {
+        (0..self.pixel_count)
+            .map(|i| {
+                let offset = if self.flipped {
+                    (self.pixel_count - 1).saturating_sub(i) as f32 * self.spacing
+                } else {
+                    i as f32 * self.spacing
+                };
+                (self.x + offset, self.y)
+            })
+            .collect()
+    }
+
+    /// `pixel_positions` with the strip's depth added to each point
+    pub fn pixel_positions_3d(&self) -> Vec<(f32, f32, Option<f32>)> {
+        self.pixel_positions().into_iter().map(|(x, y)| (x, y, self.z)).collect()
+    }
+}
+
+/// Distance between two canvas points. Depth only counts when both points have one,
+/// so rigs (or masks) without z keep their 2D distances.
+pub fn spatial_distance(a: (f32, f32, Option<f32>), b: (f32, f32, Option<f32>)) -> f32 {
+    let dz = match (a.2, b.2) {
+        (Some(az), Some(bz)) => az - bz,
+        _ => 0.0,
+    };
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + dz.powi(2)).sqrt()
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Mask {
     pub id: u64,
-    pub mask_type: String, // "scanner", "radial"
+    pub mask_type: String, // "scanner", "radial", "burst", "orbit", "wave"
     pub x: f32,
     pub y: f32,
+    #[serde(default)]
+    pub z: Option<f32>, // Depth of the mask centre; None = distances ignore strip depth
     pub params: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub modulation: Vec<AudioModulation>,
+}
+
+/// Drives a numeric mask param from an audio feature each frame:
+/// `param = base + offset + gain * feature`, where feature is 0.0-1.0.
+/// Params the mask does not set are left alone.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AudioModulation {
+    pub param: String,  // "radius", "speed", "intensity", ...
+    pub source: String, // "Level" (RMS) or "Band"
+    #[serde(default)]
+    pub band: usize,
+    pub gain: f32,
+    #[serde(default)]
+    pub offset: f32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NetworkConfig {
     pub use_multicast: bool,
-    pub unicast_ip: String,
+    #[serde(default)]
+    pub unicast_ips: Vec<String>, // Every universe without a route goes to each of these when multicast is off
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub unicast_ip: String, // DEPRECATED: single target from older saves, moved into `unicast_ips` by `migrate`
     pub universe: u16,
+    #[serde(default)]
+    pub routes: Vec<UniverseRoute>, // Per-range NIC/destination overrides
+    #[serde(default)]
+    pub probe_interval_ms: Option<u64>, // Check unicast nodes are reachable this often; None = off
+    #[serde(default)]
+    pub pause_policy: String, // On pausing output: "blackout" sends a black frame first; "hold" (or empty) just stops so nodes hold
+    #[serde(default)]
+    pub protocol: DmxProtocol,
+    #[serde(default = "default_max_fps")]
+    pub max_fps: u16, // Output frame rate cap; 0 = send every rendered frame
+    #[serde(default)]
+    pub keepalive_ms: Option<u64>, // Resend an unchanged universe only this often; None = send every frame
+}
+
+fn default_max_fps() -> u16 {
+    44
+}
+
+/// Range for `NetworkConfig::keepalive_ms`. sACN receivers drop a source after 2.5 s
+/// without data, so unchanged universes must still go out well within that.
+pub const MIN_KEEPALIVE_MS: u64 = 100;
+pub const MAX_KEEPALIVE_MS: u64 = 1000;
+
+/// Wire protocol for DMX output
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DmxProtocol {
+    /// E1.31 on UDP 5568; multicast goes to 239.255.<universe hi>.<universe lo>
+    #[default]
+    Sacn,
+    /// ArtDmx on UDP 6454; universe 1 is port-address 0, and "multicast" means broadcast
+    ArtNet,
+}
+
+impl DmxProtocol {
+    pub const ALL: [DmxProtocol; 2] = [DmxProtocol::Sacn, DmxProtocol::ArtNet];
+
+    /// Stable lowercase name, as saved
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DmxProtocol::Sacn => "sacn",
+            DmxProtocol::ArtNet => "artnet",
+        }
+    }
+
+    /// Parse a saved name; anything unknown is sACN
+    pub fn from_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("artnet") { DmxProtocol::ArtNet } else { DmxProtocol::Sacn }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DmxProtocol::Sacn => "sACN (E1.31)",
+            DmxProtocol::ArtNet => "Art-Net",
+        }
+    }
+
+    /// Highest universe the protocol can address. Art-Net's port-address is 15 bits
+    /// and universe 1 is port-address 0, so it stops at 32768.
+    pub fn max_universe(self) -> u16 {
+        match self {
+            DmxProtocol::Sacn => 63999,
+            DmxProtocol::ArtNet => 32768,
+        }
+    }
+
+    /// UDP port receivers listen on
+    pub fn port(self) -> u16 {
+        match self {
+            DmxProtocol::Sacn => 5568,
+            DmxProtocol::ArtNet => 6454,
+        }
+    }
 }
 
 impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             use_multicast: true,
-            unicast_ip: "192.168.1.50".to_string(), // Default placeholder
+            unicast_ips: vec!["192.168.1.50".to_string()], // Default placeholder
+            unicast_ip: String::new(),
             universe: 1,
+            routes: Vec::new(),
+            probe_interval_ms: None,
+            pause_policy: String::new(),
+            protocol: DmxProtocol::Sacn,
+            max_fps: default_max_fps(),
+            keepalive_ms: None,
         }
     }
 }
 
+impl NetworkConfig {
+    /// First route covering this output universe, if any.
+    /// Unmapped universes use the global multicast/unicast settings.
+    pub fn route_for(&self, universe: u16) -> Option<&UniverseRoute> {
+        self.routes.iter().find(|r| r.contains(universe))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UniverseRoute {
+    pub universes: (u16, u16), // Inclusive output universe range (first, last)
+    #[serde(default)]
+    pub bind: Option<String>,  // Local interface IP to send from; None = global bind address
+    #[serde(default)]
+    pub dest: Option<String>,  // Unicast destination IP; None = multicast
+}
+
+impl Default for UniverseRoute {
+    fn default() -> Self {
+        Self { universes: (1, 16), bind: None, dest: None }
+    }
+}
+
+impl UniverseRoute {
+    pub fn contains(&self, universe: u16) -> bool {
+        let (first, last) = self.universes;
+        universe >= first.min(last) && universe <= first.max(last)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AudioConfig {
     pub latency_ms: f32,
     pub use_flywheel: bool,
     pub hybrid_sync: bool,
     pub sensitivity: f32,
+    #[serde(default)]
+    pub input_device: Option<String>, // Capture device by name; None = system default
 }
 
 impl Default for AudioConfig {
@@ -122,17 +819,128 @@ impl Default for AudioConfig {
             use_flywheel: true,
             hybrid_sync: false,
             sensitivity: 0.5,
+            input_device: None,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MidiInitConfig {
+    pub sysex: Vec<u8>,   // Sent once the ports are open to enter programmer mode
+    #[serde(default)]
+    pub query: Vec<u8>,   // Mode readback request; empty = don't wait for acknowledgement
+    pub settle_ms: u64,   // Wait after opening ports and after each init attempt
+    pub max_attempts: u8,
+}
+
+/// Ties a MIDI CC (fader, knob or button) to a numeric param of a Global scene's effect.
+/// The CC's 0-127 value is scaled linearly onto `min..=max`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CcBinding {
+    pub controller: u8,
+    pub scene_id: u64,
+    pub effect: usize, // Index into the scene's `global_effects`
+    pub param: String,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl CcBinding {
+    /// Param value for a CC value
+    pub fn value(&self, cc_value: u8) -> f64 {
+        self.min + (self.max - self.min) * cc_value.min(127) as f64 / 127.0
+    }
+}
+
+/// Port name substrings used by automatic detection instead of the built-in Launchpad
+/// matching, for renamed ports or other grid controllers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct MidiPortFilter {
+    pub include: String, // Port names must contain this
+    #[serde(default)]
+    pub exclude: String, // ...and not this; empty = exclude nothing
+}
+
+impl MidiPortFilter {
+    pub fn matches(&self, name: &str) -> bool {
+        name.contains(&self.include) && (self.exclude.is_empty() || !name.contains(&self.exclude))
+    }
+}
+
+/// MIDI ports chosen by hand, matched by exact name, for controllers the automatic
+/// Launchpad detection doesn't recognize
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MidiPortSelection {
+    pub input: String,
+    pub output: String,
+}
+
+/// Current `AppState::version`. Bump it and add a step to `AppState::migrate`
+/// whenever saved data needs rewriting to load correctly.
+pub const STATE_VERSION: u32 = 5;
+
+/// The saved state was written by a newer build than this one
+#[derive(Debug)]
+pub struct NewerVersionError {
+    pub found: u32,
+    pub supported: u32,
+}
+
+impl std::fmt::Display for NewerVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "saved config is format version {} but this build only supports up to {}; update Lightspeed to open it",
+            self.found, self.supported
+        )
+    }
+}
+
+impl std::error::Error for NewerVersionError {}
+
+/// Channels in one DMX universe
+pub const UNIVERSE_CHANNELS: usize = 512;
+
+/// A strip needs more channels than a whole universe, so no assignment can fit it
+#[derive(Debug, PartialEq)]
+pub struct StripTooLargeError {
+    pub strip_id: u64,
+    pub channels: usize,
+}
+
+impl std::fmt::Display for StripTooLargeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "strip {} needs {} channels, more than a universe holds ({}); split it or address it manually",
+            self.strip_id, self.channels, UNIVERSE_CHANNELS
+        )
+    }
+}
+
+impl std::error::Error for StripTooLargeError {}
+
+/// A momentary scene whose pad is down, with the scene state to restore on release
+#[derive(Clone, Debug, PartialEq)]
+pub struct MomentaryHold {
+    pub scene_id: u64,
+    pub active_scene_ids: Vec<u64>,
+    pub selected_scene_id: Option<u64>,
+}
+
+/// Everything the user configures. Owned and mutated only by the UI thread;
+/// see the thread model in `main.rs`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AppState {
+    #[serde(default)]
+    pub version: u32, // Format version; 0 = written before versioning
     pub strips: Vec<PixelStrip>,
     pub masks: Vec<Mask>,
     #[serde(default)]
     pub scenes: Vec<Scene>,
-    pub selected_scene_id: Option<u64>,
+    pub selected_scene_id: Option<u64>, // Scene shown in the editors; the most recently triggered active scene
+    #[serde(default)]
+    pub active_scene_ids: Vec<u64>, // Rendered scene layers, bottom to top
     #[serde(default)]
     pub network: NetworkConfig,
     pub bind_address: Option<String>,
@@ -144,8 +952,1509 @@ pub struct AppState {
     pub layout_locked: bool,
     #[serde(default = "default_midi_enabled")]
     pub midi_enabled: bool,
-}
-
-fn default_midi_enabled() -> bool {
-    true
+    #[serde(default)]
+    pub midi_init: HashMap<String, MidiInitConfig>, // Per-model overrides keyed by LaunchpadModel::name()
+    #[serde(default)]
+    pub midi_heartbeat_ms: Option<u64>, // Probe the Launchpad this often to catch silent disconnects; None = off
+    #[serde(default = "default_midi_min_velocity")]
+    pub midi_min_velocity: u8, // Pad presses softer than this are dropped as accidental touches
+    #[serde(default)]
+    pub midi_ports: Option<MidiPortSelection>, // Pinned ports; None = detect a Launchpad automatically
+    #[serde(default)]
+    pub midi_cc_bindings: Vec<CcBinding>, // CCs riding effect params live
+    #[serde(default)]
+    pub midi_port_filter: Option<MidiPortFilter>, // None = built-in Launchpad name matching
+    #[serde(default = "default_midi_debounce_ms")]
+    pub midi_debounce_ms: u64, // Repeat presses of a pad within this are dropped as double-fires; 0 = off
+    #[serde(default)]
+    pub transition_ms: u32, // Crossfade between rendered frames when the active scenes change; 0 = cut
+    #[serde(default)]
+    pub manual_addressing: bool, // true = new strips keep the address typed in; false = repack with `reassign_dmx_addresses`
+    #[serde(default = "default_gamma")]
+    pub gamma: f32, // Output curve exponent applied per channel when packing DMX; 1.0 = linear
+    #[serde(default)]
+    pub preview_port: Option<u16>, // Live WebSocket preview; None = off
+    #[serde(default)]
+    pub bpm: Option<f64>, // Tapped tempo; None = follow Link / audio / master speed
+    #[serde(default)]
+    pub tap_btn: Option<u8>, // Launchpad pad used for tap tempo
+    #[serde(default)]
+    pub tap_is_cc: bool,
+    #[serde(default)]
+    pub beat_pad: Option<u8>, // Launchpad pad that flashes on every fourth audio beat; None = off
+    #[serde(default)]
+    pub panic_btn: Option<u8>, // Launchpad pad that toggles blackout
+    #[serde(default)]
+    pub panic_is_cc: bool,
+    #[serde(default)]
+    pub presets: Vec<EffectPreset>,
+    #[serde(default)]
+    pub render_budget_ms: Option<f32>, // Adaptive quality: lower the scene update rate while renders exceed this; None = off
+    #[serde(default)]
+    pub idle_scene_id: Option<u64>, // Scene shown after `idle_timeout_ms` without MIDI or UI input
+    #[serde(default)]
+    pub idle_timeout_ms: Option<u64>, // None = idle fallback off
+    #[serde(default)]
+    pub output_threads: u8, // sACN send threads, universes sharded across them; 0/1 = send serially
+    #[serde(default)]
+    pub startup_fade_ms: u64, // Ramp total output up from black after launch; 0 = off
+    #[serde(default = "default_master_brightness")]
+    pub master_brightness: f32, // 0.0-1.0 multiply on the whole frame, applied with the startup fade
+    #[serde(default)]
+    pub blackout: bool, // Blackout latched when the app last saved; restored on launch
+    #[serde(skip)]
+    pub frozen: bool, // Hold the current frame on the wire (runtime only, never saved)
+    #[serde(skip)]
+    pub momentary_holds: Vec<MomentaryHold>, // Momentary pads currently held, oldest first (runtime only)
+    #[serde(default)]
+    pub freeze_btn: Option<u8>, // Launchpad pad that toggles `frozen`
+    #[serde(default)]
+    pub freeze_is_cc: bool,
+    #[serde(default)]
+    pub freeze_resume_phase: bool, // Unfreeze continues effect time from the frozen moment instead of jumping to now
+    #[serde(default)]
+    pub max_flash_hz: Option<f32>, // Strobe safety: flash effects are slowed to at most this rate; None = no limit
+    #[serde(default)]
+    pub no_strobe: bool, // Accessibility: flash effects render as a steady dimmed color
+    #[serde(default)]
+    pub quantize: String, // "beat" | "bar": restart edited effects' phase on that grid; "" / "none" = off
+    #[serde(default)]
+    pub canvas_width_m: Option<f32>, // Real-world width of the 0..1 canvas; None = DEFAULT_CANVAS_WIDTH_M
+    #[serde(default)]
+    pub chains: Vec<StripChain>,
+    #[serde(default)]
+    pub shuffle_params: Vec<String>, // Params the shuffle command may change; empty = all with a range
+    #[serde(default)]
+    pub shuffle_interval_ms: Option<u64>, // Shuffle the active scene on this timer; None = manual only
+}
+
+/// Canvas width assumed for strips with physical units when `AppState.canvas_width_m` is unset
+pub const DEFAULT_CANVAS_WIDTH_M: f32 = 10.0;
+
+fn default_midi_enabled() -> bool {
+    true
+}
+
+fn default_midi_min_velocity() -> u8 {
+    1
+}
+
+fn default_midi_debounce_ms() -> u64 {
+    30
+}
+
+/// Gamma for new installs; most pixel LEDs look right around 2.2
+pub const DEFAULT_GAMMA: f32 = 2.2;
+pub const MIN_GAMMA: f32 = 1.0;
+pub const MAX_GAMMA: f32 = 4.0;
+
+fn default_gamma() -> f32 {
+    DEFAULT_GAMMA
+}
+
+fn default_master_brightness() -> f32 {
+    1.0
+}
+
+// Same values serde fills in for missing fields, so a default state renders like a fresh file
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            strips: Vec::new(),
+            masks: Vec::new(),
+            scenes: Vec::new(),
+            selected_scene_id: None,
+            active_scene_ids: Vec::new(),
+            network: NetworkConfig::default(),
+            bind_address: None,
+            mode: String::new(),
+            effect: String::new(),
+            audio: AudioConfig::default(),
+            layout_locked: false,
+            midi_enabled: default_midi_enabled(),
+            midi_init: HashMap::new(),
+            midi_heartbeat_ms: None,
+            midi_min_velocity: default_midi_min_velocity(),
+            midi_ports: None,
+            midi_cc_bindings: Vec::new(),
+            midi_port_filter: None,
+            midi_debounce_ms: default_midi_debounce_ms(),
+            transition_ms: 0,
+            manual_addressing: false,
+            gamma: default_gamma(),
+            preview_port: None,
+            bpm: None,
+            tap_btn: None,
+            tap_is_cc: false,
+            beat_pad: None,
+            panic_btn: None,
+            panic_is_cc: false,
+            presets: Vec::new(),
+            render_budget_ms: None,
+            idle_scene_id: None,
+            idle_timeout_ms: None,
+            output_threads: 0,
+            startup_fade_ms: 0,
+            master_brightness: default_master_brightness(),
+            blackout: false,
+            frozen: false,
+            momentary_holds: Vec::new(),
+            freeze_btn: None,
+            freeze_is_cc: false,
+            freeze_resume_phase: false,
+            max_flash_hz: None,
+            no_strobe: false,
+            quantize: String::new(),
+            canvas_width_m: None,
+            chains: Vec::new(),
+            shuffle_params: Vec::new(),
+            shuffle_interval_ms: None,
+        }
+    }
+}
+
+impl AppState {
+    /// Bring state loaded from an older format up to `STATE_VERSION`, one step per version.
+    /// Refuses state from a newer build instead of silently dropping fields it doesn't know.
+    pub fn migrate(&mut self) -> Result<(), NewerVersionError> {
+        if self.version > STATE_VERSION {
+            return Err(NewerVersionError { found: self.version, supported: STATE_VERSION });
+        }
+        while self.version < STATE_VERSION {
+            match self.version {
+                0 => self.migrate_legacy_globals(),
+                1 => {
+                    // The single selected scene becomes the only active layer
+                    if self.active_scene_ids.is_empty() {
+                        self.active_scene_ids.extend(self.selected_scene_id);
+                    }
+                }
+                2 => {
+                    // Params are validated against their kind now; drop leftovers from
+                    // earlier kinds, which never rendered, so they aren't reported
+                    for preset in &mut self.presets {
+                        prune_unknown_params(&preset.kind, &mut preset.params);
+                    }
+                    for scene in &mut self.scenes {
+                        for config in &mut scene.global_effects {
+                            prune_unknown_params(&config.effect.kind, &mut config.effect.params);
+                        }
+                    }
+                }
+                // Step 0 used to skip scenes whose kind wasn't "Global", stranding their effect
+                3 => self.migrate_legacy_globals(),
+                4 => {
+                    // The single unicast IP becomes the first of a list
+                    let legacy = std::mem::take(&mut self.network.unicast_ip);
+                    if self.network.unicast_ips.is_empty() && !legacy.trim().is_empty() {
+                        self.network.unicast_ips.push(legacy);
+                    }
+                }
+                _ => unreachable!("no migration step from version {}", self.version),
+            }
+            self.version += 1;
+        }
+        Ok(())
+    }
+
+    /// Move each scene's deprecated `global` into `global_effects` (applied to all strips)
+    /// when it has none yet. A scene without masks becomes a Global scene so the effect renders.
+    fn migrate_legacy_globals(&mut self) {
+        for scene in &mut self.scenes {
+            if !scene.global_effects.is_empty() {
+                continue;
+            }
+            if let Some(old_global) = scene.global.take() {
+                scene.global_effects.push(GlobalEffectConfig {
+                    effect: old_global,
+                    targets: None, // Apply to all
+                });
+                if scene.masks.is_empty() {
+                    scene.kind = "Global".to_string();
+                }
+                log::info!("Migrated scene '{}' global effect", scene.name);
+            }
+        }
+    }
+
+    /// Rebuild runtime-only state that serde skips. Call after deserializing:
+    /// loaded strips come back with empty `data`, which effects would index out of bounds.
+    pub fn init_runtime(&mut self) {
+        for strip in &mut self.strips {
+            strip.data = vec![[0, 0, 0]; strip.pixel_count];
+        }
+        self.apply_physical_spacing();
+        self.apply_chains();
+        self.normalize_colors();
+    }
+
+    /// Normalize hand-written color params everywhere effects and masks read them
+    pub fn normalize_colors(&mut self) {
+        for mask in &mut self.masks {
+            normalize_color_params(&mut mask.params);
+        }
+        for preset in &mut self.presets {
+            normalize_color_params(&mut preset.params);
+        }
+        for scene in &mut self.scenes {
+            for mask in &mut scene.masks {
+                normalize_color_params(&mut mask.params);
+            }
+            if let Some(global) = &mut scene.global {
+                normalize_color_params(&mut global.params);
+            }
+            for config in &mut scene.global_effects {
+                normalize_color_params(&mut config.effect.params);
+            }
+        }
+    }
+
+    /// Address every chained strip contiguously after the previous segment of its chain.
+    /// Unknown strip ids are skipped; a strip listed in several chains follows the last one.
+    pub fn apply_chains(&mut self) {
+        for chain in &self.chains {
+            let mut channel = chain.start_channel;
+            for id in &chain.strip_ids {
+                if let Some(strip) = self.strips.iter_mut().find(|s| s.id == *id) {
+                    strip.universe = chain.universe;
+                    strip.start_channel = channel;
+                    channel = channel.saturating_add(strip.channel_count().min(u16::MAX as usize) as u16);
+                }
+            }
+        }
+    }
+
+    /// Pack strips in list order into universes from 1/1, each starting right after the
+    /// previous one and rolling over to the next universe when it wouldn't fit (pixel stride
+    /// follows the color order). Chained strips are left to `apply_chains`, and the
+    /// channels each chain covers are skipped over so nothing is packed on top of them.
+    /// Returns the `(strip id, universe, start channel)` of every strip placed, or an error,
+    /// changing nothing, when a strip is larger than a universe.
+    pub fn reassign_dmx_addresses(&mut self) -> Result<Vec<(u64, u16, u16)>, StripTooLargeError> {
+        let chained: std::collections::HashSet<u64> = self.chains.iter().flat_map(|c| c.strip_ids.iter().copied()).collect();
+        // (universe, first channel, last channel) of every chain
+        let reserved: Vec<(u16, usize, usize)> = self.chains.iter()
+            .filter_map(|chain| {
+                let channels: usize = chain.strip_ids.iter()
+                    .filter_map(|id| self.strips.iter().find(|s| s.id == *id))
+                    .map(|s| s.channel_count())
+                    .sum();
+                let first = chain.start_channel.max(1) as usize;
+                (channels > 0).then(|| (chain.universe, first, first + channels - 1))
+            })
+            .collect();
+        let mut assignments = Vec::new();
+        let (mut universe, mut next) = (1u16, 1usize);
+        for strip in self.strips.iter().filter(|s| !chained.contains(&s.id)) {
+            let channels = strip.channel_count();
+            if channels > UNIVERSE_CHANNELS {
+                return Err(StripTooLargeError { strip_id: strip.id, channels });
+            }
+            loop {
+                if next + channels - 1 > UNIVERSE_CHANNELS {
+                    universe = universe.saturating_add(1);
+                    next = 1;
+                }
+                let last = next + channels - 1;
+                match reserved.iter().find(|(u, first, end)| *u == universe && next <= *end && *first <= last) {
+                    Some(&(_, _, end)) if universe < u16::MAX => next = end + 1,
+                    _ => break,
+                }
+            }
+            assignments.push((strip.id, universe, next as u16));
+            next += channels;
+        }
+        for &(id, universe, start_channel) in &assignments {
+            if let Some(strip) = self.strips.iter_mut().find(|s| s.id == id) {
+                strip.universe = universe;
+                strip.start_channel = start_channel;
+            }
+        }
+        Ok(assignments)
+    }
+
+    /// Point strip `id` at `source` (None clears it). Returns false, changing nothing,
+    /// when the strip doesn't exist or the link would close a mirror cycle.
+    pub fn set_mirror_source(&mut self, id: u64, source: Option<u64>) -> bool {
+        let mut next = source;
+        for _ in 0..=self.strips.len() {
+            match next {
+                Some(s) if s == id => return false,
+                Some(s) => next = self.strips.iter().find(|strip| strip.id == s).and_then(|strip| strip.mirror_source),
+                None => break,
+            }
+        }
+        let Some(strip) = self.strips.iter_mut().find(|s| s.id == id) else { return false };
+        strip.mirror_source = source;
+        true
+    }
+
+    /// Set the color order of strips `ids`, optionally keeping their current look
+    /// (see `PixelStrip::set_color_order_preserving_look`). Returns the number changed.
+    pub fn set_color_order(&mut self, ids: &[u64], order: &str, preserve_look: bool) -> usize {
+        if !is_valid_color_order(order) {
+            return 0;
+        }
+        let mut changed = 0;
+        for strip in self.strips.iter_mut().filter(|s| ids.contains(&s.id) && s.color_order != order) {
+            if preserve_look {
+                strip.set_color_order_preserving_look(order);
+            } else {
+                strip.color_order = order.to_string();
+            }
+            changed += 1;
+        }
+        changed
+    }
+
+    /// Randomize the active scene's global effects (see `shuffle_effect`).
+    /// Returns the number of params changed; 0 without an active Global scene.
+    pub fn shuffle_active_scene(&mut self, rng: &mut impl rand::Rng) -> usize {
+        let Some(scene) = self.selected_scene_id
+            .and_then(|id| self.scenes.iter_mut().find(|s| s.id == id)) else { return 0 };
+        let mut changed = 0;
+        for config in &mut scene.global_effects {
+            let kind = config.effect.resolve(&self.presets).kind.clone();
+            changed += shuffle_effect(&mut config.effect, &kind, &self.shuffle_params, rng);
+        }
+        changed
+    }
+
+    /// Copy global effect `from` onto `to`, each given as (scene id, effect index).
+    /// `params_only` keeps the target's kind and takes the source's resolved params;
+    /// otherwise the target becomes a copy of the source, preset link included.
+    /// Returns false when either effect doesn't exist.
+    pub fn copy_effect(&mut self, from: (u64, usize), to: (u64, usize), params_only: bool) -> bool {
+        let Some(source) = self.scenes.iter()
+            .find(|s| s.id == from.0)
+            .and_then(|s| s.global_effects.get(from.1))
+            .map(|c| c.effect.clone()) else { return false };
+        let Some(target) = self.scenes.iter_mut()
+            .find(|s| s.id == to.0)
+            .and_then(|s| s.global_effects.get_mut(to.1)) else { return false };
+        if params_only {
+            target.effect.copy_params_from(&source.resolve(&self.presets));
+        } else {
+            target.effect = source;
+        }
+        true
+    }
+
+    /// Ids of strips whose origin (`x`, `y`) lies in the normalized rectangle, edges included.
+    /// Corners may be given in any order.
+    pub fn strips_in_region(&self, x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<u64> {
+        let (min_x, max_x) = (x0.min(x1), x0.max(x1));
+        let (min_y, max_y) = (y0.min(y1), y0.max(y1));
+        self.strips.iter()
+            .filter(|s| (min_x..=max_x).contains(&s.x) && (min_y..=max_y).contains(&s.y))
+            .map(|s| s.id)
+            .collect()
+    }
+
+    /// Activate scene `id` as the top layer and select it. Active scenes in the same
+    /// exclusivity group are deactivated; an ungrouped scene that is already active toggles off.
+    pub fn trigger_scene(&mut self, id: u64) {
+        let Some(group) = self.scenes.iter().find(|s| s.id == id).map(|s| s.exclusive_group.clone()) else { return };
+        if group.is_empty() && self.active_scene_ids.contains(&id) {
+            self.deactivate_scene(id);
+            return;
+        }
+        let scenes = &self.scenes;
+        self.active_scene_ids.retain(|active| {
+            // Also drops ids of deleted scenes
+            *active != id && scenes.iter()
+                .find(|s| s.id == *active)
+                .is_some_and(|s| group.is_empty() || s.exclusive_group != group)
+        });
+        self.active_scene_ids.push(id);
+        self.selected_scene_id = Some(id);
+    }
+
+    /// Activate momentary scene `id` while its pad is held, remembering what was active so
+    /// `release_scene` can put it back. Holding an already-active scene keeps it on rather than
+    /// toggling it off; a repeated press of a pad that is still held is ignored.
+    pub fn hold_scene(&mut self, id: u64) {
+        if !self.scenes.iter().any(|s| s.id == id) || self.momentary_holds.iter().any(|h| h.scene_id == id) {
+            return;
+        }
+        self.momentary_holds.push(MomentaryHold {
+            scene_id: id,
+            active_scene_ids: self.active_scene_ids.clone(),
+            selected_scene_id: self.selected_scene_id,
+        });
+        if self.active_scene_ids.contains(&id) {
+            self.active_scene_ids.retain(|active| *active != id);
+            self.active_scene_ids.push(id);
+            self.selected_scene_id = Some(id);
+        } else {
+            self.trigger_scene(id);
+        }
+    }
+
+    /// Release a held momentary scene. Releasing the newest hold restores the scenes that were
+    /// active before it was pressed; releasing an older one while newer pads are still down
+    /// hands its saved state to the next hold, so the last release still lands where the
+    /// first press started.
+    pub fn release_scene(&mut self, id: u64) {
+        let Some(pos) = self.momentary_holds.iter().position(|h| h.scene_id == id) else { return };
+        let hold = self.momentary_holds.remove(pos);
+        if let Some(next) = self.momentary_holds.get_mut(pos) {
+            next.active_scene_ids = hold.active_scene_ids;
+            next.selected_scene_id = hold.selected_scene_id;
+            return;
+        }
+        let scenes = &self.scenes;
+        let exists = |id: &u64| scenes.iter().any(|s| s.id == *id);
+        self.active_scene_ids = hold.active_scene_ids.into_iter().filter(|id| exists(id)).collect();
+        self.selected_scene_id = hold.selected_scene_id.filter(|id| exists(id));
+    }
+
+    /// Remove a scene from the active layers; selection falls back to the top remaining layer
+    pub fn deactivate_scene(&mut self, id: u64) {
+        self.active_scene_ids.retain(|active| *active != id);
+        if self.selected_scene_id == Some(id) {
+            self.selected_scene_id = self.active_scene_ids.last().copied();
+        }
+    }
+
+    /// Bring saved scene references back in line with the scenes that still exist, so
+    /// startup resumes what was running: deleted scenes are dropped, and a saved selection
+    /// with nothing active becomes the active layer again.
+    pub fn resume_scenes(&mut self) {
+        let scenes = &self.scenes;
+        let exists = |id: &u64| scenes.iter().any(|s| s.id == *id);
+        self.active_scene_ids.retain(|id| exists(id));
+        self.idle_scene_id = self.idle_scene_id.filter(|id| exists(id));
+        self.selected_scene_id = match self.selected_scene_id.filter(|id| exists(id)) {
+            Some(id) if self.active_scene_ids.is_empty() => {
+                self.active_scene_ids.push(id);
+                Some(id)
+            }
+            Some(id) if self.active_scene_ids.contains(&id) => Some(id),
+            _ => self.active_scene_ids.last().copied(),
+        };
+    }
+
+    /// One message per flash effect that would run faster than `max_flash_hz` at `bpm`
+    /// and is being slowed down. Empty without a limit or with `no_strobe` on.
+    pub fn strobe_warnings(&self, bpm: f64) -> Vec<String> {
+        let Some(max_hz) = self.max_flash_hz.filter(|_| !self.no_strobe) else { return Vec::new() };
+        let mut warnings = Vec::new();
+        for scene in self.scenes.iter().filter(|s| s.kind == "Global") {
+            for config in scene.global_effects.iter().filter(|c| !c.is_disabled()) {
+                let effect = config.effect.resolve(&self.presets);
+                if !STROBE_EFFECTS.contains(&effect.kind.as_str()) {
+                    continue;
+                }
+                // Flash follows the beat grid; Strobe runs at its own frequency
+                let (hz, at) = match EffectParams::lenient(&effect.kind, &effect.params) {
+                    Some(EffectParams::Flash { rate, .. }) => {
+                        let hz = bpm / 60.0 / flash_divisor(&rate);
+                        (hz, format!("{} is {:.1} Hz", rate, hz))
+                    }
+                    Some(EffectParams::Strobe { frequency_hz, .. }) => (frequency_hz as f64, format!("{:.1} Hz", frequency_hz)),
+                    _ => continue,
+                };
+                if hz > max_hz as f64 {
+                    warnings.push(format!("{}: {} at {}, limited to {} Hz", scene.name, effect.kind, at, max_hz));
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Deactivate every scene (loose masks render instead)
+    pub fn clear_active_scenes(&mut self) {
+        self.active_scene_ids.clear();
+        self.selected_scene_id = None;
+    }
+
+    /// Replace the active scenes with the idle scene alone. Returns the previous
+    /// (active scene ids, selected scene) to restore later, or None when no idle scene
+    /// is configured or it no longer exists.
+    pub fn activate_idle_scene(&mut self) -> Option<(Vec<u64>, Option<u64>)> {
+        let id = self.idle_scene_id.filter(|id| self.scenes.iter().any(|s| s.id == *id))?;
+        let previous = (std::mem::replace(&mut self.active_scene_ids, vec![id]), self.selected_scene_id);
+        self.selected_scene_id = Some(id);
+        Some(previous)
+    }
+
+    pub fn is_scene_active(&self, id: u64) -> bool {
+        self.active_scene_ids.contains(&id)
+    }
+
+    /// First scene bound to a Launchpad pad (note, or CC when `is_cc`)
+    pub fn scene_for_pad(&self, note: u8, is_cc: bool) -> Option<&Scene> {
+        self.scenes.iter().find(|s| s.launchpad_btn == Some(note) && s.launchpad_is_cc == is_cc)
+    }
+
+    /// (pad note or button CC, palette color) for every scene with a Launchpad color, in
+    /// scene order, for `MidiCommand::SetGrid`
+    pub fn launchpad_colors(&self) -> Vec<(u8, u8)> {
+        self.scenes.iter()
+            .filter_map(|s| Some((s.launchpad_btn.filter(|b| *b != 0)?, s.launchpad_color?)))
+            .collect()
+    }
+
+    /// Bindings for `controller`; an unmapped CC has none
+    pub fn cc_bindings_for(&self, controller: u8) -> impl Iterator<Item = &CcBinding> {
+        self.midi_cc_bindings.iter().filter(move |b| b.controller == controller)
+    }
+
+    /// Pads bound to more than one scene as (note, is_cc, scene ids), sorted by pad
+    pub fn binding_conflicts(&self) -> Vec<(u8, bool, Vec<u64>)> {
+        let mut by_pad: std::collections::BTreeMap<(u8, bool), Vec<u64>> = std::collections::BTreeMap::new();
+        for scene in &self.scenes {
+            if let Some(btn) = scene.launchpad_btn.filter(|b| *b != 0) {
+                by_pad.entry((btn, scene.launchpad_is_cc)).or_default().push(scene.id);
+            }
+        }
+        by_pad.into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|((note, is_cc), ids)| (note, is_cc, ids))
+            .collect()
+    }
+
+    pub fn chain_of(&self, strip_id: u64) -> Option<&StripChain> {
+        self.chains.iter().rev().find(|c| c.strip_ids.contains(&strip_id))
+    }
+
+    pub fn canvas_width(&self) -> f32 {
+        self.canvas_width_m.filter(|w| *w > 0.0).unwrap_or(DEFAULT_CANVAS_WIDTH_M)
+    }
+
+    /// Recompute `spacing` for every strip that has physical units
+    pub fn apply_physical_spacing(&mut self) {
+        let width = self.canvas_width();
+        for strip in &mut self.strips {
+            strip.apply_physical_spacing(width);
+        }
+    }
+
+    /// Reorder scenes to follow `new_order` (scene ids). Scenes not listed keep
+    /// their relative order after the listed ones. Unknown or repeated ids are
+    /// rejected without touching `scenes`.
+    pub fn reorder_scenes(&mut self, new_order: Vec<u64>) -> Result<(), String> {
+        let mut seen = std::collections::HashSet::new();
+        for id in &new_order {
+            if !self.scenes.iter().any(|s| s.id == *id) {
+                return Err(format!("Unknown scene id {}", id));
+            }
+            if !seen.insert(*id) {
+                return Err(format!("Scene id {} listed more than once", id));
+            }
+        }
+
+        let mut remaining = std::mem::take(&mut self.scenes);
+        let mut ordered = Vec::with_capacity(remaining.len());
+        for id in new_order {
+            let idx = remaining.iter().position(|s| s.id == id).unwrap();
+            ordered.push(remaining.remove(idx));
+        }
+        ordered.extend(remaining);
+        self.scenes = ordered;
+        Ok(())
+    }
+
+    /// Assign `category` to every scene in `scene_ids`.
+    /// Unknown ids are rejected without touching `scenes`.
+    pub fn set_category(&mut self, scene_ids: &[u64], category: String) -> Result<(), String> {
+        if let Some(id) = scene_ids.iter().find(|id| !self.scenes.iter().any(|s| s.id == **id)) {
+            return Err(format!("Unknown scene id {}", id));
+        }
+        for scene in self.scenes.iter_mut().filter(|s| scene_ids.contains(&s.id)) {
+            scene.category = category.clone();
+        }
+        Ok(())
+    }
+}
+
+/// Indices of strips with a usable `mirror_source`, ordered so every source comes before the
+/// strips copying it. Strips whose source is missing or that sit on a mirror cycle are left out.
+pub fn mirror_order(strips: &[PixelStrip]) -> Vec<usize> {
+    let index: HashMap<u64, usize> = strips.iter().enumerate().map(|(i, s)| (s.id, i)).collect();
+    let source_of = |i: usize| strips[i].mirror_source.and_then(|id| index.get(&id).copied());
+    let mut ordered: Vec<(usize, usize)> = Vec::new();
+    for i in 0..strips.len() {
+        let Some(mut current) = source_of(i) else { continue };
+        let mut depth = 1;
+        while let Some(next) = source_of(current) {
+            depth += 1;
+            current = next;
+            if depth > strips.len() {
+                break;
+            }
+        }
+        if depth <= strips.len() {
+            ordered.push((depth, i));
+        }
+    }
+    ordered.sort();
+    ordered.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Copy each mirroring strip's source data onto it, sources first (see `mirror_order`).
+/// Strips of different lengths are stretched nearest-pixel. A mirror that can't be
+/// followed renders normally and is logged once, the first time its id enters `warned`.
+pub fn apply_mirrors(strips: &mut [PixelStrip], warned: &mut HashSet<u64>) {
+    let order = mirror_order(strips);
+    for (i, strip) in strips.iter().enumerate() {
+        let Some(source) = strip.mirror_source else { continue };
+        if order.contains(&i) || !warned.insert(strip.id) {
+            continue;
+        }
+        if strips.iter().any(|s| s.id == source) {
+            log::warn!("Strip {} is on a mirror cycle; rendering it normally", strip.id);
+        } else {
+            log::warn!("Strip {} mirrors missing strip {}; rendering it normally", strip.id, source);
+        }
+    }
+
+    for i in order {
+        let Some(source) = strips.iter().find(|s| Some(s.id) == strips[i].mirror_source).map(|s| s.data.clone()) else { continue };
+        if source.is_empty() {
+            continue;
+        }
+        let target = &mut strips[i];
+        let n = target.data.len();
+        for (p, pixel) in target.data.iter_mut().enumerate() {
+            let p = if target.mirror_reversed { n - 1 - p } else { p };
+            *pixel = source[p * source.len() / n];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_state(ids: &[u64]) -> AppState {
+        let scenes = ids.iter().map(|&id| Scene {
+            id,
+            name: format!("Scene {}", id),
+            kind: "Masks".into(),
+            category: default_category(),
+            masks: Vec::new(),
+            global: None,
+            global_effects: Vec::new(),
+            launchpad_btn: None,
+            launchpad_is_cc: false,
+            launchpad_color: None,
+            launchpad_momentary: false,
+            notes: String::new(),
+            exclusive_group: default_exclusive_group(),
+            layer: 0,
+            opacity: 1.0,
+            blend_mode: String::new(),
+        }).collect();
+        AppState { scenes, ..Default::default() }
+    }
+
+    fn scene_ids(state: &AppState) -> Vec<u64> {
+        state.scenes.iter().map(|s| s.id).collect()
+    }
+
+    #[test]
+    fn test_default_state_matches_serde_defaults() {
+        let parsed: AppState = serde_json::from_str(r#"{"strips": [], "masks": [], "selected_scene_id": null, "bind_address": null, "mode": "", "effect": ""}"#).unwrap();
+        for state in [AppState::default(), parsed] {
+            assert_eq!((state.master_brightness, state.gamma), (1.0, DEFAULT_GAMMA));
+            assert_eq!((state.midi_enabled, state.midi_min_velocity, state.midi_debounce_ms), (true, 1, 30));
+        }
+    }
+
+    #[test]
+    fn test_fixture_channel_parse_and_level() {
+        assert_eq!(FixtureChannel::parse(" 255 "), Some(FixtureChannel::Fixed(255)));
+        assert_eq!(FixtureChannel::parse("L200"), Some(FixtureChannel::Level { level: 200 }));
+        assert_eq!(FixtureChannel::parse("l"), Some(FixtureChannel::Level { level: 255 }));
+        assert_eq!(FixtureChannel::parse("L300"), None);
+        assert_eq!(FixtureChannel::Level { level: 200 }.to_string(), "L200");
+        assert_eq!(FixtureChannel::Fixed(9).value(0), 9);
+        assert_eq!(FixtureChannel::Level { level: 255 }.value(128), 128);
+        assert_eq!(FixtureChannel::Level { level: 128 }.value(0), 0);
+    }
+
+    #[test]
+    fn test_set_pixel_count_resizes_data() {
+        let mut strip = PixelStrip::default();
+        strip.data[0] = [255, 0, 0];
+        strip.set_pixel_count(10);
+        assert_eq!(strip.data.len(), 10);
+        assert_eq!(strip.data[0], [255, 0, 0]);
+        strip.set_pixel_count(60);
+        assert_eq!(strip.data.len(), 60);
+        assert_eq!(strip.data[59], [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_color_order_preserving_look_keeps_wire_bytes() {
+        let mut strip = PixelStrip { pixel_count: 2, data: vec![[10, 20, 30], [1, 2, 3]], ..Default::default() };
+        let wire = |s: &PixelStrip| s.data.iter().map(|p| color_order_slots(&s.color_order).map(|c| p[c])).collect::<Vec<_>>();
+        let before = wire(&strip);
+        for order in COLOR_ORDERS {
+            assert!(strip.set_color_order_preserving_look(order));
+            assert_eq!(wire(&strip), before, "order {}", order);
+        }
+        assert!(!strip.set_color_order_preserving_look("XYZ"));
+        assert_eq!(color_order_slots("GRB"), [1, 0, 2]);
+        assert_eq!(color_order_slots("nope"), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_pack_pixel_extended_orders() {
+        assert!(is_valid_color_order("GRBW") && is_valid_color_order("IRGB"));
+        assert!(!is_valid_color_order("RGBB") && !is_valid_color_order("RGWW") && !is_valid_color_order("RG"));
+
+        let mut out = [9u8; 5];
+        pack_pixel([200, 100, 50], "GRBW", "Add", &mut out);
+        assert_eq!(out, [100, 200, 50, 50, 0]); // Padding slot zeroed
+        pack_pixel([200, 100, 50], "GRBW", "Subtract", &mut out);
+        assert_eq!(out, [50, 150, 0, 50, 0]);
+        pack_pixel([200, 100, 50], "GRBW", "Off", &mut out);
+        assert_eq!(out, [100, 200, 50, 0, 0]);
+        pack_pixel([200, 100, 50], "IRGB", "Subtract", &mut out[..4]); // No W slot, RGB untouched
+        assert_eq!(out[..4], [255, 200, 100, 50]);
+        pack_pixel([200, 100, 50], "nope", "Add", &mut out[..3]);
+        assert_eq!(out[..3], [200, 100, 50]);
+        assert_eq!(color_order_slots("WGRB"), [1, 0, 2]);
+        assert_eq!(infer_color_order("RGBW", 'G', 'R').as_deref(), Some("GRBW"));
+
+        let mut strip = PixelStrip { pixel_count: 10, color_order: "RGBW".into(), pre_channels: vec![0.into()], ..Default::default() };
+        assert_eq!((strip.pixel_stride(), strip.channel_count()), (4, 41));
+        strip.channels_per_pixel = 6;
+        assert_eq!(strip.pixel_stride(), 6);
+        strip.channels_per_pixel = 2; // Never fewer slots than the spec needs
+        assert_eq!(strip.pixel_stride(), 4);
+    }
+
+    #[test]
+    fn test_infer_color_order_from_test_pattern() {
+        // A GRB strip set to RGB shows red as green and green as red
+        assert_eq!(infer_color_order("RGB", 'G', 'R').as_deref(), Some("GRB"));
+        assert_eq!(infer_color_order("RGB", 'R', 'G').as_deref(), Some("RGB"));
+        // Already set to GRB but the strip is really BRG
+        assert_eq!(infer_color_order("GRB", 'R', 'B').as_deref(), Some("BRG"));
+        assert_eq!(infer_color_order("RGB", 'R', 'R'), None);
+        assert_eq!(infer_color_order("RGB", 'W', 'R'), None);
+
+        // Whatever the hardware is, the inferred order shows every color correctly
+        for hardware in COLOR_ORDERS {
+            for current in COLOR_ORDERS {
+                let shows = |channel: usize| {
+                    let slot = color_order_slots(current).iter().position(|c| *c == channel).unwrap();
+                    hardware.chars().nth(slot).unwrap()
+                };
+                assert_eq!(infer_color_order(current, shows(0), shows(1)).as_deref(), Some(*hardware));
+            }
+        }
+
+        let mut state = AppState {
+            strips: (1..=3).map(|id| PixelStrip { id, ..Default::default() }).collect(),
+            ..Default::default()
+        };
+        assert_eq!(state.set_color_order(&[1, 3], "BGR", false), 2);
+        assert_eq!(state.set_color_order(&[1, 2], "BGR", true), 1);
+        assert_eq!(state.set_color_order(&[1], "??", false), 0);
+    }
+
+    #[test]
+    fn test_init_runtime_allocates_strip_data() {
+        let json = r#"{"strips":[{"id":1,"universe":1,"start_channel":1,"pixel_count":30,"x":0.1,"y":0.2,"spacing":0.01}],
+                       "masks":[],"selected_scene_id":null,"bind_address":null,"mode":"","effect":""}"#;
+        let mut state: AppState = serde_json::from_str(json).unwrap();
+        assert!(state.strips[0].data.is_empty());
+        state.init_runtime();
+        assert_eq!(state.strips[0].data, vec![[0, 0, 0]; 30]);
+    }
+
+    #[test]
+    fn test_resolve_layers_overrides_on_preset() {
+        let preset = EffectPreset {
+            id: 5,
+            name: "Warm Fire".into(),
+            kind: "Plasma".into(),
+            params: serde_json::from_value(serde_json::json!({"palette": "Fire", "speed": 0.5})).unwrap(),
+        };
+        let effect = GlobalEffect {
+            kind: "Rainbow".into(),
+            params: serde_json::from_value(serde_json::json!({"speed": 2.0})).unwrap(),
+            preset_id: Some(5),
+            automation: Vec::new(),
+        };
+
+        let resolved = effect.resolve(std::slice::from_ref(&preset));
+        assert_eq!(resolved.kind, "Plasma");
+        assert_eq!(resolved.params["palette"].as_str(), Some("Fire"));
+        assert_eq!(resolved.params["speed"].as_f64(), Some(2.0));
+
+        // Dangling reference renders the effect as-is
+        let resolved = effect.resolve(&[]);
+        assert_eq!(resolved.kind, "Rainbow");
+        assert!(resolved.params.get("palette").is_none());
+    }
+
+    #[test]
+    fn test_migrate_moves_legacy_global_and_stamps_version() {
+        let mut state = create_test_state(&[1]);
+        state.scenes[0].kind = "Global".into();
+        state.scenes[0].global = Some(GlobalEffect { kind: "Solid".into(), ..Default::default() });
+
+        state.migrate().unwrap();
+        assert_eq!(state.version, STATE_VERSION);
+        assert!(state.scenes[0].global.is_none());
+        assert_eq!(state.scenes[0].global_effects.len(), 1);
+        assert_eq!(state.scenes[0].global_effects[0].effect.kind, "Solid");
+    }
+
+    #[test]
+    fn test_migrate_legacy_json_global_scenes() {
+        let legacy = r#"{
+            "strips": [], "masks": [], "mode": "global", "effect": "Rainbow",
+            "scenes": [
+                { "id": 1, "name": "Red", "kind": "Global", "global": { "kind": "Solid", "params": { "color": [255, 0, 0] } } },
+                { "id": 2, "name": "Untyped", "kind": "", "global": { "kind": "Rainbow", "params": {} } }
+            ]
+        }"#;
+        let mut state: AppState = serde_json::from_str(legacy).unwrap();
+        assert_eq!(state.version, 0);
+
+        state.migrate().unwrap();
+        assert_eq!(state.version, STATE_VERSION);
+        for (scene, kind) in state.scenes.iter().zip(["Solid", "Rainbow"]) {
+            assert!(scene.global.is_none());
+            assert_eq!(scene.kind, "Global");
+            assert_eq!(scene.global_effects.len(), 1);
+            assert_eq!(scene.global_effects[0].effect.kind, kind);
+            assert!(scene.global_effects[0].targets.is_none());
+        }
+        assert_eq!(state.scenes[0].global_effects[0].effect.params["color"], serde_json::json!([255, 0, 0]));
+
+        // Saves stamped before the kind check was dropped still get their stranded effect moved
+        let mut state = create_test_state(&[1]);
+        state.version = 3;
+        state.scenes[0].global = Some(GlobalEffect { kind: "Solid".into(), ..Default::default() });
+        state.migrate().unwrap();
+        assert_eq!(state.scenes[0].global_effects.len(), 1);
+    }
+
+    #[test]
+    fn test_migrate_moves_unicast_ip_into_list() {
+        let legacy = r#"{
+            "strips": [], "masks": [], "mode": "global", "effect": "Rainbow", "version": 4,
+            "network": { "use_multicast": false, "unicast_ip": "10.0.0.5", "universe": 1 }
+        }"#;
+        let mut state: AppState = serde_json::from_str(legacy).unwrap();
+        state.migrate().unwrap();
+        assert_eq!(state.network.unicast_ips, vec!["10.0.0.5".to_string()]);
+        assert!(state.network.unicast_ip.is_empty());
+
+        // A list already present wins over a leftover single IP
+        let mut state = AppState { version: 4, ..Default::default() };
+        state.network.unicast_ips = vec!["10.0.0.1".into(), "10.0.0.2".into()];
+        state.network.unicast_ip = "10.0.0.9".into();
+        state.migrate().unwrap();
+        assert_eq!(state.network.unicast_ips.len(), 2);
+    }
+
+    #[test]
+    fn test_migrate_drops_params_of_other_kinds() {
+        let mut state = create_test_state(&[1]);
+        state.version = 2;
+        state.scenes[0].kind = "Global".into();
+        state.scenes[0].global_effects.push(GlobalEffectConfig {
+            effect: GlobalEffect {
+                kind: "Solid".into(),
+                params: serde_json::from_value(serde_json::json!({ "color": [1, 2, 3], "rate": "1/4", "speed_lfo_enabled": true })).unwrap(),
+                ..Default::default()
+            },
+            targets: None,
+        });
+
+        state.migrate().unwrap();
+        let effect = &state.scenes[0].global_effects[0].effect;
+        assert_eq!(effect.params.keys().collect::<Vec<_>>(), vec!["color"]);
+        assert!(effect.typed_params().is_ok());
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_version() {
+        let mut state = AppState { version: STATE_VERSION + 1, ..Default::default() };
+        let err = state.migrate().unwrap_err();
+        assert_eq!(err.found, STATE_VERSION + 1);
+        assert_eq!(state.version, STATE_VERSION + 1);
+    }
+
+    #[test]
+    fn test_reorder_scenes_full_permutation() {
+        let mut state = create_test_state(&[1, 2, 3]);
+        state.reorder_scenes(vec![3, 1, 2]).unwrap();
+        assert_eq!(scene_ids(&state), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn test_reorder_scenes_keeps_omitted_at_end() {
+        let mut state = create_test_state(&[1, 2, 3, 4]);
+        state.reorder_scenes(vec![4, 2]).unwrap();
+        assert_eq!(scene_ids(&state), vec![4, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_reorder_scenes_rejects_unknown_and_duplicate_ids() {
+        let mut state = create_test_state(&[1, 2, 3]);
+        assert!(state.reorder_scenes(vec![3, 9]).is_err());
+        assert!(state.reorder_scenes(vec![2, 2]).is_err());
+        assert_eq!(scene_ids(&state), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_set_category_bulk_assigns() {
+        let mut state = create_test_state(&[1, 2, 3]);
+        state.set_category(&[1, 3], "Drops".into()).unwrap();
+        let categories: Vec<&str> = state.scenes.iter().map(|s| s.category.as_str()).collect();
+        assert_eq!(categories, vec!["Drops", "Uncategorized", "Drops"]);
+    }
+
+    #[test]
+    fn test_set_category_rejects_unknown_id() {
+        let mut state = create_test_state(&[1, 2]);
+        assert!(state.set_category(&[1, 7], "Drops".into()).is_err());
+        assert!(state.scenes.iter().all(|s| s.category == "Uncategorized"));
+    }
+
+    #[test]
+    fn test_physical_spacing_matches_led_density() {
+        let mut state = AppState {
+            strips: vec![
+                PixelStrip { id: 1, leds_per_meter: Some(60.0), ..Default::default() },
+                PixelStrip { id: 2, leds_per_meter: Some(144.0), ..Default::default() },
+                PixelStrip { id: 3, pixel_count: 11, length_m: Some(2.0), ..Default::default() },
+                PixelStrip { id: 4, spacing: 0.02, ..Default::default() },
+            ],
+            canvas_width_m: Some(5.0),
+            ..Default::default()
+        };
+        state.apply_physical_spacing();
+
+        // One canvas unit is 5 m, so a meter of any strip spans 0.2 regardless of density
+        assert!((state.strips[0].spacing * 60.0 - 0.2).abs() < 1e-6);
+        assert!((state.strips[1].spacing * 144.0 - 0.2).abs() < 1e-6);
+        assert!((state.strips[2].spacing * 10.0 - 0.4).abs() < 1e-6);
+        assert_eq!(state.strips[3].spacing, 0.02);
+    }
+
+    #[test]
+    fn test_reassign_dmx_addresses_rolls_over_universes() {
+        let mut state = AppState {
+            strips: vec![
+                PixelStrip { id: 1, pixel_count: 170, universe: 9, ..Default::default() }, // 510 channels
+                PixelStrip { id: 2, pixel_count: 10, ..Default::default() },
+                PixelStrip { id: 3, pixel_count: 100, color_order: "RGBW".into(), ..Default::default() },
+                PixelStrip { id: 4, pixel_count: 5, universe: 7, start_channel: 40, ..Default::default() },
+            ],
+            chains: vec![StripChain { id: 1, name: "Chain".into(), universe: 7, start_channel: 40, strip_ids: vec![4] }],
+            ..Default::default()
+        };
+
+        let assigned = state.reassign_dmx_addresses().unwrap();
+        assert_eq!(assigned, vec![(1, 1, 1), (2, 2, 1), (3, 2, 31)]);
+        assert_eq!((state.strips[2].universe, state.strips[2].start_channel), (2, 31));
+        assert_eq!((state.strips[3].universe, state.strips[3].start_channel), (7, 40)); // Chained strips stay put
+
+        // A strip that can't fit anywhere fails without moving anything
+        state.strips[0].universe = 5;
+        state.strips.push(PixelStrip { id: 5, pixel_count: 171, ..Default::default() });
+        assert_eq!(state.reassign_dmx_addresses(), Err(StripTooLargeError { strip_id: 5, channels: 513 }));
+        assert_eq!(state.strips[0].universe, 5);
+    }
+
+    #[test]
+    fn test_reassign_dmx_addresses_skips_chain_channels() {
+        let mut state = AppState {
+            strips: vec![
+                PixelStrip { id: 1, pixel_count: 10, ..Default::default() }, // 30 channels
+                PixelStrip { id: 2, pixel_count: 20, ..Default::default() },
+                PixelStrip { id: 3, pixel_count: 100, ..Default::default() },
+                PixelStrip { id: 4, pixel_count: 100, ..Default::default() },
+            ],
+            chains: vec![StripChain { id: 1, name: "Chain".into(), universe: 1, start_channel: 1, strip_ids: vec![1, 2] }],
+            ..Default::default()
+        };
+
+        // The chain covers 1/1-90; packing starts after it and rolls over when the next strip won't fit
+        let assigned = state.reassign_dmx_addresses().unwrap();
+        assert_eq!(assigned, vec![(3, 1, 91), (4, 2, 1)]);
+        state.apply_chains();
+        assert_eq!((state.strips[1].universe, state.strips[1].start_channel), (1, 31));
+    }
+
+    #[test]
+    fn test_apply_chains_packs_segments_contiguously() {
+        let mut state = AppState {
+            strips: vec![
+                PixelStrip { id: 1, pixel_count: 10, x: 0.1, ..Default::default() },
+                PixelStrip { id: 2, pixel_count: 20, x: 0.9, universe: 7, ..Default::default() },
+                PixelStrip { id: 3, pixel_count: 5, ..Default::default() },
+            ],
+            chains: vec![StripChain {
+                id: 1,
+                name: "Stage left".into(),
+                universe: 3,
+                start_channel: 4,
+                strip_ids: vec![2, 99, 1],
+            }],
+            ..Default::default()
+        };
+        state.apply_chains();
+
+        assert_eq!((state.strips[1].universe, state.strips[1].start_channel), (3, 4));
+        assert_eq!((state.strips[0].universe, state.strips[0].start_channel), (3, 64));
+        assert_eq!((state.strips[2].universe, state.strips[2].start_channel), (1, 1));
+        assert_eq!(state.strips[0].x, 0.1); // Placement untouched
+
+        // Fixture channels widen a segment's footprint
+        state.strips[1].pre_channels = vec![255.into(), 0.into()];
+        state.strips[1].post_channels = vec![0.into()];
+        state.apply_chains();
+        assert_eq!(state.strips[1].channel_count(), 63);
+        assert_eq!(state.strips[0].start_channel, 67);
+        assert_eq!(state.chain_of(1).map(|c| c.id), Some(1));
+        assert!(state.chain_of(3).is_none());
+    }
+
+    #[test]
+    fn test_parse_color_array() {
+        assert_eq!(parse_color(&serde_json::json!([255, 128, 0])), Some([255, 128, 0]));
+        assert_eq!(parse_color(&serde_json::json!([256, 0, 0])), None);
+        assert_eq!(parse_color(&serde_json::json!([-1, 0, 0])), None);
+        assert_eq!(parse_color(&serde_json::json!([1.5, 0, 0])), None);
+        assert_eq!(parse_color(&serde_json::json!([255, 0])), None);
+        assert_eq!(parse_color(&serde_json::json!([1, 2, 3, 4])), None);
+    }
+
+    #[test]
+    fn test_parse_color_hex() {
+        assert_eq!(parse_color(&serde_json::json!("#FF00FF")), Some([255, 0, 255]));
+        assert_eq!(parse_color(&serde_json::json!("#0a1B2c")), Some([10, 27, 44]));
+        assert_eq!(parse_color(&serde_json::json!(" 00ff00 ")), Some([0, 255, 0]));
+        assert_eq!(parse_color(&serde_json::json!("#FFF")), None);
+        assert_eq!(parse_color(&serde_json::json!("#GG0000")), None);
+        assert_eq!(parse_color(&serde_json::json!("#+12345")), None);
+        assert_eq!(parse_color(&serde_json::json!("red")), None);
+    }
+
+    #[test]
+    fn test_parse_color_hsv() {
+        assert_eq!(parse_color(&serde_json::json!({ "h": 0, "s": 1.0, "v": 1.0 })), Some([255, 0, 0]));
+        assert_eq!(parse_color(&serde_json::json!({ "h": 240, "s": 1.0, "v": 1.0 })), Some([0, 0, 255]));
+        assert_eq!(parse_color(&serde_json::json!({ "h": 360, "s": 0.0, "v": 1.0 })), Some([255, 255, 255]));
+        assert_eq!(parse_color(&serde_json::json!({ "h": 0, "s": 1.5, "v": 1.0 })), None);
+        assert_eq!(parse_color(&serde_json::json!({ "h": 0, "s": 1.0 })), None);
+        assert_eq!(parse_color(&serde_json::json!(null)), None);
+    }
+
+    #[test]
+    fn test_normalize_color_params() {
+        let mut params: HashMap<String, serde_json::Value> = serde_json::from_value(serde_json::json!({
+            "color": "#102030",
+            "color_b": { "h": 120, "s": 1.0, "v": 1.0 },
+            "color_a": [1, 2, 3],
+            "gradient_colors": ["#FF0000", [0, 0, 255]],
+            "color_mode": "gradient",
+            "background_color": "nope",
+            "speed": 1.0,
+        })).unwrap();
+        normalize_color_params(&mut params);
+
+        assert_eq!(params["color"], serde_json::json!([16, 32, 48]));
+        assert_eq!(params["color_b"], serde_json::json!([0, 255, 0]));
+        assert_eq!(params["color_a"], serde_json::json!([1, 2, 3]));
+        assert_eq!(params["gradient_colors"], serde_json::json!([[255, 0, 0], [0, 0, 255]]));
+        assert_eq!(params["color_mode"], serde_json::json!("gradient"));
+        assert_eq!(params["background_color"], serde_json::json!("nope"));
+    }
+
+    #[test]
+    fn test_trigger_scene_respects_exclusivity_groups() {
+        let mut state = create_test_state(&[1, 2, 3, 4, 5]);
+        state.scenes[2].exclusive_group = "Overlay".into();
+        state.scenes[3].exclusive_group = "Overlay".into();
+        state.scenes[4].exclusive_group = String::new();
+
+        state.trigger_scene(1);
+        state.trigger_scene(3);
+        state.trigger_scene(5);
+        assert_eq!(state.active_scene_ids, vec![1, 3, 5]);
+
+        // Same group replaces; the new scene goes on top
+        state.trigger_scene(2);
+        state.trigger_scene(4);
+        assert_eq!(state.active_scene_ids, vec![5, 2, 4]);
+        assert_eq!(state.selected_scene_id, Some(4));
+
+        // Ungrouped scenes toggle
+        state.trigger_scene(5);
+        assert_eq!(state.active_scene_ids, vec![2, 4]);
+
+        state.deactivate_scene(4);
+        assert_eq!(state.selected_scene_id, Some(2));
+        state.trigger_scene(99); // Unknown scene: no-op
+        assert_eq!(state.active_scene_ids, vec![2]);
+        state.clear_active_scenes();
+        assert!(state.active_scene_ids.is_empty());
+        assert_eq!(state.selected_scene_id, None);
+    }
+
+    #[test]
+    fn test_momentary_holds_restore_previous_scenes() {
+        let mut state = create_test_state(&[1, 2, 3, 4]);
+        state.scenes[3].exclusive_group = String::new();
+        state.trigger_scene(1);
+
+        // Nested holds release in order back to the latched scene
+        state.hold_scene(2);
+        state.hold_scene(2); // Repeated press while held: ignored
+        assert_eq!(state.active_scene_ids, vec![2]);
+        state.hold_scene(3);
+        assert_eq!(state.active_scene_ids, vec![3]);
+        state.release_scene(3);
+        assert_eq!(state.active_scene_ids, vec![2]);
+        assert_eq!(state.selected_scene_id, Some(2));
+        state.release_scene(2);
+        assert_eq!(state.active_scene_ids, vec![1]);
+        assert_eq!(state.selected_scene_id, Some(1));
+
+        // Releasing the older hold first: the last release still lands on the original state
+        state.hold_scene(2);
+        state.hold_scene(3);
+        state.release_scene(2);
+        assert_eq!(state.active_scene_ids, vec![3]);
+        state.release_scene(3);
+        assert_eq!(state.active_scene_ids, vec![1]);
+        assert!(state.momentary_holds.is_empty());
+
+        // Holding an active ungrouped overlay keeps it on instead of toggling it off
+        state.trigger_scene(4);
+        state.hold_scene(4);
+        assert_eq!(state.active_scene_ids, vec![1, 4]);
+        state.release_scene(4);
+        assert_eq!(state.active_scene_ids, vec![1, 4]);
+        state.release_scene(4); // Not held: no-op
+        assert_eq!(state.active_scene_ids, vec![1, 4]);
+    }
+
+    #[test]
+    fn test_activate_idle_scene_returns_previous_selection() {
+        let mut state = create_test_state(&[1, 2, 3]);
+        state.scenes[1].exclusive_group = String::new();
+        state.trigger_scene(1);
+        state.trigger_scene(2);
+        assert_eq!(state.activate_idle_scene(), None);
+
+        state.idle_scene_id = Some(3);
+        assert_eq!(state.activate_idle_scene(), Some((vec![1, 2], Some(2))));
+        assert_eq!(state.active_scene_ids, vec![3]);
+        assert_eq!(state.selected_scene_id, Some(3));
+
+        state.idle_scene_id = Some(99); // Deleted scene
+        assert_eq!(state.activate_idle_scene(), None);
+        assert_eq!(state.active_scene_ids, vec![3]);
+    }
+
+    #[test]
+    fn test_migrate_selected_scene_becomes_active_layer() {
+        let mut state = AppState { version: 1, selected_scene_id: Some(7), ..Default::default() };
+        state.migrate().unwrap();
+        assert_eq!(state.active_scene_ids, vec![7]);
+    }
+
+    #[test]
+    fn test_cc_binding_scales_and_round_trips() {
+        let binding = CcBinding { controller: 21, scene_id: 1, effect: 0, param: "speed".into(), min: 0.5, max: 3.0 };
+        assert_eq!(binding.value(0), 0.5);
+        assert_eq!(binding.value(127), 3.0);
+        assert!((binding.value(64) - (0.5 + 2.5 * 64.0 / 127.0)).abs() < 1e-12);
+        // Inverted ranges ride the fader the other way
+        let inverted = CcBinding { min: 1.0, max: 0.0, ..binding.clone() };
+        assert_eq!(inverted.value(127), 0.0);
+
+        let state = AppState { midi_cc_bindings: vec![binding.clone()], ..Default::default() };
+        assert_eq!(state.cc_bindings_for(21).count(), 1);
+        assert_eq!(state.cc_bindings_for(22).count(), 0);
+        let json = serde_json::to_string(&state).unwrap();
+        let loaded: AppState = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.midi_cc_bindings, vec![binding]);
+    }
+
+    #[test]
+    fn test_midi_port_filter() {
+        let filter = MidiPortFilter { include: "APC".into(), exclude: String::new() };
+        assert!(filter.matches("APC MINI"));
+        assert!(!filter.matches("Launchpad Mini MK3 LPMiniMK3 MIDI"));
+        let filter = MidiPortFilter { include: "LPX".into(), exclude: "DAW".into() };
+        assert!(filter.matches("LPX MIDI"));
+        assert!(!filter.matches("LPX DAW"));
+    }
+
+    #[test]
+    fn test_param_automation_repeat_modes() {
+        let mut ramp = ParamAutomation { param: "speed".into(), target: 3.0, duration_ms: 1000, curve: String::new(), repeat: String::new() };
+        assert_eq!(ramp.value(1.0, 0), 1.0);
+        assert_eq!(ramp.value(1.0, 500), 2.0);
+        assert_eq!(ramp.value(1.0, 1250), 1.5); // Loop starts over
+
+        ramp.repeat = "bounce".into();
+        assert_eq!(ramp.value(1.0, 1250), 2.5);
+        assert_eq!(ramp.value(1.0, 2000), 1.0);
+
+        ramp.repeat = "once".into();
+        assert_eq!(ramp.value(1.0, 5000), 3.0);
+
+        ramp.curve = "ease_in".into();
+        assert_eq!(ramp.progress(500), 0.25);
+        ramp.duration_ms = 0;
+        assert_eq!(ramp.value(1.0, 0), 3.0);
+    }
+
+    #[test]
+    fn test_flash_limit_keeps_beat_grid() {
+        // 120 BPM: "1/8" flashes at 4 Hz, halved twice to get under 1.5 Hz
+        assert_eq!(limit_flash_divisor(flash_divisor("1/8"), 120.0, Some(1.5)), 2.0);
+        assert_eq!(limit_flash_divisor(flash_divisor("1/8"), 120.0, Some(4.0)), 0.5);
+        assert_eq!(limit_flash_divisor(flash_divisor("1/8"), 120.0, None), 0.5);
+        assert_eq!(limit_flash_divisor(0.5, f64::NAN, Some(1.0)), 0.5);
+
+        let mut state = create_test_state(&[1]);
+        state.scenes[0].kind = "Global".into();
+        state.scenes[0].global_effects.push(GlobalEffectConfig {
+            effect: GlobalEffect { kind: "Flash".into(), params: HashMap::from([("rate".to_string(), "1/8".into())]), preset_id: None, automation: Vec::new() },
+            targets: None,
+        });
+        assert!(state.strobe_warnings(120.0).is_empty());
+        state.max_flash_hz = Some(3.0);
+        assert_eq!(state.strobe_warnings(120.0).len(), 1);
+        assert!(state.strobe_warnings(60.0).is_empty());
+        state.no_strobe = true;
+        assert!(state.strobe_warnings(120.0).is_empty());
+    }
+
+    #[test]
+    fn test_strobe_warning_uses_frequency() {
+        let mut state = create_test_state(&[1]);
+        state.scenes[0].kind = "Global".into();
+        state.scenes[0].global_effects.push(GlobalEffectConfig {
+            effect: GlobalEffect { kind: "Strobe".into(), params: HashMap::from([("frequency_hz".to_string(), 20.0.into())]), preset_id: None, automation: Vec::new() },
+            targets: None,
+        });
+        state.max_flash_hz = Some(3.0);
+
+        // Tempo doesn't matter: 20 Hz is over the limit at any BPM
+        assert_eq!(state.strobe_warnings(60.0), vec!["Scene 1: Strobe at 20.0 Hz, limited to 3 Hz".to_string()]);
+        state.max_flash_hz = Some(25.0);
+        assert!(state.strobe_warnings(240.0).is_empty());
+    }
+
+    #[test]
+    fn test_resume_scenes_drops_deleted_scenes() {
+        let mut state = create_test_state(&[1, 2, 3]);
+        state.active_scene_ids = vec![1, 9, 2];
+        state.selected_scene_id = Some(9);
+        state.idle_scene_id = Some(9);
+        state.resume_scenes();
+        assert_eq!(state.active_scene_ids, vec![1, 2]);
+        assert_eq!(state.selected_scene_id, Some(2));
+        assert_eq!(state.idle_scene_id, None);
+
+        // A selection saved with nothing active starts running again
+        state.active_scene_ids.clear();
+        state.selected_scene_id = Some(3);
+        state.resume_scenes();
+        assert_eq!(state.active_scene_ids, vec![3]);
+
+        state.active_scene_ids = vec![9];
+        state.selected_scene_id = Some(9);
+        state.resume_scenes();
+        assert!(state.active_scene_ids.is_empty());
+        assert_eq!(state.selected_scene_id, None);
+    }
+
+    #[test]
+    fn test_scene_for_pad_and_binding_conflicts() {
+        let mut state = create_test_state(&[1, 2, 3, 4]);
+        state.scenes[0].launchpad_btn = Some(11);
+        state.scenes[1].launchpad_btn = Some(11);
+        state.scenes[2].launchpad_btn = Some(11);
+        state.scenes[2].launchpad_is_cc = true;
+        state.scenes[3].launchpad_btn = Some(0); // Unbound
+
+        assert_eq!(state.scene_for_pad(11, false).map(|s| s.id), Some(1));
+        assert_eq!(state.scene_for_pad(11, true).map(|s| s.id), Some(3));
+        assert!(state.scene_for_pad(12, false).is_none());
+        assert_eq!(state.binding_conflicts(), vec![(11, false, vec![1, 2])]);
+
+        state.scenes[1].launchpad_btn = None;
+        assert!(state.binding_conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_global_effect_targets() {
+        let mut config = GlobalEffectConfig { effect: GlobalEffect::default(), targets: None };
+        assert!(config.targets_strip(5));
+        assert!(!config.is_disabled());
+
+        config.targets = Some(vec![1, 2]);
+        assert!(config.targets_strip(2));
+        assert!(!config.targets_strip(5));
+
+        config.targets = Some(Vec::new());
+        assert!(!config.targets_strip(5));
+        assert!(config.is_disabled());
+    }
+
+    #[test]
+    fn test_spatial_distance_ignores_missing_depth() {
+        assert_eq!(spatial_distance((0.0, 0.0, None), (3.0, 4.0, None)), 5.0);
+        assert_eq!(spatial_distance((0.0, 0.0, Some(9.0)), (3.0, 4.0, None)), 5.0);
+        assert_eq!(spatial_distance((0.0, 0.0, Some(2.0)), (0.0, 3.0, Some(-2.0))), 5.0);
+
+        let strip = PixelStrip { z: Some(0.25), pixel_count: 2, ..Default::default() };
+        assert!(strip.pixel_positions_3d().iter().all(|p| p.2 == Some(0.25)));
+    }
+
+    #[test]
+    fn test_mirror_order_and_cycles() {
+        let strip = |id, source| PixelStrip { id, mirror_source: source, pixel_count: 3, data: vec![[0, 0, 0]; 3], ..Default::default() };
+        let mut state = AppState {
+            strips: vec![strip(1, Some(2)), strip(2, Some(3)), strip(3, None), strip(4, Some(99))],
+            ..Default::default()
+        };
+        // 3 renders, 2 copies it, then 1 copies 2; 4's source doesn't exist
+        assert_eq!(mirror_order(&state.strips), vec![1, 0]);
+        assert!(!state.set_mirror_source(3, Some(1)));
+        assert!(!state.set_mirror_source(3, Some(3)));
+        assert_eq!(state.strips[2].mirror_source, None);
+        assert!(state.set_mirror_source(4, Some(1)));
+
+        state.strips[2].data = vec![[1, 0, 0], [2, 0, 0], [3, 0, 0]];
+        state.strips[0].mirror_reversed = true;
+        let mut warned = HashSet::new();
+        apply_mirrors(&mut state.strips, &mut warned);
+        assert!(warned.is_empty());
+        assert_eq!(state.strips[1].data, vec![[1, 0, 0], [2, 0, 0], [3, 0, 0]]);
+        assert_eq!(state.strips[0].data, vec![[3, 0, 0], [2, 0, 0], [1, 0, 0]]);
+        assert_eq!(state.strips[3].data, state.strips[0].data);
+
+        // A cycle loaded from disk is skipped rather than followed forever
+        state.strips[2].mirror_source = Some(1);
+        assert!(mirror_order(&state.strips).is_empty());
+
+        // Broken mirrors are noted once per strip, not every frame
+        state.strips[3].mirror_source = Some(99);
+        apply_mirrors(&mut state.strips, &mut warned);
+        assert_eq!(warned, HashSet::from([1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn test_strips_in_region() {
+        let state = AppState {
+            strips: vec![
+                PixelStrip { id: 1, x: 0.1, y: 0.1, ..Default::default() },
+                PixelStrip { id: 2, x: 0.5, y: 0.5, ..Default::default() },
+                PixelStrip { id: 3, x: 0.9, y: 0.2, ..Default::default() },
+                PixelStrip { id: 4, x: 0.5, y: 0.9, ..Default::default() },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(state.strips_in_region(0.0, 0.0, 0.5, 0.5), vec![1, 2]);
+        assert_eq!(state.strips_in_region(1.0, 0.6, 0.4, 0.0), vec![2, 3]); // Swapped corners
+        assert!(state.strips_in_region(0.2, 0.2, 0.3, 0.3).is_empty());
+    }
+
+    #[test]
+    fn test_copy_effect_full_and_params_only() {
+        let mut state = create_test_state(&[1, 2]);
+        state.presets.push(EffectPreset {
+            id: 9,
+            name: "Slow Plasma".into(),
+            kind: "Plasma".into(),
+            params: HashMap::from([("scale".to_string(), 5.0.into())]),
+        });
+        state.scenes[0].global_effects.push(GlobalEffectConfig {
+            effect: GlobalEffect {
+                kind: "Plasma".into(),
+                params: HashMap::from([("speed".to_string(), 2.0.into())]),
+                preset_id: Some(9),
+                automation: Vec::new(),
+            },
+            targets: None,
+        });
+        state.scenes[1].global_effects.push(GlobalEffectConfig {
+            effect: GlobalEffect {
+                kind: "Rainbow".into(),
+                params: HashMap::from([("saturation".to_string(), 0.5.into())]),
+                preset_id: None,
+                automation: Vec::new(),
+            },
+            targets: Some(vec![3]),
+        });
+
+        assert!(state.copy_effect((1, 0), (2, 0), true));
+        let target = &state.scenes[1].global_effects[0];
+        assert_eq!(target.effect.kind, "Rainbow");
+        assert_eq!(target.effect.preset_id, None);
+        let mut keys: Vec<_> = target.effect.params.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["scale", "speed"]); // Preset base params come along
+
+        assert!(state.copy_effect((1, 0), (2, 0), false));
+        let target = &state.scenes[1].global_effects[0];
+        assert_eq!(target.effect.kind, "Plasma");
+        assert_eq!(target.effect.preset_id, Some(9));
+        assert_eq!(target.effect.params.len(), 1);
+        assert_eq!(target.targets, Some(vec![3])); // Targets stay with the slot
+
+        assert!(!state.copy_effect((1, 5), (2, 0), false));
+        assert!(!state.copy_effect((1, 0), (7, 0), false));
+    }
+
+    #[test]
+    fn test_shuffle_effect_stays_in_range() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut effect = GlobalEffect { kind: "Plasma".into(), params: HashMap::new(), preset_id: None, automation: Vec::new() };
+
+        for _ in 0..50 {
+            assert_eq!(shuffle_effect(&mut effect, "Plasma", &[], &mut rng), 3);
+            let speed = effect.params["speed"].as_f64().unwrap();
+            assert!((0.05..=5.0).contains(&speed));
+            let scale = effect.params["scale"].as_f64().unwrap();
+            assert!((0.5..=30.0).contains(&scale));
+            let palette = effect.params["palette"].as_str().unwrap();
+            assert!(["Rainbow", "Fire", "Ocean", "Duo"].contains(&palette));
+        }
+    }
+
+    #[test]
+    fn test_shuffle_effect_only_touches_chosen_params() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut effect = GlobalEffect { kind: "Plasma".into(), params: HashMap::new(), preset_id: None, automation: Vec::new() };
+
+        assert_eq!(shuffle_effect(&mut effect, "Plasma", &["palette".to_string(), "color".to_string()], &mut rng), 1);
+        assert!(effect.params.contains_key("palette"));
+        assert!(!effect.params.contains_key("speed"));
+        assert_eq!(shuffle_effect(&mut effect, "Solid", &[], &mut rng), 0);
+    }
+
+    #[test]
+    fn test_route_for_single_universe_falls_back_to_global() {
+        let network = NetworkConfig {
+            routes: vec![UniverseRoute { universes: (5, 5), bind: None, dest: Some("10.0.0.42".into()) }],
+            ..Default::default()
+        };
+        assert_eq!(network.route_for(5).and_then(|r| r.dest.as_deref()), Some("10.0.0.42"));
+        assert!(network.route_for(4).is_none());
+        assert!(network.route_for(6).is_none());
+    }
 }
@@ -0,0 +1,429 @@
+//! DMX transmission (sACN or Art-Net), serial or spread over a worker pool.
+//!
+//! The engine packs each frame into one `UniversePacket` per universe, then either sends
+//! them itself through its `SenderSet` or hands them to an `OutputPool`. Each pool worker
+//! owns its own sACN sources and Art-Net sockets and always gets the same universes
+//! (`shard`), so every universe keeps one source and one monotonically increasing
+//! sequence counter. All sources share the engine's CID (`SenderSet::fork`), so
+//! receivers see a single Lightspeed source however the universes are spread.
+//! `OutputPool::send_all` returns only after every worker has finished the frame, which
+//! is the point a sync packet may go out.
+
+use crate::model::DmxProtocol;
+use sacn::source::SacnSource;
+use std::collections::{HashMap, HashSet};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+/// One universe ready for the wire
+#[derive(Clone, Debug, PartialEq)]
+pub struct UniversePacket {
+    pub universe: u16,
+    pub bind: String, // Local interface address; "" = any
+    pub dst: Option<SocketAddr>, // Unicast destination; None = multicast (broadcast for Art-Net)
+    pub data: Vec<u8>, // Start code followed by up to 512 slots
+    pub protocol: DmxProtocol,
+}
+
+/// Art-Net packet header ID
+const ARTNET_ID: &[u8; 8] = b"Art-Net\0";
+/// OpDmx, sent little-endian
+const ARTNET_OP_DMX: u16 = 0x5000;
+/// Art-Net protocol revision, sent big-endian
+const ARTNET_PROTOCOL_VERSION: u16 = 14;
+
+/// Build an ArtDmx packet for `universe` (1-based like sACN; universe 1 is port-address 0).
+/// `data` holds the start code followed by the DMX slots; the start code isn't sent.
+/// `sequence` 0 tells receivers not to reorder. None if the universe has no port-address.
+pub fn artdmx_packet(universe: u16, sequence: u8, data: &[u8]) -> Option<Vec<u8>> {
+    if !(1..=DmxProtocol::ArtNet.max_universe()).contains(&universe) {
+        return None;
+    }
+    let port_address = universe - 1;
+    let slots = &data[1.min(data.len())..];
+    let slots = &slots[..slots.len().min(512)];
+    // Length must be even and at least 2
+    let length = (slots.len() + slots.len() % 2).max(2);
+
+    let mut packet = Vec::with_capacity(18 + length);
+    packet.extend_from_slice(ARTNET_ID);
+    packet.extend_from_slice(&ARTNET_OP_DMX.to_le_bytes());
+    packet.extend_from_slice(&ARTNET_PROTOCOL_VERSION.to_be_bytes());
+    packet.push(sequence);
+    packet.push(0); // Physical input port, informational only
+    packet.push((port_address & 0xff) as u8); // SubUni: sub-net and universe nibbles
+    packet.push((port_address >> 8) as u8); // Net: top 7 bits
+    packet.extend_from_slice(&(length as u16).to_be_bytes());
+    packet.extend_from_slice(slots);
+    packet.resize(18 + length, 0);
+    Some(packet)
+}
+
+/// sACN sources and Art-Net sockets keyed by local bind address ("" = any interface),
+/// registering sACN universes on first use
+pub struct SenderSet {
+    senders: HashMap<String, SacnSource>,
+    failed_binds: HashSet<String>,
+    registered: HashSet<(String, u16)>,
+    artnet: HashMap<String, UdpSocket>,
+    artnet_failed: HashSet<String>,
+    artnet_sequence: HashMap<u16, u8>,
+    artnet_out_of_range: HashSet<u16>, // Universes already warned about, so the log isn't flooded
+}
+
+impl SenderSet {
+    /// Start from `default`, the sender used for "" and for binds that fail
+    pub fn new(default: SacnSource) -> Self {
+        Self {
+            senders: HashMap::from([(String::new(), default)]),
+            failed_binds: HashSet::new(),
+            registered: HashSet::new(),
+            artnet: HashMap::new(),
+            artnet_failed: HashSet::new(),
+            artnet_sequence: HashMap::new(),
+            artnet_out_of_range: HashSet::new(),
+        }
+    }
+
+    /// A set whose default sender is bound to any IPv4 interface
+    pub fn bind_any() -> Result<Self, String> {
+        SacnSource::with_ip("Lightspeed", SocketAddr::from(([0, 0, 0, 0], 0)))
+            .map(Self::new)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    /// An empty set for another thread whose sources use this set's CID
+    pub fn fork(&self) -> Result<Self, String> {
+        let cid = self.senders[""].cid().map_err(|e| format!("{:?}", e))?;
+        SacnSource::with_cid_ip("Lightspeed", cid, SocketAddr::from(([0, 0, 0, 0], 0)))
+            .map(Self::new)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    /// Send stream-terminated for every universe this set has registered, so receivers
+    /// drop it at once instead of waiting out the data-loss timeout
+    pub fn terminate_all(&mut self) {
+        for (bind, u) in self.registered.drain() {
+            if let Some(sender) = self.senders.get_mut(&bind) {
+                if let Err(e) = sender.terminate_stream(u, 0) {
+                    log::warn!("Failed to terminate sACN universe {}: {:?}", u, e);
+                }
+            }
+        }
+    }
+
+    /// Return the key of a usable sender for `bind`, creating it on first use.
+    /// Unparseable or unbindable addresses fall back to the default sender.
+    fn ensure(&mut self, bind: &str) -> String {
+        if self.senders.contains_key(bind) {
+            return bind.to_string();
+        }
+        if self.failed_binds.contains(bind) {
+            return String::new();
+        }
+
+        // Every interface sends as the same source
+        let created = bind.parse::<std::net::IpAddr>()
+            .map_err(|e| format!("{}", e))
+            .and_then(|ip| {
+                let cid = self.senders[""].cid().map_err(|e| format!("{:?}", e))?;
+                SacnSource::with_cid_ip("Lightspeed", cid, SocketAddr::new(ip, 0))
+                    .map_err(|e| format!("{:?}", e))
+            });
+
+        match created {
+            Ok(sender) => {
+                log::info!("Created sACN sender bound to {}", bind);
+                self.senders.insert(bind.to_string(), sender);
+                bind.to_string()
+            }
+            Err(e) => {
+                log::warn!("Cannot bind sACN sender to '{}' ({}), using default interface", bind, e);
+                self.failed_binds.insert(bind.to_string());
+                String::new()
+            }
+        }
+    }
+
+    /// Return the key of a usable Art-Net socket for `bind`, creating it on first use.
+    /// Unusable addresses fall back to any interface; None if even that can't be bound.
+    fn ensure_artnet(&mut self, bind: &str) -> Option<String> {
+        let bind = if self.artnet_failed.contains(bind) { "" } else { bind };
+        if self.artnet.contains_key(bind) {
+            return Some(bind.to_string());
+        }
+
+        let ip = if bind.is_empty() {
+            Ok(std::net::IpAddr::from([0, 0, 0, 0]))
+        } else {
+            bind.parse::<std::net::IpAddr>().map_err(|e| e.to_string())
+        };
+        let created = ip.and_then(|ip| {
+            let socket = UdpSocket::bind(SocketAddr::new(ip, 0)).map_err(|e| e.to_string())?;
+            socket.set_broadcast(true).map_err(|e| e.to_string())?;
+            Ok(socket)
+        });
+
+        match created {
+            Ok(socket) => {
+                log::info!("Created Art-Net socket bound to '{}'", bind);
+                self.artnet.insert(bind.to_string(), socket);
+                Some(bind.to_string())
+            }
+            Err(e) if !bind.is_empty() => {
+                log::warn!("Cannot bind Art-Net socket to '{}' ({}), using default interface", bind, e);
+                self.artnet_failed.insert(bind.to_string());
+                self.ensure_artnet("")
+            }
+            Err(e) => {
+                log::error!("Cannot create Art-Net socket: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Send one ArtDmx packet; without a unicast destination it is broadcast
+    fn send_artnet(&mut self, packet: &UniversePacket) -> bool {
+        let Some(bind) = self.ensure_artnet(packet.bind.trim()) else { return false };
+        let sequence = self.artnet_sequence.entry(packet.universe).or_insert(0);
+        // 1..=255; 0 would switch sequencing off at the receiver
+        *sequence = sequence.wrapping_add(1).max(1);
+        let Some(bytes) = artdmx_packet(packet.universe, *sequence, &packet.data) else {
+            if self.artnet_out_of_range.insert(packet.universe) {
+                log::warn!("Art-Net has no universe {} (max {}), not sending it", packet.universe, DmxProtocol::ArtNet.max_universe());
+            }
+            return false;
+        };
+        let dst = packet.dst
+            .unwrap_or_else(|| SocketAddr::from(([255, 255, 255, 255], DmxProtocol::ArtNet.port())));
+        match self.artnet[&bind].send_to(&bytes, dst) {
+            Ok(_) => true,
+            Err(e) => {
+                log::warn!("Art-Net error sending to U{} (Dest: {}): {}", packet.universe, dst, e);
+                false
+            }
+        }
+    }
+
+    /// Send one packet; false if the send failed
+    pub fn send(&mut self, packet: &UniversePacket) -> bool {
+        if packet.protocol == DmxProtocol::ArtNet {
+            return self.send_artnet(packet);
+        }
+        let bind = self.ensure(packet.bind.trim());
+        let u = packet.universe;
+        let Some(sender) = self.senders.get_mut(&bind) else { return false };
+
+        if !self.registered.contains(&(bind.clone(), u)) {
+            match sender.register_universe(u) {
+                Ok(_) => {
+                    self.registered.insert((bind.clone(), u));
+                    println!("Registered sACN Universe {}", u);
+                },
+                Err(e) => {
+                    println!("Failed to register sACN Universe {}: {:?}", u, e);
+                }
+            }
+        }
+
+        match sender.send(&[u], &packet.data, Some(200), packet.dst, None) {
+            Ok(_) => true,
+            Err(e) => {
+                println!("sACN Error sending to U{} (Dest: {:?}): {:?}", u, packet.dst, e);
+                false
+            }
+        }
+    }
+}
+
+/// Worker index that always sends `universe`
+pub fn shard(universe: u16, workers: usize) -> usize {
+    universe as usize % workers.max(1)
+}
+
+/// Sent and failed packet counts for one frame
+pub type SendResult = (usize, usize);
+
+struct Worker {
+    packets: Sender<Vec<UniversePacket>>,
+    handle: JoinHandle<()>,
+}
+
+/// Fixed set of output threads, each with its own `SenderSet`
+pub struct OutputPool {
+    workers: Vec<Worker>,
+    done: Receiver<SendResult>,
+}
+
+impl OutputPool {
+    /// Start `threads` workers whose senders are forked from `template`, so they keep its CID
+    /// and bind each packet's interface the same way the serial path does
+    pub fn new(threads: usize, template: &SenderSet) -> Self {
+        let (done_tx, done) = mpsc::channel();
+        let workers = (0..threads.max(1)).map(|i| {
+            let (packets, rx) = mpsc::channel::<Vec<UniversePacket>>();
+            let done_tx = done_tx.clone();
+            let mut senders = template.fork()
+                .map_err(|e| log::error!("Output worker {} has no sACN sender: {}", i, e))
+                .ok();
+            let handle = thread::Builder::new()
+                .name(format!("sacn-out-{}", i))
+                .spawn(move || {
+                    for batch in rx {
+                        let sent = match &mut senders {
+                            Some(senders) => batch.iter().filter(|p| senders.send(p)).count(),
+                            None => 0,
+                        };
+                        if done_tx.send((sent, batch.len() - sent)).is_err() {
+                            break;
+                        }
+                    }
+                    // Pool rebuilt or turned off: its universes move to other sources
+                    if let Some(senders) = &mut senders {
+                        senders.terminate_all();
+                    }
+                })
+                .expect("spawn sACN output worker");
+            Worker { packets, handle }
+        }).collect();
+        Self { workers, done }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Send a frame's packets in parallel and wait until every worker is done
+    pub fn send_all(&self, packets: Vec<UniversePacket>) -> SendResult {
+        let mut batches: Vec<Vec<UniversePacket>> = vec![Vec::new(); self.workers.len()];
+        for packet in packets {
+            batches[shard(packet.universe, self.workers.len())].push(packet);
+        }
+        let mut pending = 0;
+        let mut failed = 0;
+        for (worker, batch) in self.workers.iter().zip(batches) {
+            if batch.is_empty() {
+                continue;
+            }
+            let count = batch.len();
+            if worker.packets.send(batch).is_ok() {
+                pending += 1;
+            } else {
+                failed += count;
+            }
+        }
+        let mut sent = 0;
+        for _ in 0..pending {
+            match self.done.recv() {
+                Ok((s, f)) => {
+                    sent += s;
+                    failed += f;
+                }
+                Err(_) => break,
+            }
+        }
+        (sent, failed)
+    }
+}
+
+impl Drop for OutputPool {
+    fn drop(&mut self) {
+        // Closing each queue ends its worker loop
+        for worker in self.workers.drain(..) {
+            drop(worker.packets);
+            let _ = worker.handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_artdmx_packet_layout() {
+        let mut data = vec![0u8]; // Start code
+        data.extend([10, 20, 30]);
+        let packet = artdmx_packet(0x1235, 7, &data).unwrap();
+
+        assert_eq!(&packet[..8], b"Art-Net\0");
+        assert_eq!(packet[8..10], [0x00, 0x50]); // OpDmx, little-endian
+        assert_eq!(packet[10..12], [0, 14]);
+        assert_eq!(packet[12], 7);
+        // Universe 0x1235 is port-address 0x1234: SubUni 0x34, Net 0x12
+        assert_eq!(packet[14..16], [0x34, 0x12]);
+        // Three slots round up to an even length; the start code isn't sent
+        assert_eq!(packet[16..18], [0, 4]);
+        assert_eq!(packet[18..], [10, 20, 30, 0]);
+
+        let full = artdmx_packet(1, 1, &[0u8; 513]).unwrap();
+        assert_eq!((full[14], full[15], full.len()), (0, 0, 18 + 512));
+        assert_eq!(artdmx_packet(1, 1, &[0]).unwrap().len(), 18 + 2);
+    }
+
+    #[test]
+    fn test_artdmx_packet_rejects_universes_past_32768() {
+        let last = artdmx_packet(32768, 1, &[0]).unwrap();
+        assert_eq!(last[14..16], [0xff, 0x7f]);
+        // 32769 would wrap onto universe 1
+        assert_eq!(artdmx_packet(32769, 1, &[0]), None);
+        assert_eq!(artdmx_packet(0, 1, &[0]), None);
+    }
+
+    #[test]
+    fn test_shard_is_stable_per_universe() {
+        for u in 1..=64 {
+            assert_eq!(shard(u, 4), shard(u, 4));
+            assert!(shard(u, 4) < 4);
+        }
+        assert_eq!(shard(5, 0), 0);
+        // Consecutive universes spread across every worker
+        let used: HashSet<usize> = (1..=8).map(|u| shard(u, 4)).collect();
+        assert_eq!(used.len(), 4);
+    }
+
+    #[test]
+    fn test_forked_senders_keep_the_cid() {
+        let serial = SenderSet::bind_any().expect("sACN sender");
+        let mut worker = serial.fork().expect("forked sender");
+        let cid = serial.senders[""].cid().unwrap();
+        assert!(worker.senders[""].cid().unwrap() == cid);
+
+        // Senders made later for a specific interface are the same source too
+        let bind = worker.ensure("127.0.0.1");
+        assert_eq!(bind, "127.0.0.1");
+        assert!(worker.senders[&bind].cid().unwrap() == cid);
+    }
+
+    /// Frame time for 64 universes, serial vs. pooled, unicast to localhost.
+    /// Run with `cargo test --release -- --ignored --nocapture bench_output`.
+    #[test]
+    #[ignore]
+    fn bench_output_serial_vs_pool() {
+        const UNIVERSES: u16 = 64;
+        const FRAMES: u32 = 200;
+        let dst = Some(SocketAddr::from(([127, 0, 0, 1], 5568)));
+        let frame: Vec<UniversePacket> = (1..=UNIVERSES)
+            .map(|universe| UniversePacket { universe, bind: String::new(), dst, data: vec![0; 513], protocol: DmxProtocol::Sacn })
+            .collect();
+
+        let mut serial = SenderSet::bind_any().expect("sACN sender");
+        let started = Instant::now();
+        for _ in 0..FRAMES {
+            assert!(frame.iter().all(|p| serial.send(p)));
+        }
+        let serial_ms = started.elapsed().as_secs_f64() * 1000.0 / FRAMES as f64;
+
+        let pool = OutputPool::new(4, &serial);
+        let started = Instant::now();
+        for _ in 0..FRAMES {
+            assert_eq!(pool.send_all(frame.clone()), (UNIVERSES as usize, 0));
+        }
+        let pool_ms = started.elapsed().as_secs_f64() * 1000.0 / FRAMES as f64;
+
+        println!("{} universes: serial {:.3} ms/frame, 4 threads {:.3} ms/frame ({:.1}x)",
+            UNIVERSES, serial_ms, pool_ms, serial_ms / pool_ms);
+    }
+}
@@ -0,0 +1,106 @@
+//! Per-pixel position and address export for external mapping tools (xLights, Jinx, ...).
+//!
+//! Read-only: every output pixel's canvas position from `PixelStrip::pixel_positions_3d`
+//! and the universe/channel the engine packs it into, as CSV or JSON.
+
+use crate::model::AppState;
+use serde::Serialize;
+use std::path::Path;
+
+/// CSV header written by `pixel_map_to_csv`
+pub const PIXEL_MAP_COLUMNS: &[&str] = &["strip_id", "pixel", "x", "y", "z", "universe", "channel"];
+
+/// One output pixel
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PixelMapEntry {
+    pub strip_id: u64,
+    pub pixel: usize, // Index along the strip, as in `PixelStrip::data`
+    pub x: f32,
+    pub y: f32,
+    pub z: Option<f32>,
+    pub universe: u16, // Output universe, global universe offset included
+    pub channel: u16, // 1-based DMX channel of the pixel's first slot
+}
+
+/// Every pixel the engine actually sends: enabled strips only, and only pixels whose
+/// slots all fit in their universe
+pub fn pixel_map(state: &AppState) -> Vec<PixelMapEntry> {
+    let global_universe_offset = state.network.universe.saturating_sub(1);
+    let mut entries = Vec::new();
+    for strip in state.strips.iter().filter(|s| s.enabled) {
+        let universe = strip.universe.saturating_add(global_universe_offset).min(63999).max(1);
+        let first = strip.start_channel.max(1) as usize + strip.pre_channels.len();
+        let stride = strip.pixel_stride();
+        for (pixel, (x, y, z)) in strip.pixel_positions_3d().into_iter().enumerate() {
+            let channel = first + pixel * stride;
+            if channel + stride - 1 > 512 {
+                break;
+            }
+            entries.push(PixelMapEntry { strip_id: strip.id, pixel, x, y, z, universe, channel: channel as u16 });
+        }
+    }
+    entries
+}
+
+/// One row per pixel under a `PIXEL_MAP_COLUMNS` header; `z` is blank for flat strips
+pub fn pixel_map_to_csv(entries: &[PixelMapEntry]) -> String {
+    let mut out = PIXEL_MAP_COLUMNS.join(",");
+    out.push('\n');
+    for e in entries {
+        let z = e.z.map(|z| z.to_string()).unwrap_or_default();
+        out.push_str(&format!("{},{},{},{},{},{},{}\n", e.strip_id, e.pixel, e.x, e.y, z, e.universe, e.channel));
+    }
+    out
+}
+
+/// Write the pixel map to `path`: JSON for a `.json` extension, CSV otherwise.
+/// Returns the number of pixels written.
+pub fn export_pixel_map(state: &AppState, path: &Path) -> std::io::Result<usize> {
+    let entries = pixel_map(state);
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let text = if is_json {
+        serde_json::to_string_pretty(&entries)?
+    } else {
+        pixel_map_to_csv(&entries)
+    };
+    std::fs::write(path, text)?;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{NetworkConfig, PixelStrip};
+
+    #[test]
+    fn test_pixel_map_positions_and_addresses() {
+        let state = AppState {
+            network: NetworkConfig { universe: 2, ..Default::default() },
+            strips: vec![
+                PixelStrip { id: 1, universe: 1, start_channel: 1, pixel_count: 3, x: 0.1, y: 0.5, spacing: 0.1, z: Some(0.25), pre_channels: vec![255.into()], ..Default::default() },
+                PixelStrip { id: 2, enabled: false, pixel_count: 3, ..Default::default() },
+                PixelStrip { id: 3, universe: 4, start_channel: 508, pixel_count: 3, flipped: true, x: 0.0, spacing: 0.1, ..Default::default() },
+            ],
+            ..Default::default()
+        };
+        let map = pixel_map(&state);
+
+        let strip1: Vec<_> = map.iter().filter(|e| e.strip_id == 1).collect();
+        assert_eq!(strip1.iter().map(|e| e.channel).collect::<Vec<_>>(), vec![2, 5, 8]); // After the pre channel
+        assert!(strip1.iter().all(|e| e.universe == 2 && e.z == Some(0.25)));
+        assert!((strip1[2].x - 0.3).abs() < 1e-6);
+        // Disabled strips aren't output
+        assert!(map.iter().all(|e| e.strip_id != 2));
+        // Only the first pixel of strip 3 fits before channel 512; flipped, it sits at the far end
+        let strip3: Vec<_> = map.iter().filter(|e| e.strip_id == 3).collect();
+        assert_eq!(strip3.len(), 1);
+        assert_eq!((strip3[0].universe, strip3[0].channel, strip3[0].z), (5, 508, None));
+        assert!((strip3[0].x - 0.2).abs() < 1e-6);
+
+        let csv = pixel_map_to_csv(&map);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("strip_id,pixel,x,y,z,universe,channel"));
+        assert_eq!(lines.next(), Some("1,0,0.1,0.5,0.25,2,2"));
+        assert_eq!(lines.last(), Some("3,0,0.2,0.5,,5,508"));
+    }
+}
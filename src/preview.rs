@@ -0,0 +1,182 @@
+use crate::model::PixelStrip;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tungstenite::Message;
+
+/// Upper bound on frames pushed to each client per second. Not a setting: the preview is a
+/// monitor, the engine rarely renders faster than the display, and slow clients already
+/// drop to the newest frame, so this only keeps a 120 Hz UI from doubling the bandwidth.
+const PREVIEW_MAX_FPS: u64 = 60;
+
+/// Latest encoded frame. Only one slot: a client that is still sending when the
+/// next frame lands simply skips to it, so slow clients drop frames instead of queueing.
+struct FrameSlot {
+    seq: u64,
+    bytes: Arc<Vec<u8>>,
+}
+
+struct Shared {
+    slot: Mutex<FrameSlot>,
+    frame_ready: Condvar,
+    shutdown: AtomicBool,
+    clients: AtomicUsize,
+}
+
+/// Live browser preview: pushes every rendered frame to WebSocket clients.
+///
+/// Each message is one binary frame:
+/// `u16 strip count`, then per strip `u64 id`, `u16 pixel count`, `pixel count * [r, g, b]`
+/// (integers little-endian).
+pub struct PreviewServer {
+    shared: Arc<Shared>,
+    pub port: u16,
+}
+
+impl PreviewServer {
+    pub fn start(port: u16) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+
+        let shared = Arc::new(Shared {
+            slot: Mutex::new(FrameSlot { seq: 0, bytes: Arc::new(Vec::new()) }),
+            frame_ready: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+            clients: AtomicUsize::new(0),
+        });
+
+        let accept_shared = shared.clone();
+        thread::spawn(move || {
+            log::info!("Preview WebSocket server listening on port {}", port);
+            while !accept_shared.shutdown.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, addr)) => {
+                        log::info!("Preview client connected: {}", addr);
+                        let client_shared = accept_shared.clone();
+                        thread::spawn(move || serve_client(stream, client_shared));
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(100));
+                    }
+                    Err(e) => {
+                        log::warn!("Preview server accept failed: {}", e);
+                        thread::sleep(Duration::from_millis(100));
+                    }
+                }
+            }
+            log::info!("Preview WebSocket server on port {} stopped", port);
+        });
+
+        Ok(Self { shared, port })
+    }
+
+    /// Publish the frame about to go out on the wire. Skips encoding when nobody is watching.
+    pub fn publish(&self, strips: &[PixelStrip]) {
+        if self.shared.clients.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let bytes = Arc::new(encode_frame(strips));
+        if let Ok(mut slot) = self.shared.slot.lock() {
+            slot.seq += 1;
+            slot.bytes = bytes;
+        }
+        self.shared.frame_ready.notify_all();
+    }
+}
+
+impl Drop for PreviewServer {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        self.shared.frame_ready.notify_all();
+    }
+}
+
+fn serve_client(stream: TcpStream, shared: Arc<Shared>) {
+    // Accepted sockets may inherit the listener's non-blocking mode
+    let _ = stream.set_nonblocking(false);
+    // A stalled client must not pin this thread forever
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+
+    let mut ws = match tungstenite::accept(stream) {
+        Ok(ws) => ws,
+        Err(e) => {
+            log::warn!("Preview WebSocket handshake failed: {}", e);
+            return;
+        }
+    };
+
+    shared.clients.fetch_add(1, Ordering::SeqCst);
+    let min_interval = Duration::from_millis(1000 / PREVIEW_MAX_FPS);
+    let mut last_seq = 0;
+    let mut last_sent: Option<Instant> = None;
+
+    while !shared.shutdown.load(Ordering::SeqCst) {
+        let frame = {
+            let Ok(slot) = shared.slot.lock() else { break };
+            let Ok((slot, _)) = shared.frame_ready
+                .wait_timeout_while(slot, Duration::from_millis(500), |s| {
+                    s.seq == last_seq && !shared.shutdown.load(Ordering::SeqCst)
+                })
+            else { break };
+            if slot.seq == last_seq {
+                continue; // Timed out; re-check shutdown
+            }
+            last_seq = slot.seq;
+            slot.bytes.clone()
+        };
+
+        if let Some(since) = last_sent.map(|t| t.elapsed()) {
+            if since < min_interval {
+                thread::sleep(min_interval - since);
+            }
+        }
+        last_sent = Some(Instant::now());
+
+        if let Err(e) = ws.send(Message::Binary(frame.to_vec())) {
+            log::info!("Preview client disconnected: {}", e);
+            break;
+        }
+    }
+
+    shared.clients.fetch_sub(1, Ordering::SeqCst);
+}
+
+/// Pack strip ids and RGB data into the binary preview format
+pub fn encode_frame(strips: &[PixelStrip]) -> Vec<u8> {
+    let pixels: usize = strips.iter().map(|s| s.data.len()).sum();
+    let mut out = Vec::with_capacity(2 + strips.len() * 10 + pixels * 3);
+    out.extend_from_slice(&(strips.len().min(u16::MAX as usize) as u16).to_le_bytes());
+    for strip in strips.iter().take(u16::MAX as usize) {
+        let count = strip.data.len().min(u16::MAX as usize);
+        out.extend_from_slice(&strip.id.to_le_bytes());
+        out.extend_from_slice(&(count as u16).to_le_bytes());
+        for pixel in &strip.data[..count] {
+            out.extend_from_slice(pixel);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_frame_layout() {
+        let mut a = PixelStrip { id: 7, ..Default::default() };
+        a.set_pixel_count(2);
+        a.data[0] = [1, 2, 3];
+        a.data[1] = [4, 5, 6];
+        let mut b = PixelStrip { id: 0x0102, ..Default::default() };
+        b.set_pixel_count(0);
+
+        let bytes = encode_frame(&[a, b]);
+        assert_eq!(bytes, vec![
+            2, 0,
+            7, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1, 2, 3, 4, 5, 6,
+            2, 1, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+    }
+}
@@ -0,0 +1,167 @@
+//! Periodic reachability checks for unicast sACN targets.
+//!
+//! sACN is fire-and-forget, so an offline node just leaves its strips dark. `NodeMonitor`
+//! probes every unicast destination from a background thread and reports when one stops
+//! (or starts again) answering. A probe is a TCP connect to `PROBE_PORT`: pixel nodes
+//! usually serve a web UI there, and a node without one still answers with a reset,
+//! which proves it is up just as well. Only silence (timeout, no route) counts as down.
+
+use crate::model::NetworkConfig;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const PROBE_PORT: u16 = 80;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeEvent {
+    Reachable(IpAddr),
+    Unreachable(IpAddr),
+}
+
+/// Every unicast destination the current config sends to, deduplicated
+pub fn unicast_targets(network: &NetworkConfig) -> Vec<IpAddr> {
+    let global = if network.use_multicast { &[][..] } else { &network.unicast_ips[..] };
+    let mut targets: Vec<IpAddr> = global.iter().map(String::as_str)
+        .chain(network.routes.iter().filter_map(|r| r.dest.as_deref()))
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect();
+    targets.sort_unstable();
+    targets.dedup();
+    targets
+}
+
+/// True when `ip` answers a TCP connect on `PROBE_PORT`, accepted or refused
+pub fn probe(ip: IpAddr, timeout: Duration) -> bool {
+    match TcpStream::connect_timeout(&SocketAddr::new(ip, PROBE_PORT), timeout) {
+        Ok(_) => true,
+        Err(e) => e.kind() == std::io::ErrorKind::ConnectionRefused,
+    }
+}
+
+/// Record probe results in `known` and return the changes worth reporting.
+/// A target seen for the first time is only reported when it is down.
+fn status_changes(known: &mut HashMap<IpAddr, bool>, results: &[(IpAddr, bool)]) -> Vec<NodeEvent> {
+    known.retain(|ip, _| results.iter().any(|(target, _)| target == ip));
+    results.iter()
+        .filter_map(|&(ip, up)| match known.insert(ip, up) {
+            Some(was_up) if was_up == up => None,
+            None if up => None,
+            _ if up => Some(NodeEvent::Reachable(ip)),
+            _ => Some(NodeEvent::Unreachable(ip)),
+        })
+        .collect()
+}
+
+struct Shared {
+    targets: Mutex<Vec<IpAddr>>,
+    shutdown: AtomicBool,
+}
+
+/// Background prober; stops when dropped
+pub struct NodeMonitor {
+    shared: Arc<Shared>,
+    events: Receiver<NodeEvent>,
+    interval: Duration,
+}
+
+impl NodeMonitor {
+    pub fn start(interval: Duration) -> Self {
+        let shared = Arc::new(Shared { targets: Mutex::new(Vec::new()), shutdown: AtomicBool::new(false) });
+        let (tx, events) = mpsc::channel();
+        let worker_shared = shared.clone();
+        thread::spawn(move || run_probes(worker_shared, tx, interval));
+        Self { shared, events, interval }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Replace the probed addresses; takes effect on the next round
+    pub fn set_targets(&self, targets: Vec<IpAddr>) {
+        if let Ok(mut current) = self.shared.targets.lock() {
+            *current = targets;
+        }
+    }
+
+    /// Status changes since the last call
+    pub fn events(&self) -> impl Iterator<Item = NodeEvent> + '_ {
+        self.events.try_iter()
+    }
+}
+
+impl Drop for NodeMonitor {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+fn run_probes(shared: Arc<Shared>, tx: Sender<NodeEvent>, interval: Duration) {
+    let mut known = HashMap::new();
+    while !shared.shutdown.load(Ordering::SeqCst) {
+        let round_start = Instant::now();
+        let targets = shared.targets.lock().map(|t| t.clone()).unwrap_or_default();
+        // Probe in parallel so several dead nodes don't stretch the round to seconds each
+        let results: Vec<(IpAddr, bool)> = thread::scope(|scope| {
+            let probes: Vec<_> = targets.iter()
+                .map(|&ip| scope.spawn(move || (ip, probe(ip, PROBE_TIMEOUT))))
+                .collect();
+            probes.into_iter().filter_map(|p| p.join().ok()).collect()
+        });
+        for event in status_changes(&mut known, &results) {
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+        // Sleep in short steps so dropping the monitor ends the thread promptly
+        while round_start.elapsed() < interval && !shared.shutdown.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::UniverseRoute;
+
+    #[test]
+    fn test_unicast_targets_and_status_changes() {
+        let mut network = NetworkConfig { use_multicast: false, unicast_ips: vec!["10.0.0.5".into()], ..Default::default() };
+        network.routes = vec![
+            UniverseRoute { dest: Some("10.0.0.9".into()), ..Default::default() },
+            UniverseRoute { dest: Some("10.0.0.5".into()), ..Default::default() },
+            UniverseRoute { dest: Some("not an ip".into()), ..Default::default() },
+            UniverseRoute::default(), // Multicast
+        ];
+        let a: IpAddr = "10.0.0.5".parse().unwrap();
+        let b: IpAddr = "10.0.0.9".parse().unwrap();
+        assert_eq!(unicast_targets(&network), vec![a, b]);
+        // The global unicast IPs only count while multicast is off
+        network.unicast_ips = vec!["10.0.0.7".into(), "bogus".into()];
+        network.use_multicast = true;
+        assert_eq!(unicast_targets(&network), vec![a, b]);
+        network.use_multicast = false;
+        let c: IpAddr = "10.0.0.7".parse().unwrap();
+        assert_eq!(unicast_targets(&network), vec![a, c, b]);
+
+        let mut known = HashMap::new();
+        assert_eq!(status_changes(&mut known, &[(a, true), (b, false)]), vec![NodeEvent::Unreachable(b)]);
+        assert_eq!(status_changes(&mut known, &[(a, true), (b, false)]), vec![]);
+        assert_eq!(status_changes(&mut known, &[(a, false), (b, true)]), vec![NodeEvent::Unreachable(a), NodeEvent::Reachable(b)]);
+        // Dropped targets are forgotten, so re-adding one starts fresh
+        status_changes(&mut known, &[(b, true)]);
+        assert_eq!(status_changes(&mut known, &[(a, true), (b, true)]), vec![]);
+    }
+
+    #[test]
+    fn test_probe_counts_refused_connection_as_reachable() {
+        assert!(probe(IpAddr::from([127, 0, 0, 1]), PROBE_TIMEOUT));
+    }
+}
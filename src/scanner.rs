@@ -27,6 +27,68 @@
 //! ```
 
 use crate::model::PixelStrip;
+use std::collections::HashMap;
+
+/// Edge behavior for a scanner mask, from its `edge_mode` param.
+///
+/// Older scanners without the param keep their previous behavior:
+/// `unidirectional` ones wrap, all others bounce.
+pub fn edge_mode(params: &HashMap<String, serde_json::Value>) -> &str {
+    let unidirectional = params.get("unidirectional").and_then(|v| v.as_bool()).unwrap_or(false);
+    params.get("edge_mode")
+        .and_then(|v| v.as_str())
+        .unwrap_or(if unidirectional { "wrap" } else { "bounce" })
+}
+
+/// Normalized bar position (-1.0 = left edge, 1.0 = right edge) for a scanner phase.
+///
+/// `phase` is in radians, one full sweep cycle per 2π.
+///
+/// * `"wrap"` - sweep left to right, then reappear at the left edge
+/// * `"clamp"` - sweep left to right once, then stay pinned at the right edge
+/// * `"bounce"` - sweep back and forth; `motion` picks "Smooth" (sine) or "Linear" (triangle)
+pub fn bar_position(phase: f64, edge_mode: &str, motion: &str) -> f64 {
+    let cycles = phase / std::f64::consts::TAU;
+    match edge_mode {
+        "wrap" => cycles.rem_euclid(1.0) * 2.0 - 1.0,
+        "clamp" => cycles.clamp(0.0, 1.0) * 2.0 - 1.0,
+        _ => {
+            if motion == "Linear" {
+                (2.0 / std::f64::consts::PI) * phase.sin().asin()
+            } else {
+                phase.sin()
+            }
+        }
+    }
+}
+
+/// Brightness (0.0-1.0) a pixel gets from a bar whose center is `distance` away along the sweep.
+///
+/// `footprint` is the stretch of the sweep axis the pixel covers (its pitch projected onto
+/// the axis), so a bar moving by less than a pixel still changes the output:
+///
+/// * hard edge - the fraction of the footprint the bar overlaps; a bar edge between two pixels
+///   splits its brightness across both
+/// * soft edge - linear falloff from the bar center, never narrower than one footprint, so a
+///   thin bar interpolates between its two nearest pixels instead of blinking between them
+///
+/// Either way the summed brightness along a strip stays constant as the bar moves.
+pub fn bar_intensity(distance: f32, bar_width: f32, footprint: f32, hard_edge: bool) -> f32 {
+    if hard_edge {
+        if footprint <= f32::EPSILON {
+            return if distance <= bar_width { 1.0 } else { 0.0 };
+        }
+        let half = footprint / 2.0;
+        let covered = (distance + half).min(bar_width) - (distance - half).max(-bar_width);
+        (covered / footprint).clamp(0.0, 1.0)
+    } else {
+        let width = bar_width.max(footprint);
+        if width <= 0.0 {
+            return 0.0;
+        }
+        (1.0 - distance / width).max(0.0)
+    }
+}
 
 /// Apply a scanner mask effect to LED strips.
 ///
@@ -39,7 +101,7 @@ use crate::model::PixelStrip;
 /// * `mask_rotation_degrees` - Mask rotation in degrees (0-360)
 /// * `bar_position_normalized` - Bar position from -1.0 (left edge) to 1.0 (right edge)
 /// * `bar_width` - Width of the scanning bar (distance threshold)
-/// * `hard_edge` - If true, full intensity within bar_width; if false, linear falloff
+/// * `hard_edge` - If true, full intensity within bar_width; if false, linear falloff (see `bar_intensity`)
 /// * `color` - RGB color to apply [R, G, B]
 /// * `strips` - Mutable slice of LED strips to modify
 ///
@@ -50,7 +112,7 @@ use crate::model::PixelStrip;
 /// 2. Transform the pixel position to the mask's local coordinate system
 /// 3. Check if the pixel is inside the rectangular mask bounds
 /// 4. Calculate distance from the pixel to the scanning bar center
-/// 5. Apply color with intensity based on distance (if within bar_width)
+/// 5. Apply color with intensity based on distance, anti-aliased at sub-pixel bar positions
 ///
 /// # Examples
 ///
@@ -101,6 +163,8 @@ pub fn apply_scanner_mask(
     for strip in strips.iter_mut() {
         // Ensure we don't exceed array bounds
         let pixel_limit = strip.pixel_count.min(strip.data.len());
+        // Strips run along world X, so each pixel spans spacing * cos(θ) of the sweep axis
+        let footprint = strip.spacing * cos_theta.abs();
 
         // Process each pixel in the strip
         for pixel_index in 0..pixel_limit {
@@ -152,23 +216,14 @@ pub fn apply_scanner_mask(
             // Distance is just the horizontal offset
             let distance_to_bar = (local_x - bar_center_x).abs();
 
-            // === 5. Apply color if within bar width ===
-
-            if distance_to_bar <= bar_width {
-                // Calculate intensity based on distance
-                let intensity = if hard_edge {
-                    // Hard edge: full intensity anywhere within bar_width
-                    1.0
-                } else {
-                    // Soft edge: linear falloff from 1.0 at center to 0.0 at bar_width
-                    // intensity = 1.0 - (distance / bar_width)
-                    (1.0 - distance_to_bar / bar_width).max(0.0)
-                };
+            // === 5. Apply color where the bar reaches the pixel ===
 
+            let intensity = bar_intensity(distance_to_bar, bar_width, footprint, hard_edge);
+            if intensity > 0.0 {
                 // Apply intensity to color
-                let r = (color[0] as f32 * intensity) as u8;
-                let g = (color[1] as f32 * intensity) as u8;
-                let b = (color[2] as f32 * intensity) as u8;
+                let r = (color[0] as f32 * intensity).round() as u8;
+                let g = (color[1] as f32 * intensity).round() as u8;
+                let b = (color[2] as f32 * intensity).round() as u8;
 
                 // Add to existing pixel color (saturating to prevent overflow)
                 let current = strip.data[pixel_index];
@@ -190,14 +245,27 @@ mod tests {
     fn create_test_strip(x: f32, y: f32, flipped: bool, pixel_count: usize) -> PixelStrip {
         PixelStrip {
             id: 1,
+            enabled: true,
             universe: 1,
             start_channel: 1,
             pixel_count,
             x,
             y,
             spacing: 0.01, // 1cm spacing in normalized coords
+            leds_per_meter: None,
+            length_m: None,
+            min_brightness: 0,
+            brightness: 1.0,
+            pre_channels: Vec::new(),
+            post_channels: Vec::new(),
             flipped,
             color_order: "RGB".to_string(),
+            white_mode: "Add".to_string(),
+            channels_per_pixel: 0,
+            notes: String::new(),
+            mirror_source: None,
+            mirror_reversed: false,
+            z: None,
             data: vec![[0, 0, 0]; pixel_count],
         }
     }
@@ -370,5 +438,92 @@ mod tests {
         // Pixel 0 is at x=0 + (100-1-0)*0.01 = 0.99. Should be dark.
         assert_eq!(strips[0].data[0], [0, 0, 0], "Pixel 0 (Rightmost in flipped strip) should be dark");
     }
+
+    const TAU: f64 = std::f64::consts::TAU;
+
+    #[test]
+    fn test_wrap_reappears_at_start() {
+        // Just before the end of a cycle the bar is at the right edge...
+        let before = bar_position(0.99 * TAU, "wrap", "Smooth");
+        assert!(before > 0.95, "Bar should be near the right edge, got {}", before);
+
+        // ...and just after, it has jumped back to the left edge
+        let after = bar_position(1.01 * TAU, "wrap", "Smooth");
+        assert!(after < -0.95, "Bar should reappear at the left edge, got {}", after);
+
+        // Rendering confirms the lit pixels moved from the end of the strip to the start
+        let mut strips = vec![create_test_strip(0.0, 0.5, false, 100)];
+        apply_scanner_mask(0.5, 0.5, 1.0, 0.2, 0.0, after as f32, 0.05, true, [255, 0, 0], &mut strips);
+        assert_eq!(strips[0].data[2], [255, 0, 0], "Pixel near the start should be lit after wrapping");
+        assert_eq!(strips[0].data[97], [0, 0, 0], "Pixel near the end should be dark after wrapping");
+    }
+
+    #[test]
+    fn test_clamp_stays_pinned_at_edge() {
+        assert_eq!(bar_position(0.0, "clamp", "Smooth"), -1.0, "Clamp starts at the left edge");
+        assert!((bar_position(0.5 * TAU, "clamp", "Smooth")).abs() < 1e-9, "Clamp is centered halfway");
+
+        // Once the sweep completes the bar never moves again
+        for cycles in [1.0, 1.01, 1.5, 7.25, 100.0] {
+            assert_eq!(bar_position(cycles * TAU, "clamp", "Smooth"), 1.0, "Clamp should stay pinned at {} cycles", cycles);
+        }
+
+        let mut strips = vec![create_test_strip(0.0, 0.5, false, 100)];
+        let pinned = bar_position(3.3 * TAU, "clamp", "Smooth");
+        apply_scanner_mask(0.5, 0.5, 1.0, 0.2, 0.0, pinned as f32, 0.05, true, [255, 0, 0], &mut strips);
+        assert_eq!(strips[0].data[97], [255, 0, 0], "Pixel near the end should stay lit");
+        assert_eq!(strips[0].data[2], [0, 0, 0], "Pixel near the start should stay dark");
+    }
+
+    /// Summed red output of a single 100-pixel strip for a hard or soft bar at `position`
+    fn total_brightness(position: f32, bar_width: f32, hard_edge: bool) -> (u32, usize) {
+        let mut strips = vec![create_test_strip(0.0, 0.5, false, 100)];
+        apply_scanner_mask(0.5, 0.5, 1.0, 0.2, 0.0, position, bar_width, hard_edge, [255, 0, 0], &mut strips);
+        let lit = strips[0].data.iter().filter(|p| p[0] > 0).count();
+        (strips[0].data.iter().map(|p| p[0] as u32).sum(), lit)
+    }
+
+    #[test]
+    fn test_sub_pixel_positions_conserve_energy() {
+        // Step the bar a fraction of a pixel (pitch 0.01) at a time
+        for step in 0..20 {
+            let position = -0.2 + step as f32 * 0.0013;
+
+            // Hard 0.05 bar covers 10 pixels' worth: 2550 spread over 10-11 pixels
+            let (hard, lit) = total_brightness(position, 0.05, true);
+            assert!(hard.abs_diff(2550) <= 2, "hard bar at {} sums to {}", position, hard);
+            assert!((10..=11).contains(&lit));
+
+            // A soft bar thinner than a pixel is shared by its two nearest pixels
+            let (soft, lit) = total_brightness(position, 0.002, false);
+            assert!(soft.abs_diff(255) <= 1, "soft bar at {} sums to {}", position, soft);
+            assert!((1..=2).contains(&lit));
+        }
+
+        // Halfway between pixels the brightness splits evenly
+        assert!((bar_intensity(0.005, 0.001, 0.01, false) - 0.5).abs() < 1e-4);
+        assert!((bar_intensity(0.05, 0.05, 0.01, true) - 0.5).abs() < 1e-4);
+        assert_eq!(bar_intensity(0.06, 0.05, 0.01, true), 0.0);
+    }
+
+    #[test]
+    fn test_bounce_reverses_at_edges() {
+        // Linear bounce reaches the right edge a quarter cycle in, then heads back
+        assert!((bar_position(0.25 * TAU, "bounce", "Linear") - 1.0).abs() < 1e-9);
+        assert!(bar_position(0.3 * TAU, "bounce", "Linear") < bar_position(0.25 * TAU, "bounce", "Linear"));
+        assert!((bar_position(0.75 * TAU, "bounce", "Linear") + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_edge_mode_defaults_from_unidirectional() {
+        let mut params = HashMap::new();
+        assert_eq!(edge_mode(&params), "bounce");
+
+        params.insert("unidirectional".to_string(), serde_json::json!(true));
+        assert_eq!(edge_mode(&params), "wrap");
+
+        params.insert("edge_mode".to_string(), serde_json::json!("clamp"));
+        assert_eq!(edge_mode(&params), "clamp");
+    }
 }
 
@@ -0,0 +1,107 @@
+//! Whole-rig `AppState` files: pretty JSON (default, interchange) or compact MessagePack.
+//!
+//! The format follows the file extension. MessagePack rather than bincode/postcard because
+//! effect params are `serde_json::Value` and most fields are `#[serde(default)]`, which
+//! need a self-describing format; structs are written with field names so older files
+//! keep loading as fields are added.
+
+use crate::model::AppState;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Extension selecting the binary format; anything else is JSON
+pub const BINARY_EXTENSION: &str = "lsbin";
+
+pub fn is_binary(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(BINARY_EXTENSION))
+}
+
+pub fn to_binary(state: &AppState) -> Result<Vec<u8>> {
+    Ok(rmp_serde::to_vec_named(state)?)
+}
+
+pub fn from_binary(bytes: &[u8]) -> Result<AppState> {
+    rmp_serde::from_slice(bytes).context("Invalid binary state file")
+}
+
+/// Write `state` to `path` in the format its extension selects
+pub fn save(state: &AppState, path: &Path) -> Result<()> {
+    let bytes = if is_binary(path) {
+        to_binary(state)?
+    } else {
+        serde_json::to_string_pretty(state)?.into_bytes()
+    };
+    std::fs::write(path, bytes).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Read an `AppState` from `path`, dispatching on its extension. Runtime state is not
+/// initialized and no migration is run; callers importing the result do both.
+pub fn load(path: &Path) -> Result<AppState> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if is_binary(path) {
+        from_binary(&bytes)
+    } else {
+        serde_json::from_slice(&bytes).context("Invalid JSON format")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::PixelStrip;
+    use std::time::Instant;
+
+    fn state_with_100_strips() -> AppState {
+        AppState {
+            strips: (1..=100)
+                .map(|id| PixelStrip { id, universe: (id / 4 + 1) as u16, pixel_count: 170, y: id as f32 / 100.0, pre_channels: vec![255.into()], ..Default::default() })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_binary_round_trip_100_strips() {
+        let state = state_with_100_strips();
+        let json = serde_json::to_string_pretty(&state).unwrap();
+        let binary = to_binary(&state).unwrap();
+        assert!(binary.len() < json.len() / 2, "binary {} vs JSON {} bytes", binary.len(), json.len());
+
+        let from_json: AppState = serde_json::from_str(&json).unwrap();
+        let loaded = from_binary(&binary).unwrap();
+
+        assert_eq!(loaded.strips.len(), 100);
+        assert_eq!(loaded.strips[41].universe, from_json.strips[41].universe);
+        assert_eq!(loaded.strips[99].y, 1.0);
+        assert_eq!(loaded.strips[0].pre_channels, vec![255.into()]);
+        assert!(is_binary(Path::new("rig.LSBIN")));
+        assert!(!is_binary(Path::new("rig.json")));
+    }
+
+    /// Load time for a 100-strip rig, pretty JSON vs. MessagePack.
+    /// Run with `cargo test --release -- --ignored --nocapture bench_state_load`.
+    #[test]
+    #[ignore]
+    fn bench_state_load_json_vs_binary() {
+        const LOADS: u32 = 200;
+        let state = state_with_100_strips();
+        let json = serde_json::to_string_pretty(&state).unwrap();
+        let binary = to_binary(&state).unwrap();
+
+        let started = Instant::now();
+        for _ in 0..LOADS {
+            let loaded: AppState = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded.strips.len(), 100);
+        }
+        let json_ms = started.elapsed().as_secs_f64() * 1000.0 / LOADS as f64;
+
+        let started = Instant::now();
+        for _ in 0..LOADS {
+            assert_eq!(from_binary(&binary).unwrap().strips.len(), 100);
+        }
+        let binary_ms = started.elapsed().as_secs_f64() * 1000.0 / LOADS as f64;
+
+        println!("100 strips: JSON {} bytes in {:.3} ms, binary {} bytes in {:.3} ms ({:.1}x)",
+            json.len(), json_ms, binary.len(), binary_ms, json_ms / binary_ms);
+    }
+}
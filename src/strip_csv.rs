@@ -0,0 +1,226 @@
+use crate::model::{is_valid_color_order, AppState, PixelStrip, WHITE_MODES};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Column order written by `export_strips_csv` and assumed for files without a header
+pub const STRIP_CSV_COLUMNS: &[&str] = &[
+    "id", "universe", "start_channel", "pixel_count", "x", "y", "spacing", "flipped", "color_order", "channels_per_pixel",
+    "white_mode",
+];
+
+/// Columns a strip definition can't do without; the rest fall back to `PixelStrip::default()`
+const REQUIRED_COLUMNS: &[&str] = &["universe", "start_channel", "pixel_count", "x", "y"];
+
+#[derive(Debug)]
+pub enum StripCsvError {
+    Io(std::io::Error),
+    /// A bad header or row; `line` is 1-based
+    Invalid { line: usize, message: String },
+}
+
+impl std::fmt::Display for StripCsvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StripCsvError::Io(e) => write!(f, "{}", e),
+            StripCsvError::Invalid { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for StripCsvError {}
+
+impl From<std::io::Error> for StripCsvError {
+    fn from(e: std::io::Error) -> Self {
+        StripCsvError::Io(e)
+    }
+}
+
+/// Write every strip as one CSV row under a `STRIP_CSV_COLUMNS` header
+pub fn strips_to_csv(strips: &[PixelStrip]) -> String {
+    let mut out = STRIP_CSV_COLUMNS.join(",");
+    out.push('\n');
+    for s in strips {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{}\n",
+            s.id, s.universe, s.start_channel, s.pixel_count, s.x, s.y, s.spacing, s.flipped, s.color_order, s.channels_per_pixel,
+            s.white_mode
+        ));
+    }
+    out
+}
+
+/// Parse strip rows. A header row (any order, optional columns omitted) is used when
+/// present; otherwise columns are read in `STRIP_CSV_COLUMNS` order.
+/// Blank ids get fresh random ids; ids in `taken` or repeated in the file are rejected.
+pub fn parse_strips_csv(text: &str, taken: &HashSet<u64>) -> Result<Vec<PixelStrip>, StripCsvError> {
+    let mut rows = text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .peekable();
+
+    let has_header = rows.peek().is_some_and(|(_, line)| {
+        line.split(',').any(|c| STRIP_CSV_COLUMNS.contains(&c.trim().to_ascii_lowercase().as_str()))
+    });
+    let columns: Vec<String> = match rows.peek() {
+        Some((line_no, line)) if has_header => {
+            let columns: Vec<String> = line.split(',').map(|c| c.trim().to_ascii_lowercase()).collect();
+            if let Some(missing) = REQUIRED_COLUMNS.iter().find(|c| !columns.iter().any(|h| h == *c)) {
+                return Err(StripCsvError::Invalid { line: *line_no, message: format!("missing column '{}'", missing) });
+            }
+            rows.next();
+            columns
+        }
+        _ => STRIP_CSV_COLUMNS.iter().map(|c| c.to_string()).collect(),
+    };
+
+    let mut seen = HashSet::new();
+    let mut strips = Vec::new();
+    for (line, row) in rows {
+        let invalid = |message: String| StripCsvError::Invalid { line, message };
+        let cells: Vec<&str> = row.split(',').map(str::trim).collect();
+        if cells.len() > columns.len() {
+            return Err(invalid(format!("expected at most {} values, found {}", columns.len(), cells.len())));
+        }
+
+        let mut strip = PixelStrip::default();
+        let mut id = None;
+        for (column, cell) in columns.iter().zip(&cells).filter(|(_, cell)| !cell.is_empty()) {
+            let bad = |expected: &str| invalid(format!("{} '{}' is not {}", column, cell, expected));
+            match column.as_str() {
+                "id" => id = Some(cell.parse::<u64>().map_err(|_| bad("a whole number"))?),
+                "universe" => {
+                    strip.universe = cell.parse().ok().filter(|u| (1..=63999).contains(u)).ok_or_else(|| bad("1-63999"))?;
+                }
+                "start_channel" => {
+                    strip.start_channel = cell.parse().ok().filter(|c| (1..=512).contains(c)).ok_or_else(|| bad("1-512"))?;
+                }
+                "pixel_count" => {
+                    strip.pixel_count = cell.parse().ok().filter(|n| *n > 0).ok_or_else(|| bad("a positive count"))?;
+                }
+                "x" => strip.x = cell.parse().ok().filter(|v| (0.0..=1.0).contains(v)).ok_or_else(|| bad("0-1"))?,
+                "y" => strip.y = cell.parse().ok().filter(|v| (0.0..=1.0).contains(v)).ok_or_else(|| bad("0-1"))?,
+                "spacing" => {
+                    strip.spacing = cell.parse().ok().filter(|v: &f32| *v > 0.0 && *v <= 1.0).ok_or_else(|| bad("in (0, 1]"))?;
+                }
+                "flipped" => {
+                    strip.flipped = match cell.to_ascii_lowercase().as_str() {
+                        "true" | "1" | "yes" => true,
+                        "false" | "0" | "no" => false,
+                        _ => return Err(bad("true/false")),
+                    };
+                }
+                "color_order" => {
+                    let order = cell.to_ascii_uppercase();
+                    if !is_valid_color_order(&order) {
+                        return Err(bad("a color order like RGB, GRB or RGBW"));
+                    }
+                    strip.color_order = order;
+                }
+                "channels_per_pixel" => {
+                    strip.channels_per_pixel = cell.parse().ok().filter(|n| *n <= 16).ok_or_else(|| bad("0-16"))?;
+                }
+                "white_mode" => {
+                    strip.white_mode = WHITE_MODES.iter()
+                        .find(|m| m.eq_ignore_ascii_case(cell))
+                        .ok_or_else(|| bad("Add, Subtract or Off"))?
+                        .to_string();
+                }
+                _ => {} // Extra spreadsheet columns are ignored
+            }
+        }
+        if let Some(missing) = REQUIRED_COLUMNS.iter().find(|c| {
+            columns.iter().position(|h| h == *c).and_then(|i| cells.get(i)).map_or(true, |cell| cell.is_empty())
+        }) {
+            return Err(invalid(format!("missing {}", missing)));
+        }
+
+        strip.id = match id {
+            Some(id) if taken.contains(&id) || !seen.insert(id) => {
+                return Err(invalid(format!("strip id {} is already in use", id)));
+            }
+            Some(id) => id,
+            None => loop {
+                let fresh = rand::random();
+                if !taken.contains(&fresh) && seen.insert(fresh) {
+                    break fresh;
+                }
+            },
+        };
+        strip.set_pixel_count(strip.pixel_count);
+        strips.push(strip);
+    }
+    Ok(strips)
+}
+
+/// Append the strips defined in a CSV file to `state`. Nothing is added if any row is invalid.
+/// Returns the number of strips added.
+pub fn import_strips_csv(state: &mut AppState, path: &Path) -> Result<usize, StripCsvError> {
+    let text = std::fs::read_to_string(path)?;
+    let taken: HashSet<u64> = state.strips.iter().map(|s| s.id).collect();
+    let strips = parse_strips_csv(&text, &taken)?;
+    let count = strips.len();
+    state.strips.extend(strips);
+    state.apply_chains();
+    Ok(count)
+}
+
+pub fn export_strips_csv(state: &AppState, path: &Path) -> Result<(), StripCsvError> {
+    std::fs::write(path, strips_to_csv(&state.strips))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_csv_round_trip() {
+        let strips = vec![
+            PixelStrip { id: 7, universe: 2, start_channel: 4, pixel_count: 30, x: 0.25, y: 0.5, spacing: 0.01, ..Default::default() },
+            PixelStrip { id: 9, flipped: true, color_order: "GRB".into(), ..Default::default() },
+            PixelStrip { id: 11, color_order: "GRBW".into(), channels_per_pixel: 5, white_mode: "Subtract".into(), ..Default::default() },
+        ];
+        let parsed = parse_strips_csv(&strips_to_csv(&strips), &HashSet::new()).unwrap();
+
+        assert_eq!(parsed.len(), 3);
+        for (a, b) in strips.iter().zip(&parsed) {
+            assert_eq!((a.id, a.universe, a.start_channel, a.pixel_count), (b.id, b.universe, b.start_channel, b.pixel_count));
+            assert_eq!((a.x, a.y, a.spacing, a.flipped), (b.x, b.y, b.spacing, b.flipped));
+            assert_eq!((&a.color_order, a.channels_per_pixel), (&b.color_order, b.channels_per_pixel));
+            assert_eq!(a.white_mode, b.white_mode);
+            assert_eq!(b.data.len(), b.pixel_count);
+        }
+    }
+
+    #[test]
+    fn test_parse_strips_csv_header_order_and_blank_ids() {
+        let csv = "x, y, pixel_count, universe, start_channel, id, notes\n\
+                   0.1, 0.2, 10, 1, 1, , left pillar\n\
+                   \n\
+                   0.9, 0.2, 20, 1, 31, 5,\n";
+        let taken = HashSet::from([1]);
+        let strips = parse_strips_csv(csv, &taken).unwrap();
+
+        assert_eq!(strips.len(), 2);
+        assert_ne!(strips[0].id, 1);
+        assert_eq!(strips[0].pixel_count, 10);
+        assert_eq!(strips[0].spacing, PixelStrip::default().spacing);
+        assert_eq!((strips[1].id, strips[1].start_channel), (5, 31));
+    }
+
+    #[test]
+    fn test_parse_strips_csv_reports_bad_rows() {
+        let err = |csv: &str| match parse_strips_csv(csv, &HashSet::from([3])) {
+            Err(StripCsvError::Invalid { line, message }) => (line, message),
+            other => panic!("expected invalid row, got {:?}", other.map(|s| s.len())),
+        };
+
+        assert_eq!(err("1,1,1,10,0.5,0.5\n2,1,600,10,0.5,0.5").0, 2);
+        assert_eq!(err("3,1,1,10,0.5,0.5").1, "strip id 3 is already in use");
+        assert_eq!(err("4,1,1,10,0.5,0.5\n4,1,1,10,0.5,0.5").0, 2);
+        assert_eq!(err("universe,x,y\n1,0.5,0.5").1, "missing column 'start_channel'");
+        assert_eq!(err("5,1,1,10,0.5,").1, "missing y");
+        assert_eq!(err("6,1,1,10,0.5,0.5,0.01,maybe").1, "flipped 'maybe' is not true/false");
+    }
+}